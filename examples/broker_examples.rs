@@ -63,6 +63,7 @@ impl LlmTool for SimpleDateTool {
                     },
                     "required": ["date"]
                 }),
+                returns: None,
             },
         }
     }
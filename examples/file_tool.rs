@@ -3,8 +3,9 @@
 /// This example shows how to use the file_manager module with its various tools
 /// for reading, writing, listing, and searching files within a sandboxed directory.
 use mojentic::llm::tools::file_manager::{
-    CreateDirectoryTool, FilesystemGateway, FindFilesByGlobTool, FindFilesContainingTool,
-    FindLinesMatchingTool, ListAllFilesTool, ListFilesTool, ReadFileTool, WriteFileTool,
+    CreateDirectoryTool, FileStatTool, FilesystemGateway, FindFilesByGlobTool,
+    FindFilesContainingTool, FindLinesMatchingTool, ListAllFilesTool, ListFilesTool, ReadFileTool,
+    WriteFileTool,
 };
 use mojentic::llm::tools::LlmTool;
 use serde_json::json;
@@ -83,6 +84,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("  Content: {}", result);
     println!();
 
+    // Check a file's metadata before reading it
+    println!("📏 Checking file metadata:");
+    let stat_tool = FileStatTool::new(gateway.clone());
+    let mut args = HashMap::new();
+    args.insert("path".to_string(), json!("example.txt"));
+    let result = stat_tool.run(&args, &mojentic::llm::tools::ToolRunCtx::default()).await?;
+    println!("  {}", result);
+    println!();
+
     // Find files by glob pattern
     println!("🔍 Finding Rust files (*.rs):");
     let glob_tool = FindFilesByGlobTool::new(gateway.clone());
@@ -118,6 +128,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("  ✓ CreateDirectoryTool: Create directories");
     println!("  ✓ WriteFileTool: Write file contents");
     println!("  ✓ ReadFileTool: Read file contents");
+    println!("  ✓ FileStatTool: File size, type, and modification time");
     println!("  ✓ ListFilesTool: List directory contents");
     println!("  ✓ ListAllFilesTool: Recursive directory listing");
     println!("  ✓ FindFilesByGlobTool: Pattern-based file search");
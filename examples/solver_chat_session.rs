@@ -102,6 +102,7 @@ impl LlmTool for IterativeProblemSolverTool {
                     "required": ["problem_to_solve"],
                     "additionalProperties": false
                 }),
+                returns: None,
             },
         }
     }
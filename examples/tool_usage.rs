@@ -42,6 +42,7 @@ impl LlmTool for GetWeatherTool {
                     },
                     "required": ["location"]
                 }),
+                returns: None,
             },
         }
     }
@@ -168,6 +168,8 @@ mod tests {
                 object: None,
                 tool_calls: vec![],
                 thinking: None,
+                usage: None,
+                payload_size: None,
             })
         }
 
@@ -239,6 +241,7 @@ mod tests {
                         name: "mock_tool".to_string(),
                         description: "A mock tool".to_string(),
                         parameters: serde_json::json!({}),
+                        returns: None,
                     },
                 }
             }
@@ -277,6 +280,7 @@ mod tests {
                         name: "mock_tool".to_string(),
                         description: "A mock tool".to_string(),
                         parameters: serde_json::json!({}),
+                        returns: None,
                     },
                 }
             }
@@ -47,6 +47,35 @@ pub trait BaseAsyncAgent: Send + Sync {
     ///
     /// A vector of new events to be dispatched, or an error if processing failed.
     async fn receive_event_async(&self, event: Box<dyn Event>) -> Result<Vec<Box<dyn Event>>>;
+
+    /// Whether the dispatcher should deliver events to this agent in batches.
+    ///
+    /// Agents that return `true` receive the events routed to them in each
+    /// dispatch iteration through a single [`handle_batch`](Self::handle_batch)
+    /// call, which suits work a gateway can do in one request, such as
+    /// embedding many documents. The default is `false`: events arrive one at
+    /// a time through [`receive_event_async`](Self::receive_event_async).
+    fn handles_batches(&self) -> bool {
+        false
+    }
+
+    /// Process several events at once and return the resulting events.
+    ///
+    /// Called instead of [`receive_event_async`](Self::receive_event_async)
+    /// when [`handles_batches`](Self::handles_batches) returns `true`. Events
+    /// arrive in the order they were dispatched. The default implementation
+    /// processes them one at a time, stopping at the first error.
+    ///
+    /// # Arguments
+    ///
+    /// * `events` - The events routed to this agent, oldest first
+    async fn handle_batch(&self, events: Vec<Box<dyn Event>>) -> Result<Vec<Box<dyn Event>>> {
+        let mut results = Vec::new();
+        for event in events {
+            results.extend(self.receive_event_async(event).await?);
+        }
+        Ok(results)
+    }
 }
 
 #[cfg(test)]
@@ -140,6 +169,27 @@ mod tests {
         assert_eq!(returned_event.data, "echoed");
     }
 
+    #[tokio::test]
+    async fn test_default_handle_batch_processes_events_one_at_a_time() {
+        let agent = EchoAgent;
+        let events: Vec<Box<dyn Event>> = ["a", "b"]
+            .iter()
+            .map(|id| {
+                Box::new(TestEvent {
+                    source: "Test".to_string(),
+                    correlation_id: Some(id.to_string()),
+                    data: "test".to_string(),
+                }) as Box<dyn Event>
+            })
+            .collect();
+
+        let result = agent.handle_batch(events).await.unwrap();
+
+        assert!(!agent.handles_batches());
+        let ids: Vec<_> = result.iter().map(|e| e.correlation_id()).collect();
+        assert_eq!(ids, vec![Some("a"), Some("b")]);
+    }
+
     #[tokio::test]
     async fn test_agent_preserves_correlation_id() {
         let agent = EchoAgent;
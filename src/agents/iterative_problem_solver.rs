@@ -7,8 +7,35 @@ use crate::error::Result;
 use crate::llm::chat_session::ChatSession;
 use crate::llm::tools::LlmTool;
 use crate::llm::LlmBroker;
+use futures::stream::{Stream, StreamExt};
+use std::pin::Pin;
 use tracing::{info, warn};
 
+/// Asks for the final result once the iterations are over
+const SUMMARY_PROMPT: &str = "Summarize the final result, and only the final result, \
+                              without commenting on the process by which you achieved it.";
+
+/// Progress reported by [`IterativeProblemSolver::solve_stream`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepEvent {
+    /// A fragment of a step's response, as it streams in
+    Content {
+        /// The step the fragment belongs to, counting from 1
+        iteration: usize,
+        /// The text received
+        chunk: String,
+    },
+    /// A step's complete response
+    StepCompleted {
+        /// The step that finished, counting from 1
+        iteration: usize,
+        /// Everything the step's response said
+        content: String,
+    },
+    /// The summary of the final result, always the last event
+    Summary(String),
+}
+
 /// An agent that iteratively attempts to solve a problem using available tools.
 ///
 /// The solver uses a chat-based approach to break down and solve complex problems.
@@ -112,45 +139,107 @@ impl IterativeProblemSolver {
     /// println!("Solution: {}", result);
     /// ```
     pub async fn solve(&mut self, problem: &str) -> Result<String> {
-        let mut iterations_remaining = self.max_iterations;
-
+        let mut iteration = 1;
         loop {
             let result = self.step(problem).await?;
-
-            // Check for explicit failure
-            if result.to_lowercase().contains("fail") {
-                info!(user_request = problem, result = result.as_str(), "Task failed");
+            if self.is_finished(problem, &result, iteration) {
                 break;
             }
+            iteration += 1;
+        }
 
-            // Check for successful completion
-            if result.to_lowercase().contains("done") {
-                info!(user_request = problem, result = result.as_str(), "Task completed");
-                break;
-            }
+        // Request final summary, in prose rather than another tool call
+        self.chat.send_no_tools(SUMMARY_PROMPT).await
+    }
 
-            iterations_remaining -= 1;
-            if iterations_remaining == 0 {
-                warn!(
-                    max_iterations = self.max_iterations,
-                    user_request = problem,
-                    result = result.as_str(),
-                    "Max iterations reached"
-                );
-                break;
+    /// Execute the problem-solving process, streaming each step as it happens.
+    ///
+    /// Runs the same loop as [`solve`](Self::solve), but streams each step's
+    /// response with [`ChatSession::send_stream`] so a UI can show progress
+    /// through a long solve. Each step yields [`StepEvent::Content`] fragments
+    /// followed by a [`StepEvent::StepCompleted`]; the stream ends with the
+    /// [`StepEvent::Summary`], or with the first error.
+    ///
+    /// # Arguments
+    ///
+    /// * `problem` - The problem or request to be solved
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use futures::stream::StreamExt;
+    /// use mojentic::agents::StepEvent;
+    ///
+    /// let mut events = solver.solve_stream("Calculate the date 7 days from now");
+    /// while let Some(event) = events.next().await {
+    ///     match event? {
+    ///         StepEvent::Content { chunk, .. } => print!("{}", chunk),
+    ///         StepEvent::StepCompleted { iteration, .. } => println!("\n-- step {} done", iteration),
+    ///         StepEvent::Summary(summary) => println!("Solution: {}", summary),
+    ///     }
+    /// }
+    /// ```
+    pub fn solve_stream<'a>(
+        &'a mut self,
+        problem: &'a str,
+    ) -> Pin<Box<dyn Stream<Item = Result<StepEvent>> + 'a>> {
+        Box::pin(async_stream::stream! {
+            let mut iteration = 1;
+            loop {
+                let mut result = String::new();
+                let mut chunks = self.chat.send_stream(&step_prompt(problem));
+                while let Some(chunk) = chunks.next().await {
+                    match chunk {
+                        Ok(chunk) => {
+                            result.push_str(&chunk);
+                            yield Ok(StepEvent::Content { iteration, chunk });
+                        }
+                        Err(err) => {
+                            yield Err(err);
+                            return;
+                        }
+                    }
+                }
+                drop(chunks);
+
+                yield Ok(StepEvent::StepCompleted { iteration, content: result.clone() });
+                if self.is_finished(problem, &result, iteration) {
+                    break;
+                }
+                iteration += 1;
             }
+
+            yield self.chat.send_no_tools(SUMMARY_PROMPT).await.map(StepEvent::Summary);
+        })
+    }
+
+    /// Whether the loop ends after step `iteration` responded with `result`
+    ///
+    /// The loop ends when the step reports failure ("FAIL") or success
+    /// ("DONE"), or when it was the last iteration allowed.
+    fn is_finished(&self, problem: &str, result: &str, iteration: usize) -> bool {
+        // Check for explicit failure
+        if result.to_lowercase().contains("fail") {
+            info!(user_request = problem, result, "Task failed");
+            return true;
         }
 
-        // Request final summary
-        let summary = self
-            .chat
-            .send(
-                "Summarize the final result, and only the final result, \
-                 without commenting on the process by which you achieved it.",
-            )
-            .await?;
+        // Check for successful completion
+        if result.to_lowercase().contains("done") {
+            info!(user_request = problem, result, "Task completed");
+            return true;
+        }
 
-        Ok(summary)
+        if iteration >= self.max_iterations {
+            warn!(
+                max_iterations = self.max_iterations,
+                user_request = problem,
+                result,
+                "Max iterations reached"
+            );
+            return true;
+        }
+        false
     }
 
     /// Execute a single problem-solving step.
@@ -166,22 +255,25 @@ impl IterativeProblemSolver {
     ///
     /// The response from the chat session, indicating the step's outcome
     async fn step(&mut self, problem: &str) -> Result<String> {
-        let prompt = format!(
-            "Given the user request:\n\
-             {}\n\
-             \n\
-             Use the tools at your disposal to act on their request. \
-             You may wish to create a step-by-step plan for more complicated requests.\n\
-             \n\
-             If you cannot provide an answer, say only \"FAIL\".\n\
-             If you have the answer, say only \"DONE\".",
-            problem
-        );
-
-        self.chat.send(&prompt).await
+        self.chat.send(&step_prompt(problem)).await
     }
 }
 
+/// The prompt sent for each step of solving `problem`
+fn step_prompt(problem: &str) -> String {
+    format!(
+        "Given the user request:\n\
+         {}\n\
+         \n\
+         Use the tools at your disposal to act on their request. \
+         You may wish to create a step-by-step plan for more complicated requests.\n\
+         \n\
+         If you cannot provide an answer, say only \"FAIL\".\n\
+         If you have the answer, say only \"DONE\".",
+        problem
+    )
+}
+
 /// Builder for constructing an `IterativeProblemSolver` with custom configuration.
 pub struct IterativeProblemSolverBuilder {
     broker: LlmBroker,
@@ -267,6 +359,18 @@ mod tests {
                 call_count: Arc::new(Mutex::new(0)),
             }
         }
+
+        fn next_response(&self) -> String {
+            let mut count = self.call_count.lock().unwrap();
+            let idx = *count;
+            *count += 1;
+
+            if idx < self.responses.len() {
+                self.responses[idx].clone()
+            } else {
+                "default response".to_string()
+            }
+        }
     }
 
     #[async_trait::async_trait]
@@ -278,21 +382,13 @@ mod tests {
             _tools: Option<&[Box<dyn LlmTool>]>,
             _config: &CompletionConfig,
         ) -> Result<LlmGatewayResponse> {
-            let mut count = self.call_count.lock().unwrap();
-            let idx = *count;
-            *count += 1;
-
-            let content = if idx < self.responses.len() {
-                self.responses[idx].clone()
-            } else {
-                "default response".to_string()
-            };
-
             Ok(LlmGatewayResponse {
-                content: Some(content),
+                content: Some(self.next_response()),
                 object: None,
                 tool_calls: vec![],
                 thinking: None,
+                usage: None,
+                payload_size: None,
             })
         }
 
@@ -325,7 +421,13 @@ mod tests {
             _tools: Option<&'a [Box<dyn LlmTool>]>,
             _config: &'a CompletionConfig,
         ) -> Pin<Box<dyn Stream<Item = Result<StreamChunk>> + Send + 'a>> {
-            Box::pin(stream::iter(vec![Ok(StreamChunk::Content("test".to_string()))]))
+            // Streams the next response one word at a time
+            let chunks: Vec<Result<StreamChunk>> = self
+                .next_response()
+                .split_inclusive(' ')
+                .map(|word| Ok(StreamChunk::Content(word.to_string())))
+                .collect();
+            Box::pin(stream::iter(chunks))
         }
     }
 
@@ -353,6 +455,7 @@ mod tests {
                     name: self.name.clone(),
                     description: "A mock tool".to_string(),
                     parameters: json!({}),
+                    returns: None,
                 },
             }
         }
@@ -538,4 +641,46 @@ mod tests {
 
         assert_eq!(result, "Failed to complete");
     }
+
+    #[tokio::test]
+    async fn test_solve_stream_yields_steps_then_summary() {
+        let gateway = Arc::new(MockGateway::new(vec![
+            "Checking the calendar".to_string(),
+            "DONE".to_string(),
+            "Next Friday is the 24th".to_string(),
+        ]));
+        let broker = LlmBroker::new("test-model", gateway, None);
+        let mut solver = IterativeProblemSolver::new(broker);
+
+        let events: Vec<StepEvent> = solver
+            .solve_stream("When is next Friday?")
+            .map(|event| event.unwrap())
+            .collect()
+            .await;
+
+        let content = |iteration, chunk: &str| StepEvent::Content {
+            iteration,
+            chunk: chunk.to_string(),
+        };
+        assert_eq!(
+            events,
+            vec![
+                content(1, "Checking "),
+                content(1, "the "),
+                content(1, "calendar"),
+                StepEvent::StepCompleted {
+                    iteration: 1,
+                    content: "Checking the calendar".to_string(),
+                },
+                content(2, "DONE"),
+                StepEvent::StepCompleted {
+                    iteration: 2,
+                    content: "DONE".to_string(),
+                },
+                StepEvent::Summary("Next Friday is the 24th".to_string()),
+            ]
+        );
+        // The streamed steps are recorded like those of `solve`
+        assert_eq!(solver.chat.messages().len(), 7);
+    }
 }
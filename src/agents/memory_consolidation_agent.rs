@@ -0,0 +1,312 @@
+//! Long-term memory for long-running chat sessions.
+//!
+//! A [`ChatSession`] drops its oldest turns once the context window fills, and
+//! whatever they said is lost. [`MemoryConsolidationAgent`] folds those turns
+//! into a compact summary stored in [`SharedWorkingMemory`] before they go, so
+//! an assistant that runs for hours keeps the gist of earlier conversation.
+
+use crate::context::SharedWorkingMemory;
+use crate::error::Result;
+use crate::llm::chat_session::ChatSession;
+use crate::llm::models::{LlmMessage, MessageRole};
+use crate::llm::LlmBroker;
+use serde_json::{json, Value};
+use tracing::info;
+
+const DEFAULT_SUMMARY_PROMPT: &str = "You consolidate conversation history into long-term memory. \
+Summarize the conversation below in a few sentences, keeping facts about the user, decisions made, \
+and open questions. Leave out pleasantries. Reply with the summary only.";
+
+/// Summarizes the older part of a chat session into shared working memory.
+///
+/// Each consolidation removes all but the most recent messages from the
+/// session (the system prompt is always kept), asks the LLM to summarize them,
+/// and appends an entry to the array stored under the memory key:
+///
+/// ```json
+/// {"memories": [{"summary": "...", "message_count": 12, "consolidated_at": "2026-10-18T09:30:00Z"}]}
+/// ```
+///
+/// Call [`MemoryConsolidationAgent::consolidate_if_needed`] after each turn to
+/// consolidate once the session reaches a token threshold, or
+/// [`MemoryConsolidationAgent::consolidate`] from your own schedule.
+///
+/// # Examples
+///
+/// ```ignore
+/// use mojentic::agents::MemoryConsolidationAgent;
+/// use mojentic::context::SharedWorkingMemory;
+///
+/// let memory = SharedWorkingMemory::default();
+/// let consolidator = MemoryConsolidationAgent::builder(summary_broker, memory.clone())
+///     .token_threshold(8000)
+///     .keep_recent(4)
+///     .build();
+///
+/// loop {
+///     let answer = session.send(&next_question()).await?;
+///     consolidator.consolidate_if_needed(&mut session).await?;
+/// }
+/// ```
+pub struct MemoryConsolidationAgent {
+    broker: LlmBroker,
+    memory: SharedWorkingMemory,
+    token_threshold: usize,
+    keep_recent: usize,
+    memory_key: String,
+    summary_prompt: String,
+}
+
+impl MemoryConsolidationAgent {
+    /// Create a consolidation agent with default settings.
+    ///
+    /// # Arguments
+    ///
+    /// * `broker` - The LLM broker used to write summaries
+    /// * `memory` - The working memory summaries are stored in
+    pub fn new(broker: LlmBroker, memory: SharedWorkingMemory) -> Self {
+        Self::builder(broker, memory).build()
+    }
+
+    /// Create a consolidation agent builder for custom configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `broker` - The LLM broker used to write summaries
+    /// * `memory` - The working memory summaries are stored in
+    pub fn builder(
+        broker: LlmBroker,
+        memory: SharedWorkingMemory,
+    ) -> MemoryConsolidationAgentBuilder {
+        MemoryConsolidationAgentBuilder::new(broker, memory)
+    }
+
+    /// Consolidate the session if it has reached the token threshold.
+    ///
+    /// # Returns
+    ///
+    /// The new summary, or `None` if the session is under the threshold or
+    /// had nothing old enough to consolidate
+    pub async fn consolidate_if_needed(&self, session: &mut ChatSession) -> Result<Option<String>> {
+        if session.total_tokens() < self.token_threshold {
+            return Ok(None);
+        }
+        self.consolidate(session).await
+    }
+
+    /// Summarize and remove the session's older messages.
+    ///
+    /// The session is only trimmed once the summary has been generated, so a
+    /// failed LLM call leaves it unchanged.
+    ///
+    /// # Returns
+    ///
+    /// The new summary, or `None` if no more than `keep_recent` messages
+    /// follow the system prompt
+    pub async fn consolidate(&self, session: &mut ChatSession) -> Result<Option<String>> {
+        let older: Vec<LlmMessage> = session
+            .oldest_turns(self.keep_recent)
+            .iter()
+            .map(|m| m.message.clone())
+            .collect();
+        if older.is_empty() {
+            return Ok(None);
+        }
+
+        let messages = vec![
+            LlmMessage::system(&self.summary_prompt),
+            LlmMessage::user(transcript(&older)),
+        ];
+        let summary = self.broker.generate(&messages, None, None, None).await?;
+        let summary = summary.trim().to_string();
+
+        let removed = session.remove_oldest_turns(self.keep_recent);
+        self.store(&summary, removed.len());
+        info!(
+            messages = removed.len(),
+            remaining_tokens = session.total_tokens(),
+            "Consolidated session history into memory"
+        );
+        Ok(Some(summary))
+    }
+
+    /// Append a memory entry under the memory key
+    fn store(&self, summary: &str, message_count: usize) {
+        let mut entries = match self.memory.get_working_memory().get(&self.memory_key) {
+            Some(Value::Array(entries)) => entries.clone(),
+            _ => Vec::new(),
+        };
+        entries.push(json!({
+            "summary": summary,
+            "message_count": message_count,
+            "consolidated_at": chrono::Utc::now().to_rfc3339(),
+        }));
+        self.memory.merge_to_working_memory(json!({ self.memory_key.clone(): entries }));
+    }
+}
+
+/// Render messages as a plain-text transcript for the summarizer
+fn transcript(messages: &[LlmMessage]) -> String {
+    messages
+        .iter()
+        .filter_map(|message| {
+            let role = match message.role {
+                MessageRole::System => "System",
+                MessageRole::User => "User",
+                MessageRole::Assistant => "Assistant",
+                MessageRole::Tool => "Tool result",
+            };
+            let content = message.content.as_deref().unwrap_or_default().trim();
+            let calls: Vec<&str> =
+                message.tool_calls.iter().flatten().map(|call| call.name.as_str()).collect();
+            if !content.is_empty() {
+                Some(format!("{}: {}", role, content))
+            } else if !calls.is_empty() {
+                Some(format!("{}: (called {})", role, calls.join(", ")))
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Builder for constructing a `MemoryConsolidationAgent` with custom configuration.
+pub struct MemoryConsolidationAgentBuilder {
+    broker: LlmBroker,
+    memory: SharedWorkingMemory,
+    token_threshold: usize,
+    keep_recent: usize,
+    memory_key: String,
+    summary_prompt: String,
+}
+
+impl MemoryConsolidationAgentBuilder {
+    /// Create a new builder
+    fn new(broker: LlmBroker, memory: SharedWorkingMemory) -> Self {
+        Self {
+            broker,
+            memory,
+            token_threshold: 24576,
+            keep_recent: 6,
+            memory_key: "memories".to_string(),
+            summary_prompt: DEFAULT_SUMMARY_PROMPT.to_string(),
+        }
+    }
+
+    /// Set the session size in tokens that triggers consolidation (default: 24576)
+    pub fn token_threshold(mut self, token_threshold: usize) -> Self {
+        self.token_threshold = token_threshold;
+        self
+    }
+
+    /// Set how many recent messages stay in the session (default: 6)
+    pub fn keep_recent(mut self, keep_recent: usize) -> Self {
+        self.keep_recent = keep_recent;
+        self
+    }
+
+    /// Set the working memory key entries are appended under (default: "memories")
+    pub fn memory_key(mut self, key: impl Into<String>) -> Self {
+        self.memory_key = key.into();
+        self
+    }
+
+    /// Set the system prompt used to write summaries
+    pub fn summary_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.summary_prompt = prompt.into();
+        self
+    }
+
+    /// Build the consolidation agent
+    pub fn build(self) -> MemoryConsolidationAgent {
+        MemoryConsolidationAgent {
+            broker: self.broker,
+            memory: self.memory,
+            token_threshold: self.token_threshold,
+            keep_recent: self.keep_recent,
+            memory_key: self.memory_key,
+            summary_prompt: self.summary_prompt,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::test_support::{text_response, MockGateway};
+    use std::sync::Arc;
+
+    // Answers every request with the same text, recording the prompts it saw
+    fn echo_gateway(reply: &'static str) -> Arc<MockGateway> {
+        Arc::new(MockGateway::new(vec![]).with_responder(move |_| Ok(text_response(reply))))
+    }
+
+    fn session_with_history(turns: usize) -> ChatSession {
+        let gateway = echo_gateway("Noted.");
+        let mut session = ChatSession::new(LlmBroker::new("chat-model", gateway, None));
+        for i in 0..turns {
+            session.insert_message(LlmMessage::user(format!("My favourite number is {}", i)));
+            session.insert_message(LlmMessage::assistant("Noted."));
+        }
+        session
+    }
+
+    #[tokio::test]
+    async fn test_consolidates_after_token_threshold() {
+        let gateway = echo_gateway("User listed favourite numbers 0 to 3.");
+        let memory = SharedWorkingMemory::default();
+        let agent = MemoryConsolidationAgent::builder(
+            LlmBroker::new("summarizer", gateway.clone(), None),
+            memory.clone(),
+        )
+        .token_threshold(40)
+        .keep_recent(2)
+        .build();
+        let mut session = session_with_history(2);
+
+        assert_eq!(agent.consolidate_if_needed(&mut session).await.unwrap(), None);
+        assert!(gateway.requests().is_empty());
+
+        session.insert_message(LlmMessage::user("My favourite number is 2"));
+        session.insert_message(LlmMessage::assistant("Noted."));
+        session.insert_message(LlmMessage::user("My favourite number is 3"));
+        session.insert_message(LlmMessage::assistant("Noted."));
+        let summary = agent.consolidate_if_needed(&mut session).await.unwrap();
+
+        assert_eq!(summary.as_deref(), Some("User listed favourite numbers 0 to 3."));
+        assert_eq!(session.messages().len(), 3);
+        assert_eq!(session.messages()[0].role(), MessageRole::System);
+        assert_eq!(session.messages()[1].content(), Some("My favourite number is 3"));
+
+        let transcript = gateway.requests()[0].messages[1].content.clone().unwrap();
+        assert!(transcript.starts_with("User: My favourite number is 0\nAssistant: Noted."));
+        assert!(transcript.contains("My favourite number is 2"));
+
+        let entries = memory.get_working_memory()["memories"].clone();
+        assert_eq!(entries.as_array().unwrap().len(), 1);
+        assert_eq!(entries[0]["summary"], "User listed favourite numbers 0 to 3.");
+        assert_eq!(entries[0]["message_count"], 6);
+    }
+
+    #[tokio::test]
+    async fn test_consolidation_appends_entries() {
+        let gateway = echo_gateway("Summary.");
+        let memory = SharedWorkingMemory::default();
+        let agent = MemoryConsolidationAgent::builder(
+            LlmBroker::new("summarizer", gateway, None),
+            memory.clone(),
+        )
+        .keep_recent(0)
+        .memory_key("long_term")
+        .build();
+
+        let mut session = session_with_history(1);
+        agent.consolidate(&mut session).await.unwrap();
+        session.insert_message(LlmMessage::user("Another fact"));
+        agent.consolidate(&mut session).await.unwrap();
+
+        assert_eq!(memory.get_working_memory()["long_term"].as_array().unwrap().len(), 2);
+        assert_eq!(agent.consolidate(&mut session).await.unwrap(), None);
+    }
+}
@@ -13,6 +13,7 @@
 //! - [`AsyncLlmAgent`] - LLM-powered async agent
 //! - [`AsyncAggregatorAgent`] - Aggregates events from multiple sources
 //! - [`IterativeProblemSolver`] - Iterative approach to problem solving
+//! - [`MemoryConsolidationAgent`] - Summarizes older chat history into working memory
 //! - [`SimpleRecursiveAgent`] - Basic recursive event processing
 
 pub mod async_aggregator_agent;
@@ -20,11 +21,13 @@ pub mod async_llm_agent;
 pub mod base_agent;
 pub mod base_async_agent;
 pub mod iterative_problem_solver;
+pub mod memory_consolidation_agent;
 pub mod simple_recursive_agent;
 
 pub use async_aggregator_agent::AsyncAggregatorAgent;
 pub use async_llm_agent::AsyncLlmAgent;
 pub use base_agent::BaseAgent;
 pub use base_async_agent::BaseAsyncAgent;
-pub use iterative_problem_solver::IterativeProblemSolver;
+pub use iterative_problem_solver::{IterativeProblemSolver, StepEvent};
+pub use memory_consolidation_agent::MemoryConsolidationAgent;
 pub use simple_recursive_agent::SimpleRecursiveAgent;
@@ -14,6 +14,12 @@
 //!
 //! # Events
 //!
+//! Solver events describe this agent's own solve lifecycle and are only
+//! delivered through its [`EventEmitter`]. They are deliberately not
+//! [`crate::event::Event`]s: they carry no source or correlation ID and are never
+//! routed between agents. For events exchanged between agents, use
+//! [`crate::event::EventBus`] or the async dispatcher.
+//!
 //! The agent emits the following events during problem-solving:
 //!
 //! - `GoalSubmittedEvent` - When a problem is submitted
@@ -62,6 +68,7 @@
 //! - "DONE" - Task completed successfully
 //! - "FAIL" - Task cannot be completed
 
+use crate::clock::{Clock, SystemClock};
 use crate::error::{MojenticError, Result};
 use crate::llm::chat_session::ChatSession;
 use crate::llm::tools::LlmTool;
@@ -69,8 +76,8 @@ use crate::llm::LlmBroker;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
-use tokio::sync::{mpsc, Mutex};
-use tokio::time::{timeout, Duration};
+use tokio::sync::{broadcast, mpsc, Mutex};
+use tokio::time::Duration;
 use tracing::warn;
 
 /// Represents the state of a problem-solving process.
@@ -192,29 +199,73 @@ impl AnySolverEvent {
     }
 }
 
-/// Event handler callback type
-type EventCallback = Arc<dyn Fn(AnySolverEvent) + Send + Sync>;
+/// Event handler callback type; synchronous callbacks are wrapped as ready futures
+type EventCallback =
+    Arc<dyn Fn(AnySolverEvent) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>;
+
+/// Default number of events a broadcast receiver can fall behind before lagging
+const DEFAULT_BROADCAST_CAPACITY: usize = 1024;
 
 /// A simple event emitter that allows subscribing to and emitting events.
 ///
 /// This implementation uses async channels to dispatch events to subscribers
 /// asynchronously without blocking the emitter. Handler panics or errors are
 /// routed back to any registered error sink so that callers can observe them.
+///
+/// Callback subscribers cost one spawned task per event. For high-frequency
+/// events, prefer [`EventEmitter::subscribe_channel`], which shares a single
+/// broadcast channel between all receivers and spawns nothing.
 pub struct EventEmitter {
     subscribers: Arc<Mutex<Vec<EventCallback>>>,
     /// Optional channel to forward handler errors (e.g. panics) to the solve future.
     error_tx: Arc<Mutex<Option<mpsc::Sender<MojenticError>>>>,
+    broadcast_tx: broadcast::Sender<AnySolverEvent>,
 }
 
 impl EventEmitter {
     /// Create a new event emitter
     pub fn new() -> Self {
+        Self::with_broadcast_capacity(DEFAULT_BROADCAST_CAPACITY)
+    }
+
+    /// Create a new event emitter whose channel subscribers can lag by up to `capacity` events
+    ///
+    /// See [`EventEmitter::subscribe_channel`] for what happens when a receiver
+    /// falls further behind.
+    pub fn with_broadcast_capacity(capacity: usize) -> Self {
         Self {
             subscribers: Arc::new(Mutex::new(Vec::new())),
             error_tx: Arc::new(Mutex::new(None)),
+            broadcast_tx: broadcast::channel(capacity).0,
         }
     }
 
+    /// Subscribe to events through a shared broadcast channel.
+    ///
+    /// Every emitted event is sent once into a bounded ring buffer that all
+    /// channel receivers read from, so emitting never spawns tasks or waits on
+    /// slow receivers. The receiver only sees events emitted after subscribing.
+    ///
+    /// The buffer holds the most recent events, as many as the emitter's
+    /// broadcast capacity (1024 by default). A receiver that falls further
+    /// behind doesn't block the emitter; instead its next `recv` returns
+    /// [`broadcast::error::RecvError::Lagged`] with the number of skipped events
+    /// and then resumes from the oldest event still buffered.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let mut events = emitter.subscribe_channel();
+    /// tokio::spawn(async move {
+    ///     while let Ok(event) = events.recv().await {
+    ///         println!("Iteration {}", event.state().iteration);
+    ///     }
+    /// });
+    /// ```
+    pub fn subscribe_channel(&self) -> broadcast::Receiver<AnySolverEvent> {
+        self.broadcast_tx.subscribe()
+    }
+
     /// Register a channel to receive handler errors.
     ///
     /// When a subscriber callback panics, the panic message is forwarded as a
@@ -238,7 +289,31 @@ impl EventEmitter {
         F: Fn(AnySolverEvent) + Send + Sync + 'static,
     {
         let mut subscribers = self.subscribers.lock().await;
-        subscribers.push(Arc::new(callback));
+        subscribers.push(Arc::new(move |event| {
+            callback(event);
+            Box::pin(async {})
+        }));
+    }
+
+    /// Subscribe to events with an async handler.
+    ///
+    /// The handler's future runs to completion for each event; use
+    /// [`EventEmitter::emit_and_wait`] to wait for it.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// emitter.subscribe_async(|event: AnySolverEvent| async move {
+    ///     store.record(event).await;
+    /// });
+    /// ```
+    pub async fn subscribe_async<F, Fut>(&self, handler: F)
+    where
+        F: Fn(AnySolverEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let mut subscribers = self.subscribers.lock().await;
+        subscribers.push(Arc::new(move |event| Box::pin(handler(event))));
     }
 
     /// Emit an event to all subscribers asynchronously.
@@ -247,6 +322,9 @@ impl EventEmitter {
     /// If a subscriber panics, the panic is caught and forwarded as a
     /// [`MojenticError::HandlerError`] to the registered error sink (if any).
     pub async fn emit(&self, event: AnySolverEvent) {
+        // Sending only fails when there are no channel subscribers
+        let _ = self.broadcast_tx.send(event.clone());
+
         let subscribers = self.subscribers.lock().await.clone();
         let error_tx = self.error_tx.lock().await.clone();
 
@@ -257,7 +335,7 @@ impl EventEmitter {
 
             // Spawn a task to call the callback asynchronously and catch panics.
             let handle = tokio::spawn(async move {
-                callback(event);
+                callback(event).await;
             });
 
             // If we have an error sink, watch for panics from the spawned task.
@@ -265,11 +343,7 @@ impl EventEmitter {
                 tokio::spawn(async move {
                     if let Err(join_err) = handle.await {
                         if join_err.is_panic() {
-                            let msg = join_err
-                                .into_panic()
-                                .downcast::<&str>()
-                                .map(|s| s.to_string())
-                                .unwrap_or_else(|_| "handler panicked".to_string());
+                            let msg = panic_message(join_err);
                             let _ = tx.send(MojenticError::HandlerError(msg)).await;
                         }
                     }
@@ -277,6 +351,47 @@ impl EventEmitter {
             }
         }
     }
+
+    /// Emit an event and wait for every subscriber to finish handling it.
+    ///
+    /// Handlers still run concurrently, but this only returns once all of them
+    /// have completed, so callers can observe their effects without sleeping.
+    /// If any handler panics, the remaining handlers still complete and the
+    /// first panic is returned as a [`MojenticError::HandlerError`].
+    pub async fn emit_and_wait(&self, event: AnySolverEvent) -> Result<()> {
+        let _ = self.broadcast_tx.send(event.clone());
+
+        let subscribers = self.subscribers.lock().await.clone();
+
+        let handles: Vec<_> = subscribers
+            .into_iter()
+            .map(|callback| {
+                let event = event.clone();
+                tokio::spawn(async move { callback(event).await })
+            })
+            .collect();
+
+        let mut first_error = None;
+        for handle in handles {
+            if let Err(join_err) = handle.await {
+                if join_err.is_panic() && first_error.is_none() {
+                    first_error = Some(MojenticError::HandlerError(panic_message(join_err)));
+                }
+            }
+        }
+
+        first_error.map_or(Ok(()), Err)
+    }
+}
+
+/// Extract a readable message from a panicked handler task
+fn panic_message(join_err: tokio::task::JoinError) -> String {
+    let payload = join_err.into_panic();
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "handler panicked".to_string())
 }
 
 impl Default for EventEmitter {
@@ -297,6 +412,7 @@ pub struct SimpleRecursiveAgent {
     system_prompt: String,
     /// The event emitter used to manage events
     pub emitter: Arc<EventEmitter>,
+    clock: Arc<dyn Clock>,
 }
 
 impl SimpleRecursiveAgent {
@@ -409,21 +525,15 @@ impl SimpleRecursiveAgent {
         });
 
         // Wait for solution, handler error, or timeout (300 seconds)
-        match timeout(Duration::from_secs(300), async {
-            tokio::select! {
-                solution = solution_rx.recv() => {
-                    Ok(solution)
-                }
-                Some(err) = error_rx.recv() => {
-                    Err(err)
-                }
-            }
-        })
-        .await
-        {
-            Ok(Ok(Some(solution))) => Ok(solution),
-            Ok(Err(handler_err)) => Err(handler_err),
-            Ok(Ok(None)) | Err(_) => {
+        let outcome = tokio::select! {
+            solution = solution_rx.recv() => Ok(solution),
+            Some(err) = error_rx.recv() => Err(err),
+            _ = self.clock.sleep(Duration::from_secs(300)) => Ok(None),
+        };
+        match outcome {
+            Ok(Some(solution)) => Ok(solution),
+            Err(handler_err) => Err(handler_err),
+            Ok(None) => {
                 let timeout_message =
                     "Timeout: Could not solve the problem within 300 seconds.".to_string();
                 let mut timeout_state = GoalState::new(problem, self.max_iterations);
@@ -573,6 +683,7 @@ impl SimpleRecursiveAgent {
             max_iterations: self.max_iterations,
             system_prompt: self.system_prompt.clone(),
             emitter: self.emitter.clone(),
+            clock: self.clock.clone(),
         }
     }
 }
@@ -583,6 +694,7 @@ pub struct SimpleRecursiveAgentBuilder {
     tools: Vec<Box<dyn LlmTool>>,
     max_iterations: usize,
     system_prompt: Option<String>,
+    clock: Arc<dyn Clock>,
 }
 
 impl SimpleRecursiveAgentBuilder {
@@ -593,6 +705,7 @@ impl SimpleRecursiveAgentBuilder {
             tools: Vec::new(),
             max_iterations: 5,
             system_prompt: None,
+            clock: Arc::new(SystemClock),
         }
     }
 
@@ -614,6 +727,12 @@ impl SimpleRecursiveAgentBuilder {
         self
     }
 
+    /// Set the clock that times out [`SimpleRecursiveAgent::solve`] (default: [`SystemClock`])
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
     /// Build the agent
     pub fn build(self) -> SimpleRecursiveAgent {
         let system_prompt = self.system_prompt.unwrap_or_else(|| {
@@ -629,6 +748,7 @@ impl SimpleRecursiveAgentBuilder {
             max_iterations: self.max_iterations,
             system_prompt,
             emitter: Arc::new(EventEmitter::new()),
+            clock: self.clock,
         }
     }
 }
@@ -683,6 +803,8 @@ mod tests {
                 object: None,
                 tool_calls: vec![],
                 thinking: None,
+                usage: None,
+                payload_size: None,
             })
         }
 
@@ -743,6 +865,7 @@ mod tests {
                     name: self.name.clone(),
                     description: "A mock tool".to_string(),
                     parameters: json!({}),
+                    returns: None,
                 },
             }
         }
@@ -787,6 +910,109 @@ mod tests {
         assert!(*received.lock().await);
     }
 
+    #[tokio::test]
+    async fn test_emit_and_wait_awaits_async_handlers() {
+        let emitter = EventEmitter::new();
+        let handled = Arc::new(Mutex::new(Vec::new()));
+
+        for name in ["first", "second"] {
+            let handled = handled.clone();
+            emitter
+                .subscribe_async(move |event: AnySolverEvent| {
+                    let handled = handled.clone();
+                    async move {
+                        tokio::task::yield_now().await;
+                        handled.lock().await.push((name, event.state().goal.clone()));
+                    }
+                })
+                .await;
+        }
+        let sync_handled = Arc::new(std::sync::Mutex::new(0));
+        let sync_handled_clone = sync_handled.clone();
+        emitter
+            .subscribe(move |_event: AnySolverEvent| {
+                *sync_handled_clone.lock().unwrap() += 1;
+            })
+            .await;
+
+        let state = GoalState::new("Test", 5);
+        emitter
+            .emit_and_wait(AnySolverEvent::GoalSubmitted(GoalSubmittedEvent { state }))
+            .await
+            .unwrap();
+
+        let mut handled = handled.lock().await.clone();
+        handled.sort();
+        assert_eq!(
+            handled,
+            vec![
+                ("first", "Test".to_string()),
+                ("second", "Test".to_string())
+            ]
+        );
+        assert_eq!(*sync_handled.lock().unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_emit_and_wait_returns_handler_panic() {
+        let emitter = EventEmitter::new();
+        emitter
+            .subscribe_async(|_event: AnySolverEvent| async move {
+                panic!("async handler failed: {}", 42);
+            })
+            .await;
+
+        let state = GoalState::new("Test", 5);
+        let result = emitter
+            .emit_and_wait(AnySolverEvent::GoalSubmitted(GoalSubmittedEvent { state }))
+            .await;
+
+        match result {
+            Err(MojenticError::HandlerError(msg)) => assert_eq!(msg, "async handler failed: 42"),
+            other => panic!("Expected HandlerError, got: {:?}", other),
+        }
+    }
+
+    fn iteration_event(iteration: usize) -> AnySolverEvent {
+        let mut state = GoalState::new("Test", 1000);
+        state.iteration = iteration;
+        AnySolverEvent::GoalSubmitted(GoalSubmittedEvent { state })
+    }
+
+    #[tokio::test]
+    async fn test_channel_subscribers_keep_up_with_rapid_emits() {
+        let emitter = EventEmitter::new();
+        let receivers: Vec<_> = (0..3).map(|_| emitter.subscribe_channel()).collect();
+
+        for iteration in 0..500 {
+            emitter.emit(iteration_event(iteration)).await;
+        }
+
+        for mut receiver in receivers {
+            for expected in 0..500 {
+                let event = receiver.recv().await.unwrap();
+                assert_eq!(event.state().iteration, expected);
+            }
+            assert!(receiver.try_recv().is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_channel_subscriber_that_falls_behind_reports_lag() {
+        let emitter = EventEmitter::with_broadcast_capacity(4);
+        let mut receiver = emitter.subscribe_channel();
+
+        for iteration in 0..10 {
+            emitter.emit(iteration_event(iteration)).await;
+        }
+
+        match receiver.recv().await {
+            Err(broadcast::error::RecvError::Lagged(skipped)) => assert_eq!(skipped, 6),
+            other => panic!("Expected Lagged, got: {:?}", other.map(|e| e.state().iteration)),
+        }
+        assert_eq!(receiver.recv().await.unwrap().state().iteration, 6);
+    }
+
     #[tokio::test]
     async fn test_builder_default_settings() {
         let gateway = Arc::new(MockGateway::new(vec![]));
@@ -1037,4 +1263,68 @@ mod tests {
             other => panic!("Expected HandlerError, got: {:?}", other),
         }
     }
+
+    #[tokio::test]
+    async fn test_solve_times_out_on_the_agent_clock() {
+        // Never answers, so only the timeout can end the solve
+        struct HangingGateway;
+
+        #[async_trait::async_trait]
+        impl LlmGateway for HangingGateway {
+            async fn complete(
+                &self,
+                _model: &str,
+                _messages: &[LlmMessage],
+                _tools: Option<&[Box<dyn LlmTool>]>,
+                _config: &CompletionConfig,
+            ) -> Result<LlmGatewayResponse> {
+                std::future::pending().await
+            }
+
+            async fn complete_json(
+                &self,
+                _model: &str,
+                _messages: &[LlmMessage],
+                _schema: Value,
+                _config: &CompletionConfig,
+            ) -> Result<Value> {
+                std::future::pending().await
+            }
+
+            async fn get_available_models(&self) -> Result<Vec<String>> {
+                Ok(vec![])
+            }
+
+            async fn calculate_embeddings(
+                &self,
+                _text: &str,
+                _model: Option<&str>,
+            ) -> Result<Vec<f32>> {
+                Ok(vec![])
+            }
+
+            fn complete_stream<'a>(
+                &'a self,
+                _model: &'a str,
+                _messages: &'a [LlmMessage],
+                _tools: Option<&'a [Box<dyn LlmTool>]>,
+                _config: &'a CompletionConfig,
+            ) -> Pin<Box<dyn Stream<Item = Result<StreamChunk>> + Send + 'a>> {
+                Box::pin(stream::pending())
+            }
+        }
+
+        let clock = crate::clock::ManualClock::new();
+        let broker = Arc::new(LlmBroker::new("test-model", Arc::new(HangingGateway), None));
+        let agent = SimpleRecursiveAgent::builder(broker).clock(Arc::new(clock.clone())).build();
+
+        let solving = tokio::spawn(async move { agent.solve("Test problem").await });
+        while clock.pending_sleeps() == 0 {
+            tokio::task::yield_now().await;
+        }
+        clock.advance(Duration::from_secs(300));
+
+        let result = solving.await.unwrap().unwrap();
+        assert_eq!(result, "Timeout: Could not solve the problem within 300 seconds.");
+    }
 }
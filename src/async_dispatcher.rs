@@ -3,6 +3,7 @@
 //! This module provides the `AsyncDispatcher` that manages event processing
 //! in a background task, routing events to registered agents via a router.
 
+use crate::agents::BaseAsyncAgent;
 use crate::event::{Event, TerminateEvent};
 use crate::router::Router;
 use crate::{MojenticError, Result};
@@ -11,15 +12,36 @@ use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::task::JoinHandle;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 use uuid::Uuid;
 
+/// Events collected for an agent that handles batches
+type AgentBatch = (Arc<dyn BaseAsyncAgent>, Vec<Box<dyn Event>>);
+
 /// Asynchronous event dispatcher for agent systems.
 ///
 /// The dispatcher manages a queue of events and routes them to registered
 /// agents via a router. It runs in a background task and can be stopped
 /// gracefully.
 ///
+/// # Termination
+///
+/// Dispatching a [`TerminateEvent`] asks the dispatcher to shut down
+/// gracefully, giving agents a standard way to end a run:
+///
+/// - Events queued ahead of the `TerminateEvent` are processed as usual, since
+///   the queue is first-in, first-out.
+/// - The `TerminateEvent` is then delivered to every agent routed for it, so
+///   they can release resources. Events those agents return are discarded.
+/// - Events still queued behind it are dropped, and later calls to
+///   [`dispatch`](Self::dispatch) are ignored until the dispatcher is started
+///   again.
+/// - The background task then exits. [`is_terminated`](Self::is_terminated)
+///   reports `true`, and [`stop`](Self::stop) only joins the finished task.
+///
+/// Termination applies to this dispatcher only; agents that are not routed for
+/// `TerminateEvent` never see it.
+///
 /// # Examples
 ///
 /// ```ignore
@@ -38,9 +60,11 @@ pub struct AsyncDispatcher {
     router: Arc<Router>,
     event_queue: Arc<Mutex<VecDeque<Box<dyn Event>>>>,
     stop_flag: Arc<AtomicBool>,
+    /// Set once a `TerminateEvent` has been processed.
+    terminated: Arc<AtomicBool>,
     task_handle: Option<JoinHandle<()>>,
     batch_size: usize,
-    /// Number of agent handler tasks currently executing.
+    /// Number of events taken from the queue whose handling hasn't finished.
     in_flight: Arc<AtomicUsize>,
 }
 
@@ -55,6 +79,7 @@ impl AsyncDispatcher {
             router,
             event_queue: Arc::new(Mutex::new(VecDeque::new())),
             stop_flag: Arc::new(AtomicBool::new(false)),
+            terminated: Arc::new(AtomicBool::new(false)),
             task_handle: None,
             batch_size: 5,
             in_flight: Arc::new(AtomicUsize::new(0)),
@@ -63,6 +88,11 @@ impl AsyncDispatcher {
 
     /// Set the batch size (number of events to process per iteration).
     ///
+    /// This also bounds the batches delivered to agents that opt in through
+    /// [`BaseAsyncAgent::handles_batches`]: each receives the events routed to
+    /// it among the iteration's events in a single
+    /// [`BaseAsyncAgent::handle_batch`] call.
+    ///
     /// # Arguments
     ///
     /// * `size` - The batch size
@@ -81,15 +111,17 @@ impl AsyncDispatcher {
 
         debug!("Starting async dispatcher");
         self.stop_flag.store(false, Ordering::Relaxed);
+        self.terminated.store(false, Ordering::Release);
 
         let router = self.router.clone();
         let queue = self.event_queue.clone();
         let stop_flag = self.stop_flag.clone();
+        let terminated = self.terminated.clone();
         let in_flight = self.in_flight.clone();
         let batch_size = self.batch_size;
 
         let handle = tokio::spawn(async move {
-            Self::dispatch_loop(router, queue, stop_flag, in_flight, batch_size).await;
+            Self::dispatch_loop(router, queue, stop_flag, terminated, in_flight, batch_size).await;
         });
 
         self.task_handle = Some(handle);
@@ -117,11 +149,18 @@ impl AsyncDispatcher {
     /// Dispatch an event to the queue.
     ///
     /// The event will be processed asynchronously by the background task.
+    /// Events dispatched after a [`TerminateEvent`] has been processed are
+    /// dropped.
     ///
     /// # Arguments
     ///
     /// * `event` - The event to dispatch
     pub fn dispatch(&self, mut event: Box<dyn Event>) {
+        if self.is_terminated() {
+            warn!("Dispatcher terminated, dropping event: {:?}", event);
+            return;
+        }
+
         // Assign correlation_id if not set
         if event.correlation_id().is_none() {
             event.set_correlation_id(Uuid::new_v4().to_string());
@@ -169,59 +208,55 @@ impl AsyncDispatcher {
         self.event_queue.lock().unwrap().len()
     }
 
+    /// Whether the dispatcher has shut down in response to a [`TerminateEvent`].
+    pub fn is_terminated(&self) -> bool {
+        self.terminated.load(Ordering::Acquire)
+    }
+
     /// Background dispatch loop.
     async fn dispatch_loop(
         router: Arc<Router>,
         queue: Arc<Mutex<VecDeque<Box<dyn Event>>>>,
         stop_flag: Arc<AtomicBool>,
+        terminated: Arc<AtomicBool>,
         in_flight: Arc<AtomicUsize>,
         batch_size: usize,
     ) {
         while !stop_flag.load(Ordering::Relaxed) {
-            for _ in 0..batch_size {
-                let event = {
-                    let mut q = queue.lock().unwrap();
-                    q.pop_front()
-                };
-
-                if let Some(event) = event {
-                    debug!("Processing event: {:?}", event);
-
-                    // Check for terminate event
-                    if event.as_any().is::<TerminateEvent>() {
-                        info!("Received TerminateEvent, stopping dispatcher");
-                        stop_flag.store(true, Ordering::Relaxed);
-                        break;
-                    }
-
-                    // Get the event type
-                    let type_id = event.as_any().type_id();
-
-                    // Get agents for this event type
-                    let agents = router.get_agents(type_id);
-                    debug!("Found {} agents for event type", agents.len());
-
-                    // Process event through each agent serially (deterministic, no races on SharedWorkingMemory)
-                    for agent in agents {
-                        debug!("Sending event to agent");
-                        in_flight.fetch_add(1, Ordering::AcqRel);
-                        let event_box = event.clone_box();
-
-                        match agent.receive_event_async(event_box).await {
-                            Ok(new_events) => {
-                                debug!("Agent returned {} events", new_events.len());
-                                let mut q = queue.lock().unwrap();
-                                for new_event in new_events {
-                                    q.push_back(new_event);
-                                }
-                            }
-                            Err(e) => {
-                                tracing::error!("Agent error processing event: {}", e);
-                            }
+            // Take up to batch_size events, stopping at a TerminateEvent so the
+            // events ahead of it are handled first
+            let (events, terminate_event) = {
+                let mut q = queue.lock().unwrap();
+                let mut events = Vec::new();
+                let mut terminate_event = None;
+                while events.len() < batch_size {
+                    match q.pop_front() {
+                        Some(event) if event.as_any().is::<TerminateEvent>() => {
+                            terminate_event = Some(event);
+                            break;
                         }
-                        in_flight.fetch_sub(1, Ordering::AcqRel);
+                        Some(event) => events.push(event),
+                        None => break,
                     }
                 }
+                // Count taken events while the queue is locked, so waiters never
+                // see an empty queue with work still pending
+                let taken = events.len() + usize::from(terminate_event.is_some());
+                in_flight.fetch_add(taken, Ordering::AcqRel);
+                (events, terminate_event)
+            };
+
+            let taken = events.len();
+            Self::process_events(&router, events, &queue).await;
+            in_flight.fetch_sub(taken, Ordering::AcqRel);
+
+            if let Some(event) = terminate_event {
+                info!("Received TerminateEvent, stopping dispatcher");
+                let agents = router.get_agents(event.as_any().type_id());
+                Self::terminate(agents, event, &queue, &terminated).await;
+                in_flight.fetch_sub(1, Ordering::AcqRel);
+                stop_flag.store(true, Ordering::Relaxed);
+                break;
             }
 
             tokio::time::sleep(Duration::from_millis(100)).await;
@@ -229,6 +264,89 @@ impl AsyncDispatcher {
 
         debug!("Dispatch loop exiting");
     }
+
+    /// Route one iteration's events to their agents.
+    ///
+    /// Agents process events serially in dispatch order (deterministic, no
+    /// races on SharedWorkingMemory). Agents that handle batches receive all of
+    /// their events from this iteration in one call, after the one-at-a-time
+    /// agents have run.
+    async fn process_events(
+        router: &Router,
+        events: Vec<Box<dyn Event>>,
+        queue: &Mutex<VecDeque<Box<dyn Event>>>,
+    ) {
+        let mut batches: Vec<AgentBatch> = Vec::new();
+
+        for event in events {
+            debug!("Processing event: {:?}", event);
+            let agents = router.get_agents(event.as_any().type_id());
+            debug!("Found {} agents for event type", agents.len());
+
+            for agent in agents {
+                if agent.handles_batches() {
+                    match batches.iter_mut().find(|(batcher, _)| Arc::ptr_eq(batcher, &agent)) {
+                        Some((_, batch)) => batch.push(event.clone_box()),
+                        None => batches.push((agent, vec![event.clone_box()])),
+                    }
+                    continue;
+                }
+
+                debug!("Sending event to agent");
+                let result = agent.receive_event_async(event.clone_box()).await;
+                Self::enqueue_results(result, queue);
+            }
+        }
+
+        for (agent, batch) in batches {
+            debug!("Sending batch of {} events to agent", batch.len());
+            let result = agent.handle_batch(batch).await;
+            Self::enqueue_results(result, queue);
+        }
+    }
+
+    /// Queue the events an agent returned, or log its error.
+    fn enqueue_results(
+        result: Result<Vec<Box<dyn Event>>>,
+        queue: &Mutex<VecDeque<Box<dyn Event>>>,
+    ) {
+        match result {
+            Ok(new_events) => {
+                debug!("Agent returned {} events", new_events.len());
+                queue.lock().unwrap().extend(new_events);
+            }
+            Err(e) => {
+                tracing::error!("Agent error processing event: {}", e);
+            }
+        }
+    }
+
+    /// Deliver a `TerminateEvent` to its agents and drop everything still queued.
+    async fn terminate(
+        agents: Vec<Arc<dyn BaseAsyncAgent>>,
+        event: Box<dyn Event>,
+        queue: &Mutex<VecDeque<Box<dyn Event>>>,
+        terminated: &AtomicBool,
+    ) {
+        // Refuse new events before notifying agents so nothing slips in behind us
+        terminated.store(true, Ordering::Release);
+
+        for agent in agents {
+            if let Err(e) = agent.receive_event_async(event.clone_box()).await {
+                tracing::error!("Agent error processing TerminateEvent: {}", e);
+            }
+        }
+
+        let dropped = {
+            let mut q = queue.lock().unwrap();
+            let dropped = q.len();
+            q.clear();
+            dropped
+        };
+        if dropped > 0 {
+            warn!("Dropped {} queued events after TerminateEvent", dropped);
+        }
+    }
 }
 
 impl Drop for AsyncDispatcher {
@@ -283,6 +401,34 @@ mod tests {
         }
     }
 
+    /// Embeds every document in a batch with one (simulated) gateway call
+    struct BatchEmbeddingAgent {
+        batch_sizes: Arc<Mutex<Vec<usize>>>,
+        embedded: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl BaseAsyncAgent for BatchEmbeddingAgent {
+        async fn receive_event_async(&self, event: Box<dyn Event>) -> Result<Vec<Box<dyn Event>>> {
+            self.handle_batch(vec![event]).await
+        }
+
+        fn handles_batches(&self) -> bool {
+            true
+        }
+
+        async fn handle_batch(&self, events: Vec<Box<dyn Event>>) -> Result<Vec<Box<dyn Event>>> {
+            let texts: Vec<String> = events
+                .iter()
+                .filter_map(|e| e.as_any().downcast_ref::<TestEvent>())
+                .map(|e| e.data.clone())
+                .collect();
+            self.batch_sizes.lock().unwrap().push(texts.len());
+            self.embedded.lock().unwrap().extend(texts);
+            Ok(vec![])
+        }
+    }
+
     #[tokio::test]
     async fn test_dispatcher_new() {
         let router = Arc::new(Router::new());
@@ -298,6 +444,42 @@ mod tests {
         assert_eq!(dispatcher.batch_size, 10);
     }
 
+    #[tokio::test]
+    async fn test_batching_agent_receives_queued_events_in_one_call() {
+        let batch_sizes = Arc::new(Mutex::new(Vec::new()));
+        let embedded = Arc::new(Mutex::new(Vec::new()));
+        let count = Arc::new(Mutex::new(0));
+        let mut router = Router::new();
+        router.add_route::<TestEvent>(Arc::new(BatchEmbeddingAgent {
+            batch_sizes: batch_sizes.clone(),
+            embedded: embedded.clone(),
+        }));
+        router.add_route::<TestEvent>(Arc::new(CountingAgent {
+            count: count.clone(),
+        }));
+
+        let mut dispatcher = AsyncDispatcher::new(Arc::new(router)).with_batch_size(10);
+        for i in 0..4 {
+            dispatcher.dispatch(Box::new(TestEvent {
+                source: "Test".to_string(),
+                correlation_id: None,
+                data: format!("document {}", i),
+            }));
+        }
+
+        dispatcher.start().await.unwrap();
+        dispatcher.wait_for_empty_queue(Some(Duration::from_secs(2))).await.unwrap();
+        dispatcher.stop().await.unwrap();
+
+        assert_eq!(*batch_sizes.lock().unwrap(), vec![4]);
+        assert_eq!(
+            *embedded.lock().unwrap(),
+            vec!["document 0", "document 1", "document 2", "document 3"]
+        );
+        // Agents that don't batch still see each event individually
+        assert_eq!(*count.lock().unwrap(), 4);
+    }
+
     #[tokio::test]
     async fn test_start_and_stop() {
         let router = Arc::new(Router::new());
@@ -508,6 +690,51 @@ mod tests {
         dispatcher.stop().await.unwrap();
     }
 
+    #[tokio::test]
+    async fn test_terminate_event_stops_processing_new_events() {
+        let mut router = Router::new();
+        let test_count = Arc::new(Mutex::new(0));
+        let terminate_count = Arc::new(Mutex::new(0));
+
+        router.add_route::<TestEvent>(Arc::new(CountingAgent {
+            count: test_count.clone(),
+        }));
+        router.add_route::<TerminateEvent>(Arc::new(CountingAgent {
+            count: terminate_count.clone(),
+        }));
+
+        let mut dispatcher = AsyncDispatcher::new(Arc::new(router));
+        let test_event = |id: &str| {
+            Box::new(TestEvent {
+                source: "Test".to_string(),
+                correlation_id: Some(id.to_string()),
+                data: "test".to_string(),
+            }) as Box<dyn Event>
+        };
+
+        // Queue everything up front so ordering is deterministic
+        dispatcher.dispatch(test_event("before"));
+        dispatcher.dispatch(Box::new(TerminateEvent::new("System")));
+        dispatcher.dispatch(test_event("queued-after"));
+        dispatcher.start().await.unwrap();
+
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        while !dispatcher.is_terminated() {
+            assert!(tokio::time::Instant::now() < deadline, "dispatcher never terminated");
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        dispatcher.wait_for_empty_queue(Some(Duration::from_secs(5))).await.unwrap();
+
+        dispatcher.dispatch(test_event("dispatched-after"));
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        assert_eq!(*test_count.lock().unwrap(), 1);
+        assert_eq!(*terminate_count.lock().unwrap(), 1);
+        assert_eq!(dispatcher.queue_len(), 0);
+
+        dispatcher.stop().await.unwrap();
+    }
+
     #[tokio::test]
     async fn test_multiple_agents_receive_event() {
         let mut router = Router::new();
@@ -0,0 +1,140 @@
+//! Time source for durations and timeouts.
+//!
+//! The broker times its requests and agents give up after a deadline. Both ask
+//! a [`Clock`] for the time instead of calling [`std::time::Instant::now`] or
+//! [`tokio::time::sleep`] directly, so tests can substitute a [`ManualClock`]
+//! and advance time by hand rather than waiting for it.
+
+use async_trait::async_trait;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+/// Where time comes from
+#[async_trait]
+pub trait Clock: Send + Sync {
+    /// The current instant
+    fn now(&self) -> Instant;
+
+    /// Wait until `duration` has passed on this clock
+    async fn sleep(&self, duration: Duration);
+}
+
+/// Real time, from the operating system and the tokio timer
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+#[async_trait]
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+}
+
+/// A clock that only moves when [`ManualClock::advance`] is called
+///
+/// Sleeps finish once the clock has been advanced past their deadline, so a
+/// timeout can be driven without any real waiting. Clones share the same time.
+///
+/// # Examples
+///
+/// ```
+/// use mojentic::clock::{Clock, ManualClock};
+/// use std::time::Duration;
+///
+/// let clock = ManualClock::new();
+/// let start = clock.now();
+/// clock.advance(Duration::from_secs(90));
+/// assert_eq!(clock.now() - start, Duration::from_secs(90));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    origin: Instant,
+    elapsed: Arc<watch::Sender<Duration>>,
+    sleeping: Arc<AtomicUsize>,
+}
+
+impl ManualClock {
+    /// Create a clock stopped at the current instant
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+            elapsed: Arc::new(watch::channel(Duration::ZERO).0),
+            sleeping: Arc::default(),
+        }
+    }
+
+    /// Move the clock forward, waking sleeps whose deadline has passed
+    pub fn advance(&self, duration: Duration) {
+        self.elapsed.send_modify(|elapsed| *elapsed += duration);
+    }
+
+    /// Number of sleeps still waiting for the clock to advance
+    ///
+    /// Lets a test wait until the code under test has started its timeout
+    /// before advancing past it.
+    pub fn pending_sleeps(&self) -> usize {
+        self.sleeping.load(Ordering::SeqCst)
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Counts a sleep as pending until it finishes or is dropped
+struct PendingSleep<'a>(&'a AtomicUsize);
+
+impl Drop for PendingSleep<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[async_trait]
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.origin + *self.elapsed.borrow()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        let mut elapsed = self.elapsed.subscribe();
+        let deadline = *elapsed.borrow() + duration;
+        self.sleeping.fetch_add(1, Ordering::SeqCst);
+        let _pending = PendingSleep(&self.sleeping);
+        // The sender lives as long as `self`, so this can't fail
+        let _ = elapsed.wait_for(|elapsed| *elapsed >= deadline).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_manual_sleep_finishes_once_advanced_past_deadline() {
+        let clock = ManualClock::new();
+        let sleeper = {
+            let clock = clock.clone();
+            tokio::spawn(async move { clock.sleep(Duration::from_secs(10)).await })
+        };
+        while clock.pending_sleeps() == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        clock.advance(Duration::from_secs(9));
+        tokio::task::yield_now().await;
+        assert!(!sleeper.is_finished());
+
+        clock.advance(Duration::from_secs(1));
+        sleeper.await.unwrap();
+        assert_eq!(clock.pending_sleeps(), 0);
+    }
+}
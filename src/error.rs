@@ -6,7 +6,12 @@
 
 use thiserror::Error;
 
+/// Errors returned by Mojentic
+///
+/// New variants are added as the library grows, so matches on this enum need a
+/// wildcard arm.
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum MojenticError {
     #[error("LLM gateway error: {0}")]
     GatewayError(String),
@@ -56,12 +61,37 @@ pub enum MojenticError {
     #[error("Runtime error: {0}")]
     RuntimeError(String),
 
+    #[error("Tool not found: {name} (available tools: {})", .available.join(", "))]
+    ToolNotFound {
+        name: String,
+        available: Vec<String>,
+    },
+
+    #[error("Context length exceeded: estimated {estimated} tokens, limit {limit}")]
+    ContextLengthExceeded { estimated: usize, limit: usize },
+
     #[error("Max tool iterations exceeded: limit {limit}")]
     MaxToolIterationsExceeded { limit: usize },
 
+    /// Returned by [`crate::llm::gateways::RateLimitedGateway`] when a request
+    /// would have to wait longer than the configured maximum for capacity.
+    #[error("Rate limited: capacity available in {retry_after:?}")]
+    RateLimited { retry_after: std::time::Duration },
+
+    /// The provider rejected the request's credentials (HTTP 401 or 403)
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    /// The provider could not be reached: connection refused, DNS failure, or timeout
+    #[error("Network error: {0}")]
+    NetworkError(String),
+
     #[error("Event handler error: {0}")]
     HandlerError(String),
 
+    #[error("Unsupported operation: {0}")]
+    Unsupported(String),
+
     /// Returned when a tool aborts early because its [`crate::llm::tools::ToolRunCtx`]
     /// cancellation token was signalled (e.g. barge-in, manual `interrupt()`).
     #[error("Cancelled")]
@@ -86,6 +116,18 @@ mod tests {
         assert_eq!(err.to_string(), "API error: rate limit exceeded");
     }
 
+    #[test]
+    fn test_unauthorized_display() {
+        let err = MojenticError::Unauthorized("401 Unauthorized".to_string());
+        assert_eq!(err.to_string(), "Unauthorized: 401 Unauthorized");
+    }
+
+    #[test]
+    fn test_network_error_display() {
+        let err = MojenticError::NetworkError("connection refused".to_string());
+        assert_eq!(err.to_string(), "Network error: connection refused");
+    }
+
     #[test]
     fn test_tool_error_display() {
         let err = MojenticError::ToolError("invalid parameters".to_string());
@@ -150,10 +192,36 @@ mod tests {
 
     #[test]
     fn test_handler_error_display() {
+        let err = MojenticError::ToolNotFound {
+            name: "fly".to_string(),
+            available: vec!["walk".to_string(), "swim".to_string()],
+        };
+        assert_eq!(err.to_string(), "Tool not found: fly (available tools: walk, swim)");
+
+        let err = MojenticError::ContextLengthExceeded {
+            estimated: 9000,
+            limit: 8192,
+        };
+        assert_eq!(err.to_string(), "Context length exceeded: estimated 9000 tokens, limit 8192");
+
         let err = MojenticError::HandlerError("callback panicked".to_string());
         assert_eq!(err.to_string(), "Event handler error: callback panicked");
     }
 
+    #[test]
+    fn test_rate_limited_display() {
+        let err = MojenticError::RateLimited {
+            retry_after: std::time::Duration::from_millis(1500),
+        };
+        assert_eq!(err.to_string(), "Rate limited: capacity available in 1.5s");
+    }
+
+    #[test]
+    fn test_unsupported_display() {
+        let err = MojenticError::Unsupported("embeddings".to_string());
+        assert_eq!(err.to_string(), "Unsupported operation: embeddings");
+    }
+
     #[test]
     fn test_result_type() {
         let ok_result: Result<i32> = Ok(42);
@@ -0,0 +1,258 @@
+//! Typed publish/subscribe for [`Event`]s.
+//!
+//! An [`EventBus`] delivers each published event to the handlers subscribed to
+//! its concrete type, plus any handlers subscribed to every event. Delivery is
+//! synchronous and happens on the publishing task; handlers that need to do
+//! async work should forward events into a channel or spawn a task.
+
+use super::Event;
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+type Handler = Arc<dyn Fn(&dyn Event) + Send + Sync>;
+
+/// Identifies a subscription so it can be removed with [`EventBus::unsubscribe`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+#[derive(Default)]
+struct Subscriptions {
+    next_id: u64,
+    typed: HashMap<TypeId, Vec<(SubscriptionId, Handler)>>,
+    all: Vec<(SubscriptionId, Handler)>,
+}
+
+/// Typed publish/subscribe hub for any [`Event`] type.
+///
+/// # Examples
+///
+/// ```
+/// use mojentic::event::{EventBus, TerminateEvent};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+///
+/// let bus = EventBus::new();
+/// let stops = Arc::new(AtomicUsize::new(0));
+///
+/// let counter = stops.clone();
+/// bus.subscribe(move |event: &TerminateEvent| {
+///     assert_eq!(event.source, "System");
+///     counter.fetch_add(1, Ordering::SeqCst);
+/// });
+///
+/// bus.publish(&TerminateEvent::new("System"));
+/// assert_eq!(stops.load(Ordering::SeqCst), 1);
+/// ```
+#[derive(Default)]
+pub struct EventBus {
+    subscriptions: Mutex<Subscriptions>,
+}
+
+impl EventBus {
+    /// Create an event bus with no subscribers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subscribe to events of type `E`.
+    ///
+    /// The handler receives the concrete event, whether it was published with
+    /// [`EventBus::publish`] or as a trait object with [`EventBus::publish_dyn`].
+    pub fn subscribe<E, F>(&self, handler: F) -> SubscriptionId
+    where
+        E: Event + 'static,
+        F: Fn(&E) + Send + Sync + 'static,
+    {
+        let handler: Handler = Arc::new(move |event: &dyn Event| {
+            if let Some(event) = event.as_any().downcast_ref::<E>() {
+                handler(event);
+            }
+        });
+
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        let id = next_id(&mut subscriptions);
+        subscriptions.typed.entry(TypeId::of::<E>()).or_default().push((id, handler));
+        id
+    }
+
+    /// Subscribe to every event published on the bus, whatever its type
+    pub fn subscribe_all<F>(&self, handler: F) -> SubscriptionId
+    where
+        F: Fn(&dyn Event) + Send + Sync + 'static,
+    {
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        let id = next_id(&mut subscriptions);
+        subscriptions.all.push((id, Arc::new(handler)));
+        id
+    }
+
+    /// Remove a subscription, returning whether it existed
+    pub fn unsubscribe(&self, id: SubscriptionId) -> bool {
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        let before = subscription_count(&subscriptions);
+        subscriptions.all.retain(|(sub_id, _)| *sub_id != id);
+        for handlers in subscriptions.typed.values_mut() {
+            handlers.retain(|(sub_id, _)| *sub_id != id);
+        }
+        subscription_count(&subscriptions) != before
+    }
+
+    /// Publish an event to its typed subscribers and to catch-all subscribers.
+    ///
+    /// Returns the number of handlers the event was delivered to.
+    pub fn publish<E: Event + 'static>(&self, event: &E) -> usize {
+        self.publish_dyn(event)
+    }
+
+    /// Publish an event held as a trait object, such as one returned by an agent.
+    ///
+    /// Typed subscribers are matched on the event's concrete type via
+    /// [`Event::as_any`]. Returns the number of handlers the event was delivered to.
+    pub fn publish_dyn(&self, event: &dyn Event) -> usize {
+        // Snapshot the handlers so they can subscribe or publish re-entrantly
+        let handlers: Vec<Handler> = {
+            let subscriptions = self.subscriptions.lock().unwrap();
+            subscriptions
+                .typed
+                .get(&event.as_any().type_id())
+                .into_iter()
+                .flatten()
+                .chain(subscriptions.all.iter())
+                .map(|(_, handler)| Arc::clone(handler))
+                .collect()
+        };
+
+        for handler in &handlers {
+            handler(event);
+        }
+        handlers.len()
+    }
+}
+
+fn next_id(subscriptions: &mut Subscriptions) -> SubscriptionId {
+    subscriptions.next_id += 1;
+    SubscriptionId(subscriptions.next_id)
+}
+
+fn subscription_count(subscriptions: &Subscriptions) -> usize {
+    subscriptions.all.len() + subscriptions.typed.values().map(Vec::len).sum::<usize>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::TerminateEvent;
+    use std::any::Any;
+
+    #[derive(Debug, Clone)]
+    struct PingEvent {
+        source: String,
+        count: usize,
+    }
+
+    impl Event for PingEvent {
+        fn source(&self) -> &str {
+            &self.source
+        }
+
+        fn correlation_id(&self) -> Option<&str> {
+            None
+        }
+
+        fn set_correlation_id(&mut self, _id: String) {}
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn clone_box(&self) -> Box<dyn Event> {
+            Box::new(self.clone())
+        }
+    }
+
+    fn ping(count: usize) -> PingEvent {
+        PingEvent {
+            source: "Pinger".to_string(),
+            count,
+        }
+    }
+
+    #[test]
+    fn test_typed_subscribers_only_receive_their_type() {
+        let bus = EventBus::new();
+        let pings = Arc::new(Mutex::new(Vec::new()));
+        let terminations = Arc::new(Mutex::new(0));
+
+        let received = pings.clone();
+        bus.subscribe(move |event: &PingEvent| received.lock().unwrap().push(event.count));
+        let received = terminations.clone();
+        bus.subscribe(move |_event: &TerminateEvent| *received.lock().unwrap() += 1);
+
+        assert_eq!(bus.publish(&ping(1)), 1);
+        assert_eq!(bus.publish(&ping(2)), 1);
+        assert_eq!(bus.publish(&TerminateEvent::new("System")), 1);
+
+        assert_eq!(*pings.lock().unwrap(), vec![1, 2]);
+        assert_eq!(*terminations.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_publish_dyn_dispatches_on_concrete_type() {
+        let bus = EventBus::new();
+        let pings = Arc::new(Mutex::new(Vec::new()));
+        let received = pings.clone();
+        bus.subscribe(move |event: &PingEvent| received.lock().unwrap().push(event.count));
+
+        let events: Vec<Box<dyn Event>> =
+            vec![Box::new(ping(7)), Box::new(TerminateEvent::new("System"))];
+        let delivered: usize = events.iter().map(|event| bus.publish_dyn(event.as_ref())).sum();
+
+        assert_eq!(delivered, 1);
+        assert_eq!(*pings.lock().unwrap(), vec![7]);
+    }
+
+    #[test]
+    fn test_subscribe_all_receives_every_event() {
+        let bus = EventBus::new();
+        let sources = Arc::new(Mutex::new(Vec::new()));
+        let received = sources.clone();
+        bus.subscribe_all(move |event| received.lock().unwrap().push(event.source().to_string()));
+
+        bus.publish(&ping(1));
+        bus.publish(&TerminateEvent::new("System"));
+
+        assert_eq!(*sources.lock().unwrap(), vec!["Pinger", "System"]);
+    }
+
+    #[test]
+    fn test_unsubscribe_stops_delivery() {
+        let bus = EventBus::new();
+        let id = bus.subscribe(|_event: &PingEvent| panic!("should not be called"));
+
+        assert!(bus.unsubscribe(id));
+        assert!(!bus.unsubscribe(id));
+        assert_eq!(bus.publish(&ping(1)), 0);
+    }
+
+    #[test]
+    fn test_handlers_can_publish_reentrantly() {
+        let bus = Arc::new(EventBus::new());
+        let terminations = Arc::new(Mutex::new(0));
+
+        let inner_bus = Arc::downgrade(&bus);
+        bus.subscribe(move |event: &PingEvent| {
+            if event.count == 0 {
+                if let Some(bus) = inner_bus.upgrade() {
+                    bus.publish(&TerminateEvent::new("Pinger"));
+                }
+            }
+        });
+        let received = terminations.clone();
+        bus.subscribe(move |_event: &TerminateEvent| *received.lock().unwrap() += 1);
+
+        bus.publish(&ping(0));
+
+        assert_eq!(*terminations.lock().unwrap(), 1);
+    }
+}
@@ -3,6 +3,15 @@
 //! This module provides the core event types that agents use to communicate.
 //! Events are the fundamental unit of information exchange in the agent system.
 //!
+//! Any [`Event`] can be routed between agents by the
+//! [`AsyncDispatcher`](crate::async_dispatcher::AsyncDispatcher), or delivered in-process
+//! to typed subscribers through an [`EventBus`]. Build on these for new
+//! event-driven code. The [`SimpleRecursiveAgent`](crate::agents::SimpleRecursiveAgent)'s
+//! `EventEmitter` is not a second general-purpose event system: it only
+//! reports that one agent's solve lifecycle (a closed set of `AnySolverEvent`
+//! variants carrying its goal state), and it exists so callers can observe a
+//! solve in progress without wiring up a bus.
+//!
 //! # Examples
 //!
 //! ```
@@ -40,6 +49,10 @@
 //! }
 //! ```
 
+mod event_bus;
+
+pub use event_bus::{EventBus, SubscriptionId};
+
 use serde::{Deserialize, Serialize};
 use std::any::Any;
 
@@ -65,7 +78,11 @@ pub trait Event: Send + Sync + std::fmt::Debug {
     fn clone_box(&self) -> Box<dyn Event>;
 }
 
-/// A special event type that signals the dispatcher to terminate
+/// A special event type that signals the dispatcher to terminate.
+///
+/// When an [`AsyncDispatcher`](crate::async_dispatcher::AsyncDispatcher) processes
+/// this event it delivers it to the agents routed for it, drops any events still
+/// queued, and shuts down. See the dispatcher's termination docs for details.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TerminateEvent {
     pub source: String,
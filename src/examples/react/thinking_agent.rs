@@ -77,6 +77,7 @@ impl BaseAsyncAgent for ThinkingAgent {
                     content: Some(prompt),
                     tool_calls: None,
                     image_paths: None,
+                    cache: false,
                 }],
                 None,
                 thinking_event.correlation_id.clone(),
@@ -1,5 +1,6 @@
 pub mod agents;
 pub mod async_dispatcher;
+pub mod clock;
 pub mod context;
 pub mod error;
 pub mod event;
@@ -21,7 +22,7 @@ pub mod prelude {
     pub use crate::async_dispatcher::AsyncDispatcher;
     pub use crate::context::SharedWorkingMemory;
     pub use crate::error::{MojenticError, Result};
-    pub use crate::event::{Event, TerminateEvent};
+    pub use crate::event::{Event, EventBus, TerminateEvent};
     pub use crate::llm::gateways::OllamaGateway;
     pub use crate::llm::tools::{FunctionDescriptor, LlmTool, ToolDescriptor};
     pub use crate::llm::{CompletionConfig, LlmBroker, LlmGateway, LlmMessage, MessageRole};
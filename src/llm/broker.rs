@@ -1,15 +1,119 @@
+use crate::clock::{Clock, SystemClock};
 use crate::error::{MojenticError, Result};
-use crate::llm::gateway::{CompletionConfig, LlmGateway, StreamChunk};
-use crate::llm::models::{LlmGatewayResponse, LlmMessage, MessageRole};
-use crate::llm::tools::{LlmTool, SerialToolRunner, ToolCallExecution, ToolRunCtx, ToolRunner};
-use crate::tracer::TracerSystem;
+use crate::llm::embedding_gateway::EmbeddingGateway;
+use crate::llm::gateway::{
+    CompletionConfig, LlmGateway, ModelDescription, ResponseFormat, StreamChunk, ToolResultFormat,
+    DEFAULT_NUM_CTX,
+};
+use crate::llm::gateways::{OpenAIModelRegistry, TokenizerGateway};
+use crate::llm::models::{
+    ImageInput, LlmGatewayResponse, LlmMessage, LlmToolCall, MessageRole, TokenUsage,
+};
+use crate::llm::partial_json::{
+    parse_partial_json, ObjectStream, ObjectStreamItem, ValidatedObjectStream, ValidatedStreamItem,
+};
+use crate::llm::reasoning::ResponsePostProcessor;
+use crate::llm::retry::{is_transient, retry_delay, BudgetRelease, RetryBudget};
+use crate::llm::schema_transform::SchemaTransformer;
+use crate::llm::schema_validation::{validate_against_schema, validate_partial_against_schema};
+use crate::llm::tools::{
+    LlmTool, SerialToolRunner, ToolCallExecution, ToolCallOutcome, ToolProgressSender,
+    ToolProvider, ToolResultCache, ToolRunCtx, ToolRunner,
+};
+use crate::tracer::{ToolCallStatus, TracerSystem, TracerVerbosity};
 use futures::stream::{Stream, StreamExt};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::sync::{OnceCell, Semaphore};
 use tracing::{info, warn};
 use uuid::Uuid;
 
+/// Outcome of [`LlmBroker::generate_full`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenerationResult {
+    /// The final text response
+    pub content: String,
+    /// Token usage summed over every gateway call in the tool loop, when reported
+    pub usage: Option<TokenUsage>,
+    /// Names of the tools that ran, in the order each was first called
+    pub tools_used: Vec<String>,
+    /// How many times each tool in `tools_used` ran
+    pub tool_call_counts: HashMap<String, usize>,
+}
+
+impl GenerationResult {
+    /// A result whose tool loop ran the named tools, one entry per call
+    fn new(content: String, usage: Option<TokenUsage>, tool_calls: &[String]) -> Self {
+        let mut tools_used: Vec<String> = Vec::new();
+        let mut tool_call_counts: HashMap<String, usize> = HashMap::new();
+        for name in tool_calls {
+            let count = tool_call_counts.entry(name.clone()).or_default();
+            if *count == 0 {
+                tools_used.push(name.clone());
+            }
+            *count += 1;
+        }
+        Self {
+            content,
+            usage,
+            tools_used,
+            tool_call_counts,
+        }
+    }
+}
+
+/// How the broker handles a model calling a tool that isn't registered
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ToolNotFoundPolicy {
+    /// Feed a tool result back to the model naming the available tools, so it can correct itself
+    #[default]
+    Feedback,
+    /// Fail the generation with [`MojenticError::ToolNotFound`]
+    Error,
+}
+
+/// How the broker narrows the tools offered to the model on each request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolSelection {
+    /// Offer the `k` tools whose descriptions are most similar to the latest user message
+    TopK { k: usize },
+}
+
+/// Pre-flight check that a prompt fits the model's context window
+#[derive(Clone)]
+struct ContextGuard {
+    registry: Arc<OpenAIModelRegistry>,
+    /// Context limit of each model checked so far, `None` where unknown
+    limits: Arc<Mutex<HashMap<String, Option<usize>>>>,
+}
+
+impl ContextGuard {
+    fn limit(&self, model: &str) -> Option<usize> {
+        let mut limits = self.limits.lock().unwrap_or_else(PoisonError::into_inner);
+        *limits.entry(model.to_string()).or_insert_with(|| {
+            let limit = self.registry.get_model_capabilities(model).max_context_tokens;
+            if limit.is_none() {
+                warn!(model = %model, "No context limit known, skipping context guard");
+            }
+            limit.map(|limit| limit as usize)
+        })
+    }
+}
+
+/// A request with the broker's defaults applied, ready to send
+struct PreparedRequest<'m> {
+    messages: Cow<'m, [LlmMessage]>,
+    config: CompletionConfig,
+    correlation_id: String,
+    /// Releases a generated correlation ID's retry budget once the request is done
+    _release: Option<BudgetRelease>,
+}
+
 /// Main interface for LLM interactions
 #[derive(Clone)]
 pub struct LlmBroker {
@@ -17,8 +121,35 @@ pub struct LlmBroker {
     gateway: Arc<dyn LlmGateway>,
     tracer: Option<Arc<TracerSystem>>,
     tool_runner: Arc<dyn ToolRunner>,
+    embedding_gateway: Option<Arc<dyn EmbeddingGateway>>,
+    correlation_id: Option<String>,
+    span_id: String,
+    tool_not_found: ToolNotFoundPolicy,
+    context_guard: Option<ContextGuard>,
+    max_response_chars: Option<usize>,
+    stop_phrases: Vec<String>,
+    schema_transformer: Option<SchemaTransformer>,
+    post_processor: Option<ResponsePostProcessor>,
+    tool_permits: Arc<Semaphore>,
+    dedupe_tool_calls: bool,
+    auto_context: Option<Arc<OnceCell<Option<usize>>>>,
+    tool_result_cache: Option<ToolResultCache>,
+    system_prompt: Option<String>,
+    trim_on_context_overflow: bool,
+    response_language: Option<String>,
+    max_retries: u32,
+    retry_budget: Option<RetryBudget>,
+    clock: Arc<dyn Clock>,
+    tool_selection: Option<ToolSelection>,
+    tool_embeddings: Arc<Mutex<HashMap<String, Vec<f32>>>>,
 }
 
+/// Tool calls a broker runs at once unless configured otherwise
+pub const DEFAULT_MAX_CONCURRENT_TOOLS: usize = 8;
+
+/// Appended to responses cut short by [`LlmBroker::with_max_response_chars`]
+pub const RESPONSE_TRUNCATION_MARKER: &str = "…[truncated]";
+
 impl LlmBroker {
     /// Create a new LLM broker
     ///
@@ -40,6 +171,27 @@ impl LlmBroker {
             gateway,
             tracer,
             tool_runner: Arc::new(SerialToolRunner),
+            embedding_gateway: None,
+            correlation_id: None,
+            span_id: Uuid::new_v4().to_string(),
+            tool_not_found: ToolNotFoundPolicy::default(),
+            context_guard: None,
+            max_response_chars: None,
+            stop_phrases: Vec::new(),
+            schema_transformer: None,
+            post_processor: None,
+            tool_permits: Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT_TOOLS)),
+            dedupe_tool_calls: false,
+            auto_context: None,
+            tool_result_cache: None,
+            system_prompt: None,
+            trim_on_context_overflow: false,
+            response_language: None,
+            max_retries: 0,
+            retry_budget: None,
+            clock: Arc::new(SystemClock),
+            tool_selection: None,
+            tool_embeddings: Arc::default(),
         }
     }
 
@@ -54,1096 +206,4581 @@ impl LlmBroker {
         tool_runner: Arc<dyn ToolRunner>,
     ) -> Self {
         Self {
-            model: model.into(),
-            gateway,
-            tracer,
             tool_runner,
+            ..Self::new(model, gateway, tracer)
         }
     }
 
-    /// Generate text response from LLM
-    ///
-    /// # Arguments
+    /// Use a dedicated gateway for embeddings.
     ///
-    /// * `messages` - The messages to send to the LLM
-    /// * `tools` - Optional tools available to the LLM
-    /// * `config` - Optional completion configuration
-    /// * `correlation_id` - Optional correlation ID for tracing (generates UUID if None)
-    pub async fn generate(
-        &self,
-        messages: &[LlmMessage],
-        tools: Option<&[Box<dyn LlmTool>]>,
-        config: Option<CompletionConfig>,
-        correlation_id: Option<String>,
-    ) -> Result<String> {
-        let config = config.unwrap_or_default();
-        let current_messages = messages.to_vec();
-        let correlation_id = correlation_id.unwrap_or_else(|| Uuid::new_v4().to_string());
+    /// This allows pairing a chat provider with an embeddings provider from a
+    /// different vendor (e.g. Anthropic chat with OpenAI embeddings). Without one,
+    /// [`LlmBroker::calculate_embeddings`] falls back to the chat gateway.
+    pub fn with_embedding_gateway(mut self, embedding_gateway: Arc<dyn EmbeddingGateway>) -> Self {
+        self.embedding_gateway = Some(embedding_gateway);
+        self
+    }
 
-        // Record LLM call
-        if let Some(tracer) = &self.tracer {
-            let messages_json: Vec<std::collections::HashMap<String, serde_json::Value>> =
-                current_messages
-                    .iter()
-                    .map(|m| {
-                        let mut map = std::collections::HashMap::new();
-                        map.insert("role".to_string(), serde_json::json!(format!("{:?}", m.role)));
-                        if let Some(content) = &m.content {
-                            map.insert("content".to_string(), serde_json::json!(content));
-                        }
-                        map
-                    })
-                    .collect();
+    /// Choose how calls to unregistered tools are handled.
+    ///
+    /// By default ([`ToolNotFoundPolicy::Feedback`]) the model receives a tool
+    /// result listing the available tools and gets a chance to retry.
+    pub fn with_tool_not_found_policy(mut self, policy: ToolNotFoundPolicy) -> Self {
+        self.tool_not_found = policy;
+        self
+    }
 
-            let tools_json = tools.map(|t| {
-                t.iter()
-                    .map(|tool| {
-                        let desc = tool.descriptor();
-                        let mut map = std::collections::HashMap::new();
-                        map.insert("name".to_string(), serde_json::json!(desc.function.name));
-                        map.insert(
-                            "description".to_string(),
-                            serde_json::json!(desc.function.description),
-                        );
-                        map
-                    })
-                    .collect()
-            });
+    /// Drop repeated tool calls from a response before running them.
+    ///
+    /// Some models emit the same call twice in one turn, which would run a
+    /// side-effecting tool twice. When enabled, a call with the same name and
+    /// arguments as an earlier call in the same response is removed, both from
+    /// execution and from the recorded assistant message, and a warning is
+    /// logged. Calls repeated across separate turns still run. Off by default.
+    pub fn with_tool_call_deduplication(mut self, enabled: bool) -> Self {
+        self.dedupe_tool_calls = enabled;
+        self
+    }
 
-            tracer.record_llm_call(
-                &self.model,
-                messages_json,
-                config.temperature as f64,
-                tools_json,
-                "LlmBroker",
-                &correlation_id,
-            );
-        }
+    /// Tag every event this broker traces, such as with a tenant or experiment.
+    ///
+    /// LLM call, response and tool events are recorded through
+    /// [`TracerSystem::tagged`], so they can be found with
+    /// [`TracerSystem::get_events_by_tag`]. Brokers are cheap to clone, so
+    /// tags for a single request can go on a clone:
+    ///
+    /// ```ignore
+    /// let tags = HashMap::from([("tenant".to_string(), "acme".to_string())]);
+    /// let reply = broker.clone().with_trace_tags(tags).generate(&messages, None, None, None).await?;
+    /// ```
+    ///
+    /// Has no effect without a tracer.
+    pub fn with_trace_tags(mut self, tags: HashMap<String, String>) -> Self {
+        self.tracer = self.tracer.map(|tracer| Arc::new(tracer.tagged(tags)));
+        self
+    }
 
-        // Measure call duration
-        let start = std::time::Instant::now();
+    /// Send a system prompt with every request that doesn't have its own.
+    ///
+    /// When the messages passed to [`LlmBroker::generate`], its variants, the
+    /// structured-output methods, or the streaming methods contain no system
+    /// message, `prompt` is sent as one in front of them. Messages that
+    /// already have a system message are sent unchanged.
+    pub fn with_system_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.system_prompt = Some(prompt.into());
+        self
+    }
 
-        // Make initial LLM call
-        let response =
-            self.gateway.complete(&self.model, &current_messages, tools, &config).await?;
+    /// Ask for responses in the given language, e.g. `"French"` or `"fr-CA"`.
+    ///
+    /// A short instruction naming the language is appended to the system
+    /// message of every request (one is added if there is none), so callers
+    /// don't have to write it into their prompts. A
+    /// [`CompletionConfig::response_language`] set on a call takes precedence.
+    pub fn with_response_language(mut self, language: impl Into<String>) -> Self {
+        self.response_language = Some(language.into());
+        self
+    }
 
-        let call_duration_ms = start.elapsed().as_secs_f64() * 1000.0;
+    /// Offer the model only the tools relevant to the request.
+    ///
+    /// With [`ToolSelection::TopK`], the latest user message and each tool's
+    /// name and description are embedded with [`LlmBroker::calculate_embeddings`],
+    /// and only the `k` tools most similar to the message are sent (and can be
+    /// run) for that generation. Tool embeddings are computed once and reused,
+    /// including by clones of this broker. When there are no more than `k`
+    /// tools, no user message, or embedding fails, every tool is offered.
+    /// Applies to [`LlmBroker::generate`], [`LlmBroker::generate_full`],
+    /// [`LlmBroker::generate_once`] and [`LlmBroker::generate_many`].
+    pub fn with_tool_selection(mut self, selection: ToolSelection) -> Self {
+        self.tool_selection = Some(selection);
+        self
+    }
 
-        // Record LLM response
-        if let Some(tracer) = &self.tracer {
-            let tool_calls_json = if !response.tool_calls.is_empty() {
-                Some(
-                    response
-                        .tool_calls
-                        .iter()
-                        .map(|tc| {
-                            let mut map = std::collections::HashMap::new();
-                            map.insert("name".to_string(), serde_json::json!(&tc.name));
-                            if let Some(id) = &tc.id {
-                                map.insert("id".to_string(), serde_json::json!(id));
-                            }
-                            map
-                        })
-                        .collect(),
-                )
-            } else {
-                None
-            };
+    /// Reuse the results of [cacheable](LlmTool::cacheable) tools.
+    ///
+    /// A call to a cacheable tool with the same name and arguments as an
+    /// earlier successful call is answered from `cache` without running the
+    /// tool again. Tools that aren't cacheable always run. Pass a clone of the
+    /// same cache to share results between brokers; [`ChatSession`](super::ChatSession)
+    /// gives each session its own.
+    pub fn with_tool_result_cache(mut self, cache: ToolResultCache) -> Self {
+        self.tool_result_cache = Some(cache);
+        self
+    }
 
-            tracer.record_llm_response(
-                &self.model,
-                response.content.as_ref().unwrap_or(&String::new()),
-                tool_calls_json,
-                Some(call_duration_ms),
-                "LlmBroker",
-                &correlation_id,
-            );
-        }
+    /// Size `num_ctx` to the model's real context window.
+    ///
+    /// [`CompletionConfig::default`] asks for [`DEFAULT_NUM_CTX`] tokens, which
+    /// can exceed what a small model supports or waste a large one. When
+    /// enabled, a config whose `num_ctx` is still the default gets the context
+    /// length the gateway reports for the model instead (see
+    /// [`LlmGateway::context_length`]); an explicitly chosen `num_ctx` is kept.
+    /// The gateway is asked once and the answer is reused, including by clones
+    /// of this broker. If the gateway doesn't know the limit, the default stays.
+    pub fn with_auto_context_length(mut self) -> Self {
+        self.auto_context = Some(Arc::new(OnceCell::new()));
+        self
+    }
 
-        // Handle tool calls if present
-        if !response.tool_calls.is_empty() {
-            if let Some(tools) = tools {
-                return self
-                    .handle_tool_calls(
-                        current_messages,
-                        response,
-                        tools,
-                        &config,
-                        &correlation_id,
-                        0,
-                    )
-                    .await;
-            }
-        }
+    /// Fail fast when a prompt won't fit the model's context window.
+    ///
+    /// Before each request, the broker estimates the tokens used by the messages
+    /// and tool definitions and returns [`MojenticError::ContextLengthExceeded`]
+    /// instead of sending a request the provider would reject. The limit is the
+    /// `max_context_tokens` in the given registry (usually
+    /// [`crate::llm::gateways::get_model_registry`]) of the model the request
+    /// goes to, including a [`CompletionConfig::model`] override, and tokens are
+    /// counted with that model's tokenizer. Requests to a model whose limit the
+    /// registry doesn't know are sent unchecked.
+    pub fn with_context_length_guard(mut self, registry: &OpenAIModelRegistry) -> Self {
+        self.context_guard = Some(ContextGuard {
+            registry: Arc::new(registry.clone()),
+            limits: Arc::new(Mutex::new(HashMap::new())),
+        });
+        self
+    }
 
-        Ok(response.content.unwrap_or_default())
+    /// Cap the number of characters a response may return.
+    ///
+    /// Some providers overshoot `max_tokens`, and UIs often need a hard limit.
+    /// Text from [`LlmBroker::generate`], [`LlmBroker::generate_full`] and
+    /// [`LlmBroker::generate_many`] longer than `max_chars` is cut at the cap and
+    /// [`RESPONSE_TRUNCATION_MARKER`] appended. Streams from
+    /// [`LlmBroker::generate_stream`] stop forwarding chunks once the cap is
+    /// reached, emit the marker, and drop the upstream stream so the provider
+    /// request is cancelled. The marker is not counted against the cap.
+    pub fn with_max_response_chars(mut self, max_chars: usize) -> Self {
+        self.max_response_chars = Some(max_chars);
+        self
     }
 
-    fn handle_tool_calls<'a>(
-        &'a self,
-        mut messages: Vec<LlmMessage>,
-        response: LlmGatewayResponse,
-        tools: &'a [Box<dyn LlmTool>],
-        config: &'a CompletionConfig,
-        correlation_id: &'a str,
-        iteration: usize,
-    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<String>> + Send + 'a>> {
-        Box::pin(async move {
-            if iteration >= config.max_tool_iterations {
-                return Err(MojenticError::MaxToolIterationsExceeded {
-                    limit: config.max_tool_iterations,
-                });
-            }
+    /// Stop streamed responses when the model starts producing any of these phrases.
+    ///
+    /// Unlike provider stop sequences, the phrases are matched client-side against
+    /// the accumulated output, so a phrase split across chunks is still caught.
+    /// Streams from [`LlmBroker::generate_stream`] end just before the phrase and
+    /// drop the upstream stream so the provider request is cancelled. Text that
+    /// could be the start of a phrase is held back until it can be ruled out.
+    pub fn with_stop_phrases<I, S>(mut self, phrases: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.stop_phrases = phrases
+            .into_iter()
+            .map(Into::into)
+            .filter(|phrase| !phrase.is_empty())
+            .collect();
+        self
+    }
 
-            info!("Tool calls requested: {}", response.tool_calls.len());
+    /// Transform the schema [`LlmBroker::generate_object`] derives before it is sent.
+    ///
+    /// `schemars` emits schemas that some providers reject; the transformer can
+    /// strip unsupported keywords or tighten the schema. Use
+    /// [`crate::llm::schema_transform::openai_strict`] for OpenAI's strict mode.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use mojentic::llm::schema_transform::openai_strict;
+    ///
+    /// let broker = LlmBroker::new("gpt-4o", gateway, None).with_schema_transformer(openai_strict);
+    /// ```
+    pub fn with_schema_transformer(
+        mut self,
+        transformer: impl Fn(serde_json::Value) -> serde_json::Value + Send + Sync + 'static,
+    ) -> Self {
+        self.schema_transformer = Some(Arc::new(transformer));
+        self
+    }
 
-            // Record the assistant turn that asked for tools so subsequent
-            // messages have a coherent history.
-            messages.push(LlmMessage {
-                role: MessageRole::Assistant,
-                content: response.content.clone(),
-                tool_calls: Some(response.tool_calls.clone()),
-                image_paths: None,
-            });
+    /// Recover from prompts that are too long by dropping old history and retrying.
+    ///
+    /// When a request fails with [`MojenticError::ContextLengthExceeded`],
+    /// whether from [`LlmBroker::with_context_length_guard`] or reported by the
+    /// provider, the oldest half of the non-system messages is dropped and the
+    /// request is retried once. An assistant turn is never separated from its
+    /// tool results, and system messages are always kept. Applies to
+    /// [`LlmBroker::generate`] and [`LlmBroker::generate_full`], including the
+    /// requests of their tool loop.
+    pub fn with_context_overflow_trimming(mut self) -> Self {
+        self.trim_on_context_overflow = true;
+        self
+    }
 
-            let outcomes = self
-                .run_tool_batch(&response.tool_calls, tools, correlation_id, "LlmBroker")
-                .await?;
+    /// Retry model requests that fail with a transient error.
+    ///
+    /// A request that fails because the provider was unreachable or rate
+    /// limited (see [`crate::llm::retry::is_transient`]) is sent again, up to
+    /// `max_retries` times, waiting longer before each attempt. Other errors
+    /// are returned immediately. Applies to every request of
+    /// [`LlmBroker::generate`] and [`LlmBroker::generate_full`], including
+    /// those of their tool loop, to [`LlmBroker::generate_many`], and to the
+    /// structured requests of [`LlmBroker::generate_object`] and its variants;
+    /// streams are not retried.
+    pub fn with_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
 
-            for (call, outcome) in response.tool_calls.iter().zip(outcomes.iter()) {
-                let content = if outcome.ok {
-                    serde_json::to_string(
-                        outcome.result.as_ref().unwrap_or(&serde_json::Value::Null),
-                    )?
-                } else {
-                    serde_json::json!({
-                        "error": outcome.error.clone().unwrap_or_default(),
-                    })
-                    .to_string()
-                };
-                messages.push(LlmMessage {
-                    role: MessageRole::Tool,
-                    content: Some(content),
-                    tool_calls: Some(vec![call.clone()]),
-                    image_paths: None,
-                });
-            }
+    /// Cap the retries of a whole agent run, not just of each request.
+    ///
+    /// Every retry allowed by [`LlmBroker::with_retries`] is also drawn from
+    /// `budget` under the request's correlation ID. Once the run's budget is
+    /// spent, failures are returned without retrying. Pass a clone of the same
+    /// budget to share it between brokers; [`child`](LlmBroker::child) brokers
+    /// share it already. A call without a correlation ID gets a budget of its
+    /// own, which is released when the call returns.
+    pub fn with_retry_budget(mut self, budget: RetryBudget) -> Self {
+        self.retry_budget = Some(budget);
+        self
+    }
 
-            // Record next LLM call
-            if let Some(tracer) = &self.tracer {
-                let messages_json: Vec<std::collections::HashMap<String, serde_json::Value>> =
-                    messages
-                        .iter()
-                        .map(|m| {
-                            let mut map = std::collections::HashMap::new();
-                            map.insert(
-                                "role".to_string(),
-                                serde_json::json!(format!("{:?}", m.role)),
-                            );
-                            if let Some(content) = &m.content {
-                                map.insert("content".to_string(), serde_json::json!(content));
-                            }
-                            map
-                        })
-                        .collect();
+    /// Take time from `clock` instead of the system clock.
+    ///
+    /// Used for the durations recorded by the tracer and the waits between
+    /// [retries](LlmBroker::with_retries). Tests can pass a
+    /// [`ManualClock`](crate::clock::ManualClock) to control both.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
 
-                let tools_json: Vec<std::collections::HashMap<String, serde_json::Value>> = tools
-                    .iter()
-                    .map(|tool| {
-                        let desc = tool.descriptor();
-                        let mut map = std::collections::HashMap::new();
-                        map.insert("name".to_string(), serde_json::json!(desc.function.name));
-                        map.insert(
-                            "description".to_string(),
-                            serde_json::json!(desc.function.description),
-                        );
-                        map
-                    })
-                    .collect();
+    /// Limit how many tool calls this broker runs at the same time.
+    ///
+    /// The limit applies across every batch and every concurrent generate call
+    /// on this broker and the brokers cloned or [`child`](LlmBroker::child)-ed
+    /// from it, so a parallel tool runner can't overwhelm the services tools
+    /// talk to. Calls beyond the limit wait for a running call to finish.
+    /// Defaults to [`DEFAULT_MAX_CONCURRENT_TOOLS`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_concurrent_tools` is zero.
+    pub fn with_max_concurrent_tools(mut self, max_concurrent_tools: usize) -> Self {
+        assert!(max_concurrent_tools >= 1, "max_concurrent_tools must be >= 1");
+        self.tool_permits = Arc::new(Semaphore::new(max_concurrent_tools));
+        self
+    }
 
-                tracer.record_llm_call(
-                    &self.model,
-                    messages_json,
-                    config.temperature as f64,
-                    Some(tools_json),
-                    "LlmBroker",
-                    correlation_id,
-                );
-            }
+    /// Rewrite the final content of every text response before it is returned.
+    ///
+    /// Applies to [`LlmBroker::generate`], [`LlmBroker::generate_full`] and
+    /// [`LlmBroker::generate_many`], before any
+    /// [`LlmBroker::with_max_response_chars`] cap. The tracer records the
+    /// response as the model produced it, so reasoning removed here is still
+    /// available for audit. Streams are passed through unchanged. Use
+    /// [`crate::llm::reasoning::strip_reasoning`] to hide chain-of-thought.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use mojentic::llm::reasoning::strip_reasoning;
+    ///
+    /// let broker = LlmBroker::new("qwen3", gateway, Some(tracer))
+    ///     .with_response_post_processor(strip_reasoning);
+    /// ```
+    pub fn with_response_post_processor(
+        mut self,
+        post_processor: impl Fn(String) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.post_processor = Some(Arc::new(post_processor));
+        self
+    }
 
-            let start = std::time::Instant::now();
-            let next_response =
-                self.gateway.complete(&self.model, &messages, Some(tools), config).await?;
-            let call_duration_ms = start.elapsed().as_secs_f64() * 1000.0;
+    /// Create a child broker bound to a parent's correlation ID.
+    ///
+    /// The child shares this broker's model, gateway, tracer, and tool runner, but
+    /// every generate call that doesn't pass its own correlation ID uses the given
+    /// one. Sub-agents can therefore record their tracer events under the parent's
+    /// correlation ID without threading it through every call. The child gets its
+    /// own span, and its events carry this broker's [`span_id`](Self::span_id) as
+    /// their `parent_span_id`.
+    ///
+    /// # Arguments
+    ///
+    /// * `correlation_id` - The parent correlation ID to tag child events with
+    pub fn child(&self, correlation_id: impl Into<String>) -> LlmBroker {
+        LlmBroker {
+            correlation_id: Some(correlation_id.into()),
+            span_id: Uuid::new_v4().to_string(),
+            tracer: self.tracer.as_ref().map(|tracer| Arc::new(tracer.in_span(&self.span_id))),
+            ..self.clone()
+        }
+    }
 
-            if let Some(tracer) = &self.tracer {
-                let tool_calls_json = if !next_response.tool_calls.is_empty() {
-                    Some(
-                        next_response
-                            .tool_calls
-                            .iter()
-                            .map(|tc| {
-                                let mut map = std::collections::HashMap::new();
-                                map.insert("name".to_string(), serde_json::json!(&tc.name));
-                                if let Some(id) = &tc.id {
-                                    map.insert("id".to_string(), serde_json::json!(id));
-                                }
-                                map
-                            })
-                            .collect(),
-                    )
-                } else {
-                    None
-                };
+    /// ID of this broker's span, which its [child brokers](Self::child) link to
+    ///
+    /// Clones share the span of the broker they were cloned from.
+    pub fn span_id(&self) -> &str {
+        &self.span_id
+    }
 
-                tracer.record_llm_response(
-                    &self.model,
-                    next_response.content.as_ref().unwrap_or(&String::new()),
-                    tool_calls_json,
-                    Some(call_duration_ms),
-                    "LlmBroker",
-                    correlation_id,
-                );
+    /// `messages` led by the default system prompt, unless they have a system message already,
+    /// with the response language instruction added to the system message
+    fn with_default_system<'m>(
+        &self,
+        messages: &'m [LlmMessage],
+        config: &CompletionConfig,
+    ) -> Cow<'m, [LlmMessage]> {
+        let mut messages = match &self.system_prompt {
+            Some(prompt) if !messages.iter().any(|m| m.role == MessageRole::System) => {
+                let mut prefixed = Vec::with_capacity(messages.len() + 1);
+                prefixed.push(LlmMessage::system(prompt.as_str()));
+                prefixed.extend_from_slice(messages);
+                Cow::Owned(prefixed)
             }
+            _ => Cow::Borrowed(messages),
+        };
 
-            if !next_response.tool_calls.is_empty() {
-                return self
-                    .handle_tool_calls(
-                        messages,
-                        next_response,
-                        tools,
-                        config,
-                        correlation_id,
-                        iteration + 1,
-                    )
-                    .await;
+        let language = config.response_language.as_ref().or(self.response_language.as_ref());
+        if let Some(language) = language {
+            let instruction = format!("Respond in {}.", language);
+            let messages = messages.to_mut();
+            match messages.iter_mut().find(|m| m.role == MessageRole::System) {
+                Some(system) => {
+                    system.content = Some(match system.content.take() {
+                        Some(content) if !content.is_empty() => {
+                            format!("{}\n\n{}", content, instruction)
+                        }
+                        _ => instruction,
+                    });
+                }
+                None => messages.insert(0, LlmMessage::system(instruction)),
             }
+        }
+        messages
+    }
 
-            Ok(next_response.content.unwrap_or_default())
-        })
+    /// Resolve the correlation ID for a call: explicit, then the broker default, then a new UUID
+    fn resolve_correlation_id(&self, correlation_id: Option<String>) -> String {
+        correlation_id
+            .or_else(|| self.correlation_id.clone())
+            .unwrap_or_else(|| Uuid::new_v4().to_string())
     }
 
-    /// Dispatch a batch of tool calls through the configured [`ToolRunner`]
-    /// and record per-call + batch tracer events.
-    async fn run_tool_batch(
+    /// Apply the broker's defaults to a request
+    ///
+    /// Every entry point goes through here, so the automatic context length,
+    /// the default system prompt and the response language apply the same way
+    /// to text, structured and streamed generation.
+    async fn prepare<'m>(
         &self,
-        tool_calls: &[crate::llm::models::LlmToolCall],
-        tools: &[Box<dyn LlmTool>],
-        correlation_id: &str,
-        source: &'static str,
-    ) -> Result<Vec<crate::llm::tools::ToolCallOutcome>> {
-        let executions: Vec<ToolCallExecution> = tool_calls
-            .iter()
-            .enumerate()
-            .map(|(idx, tc)| ToolCallExecution {
-                id: tc.id.clone().unwrap_or_else(|| format!("call_{idx}")),
-                name: tc.name.clone(),
-                args: tc.arguments.clone(),
-            })
-            .collect();
+        messages: &'m [LlmMessage],
+        config: Option<CompletionConfig>,
+        correlation_id: Option<String>,
+    ) -> PreparedRequest<'m> {
+        let mut config = config.unwrap_or_default();
+        self.apply_auto_context(&mut config).await;
+        let (correlation_id, release) = self.resolve_retry_scope(correlation_id);
+        PreparedRequest {
+            messages: self.with_default_system(messages, &config),
+            config,
+            correlation_id,
+            _release: release,
+        }
+    }
 
-        let ctx = ToolRunCtx {
-            correlation_id: Some(correlation_id.to_string()),
-            source: Some(source.to_string()),
-            ..Default::default()
+    /// Resolve the correlation ID for a call that may retry
+    ///
+    /// A generated ID belongs to this call alone, so the returned guard drops
+    /// its retry budget entry once the call is over.
+    fn resolve_retry_scope(
+        &self,
+        correlation_id: Option<String>,
+    ) -> (String, Option<BudgetRelease>) {
+        let scoped = correlation_id.is_some() || self.correlation_id.is_some();
+        let correlation_id = self.resolve_correlation_id(correlation_id);
+        let release = match &self.retry_budget {
+            Some(budget) if !scoped => {
+                Some(BudgetRelease::new(budget.clone(), correlation_id.clone()))
+            }
+            _ => None,
         };
+        (correlation_id, release)
+    }
 
-        let batch_start = std::time::Instant::now();
-        let outcomes = self.tool_runner.run_batch(&executions, tools, &ctx).await;
-        let batch_duration_ms = batch_start.elapsed().as_secs_f64() * 1000.0;
+    /// The model a request targets: the config's override, or the broker's model
+    fn model_for<'c>(&'c self, config: &'c CompletionConfig) -> &'c str {
+        config.model.as_deref().unwrap_or(&self.model)
+    }
 
-        if let Some(tracer) = &self.tracer {
-            for (exec, outcome) in executions.iter().zip(outcomes.iter()) {
-                tracer.record_tool_call(
-                    &outcome.name,
-                    exec.args.clone(),
-                    outcome.result.clone().unwrap_or(serde_json::Value::Null),
-                    Some(source.to_string()),
-                    Some(outcome.duration_ms as f64),
-                    source,
-                    correlation_id,
-                );
-            }
-            if outcomes.len() > 1 {
-                let ok = outcomes.iter().filter(|o| o.ok).count();
-                let fail = outcomes.len() - ok;
-                tracer.record_tool_batch(
-                    uuid::Uuid::new_v4().to_string(),
-                    outcomes.iter().map(|o| o.name.clone()).collect(),
-                    ok,
-                    fail,
-                    batch_duration_ms,
-                    Some(source.to_string()),
-                    source,
-                    correlation_id,
-                );
-            }
+    /// Replace a default `num_ctx` with the model's context length, when auto-sizing is enabled
+    ///
+    /// The length is looked up for the broker's model, so requests that
+    /// override the model keep their `num_ctx`.
+    async fn apply_auto_context(&self, config: &mut CompletionConfig) {
+        let Some(cell) = &self.auto_context else {
+            return;
+        };
+        if config.num_ctx != DEFAULT_NUM_CTX || config.model.is_some() {
+            return;
         }
+        let length = cell.get_or_init(|| self.gateway.context_length(&self.model)).await;
+        if let Some(length) = *length {
+            config.num_ctx = length;
+        }
+    }
 
-        // Tool execution errors surface inside outcomes; we only short-circuit
-        // if no outcomes were produced (which the runner shouldn't do, but
-        // belt-and-braces).
-        if outcomes.len() != tool_calls.len() {
-            return Err(MojenticError::ToolError(format!(
-                "tool runner returned {} outcomes for {} calls",
-                outcomes.len(),
-                tool_calls.len()
-            )));
+    /// Check the prompt against the context guard, if one is installed
+    fn check_context_length(
+        &self,
+        messages: &[LlmMessage],
+        tools: Option<&[Box<dyn LlmTool>]>,
+        config: &CompletionConfig,
+    ) -> Result<()> {
+        let model = self.model_for(config);
+        let Some(limit) = self.context_guard.as_ref().and_then(|guard| guard.limit(model)) else {
+            return Ok(());
+        };
+
+        let tokenizer = TokenizerGateway::for_model(model);
+        let estimated =
+            estimate_prompt_tokens(&tokenizer, messages, tools, self.gateway.tool_provider());
+        if estimated > limit {
+            return Err(MojenticError::ContextLengthExceeded { estimated, limit });
         }
+        Ok(())
+    }
 
-        Ok(outcomes)
+    /// The history to retry with after `error`, when trimming is enabled and `error` is an overflow
+    ///
+    /// Returns `error` itself when there is nothing to do.
+    fn trimmed_after_overflow(
+        &self,
+        error: MojenticError,
+        messages: &[LlmMessage],
+    ) -> Result<Vec<LlmMessage>> {
+        if !self.trim_on_context_overflow
+            || !matches!(error, MojenticError::ContextLengthExceeded { .. })
+        {
+            return Err(error);
+        }
+        let Some(trimmed) = trim_oldest_history(messages) else {
+            return Err(error);
+        };
+        warn!("{}; retrying with {} of {} messages", error, trimmed.len(), messages.len());
+        Ok(trimmed)
     }
 
-    /// Generate structured object response from LLM
+    /// Apply the configured post-processor and response cap to a complete response
+    fn finish_response(&self, content: String) -> String {
+        let content = match &self.post_processor {
+            Some(post_process) => post_process(content),
+            None => content,
+        };
+        match self.max_response_chars {
+            Some(max_chars) => truncate_response(content, max_chars),
+            None => content,
+        }
+    }
+
+    /// Calculate embeddings for text
+    ///
+    /// Uses the gateway configured with [`LlmBroker::with_embedding_gateway`] when
+    /// present, otherwise the chat gateway.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to embed
+    /// * `model` - Optional embedding model (the provider's default is used if None)
+    pub async fn calculate_embeddings(&self, text: &str, model: Option<&str>) -> Result<Vec<f32>> {
+        match &self.embedding_gateway {
+            Some(embedding_gateway) => embedding_gateway.calculate_embeddings(text, model).await,
+            None => self.gateway.calculate_embeddings(text, model).await,
+        }
+    }
+
+    /// Generate text response from LLM
     ///
     /// # Arguments
     ///
     /// * `messages` - The messages to send to the LLM
+    /// * `tools` - Optional tools available to the LLM
     /// * `config` - Optional completion configuration
-    /// * `correlation_id` - Optional correlation ID for tracing (generates UUID if None)
-    pub async fn generate_object<T>(
+    /// * `correlation_id` - Optional correlation ID for tracing (broker default or new UUID if None)
+    pub async fn generate(
         &self,
         messages: &[LlmMessage],
+        tools: Option<&[Box<dyn LlmTool>]>,
         config: Option<CompletionConfig>,
         correlation_id: Option<String>,
-    ) -> Result<T>
-    where
-        T: for<'de> Deserialize<'de> + Serialize + schemars::JsonSchema + Send,
-    {
-        let config = config.unwrap_or_default();
-        let correlation_id = correlation_id.unwrap_or_else(|| Uuid::new_v4().to_string());
-
-        // Generate JSON schema for the type
-        let schema = serde_json::to_value(schemars::schema_for!(T))?;
-
-        // Record LLM call
-        if let Some(tracer) = &self.tracer {
-            let messages_json: Vec<std::collections::HashMap<String, serde_json::Value>> = messages
-                .iter()
-                .map(|m| {
-                    let mut map = std::collections::HashMap::new();
-                    map.insert("role".to_string(), serde_json::json!(format!("{:?}", m.role)));
-                    if let Some(content) = &m.content {
-                        map.insert("content".to_string(), serde_json::json!(content));
-                    }
-                    map
-                })
-                .collect();
-
-            tracer.record_llm_call(
-                &self.model,
-                messages_json,
-                config.temperature as f64,
-                None,
-                "LlmBroker::generate_object",
-                &correlation_id,
-            );
-        }
-
-        // Measure call duration
-        let start = std::time::Instant::now();
-
-        // Call the gateway with the schema
-        let json_response =
-            self.gateway.complete_json(&self.model, messages, schema, &config).await?;
-
-        let call_duration_ms = start.elapsed().as_secs_f64() * 1000.0;
-
-        // Deserialize the JSON into the target type
-        let object: T = serde_json::from_value(json_response.clone())?;
-
-        // Record LLM response
-        if let Some(tracer) = &self.tracer {
-            let object_str = serde_json::to_string_pretty(&json_response).unwrap_or_default();
-            tracer.record_llm_response(
-                &self.model,
-                format!("Structured response: {}", object_str),
-                None,
-                Some(call_duration_ms),
-                "LlmBroker::generate_object",
-                &correlation_id,
-            );
-        }
-
-        Ok(object)
+    ) -> Result<String> {
+        self.generate_full(messages, tools, config, correlation_id)
+            .await
+            .map(|result| result.content)
     }
 
-    /// Generate streaming text response from LLM
+    /// Generate text response from LLM along with generation metadata
     ///
-    /// Returns a stream that yields content chunks as they arrive. When tool calls
-    /// are detected, the broker executes them and recursively streams the LLM's
-    /// follow-up response.
+    /// Behaves exactly like [`LlmBroker::generate`], but also returns details
+    /// gathered across the whole tool loop, such as token usage and which
+    /// tools ran.
     ///
     /// # Arguments
     ///
     /// * `messages` - The messages to send to the LLM
     /// * `tools` - Optional tools available to the LLM
     /// * `config` - Optional completion configuration
-    /// * `correlation_id` - Optional correlation ID for tracing (generates UUID if None)
-    ///
-    /// # Example
+    /// * `correlation_id` - Optional correlation ID for tracing (broker default or new UUID if None)
+    pub async fn generate_full(
+        &self,
+        messages: &[LlmMessage],
+        tools: Option<&[Box<dyn LlmTool>]>,
+        config: Option<CompletionConfig>,
+        correlation_id: Option<String>,
+    ) -> Result<GenerationResult> {
+        let result = self.generate_uncapped(messages, tools, config, correlation_id, true).await?;
+        Ok(GenerationResult {
+            content: self.finish_response(result.content),
+            ..result
+        })
+    }
+
+    /// Make a single LLM request without running any tools
     ///
-    /// ```ignore
-    /// use futures::stream::StreamExt;
+    /// Returns the gateway's response as-is, including any tool calls the LLM
+    /// requested, so callers building their own orchestration can decide what
+    /// to run before calling again. [`LlmBroker::generate`] does the same
+    /// request but runs the tools and loops until the LLM answers. The call and
+    /// response are traced, the response post-processor and character cap
+    /// apply to the content, and repeated tool calls are dropped when
+    /// [deduplication](LlmBroker::with_tool_call_deduplication) is enabled.
     ///
-    /// let broker = LlmBroker::new("qwen3:32b", gateway, None);
-    /// let messages = vec![LlmMessage::user("Tell me a story")];
+    /// # Arguments
     ///
-    /// let mut stream = broker.generate_stream(&messages, None, None, None);
-    /// while let Some(result) = stream.next().await {
-    ///     match result {
-    ///         Ok(chunk) => print!("{}", chunk),
-    ///         Err(e) => eprintln!("Error: {}", e),
-    ///     }
-    /// }
-    /// ```
-    pub fn generate_stream<'a>(
-        &'a self,
-        messages: &'a [LlmMessage],
-        tools: Option<&'a [Box<dyn LlmTool>]>,
+    /// * `messages` - The messages to send to the LLM
+    /// * `tools` - Optional tools offered to the LLM
+    /// * `config` - Optional completion configuration
+    /// * `correlation_id` - Optional correlation ID for tracing (broker default or new UUID if None)
+    pub async fn generate_once(
+        &self,
+        messages: &[LlmMessage],
+        tools: Option<&[Box<dyn LlmTool>]>,
         config: Option<CompletionConfig>,
         correlation_id: Option<String>,
-    ) -> Pin<Box<dyn Stream<Item = Result<String>> + 'a>> {
-        let config = config.unwrap_or_default();
-        let correlation_id = correlation_id.unwrap_or_else(|| Uuid::new_v4().to_string());
-        Box::pin(self.generate_stream_with_depth(
-            messages.to_vec(),
-            tools,
-            config,
-            correlation_id,
-            0,
-        ))
+    ) -> Result<LlmGatewayResponse> {
+        let request = self.prepare(messages, config, correlation_id).await;
+        let selected = self.select_tools(&request.messages, tools).await;
+        let tools = selected.as_deref().or(tools);
+
+        let mut response = self
+            .complete_traced(&request.messages, tools, &request.config, &request.correlation_id)
+            .await?;
+        response.content = response.content.map(|content| self.finish_response(content));
+        if self.dedupe_tool_calls {
+            response.tool_calls = without_duplicate_calls(response.tool_calls);
+        }
+        Ok(response)
     }
 
-    fn generate_stream_with_depth<'a>(
-        &'a self,
-        current_messages: Vec<LlmMessage>,
-        tools: Option<&'a [Box<dyn LlmTool>]>,
-        config: CompletionConfig,
-        correlation_id: String,
-        depth: usize,
-    ) -> impl Stream<Item = Result<String>> + 'a {
-        async_stream::stream! {
-            if depth >= config.max_tool_iterations {
-                yield Err(MojenticError::MaxToolIterationsExceeded {
-                    limit: config.max_tool_iterations,
-                });
-                return;
-            }
-
-            // Record LLM call
-            if let Some(tracer) = &self.tracer {
-                let messages_json: Vec<std::collections::HashMap<String, serde_json::Value>> =
-                    current_messages
-                        .iter()
-                        .map(|m| {
-                            let mut map = std::collections::HashMap::new();
-                            map.insert("role".to_string(), serde_json::json!(format!("{:?}", m.role)));
-                            if let Some(content) = &m.content {
-                                map.insert("content".to_string(), serde_json::json!(content));
-                            }
-                            map
-                        })
-                        .collect();
+    /// Run the tool loop without the post-processor or response cap
+    ///
+    /// With `stop_at_terminal`, a terminal tool's result ends the loop as the
+    /// response; otherwise it goes back to the model like any other result.
+    async fn generate_uncapped(
+        &self,
+        messages: &[LlmMessage],
+        tools: Option<&[Box<dyn LlmTool>]>,
+        config: Option<CompletionConfig>,
+        correlation_id: Option<String>,
+        stop_at_terminal: bool,
+    ) -> Result<GenerationResult> {
+        let request = self.prepare(messages, config, correlation_id).await;
+        let (config, correlation_id) = (&request.config, &request.correlation_id);
+        let mut current_messages = request.messages.to_vec();
+        let selected = self.select_tools(&current_messages, tools).await;
+        let tools = selected.as_deref().or(tools);
 
-                let tools_json = tools.map(|t| {
-                    t.iter()
-                        .map(|tool| {
-                            let desc = tool.descriptor();
-                            let mut map = std::collections::HashMap::new();
-                            map.insert("name".to_string(), serde_json::json!(desc.function.name));
-                            map.insert(
-                                "description".to_string(),
-                                serde_json::json!(desc.function.description),
-                            );
-                            map
-                        })
-                        .collect()
-                });
+        let response =
+            match self.complete_traced(&current_messages, tools, config, correlation_id).await {
+                Ok(response) => response,
+                Err(e) => {
+                    current_messages = self.trimmed_after_overflow(e, &current_messages)?;
+                    self.complete_traced(&current_messages, tools, config, correlation_id).await?
+                }
+            };
 
-                tracer.record_llm_call(
-                    &self.model,
-                    messages_json,
-                    config.temperature as f64,
-                    tools_json,
-                    "LlmBroker::generate_stream",
-                    &correlation_id,
-                );
+        // Handle tool calls if present
+        if !response.tool_calls.is_empty() {
+            if let Some(tools) = tools {
+                let usage = response.usage;
+                return self
+                    .handle_tool_calls(
+                        current_messages,
+                        response,
+                        tools,
+                        config,
+                        correlation_id,
+                        0,
+                        usage,
+                        Vec::new(),
+                        stop_at_terminal,
+                    )
+                    .await;
             }
+        }
 
-            let mut accumulated_content = String::new();
-            let mut accumulated_tool_calls = Vec::new();
+        Ok(GenerationResult::new(response.content.unwrap_or_default(), response.usage, &[]))
+    }
 
-            // Measure stream duration
-            let start = std::time::Instant::now();
+    /// The tools chosen by the tool selection policy, or `None` to offer all of `tools`
+    async fn select_tools(
+        &self,
+        messages: &[LlmMessage],
+        tools: Option<&[Box<dyn LlmTool>]>,
+    ) -> Option<Vec<Box<dyn LlmTool>>> {
+        let (Some(ToolSelection::TopK { k }), Some(tools)) = (self.tool_selection, tools) else {
+            return None;
+        };
+        if tools.len() <= k {
+            return None;
+        }
+        let query = messages
+            .iter()
+            .rev()
+            .find(|m| m.role == MessageRole::User)
+            .and_then(|m| m.content.as_deref())?;
+
+        match self.rank_tools(query, tools).await {
+            Ok(ranked) => Some(ranked.into_iter().take(k).map(|i| tools[i].clone_box()).collect()),
+            Err(e) => {
+                warn!("Tool selection failed, offering every tool: {}", e);
+                None
+            }
+        }
+    }
 
-            // Stream from gateway
-            let mut stream = self.gateway.complete_stream(
-                &self.model,
-                &current_messages,
-                tools,
-                &config,
+    /// Indices of `tools`, most similar to `query` first
+    async fn rank_tools(&self, query: &str, tools: &[Box<dyn LlmTool>]) -> Result<Vec<usize>> {
+        let query = self.calculate_embeddings(query, None).await?;
+        let mut scored = Vec::with_capacity(tools.len());
+        for (index, tool) in tools.iter().enumerate() {
+            let descriptor = tool.descriptor();
+            let text = format!("{}: {}", descriptor.function.name, descriptor.function.description);
+            let cached = self
+                .tool_embeddings
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .get(&text)
+                .cloned();
+            let embedding = match cached {
+                Some(embedding) => embedding,
+                None => {
+                    let embedding = self.calculate_embeddings(&text, None).await?;
+                    self.tool_embeddings
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .insert(text, embedding.clone());
+                    embedding
+                }
+            };
+            scored.push((index, cosine_similarity(&query, &embedding)));
+        }
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        Ok(scored.into_iter().map(|(index, _)| index).collect())
+    }
+
+    /// `messages` set up so the response starts with the config's assistant prefill
+    ///
+    /// Gateways that continue assistant turns get the prefill as a trailing
+    /// assistant message; others get an instruction in the last user turn.
+    fn with_prefill<'m>(
+        &self,
+        messages: &'m [LlmMessage],
+        config: &CompletionConfig,
+    ) -> Cow<'m, [LlmMessage]> {
+        let Some(prefill) = &config.assistant_prefill else {
+            return Cow::Borrowed(messages);
+        };
+        let mut messages = messages.to_vec();
+        if self.gateway.supports_assistant_prefill(self.model_for(config)) {
+            messages.push(LlmMessage::assistant(prefill.as_str()));
+            return Cow::Owned(messages);
+        }
+
+        let instruction = format!("Begin your response with exactly: {}", prefill);
+        match messages.last_mut() {
+            Some(last) if last.role == MessageRole::User => {
+                let content = last.content.take().unwrap_or_default();
+                last.content = Some(format!("{}\n\n{}", content, instruction));
+            }
+            _ => messages.push(LlmMessage::user(instruction)),
+        }
+        Cow::Owned(messages)
+    }
+
+    /// Check the context length, then make one traced gateway request
+    async fn complete_traced(
+        &self,
+        messages: &[LlmMessage],
+        tools: Option<&[Box<dyn LlmTool>]>,
+        config: &CompletionConfig,
+        correlation_id: &str,
+    ) -> Result<LlmGatewayResponse> {
+        let prefilled = self.with_prefill(messages, config);
+        let messages = &*prefilled;
+        self.check_context_length(messages, tools, config)?;
+
+        // Record LLM call
+        if let Some(tracer) = &self.tracer {
+            let messages_json = trace_messages(tracer, messages);
+
+            let tools_json = tools.map(|t| {
+                t.iter()
+                    .map(|tool| {
+                        let desc = tool.descriptor();
+                        let mut map = std::collections::HashMap::new();
+                        map.insert("name".to_string(), serde_json::json!(desc.function.name));
+                        map.insert(
+                            "description".to_string(),
+                            serde_json::json!(desc.function.description),
+                        );
+                        map
+                    })
+                    .collect()
+            });
+
+            tracer.record_llm_call(
+                self.model_for(config),
+                messages_json,
+                config.temperature as f64,
+                tools_json,
+                "LlmBroker",
+                correlation_id,
             );
+        }
 
-            while let Some(chunk_result) = stream.next().await {
-                match chunk_result {
-                    Ok(StreamChunk::Content(content)) => {
-                        accumulated_content.push_str(&content);
-                        yield Ok(content);
-                    }
-                    Ok(StreamChunk::ToolCalls(tool_calls)) => {
-                        accumulated_tool_calls = tool_calls;
-                    }
-                    Ok(StreamChunk::Thinking(_))
-                    | Ok(StreamChunk::Progress(_))
-                    | Ok(StreamChunk::Metrics(_)) => {}
-                    Err(e) => {
-                        yield Err(e);
-                        return;
-                    }
-                }
+        // Measure call duration
+        let start = self.clock.now();
+
+        let mut response = self
+            .retrying(correlation_id, || {
+                self.gateway.complete(self.model_for(config), messages, tools, config)
+            })
+            .await?;
+        if let Some(prefill) = &config.assistant_prefill {
+            if response.tool_calls.is_empty() {
+                let content = response.content.take().unwrap_or_default();
+                response.content = Some(prefixed(prefill, is_continued(messages), content));
             }
+        }
 
-            let call_duration_ms = start.elapsed().as_secs_f64() * 1000.0;
+        let call_duration_ms = self.clock.now().duration_since(start).as_secs_f64() * 1000.0;
 
-            // Record LLM response
-            if let Some(tracer) = &self.tracer {
-                let tool_calls_json = if !accumulated_tool_calls.is_empty() {
-                    Some(
-                        accumulated_tool_calls
-                            .iter()
-                            .map(|tc| {
-                                let mut map = std::collections::HashMap::new();
-                                map.insert("name".to_string(), serde_json::json!(&tc.name));
-                                if let Some(id) = &tc.id {
-                                    map.insert("id".to_string(), serde_json::json!(id));
-                                }
-                                map
-                            })
-                            .collect(),
-                    )
-                } else {
-                    None
-                };
+        // Record LLM response
+        if let Some(tracer) = &self.tracer {
+            let tool_calls_json = if !response.tool_calls.is_empty() {
+                Some(
+                    response
+                        .tool_calls
+                        .iter()
+                        .map(|tc| {
+                            let mut map = std::collections::HashMap::new();
+                            map.insert("name".to_string(), serde_json::json!(&tc.name));
+                            if let Some(id) = &tc.id {
+                                map.insert("id".to_string(), serde_json::json!(id));
+                            }
+                            map
+                        })
+                        .collect(),
+                )
+            } else {
+                None
+            };
 
-                tracer.record_llm_response(
-                    &self.model,
-                    &accumulated_content,
-                    tool_calls_json,
-                    Some(call_duration_ms),
-                    "LlmBroker::generate_stream",
-                    &correlation_id,
-                );
+            tracer.record_llm_response_with_usage(
+                self.model_for(config),
+                trace_content(tracer, response.content.as_deref().unwrap_or_default()),
+                tool_calls_json,
+                Some(call_duration_ms),
+                response.payload_size,
+                response.usage,
+                "LlmBroker",
+                correlation_id,
+            );
+        }
+
+        Ok(response)
+    }
+
+    /// Run `request`, again while it fails with a transient error and retries remain
+    async fn retrying<T, F, Fut>(&self, correlation_id: &str, mut request: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            let error = match request().await {
+                Ok(response) => return Ok(response),
+                Err(error) => error,
+            };
+            let can_retry = attempt < self.max_retries
+                && is_transient(&error)
+                && self.retry_budget.as_ref().is_none_or(|budget| budget.try_spend(correlation_id));
+            if !can_retry {
+                return Err(error);
             }
+            attempt += 1;
+            let delay = retry_delay(&error, attempt);
+            warn!(
+                "{}; retrying in {:?} (attempt {} of {})",
+                error, delay, attempt, self.max_retries
+            );
+            self.clock.sleep(delay).await;
+        }
+    }
 
-            // Handle tool calls if present
-            if !accumulated_tool_calls.is_empty() {
-                if let Some(tools) = tools {
-                    info!("Processing {} tool call(s) in stream", accumulated_tool_calls.len());
+    #[allow(clippy::too_many_arguments)]
+    fn handle_tool_calls<'a>(
+        &'a self,
+        mut messages: Vec<LlmMessage>,
+        mut response: LlmGatewayResponse,
+        tools: &'a [Box<dyn LlmTool>],
+        config: &'a CompletionConfig,
+        correlation_id: &'a str,
+        iteration: usize,
+        usage: Option<TokenUsage>,
+        mut tools_run: Vec<String>,
+        stop_at_terminal: bool,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<GenerationResult>> + Send + 'a>>
+    {
+        Box::pin(async move {
+            if iteration >= config.max_tool_iterations {
+                return Err(MojenticError::MaxToolIterationsExceeded {
+                    limit: config.max_tool_iterations,
+                });
+            }
 
-                    // Build new messages with tool results
-                    let mut new_messages = current_messages.clone();
+            if self.dedupe_tool_calls {
+                response.tool_calls = without_duplicate_calls(response.tool_calls);
+            }
 
-                    // Add assistant message with tool calls
-                    new_messages.push(LlmMessage {
-                        role: MessageRole::Assistant,
-                        content: Some(accumulated_content),
-                        tool_calls: Some(accumulated_tool_calls.clone()),
-                        image_paths: None,
-                    });
+            info!("Tool calls requested: {}", response.tool_calls.len());
 
-                    let outcomes = match self
-                        .run_tool_batch(
-                            &accumulated_tool_calls,
-                            tools,
-                            &correlation_id,
-                            "LlmBroker::generate_stream",
-                        )
-                        .await
-                    {
-                        Ok(o) => o,
-                        Err(e) => {
-                            yield Err(e);
-                            return;
-                        }
-                    };
+            // Record the assistant turn that asked for tools so subsequent
+            // messages have a coherent history.
+            messages.push(LlmMessage {
+                role: MessageRole::Assistant,
+                content: response.content.clone(),
+                tool_calls: Some(response.tool_calls.clone()),
+                image_paths: None,
+                cache: false,
+            });
 
-                    for (call, outcome) in accumulated_tool_calls.iter().zip(outcomes.iter()) {
-                        let content = if outcome.ok {
-                            match serde_json::to_string(
-                                outcome.result.as_ref().unwrap_or(&serde_json::Value::Null),
-                            ) {
-                                Ok(s) => s,
-                                Err(e) => {
-                                    yield Err(e.into());
-                                    return;
-                                }
-                            }
-                        } else {
-                            serde_json::json!({
-                                "error": outcome.error.clone().unwrap_or_default(),
-                            })
-                            .to_string()
-                        };
-                        new_messages.push(LlmMessage {
-                            role: MessageRole::Tool,
-                            content: Some(content),
-                            tool_calls: Some(vec![call.clone()]),
-                            image_paths: None,
-                        });
-                    }
+            let outcomes = self
+                .run_tool_batch(&response.tool_calls, tools, correlation_id, "LlmBroker", None)
+                .await?;
+            tools_run.extend(
+                outcomes
+                    .iter()
+                    .filter(|outcome| tools.iter().any(|t| t.matches(&outcome.name)))
+                    .map(|outcome| outcome.name.clone()),
+            );
 
-                    // Continue streaming with updated messages, incrementing depth
-                    let mut recursive_stream = Box::pin(self.generate_stream_with_depth(
-                        new_messages,
-                        Some(tools),
-                        config.clone(),
-                        correlation_id.clone(),
-                        depth + 1,
-                    ));
+            if let Some(output) = terminal_output(&outcomes, tools).filter(|_| stop_at_terminal) {
+                info!("Terminal tool ran, returning its result");
+                return Ok(GenerationResult::new(output, usage, &tools_run));
+            }
 
-                    while let Some(result) = recursive_stream.next().await {
-                        yield result;
+            messages.extend(tool_result_messages(
+                &response.tool_calls,
+                &outcomes,
+                config.tool_result_format,
+            ));
+
+            let next_response =
+                match self.complete_traced(&messages, Some(tools), config, correlation_id).await {
+                    Ok(response) => response,
+                    Err(e) => {
+                        messages = self.trimmed_after_overflow(e, &messages)?;
+                        self.complete_traced(&messages, Some(tools), config, correlation_id).await?
                     }
-                } else {
-                    warn!("LLM requested tool calls but no tools provided");
+                };
+            let usage = combine_usage(usage, next_response.usage);
+
+            if !next_response.tool_calls.is_empty() {
+                return self
+                    .handle_tool_calls(
+                        messages,
+                        next_response,
+                        tools,
+                        config,
+                        correlation_id,
+                        iteration + 1,
+                        usage,
+                        tools_run,
+                        stop_at_terminal,
+                    )
+                    .await;
+            }
+
+            Ok(GenerationResult::new(
+                next_response.content.unwrap_or_default(),
+                usage,
+                &tools_run,
+            ))
+        })
+    }
+
+    /// Dispatch a batch of tool calls through the configured [`ToolRunner`]
+    /// and record per-call + batch tracer events.
+    async fn run_tool_batch(
+        &self,
+        tool_calls: &[crate::llm::models::LlmToolCall],
+        tools: &[Box<dyn LlmTool>],
+        correlation_id: &str,
+        source: &'static str,
+        progress: Option<ToolProgressSender>,
+    ) -> Result<Vec<crate::llm::tools::ToolCallOutcome>> {
+        if self.tool_not_found == ToolNotFoundPolicy::Error {
+            if let Some(call) =
+                tool_calls.iter().find(|tc| !tools.iter().any(|t| t.matches(&tc.name)))
+            {
+                if let Some(tracer) = &self.tracer {
+                    tracer.record_tool_outcome(
+                        &call.name,
+                        call.arguments.clone(),
+                        serde_json::Value::Null,
+                        ToolCallStatus::HardError,
+                        Some(source.to_string()),
+                        None,
+                        source,
+                        correlation_id,
+                    );
                 }
+                return Err(MojenticError::ToolNotFound {
+                    name: call.name.clone(),
+                    available: tools.iter().map(|t| t.descriptor().function.name).collect(),
+                });
             }
         }
-    }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::llm::models::LlmToolCall;
-    use crate::llm::tools::{FunctionDescriptor, ToolDescriptor};
-    use async_trait::async_trait;
-    use serde::{Deserialize, Serialize};
-    use serde_json::Value;
-    use std::collections::HashMap;
+        let executions: Vec<ToolCallExecution> = tool_calls
+            .iter()
+            .enumerate()
+            .map(|(idx, tc)| ToolCallExecution {
+                id: tc.id.clone().unwrap_or_else(|| format!("call_{idx}")),
+                name: tc.name.clone(),
+                args: tc.arguments.clone(),
+            })
+            .collect();
 
-    // Mock gateway for testing
-    struct MockGateway {
-        responses: Vec<LlmGatewayResponse>,
-        call_count: std::sync::Mutex<usize>,
+        let ctx = ToolRunCtx {
+            correlation_id: Some(correlation_id.to_string()),
+            source: Some(source.to_string()),
+            concurrency: Some(Arc::clone(&self.tool_permits)),
+            result_cache: self.tool_result_cache.clone(),
+            progress,
+            ..Default::default()
+        };
+
+        let batch_start = self.clock.now();
+        let outcomes = self.tool_runner.run_batch(&executions, tools, &ctx).await;
+        let batch_duration_ms = self.clock.now().duration_since(batch_start).as_secs_f64() * 1000.0;
+        // Results are appended in the order the provider returned the calls
+        let outcomes = in_call_order(&executions, outcomes)?;
+
+        if let Some(tracer) = &self.tracer {
+            for (exec, outcome) in executions.iter().zip(outcomes.iter()) {
+                // A failed tool's error goes back to the model, which can recover
+                let status = if outcome.ok {
+                    ToolCallStatus::Succeeded
+                } else {
+                    ToolCallStatus::RecoverableError
+                };
+                tracer.record_tool_outcome(
+                    &outcome.name,
+                    exec.args.clone(),
+                    outcome.result.clone().unwrap_or(serde_json::Value::Null),
+                    status,
+                    Some(source.to_string()),
+                    Some(outcome.duration_ms as f64),
+                    source,
+                    correlation_id,
+                );
+            }
+            if outcomes.len() > 1 {
+                let ok = outcomes.iter().filter(|o| o.ok).count();
+                let fail = outcomes.len() - ok;
+                tracer.record_tool_batch(
+                    uuid::Uuid::new_v4().to_string(),
+                    outcomes.iter().map(|o| o.name.clone()).collect(),
+                    ok,
+                    fail,
+                    batch_duration_ms,
+                    Some(source.to_string()),
+                    source,
+                    correlation_id,
+                );
+            }
+        }
+
+        Ok(outcomes)
+    }
+
+    /// Generate several independent text responses for the same messages
+    ///
+    /// Useful for best-of-N sampling and self-consistency checks. The number of
+    /// choices comes from `config.n`; gateways that support it (such as OpenAI)
+    /// return them all in a single round-trip, while others make one request per
+    /// choice. Tools are offered to the LLM, but tool calls are not executed, so
+    /// a choice that only requests tools yields an empty string.
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - The messages to send to the LLM
+    /// * `tools` - Optional tools available to the LLM
+    /// * `config` - Optional completion configuration (set `n` for the number of choices)
+    /// * `correlation_id` - Optional correlation ID for tracing (broker default or new UUID if None)
+    pub async fn generate_many(
+        &self,
+        messages: &[LlmMessage],
+        tools: Option<&[Box<dyn LlmTool>]>,
+        config: Option<CompletionConfig>,
+        correlation_id: Option<String>,
+    ) -> Result<Vec<String>> {
+        let request = self.prepare(messages, config, correlation_id).await;
+        let (messages, config, correlation_id) =
+            (&*request.messages, &request.config, &request.correlation_id);
+        let selected = self.select_tools(messages, tools).await;
+        let tools = selected.as_deref().or(tools);
+        let prefilled = self.with_prefill(messages, config);
+        let messages = &*prefilled;
+
+        self.check_context_length(messages, tools, config)?;
+
+        // Record LLM call
+        if let Some(tracer) = &self.tracer {
+            tracer.record_llm_call(
+                self.model_for(config),
+                trace_messages(tracer, messages),
+                config.temperature as f64,
+                None,
+                "LlmBroker::generate_many",
+                correlation_id,
+            );
+        }
+
+        // Measure call duration
+        let start = self.clock.now();
+
+        let responses = self
+            .retrying(correlation_id, || {
+                self.gateway.complete_many(self.model_for(config), messages, tools, config)
+            })
+            .await?;
+
+        let call_duration_ms = self.clock.now().duration_since(start).as_secs_f64() * 1000.0;
+
+        // Record each choice as a response to the same call
+        if let Some(tracer) = &self.tracer {
+            for response in &responses {
+                tracer.record_llm_response_with_usage(
+                    self.model_for(config),
+                    trace_content(tracer, response.content.as_deref().unwrap_or_default()),
+                    None,
+                    Some(call_duration_ms),
+                    response.payload_size,
+                    response.usage,
+                    "LlmBroker::generate_many",
+                    correlation_id,
+                );
+            }
+        }
+
+        let choices: Vec<String> = responses
+            .into_iter()
+            .map(|response| {
+                let content = response.content.unwrap_or_default();
+                match &config.assistant_prefill {
+                    Some(prefill) => prefixed(prefill, is_continued(messages), content),
+                    None => content,
+                }
+            })
+            .collect();
+
+        Ok(choices.into_iter().map(|choice| self.finish_response(choice)).collect())
+    }
+
+    /// Generate structured object response from LLM
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - The messages to send to the LLM
+    /// * `config` - Optional completion configuration
+    /// * `correlation_id` - Optional correlation ID for tracing (broker default or new UUID if None)
+    pub async fn generate_object<T>(
+        &self,
+        messages: &[LlmMessage],
+        config: Option<CompletionConfig>,
+        correlation_id: Option<String>,
+    ) -> Result<T>
+    where
+        T: for<'de> Deserialize<'de> + Serialize + schemars::JsonSchema + Send,
+    {
+        let schema = serde_json::to_value(schemars::schema_for!(T))?;
+        let json_response = self
+            .generate_json(messages, schema, config, correlation_id, "LlmBroker::generate_object")
+            .await?;
+
+        // Deserialize the JSON into the target type
+        Ok(serde_json::from_value(json_response)?)
+    }
+
+    /// Generate a structured object after letting the LLM call tools
+    ///
+    /// Unlike [`LlmBroker::generate_object`], which makes a single JSON request,
+    /// this runs the regular tool loop with `T`'s schema set as the response
+    /// format on every turn, so the model can call tools first and then answer
+    /// with an object. The final content is deserialized into `T` as the
+    /// model sent it, without the broker's post-processor or response cap.
+    /// [Terminal](LlmTool::terminal) tools don't end this loop: their results
+    /// go back to the model, which still has to answer with the object.
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - The messages to send to the LLM
+    /// * `tools` - Tools available to the LLM
+    /// * `config` - Optional completion configuration
+    /// * `correlation_id` - Optional correlation ID for tracing (broker default or new UUID if None)
+    pub async fn generate_object_with_tools<T>(
+        &self,
+        messages: &[LlmMessage],
+        tools: &[Box<dyn LlmTool>],
+        config: Option<CompletionConfig>,
+        correlation_id: Option<String>,
+    ) -> Result<T>
+    where
+        T: DeserializeOwned + schemars::JsonSchema,
+    {
+        let mut schema = serde_json::to_value(schemars::schema_for!(T))?;
+        if let Some(transform) = &self.schema_transformer {
+            schema = transform(schema);
+        }
+        let mut config = config.unwrap_or_default();
+        ignore_prefill(&mut config, "LlmBroker::generate_object_with_tools");
+        config.response_format = Some(ResponseFormat::JsonObject {
+            schema: Some(schema),
+        });
+
+        let result = self
+            .generate_uncapped(messages, Some(tools), Some(config), correlation_id, false)
+            .await?;
+        Ok(serde_json::from_str(&result.content)?)
+    }
+
+    /// Describe the broker's model: whether the gateway offers it, and what it supports
+    ///
+    /// Capabilities come from the gateway, e.g. the OpenAI model registry or
+    /// Ollama's `/api/show`. Useful for checking a configuration at startup
+    /// rather than on the first failed request.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let description = broker.describe_model().await?;
+    /// if !description.available {
+    ///     eprintln!("{} is not installed", description.name);
+    /// }
+    /// ```
+    pub async fn describe_model(&self) -> Result<ModelDescription> {
+        self.gateway.describe_model(&self.model).await
+    }
+
+    /// Describe an image, or answer a question about it, with a vision model
+    ///
+    /// Sends `prompt` with the image attached as a single user turn. Image
+    /// bytes are written to a temporary file for the duration of the call,
+    /// since gateways read images from disk.
+    ///
+    /// # Arguments
+    ///
+    /// * `image` - A file path or the encoded image bytes
+    /// * `prompt` - What to ask about the image
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MojenticError::ModelNotSupported`] without calling the model
+    /// if the gateway reports that the broker's model can't accept images.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let description = broker.describe_image("receipt.jpg", "List the items and their prices.").await?;
+    /// ```
+    pub async fn describe_image(
+        &self,
+        image: impl Into<ImageInput>,
+        prompt: &str,
+    ) -> Result<String> {
+        if !self.gateway.supports_vision(&self.model).await {
+            return Err(MojenticError::ModelNotSupported(format!(
+                "{} does not accept image input",
+                self.model
+            )));
+        }
+
+        let (path, _spilled) = match image.into() {
+            ImageInput::Path(path) => (path, None),
+            ImageInput::Bytes(bytes) => {
+                let spilled = SpilledImage::write(&bytes)?;
+                (spilled.path.clone(), Some(spilled))
+            }
+        };
+        let messages = vec![LlmMessage::user(prompt).with_images(vec![path])];
+        self.generate(&messages, None, None, None).await
+    }
+
+    /// Stream a structured object, yielding partial views as the JSON arrives
+    ///
+    /// The response is requested with `T`'s schema as its response format. After
+    /// each chunk the accumulated JSON is completed as far as possible (see
+    /// [`parse_partial_json`]) and deserialized into `P`, and a new
+    /// [`ObjectStreamItem::Partial`] is yielded whenever that view changes. Once
+    /// the stream ends the full response is deserialized into `T` and yielded as
+    /// [`ObjectStreamItem::Complete`].
+    ///
+    /// `P` is the partial view of `T`: typically a mirror of `T` whose fields are
+    /// all `Option` under `#[serde(default)]`, so it deserializes from any subset
+    /// of the fields. Views that don't yet deserialize into `P` are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - The messages to send to the LLM
+    /// * `config` - Optional completion configuration
+    /// * `correlation_id` - Optional correlation ID for tracing (broker default or new UUID if None)
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// #[derive(Deserialize, Serialize, JsonSchema)]
+    /// struct Profile { name: String, skills: Vec<String> }
+    ///
+    /// #[derive(Deserialize, Default)]
+    /// #[serde(default)]
+    /// struct PartialProfile { name: Option<String>, skills: Option<Vec<String>> }
+    ///
+    /// let mut stream = broker.generate_object_partial::<Profile, PartialProfile>(&messages, None, None);
+    /// while let Some(item) = stream.next().await {
+    ///     match item? {
+    ///         ObjectStreamItem::Partial(partial) => render_form(&partial),
+    ///         ObjectStreamItem::Complete(profile) => save(profile),
+    ///     }
+    /// }
+    /// ```
+    pub fn generate_object_partial<'a, T, P>(
+        &'a self,
+        messages: &'a [LlmMessage],
+        config: Option<CompletionConfig>,
+        correlation_id: Option<String>,
+    ) -> ObjectStream<'a, T, P>
+    where
+        T: DeserializeOwned + schemars::JsonSchema + 'a,
+        P: DeserializeOwned + 'a,
+    {
+        Box::pin(async_stream::stream! {
+            let mut request = self.prepare(messages, config, correlation_id).await;
+            ignore_prefill(&mut request.config, "LlmBroker::generate_object_partial");
+            let messages = &*request.messages;
+            let correlation_id = &request.correlation_id;
+
+            if let Err(e) = self.check_context_length(messages, None, &request.config) {
+                yield Err(e);
+                return;
+            }
+
+            let mut schema = match serde_json::to_value(schemars::schema_for!(T)) {
+                Ok(schema) => schema,
+                Err(e) => {
+                    yield Err(e.into());
+                    return;
+                }
+            };
+            if let Some(transform) = &self.schema_transformer {
+                schema = transform(schema);
+            }
+            request.config.response_format = Some(ResponseFormat::JsonObject {
+                schema: Some(schema),
+            });
+
+            if let Some(tracer) = &self.tracer {
+                let messages_json = trace_messages(tracer, messages);
+                tracer.record_llm_call(
+                    self.model_for(&request.config),
+                    messages_json,
+                    request.config.temperature as f64,
+                    None,
+                    "LlmBroker::generate_object_partial",
+                    correlation_id,
+                );
+            }
+
+            let start = self.clock.now();
+            let mut upstream = self.gateway.complete_stream(self.model_for(&request.config), messages, None, &request.config);
+            let mut accumulated = String::new();
+            let mut last_view: Option<Value> = None;
+
+            while let Some(chunk) = upstream.next().await {
+                match chunk {
+                    Ok(StreamChunk::Content(text)) => accumulated.push_str(&text),
+                    Ok(_) => continue,
+                    Err(e) => {
+                        yield Err(e);
+                        return;
+                    }
+                }
+
+                let Some(view) = parse_partial_json(&accumulated) else {
+                    continue;
+                };
+                if last_view.as_ref() == Some(&view) {
+                    continue;
+                }
+                if let Ok(partial) = serde_json::from_value::<P>(view.clone()) {
+                    last_view = Some(view);
+                    yield Ok(ObjectStreamItem::Partial(partial));
+                }
+            }
+
+            if let Some(tracer) = &self.tracer {
+                tracer.record_llm_response(
+                    self.model_for(&request.config),
+                    trace_content(tracer, &accumulated).to_string(),
+                    None,
+                    Some(self.clock.now().duration_since(start).as_secs_f64() * 1000.0),
+                    "LlmBroker::generate_object_partial",
+                    correlation_id,
+                );
+            }
+
+            match serde_json::from_str::<T>(&accumulated) {
+                Ok(object) => yield Ok(ObjectStreamItem::Complete(object)),
+                Err(e) => yield Err(e.into()),
+            }
+        })
+    }
+
+    /// Stream a structured object as raw text, validating it as it arrives
+    ///
+    /// The response is requested with `T`'s schema as its response format. Each
+    /// content chunk is yielded unchanged as [`ValidatedStreamItem::Text`] so it
+    /// can be shown as it arrives, while the full response is buffered.
+    ///
+    /// After each chunk the JSON received so far is checked against the schema
+    /// (after any [`LlmBroker::with_schema_transformer`] rewrite) with
+    /// [`validate_partial_against_schema`]. As soon as the response diverges in
+    /// a way the rest of it couldn't fix, such as a property the schema forbids
+    /// or a value of the wrong type, the stream ends with a
+    /// [`MojenticError::ParseError`] and stops reading from the model.
+    ///
+    /// When the stream ends the buffered JSON is validated in full and the last
+    /// item is either [`ValidatedStreamItem::Complete`] or an error: a
+    /// [`MojenticError::SerializationError`] if the response isn't JSON, or a
+    /// [`MojenticError::ParseError`] listing every schema violation.
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - The messages to send to the LLM
+    /// * `config` - Optional completion configuration
+    /// * `correlation_id` - Optional correlation ID for tracing (broker default or new UUID if None)
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut stream = broker.generate_object_validated::<Profile>(&messages, None, None);
+    /// while let Some(item) = stream.next().await {
+    ///     match item? {
+    ///         ValidatedStreamItem::Text(text) => print!("{}", text),
+    ///         ValidatedStreamItem::Complete(profile) => save(profile),
+    ///     }
+    /// }
+    /// ```
+    pub fn generate_object_validated<'a, T>(
+        &'a self,
+        messages: &'a [LlmMessage],
+        config: Option<CompletionConfig>,
+        correlation_id: Option<String>,
+    ) -> ValidatedObjectStream<'a, T>
+    where
+        T: DeserializeOwned + schemars::JsonSchema + 'a,
+    {
+        Box::pin(async_stream::stream! {
+            let mut request = self.prepare(messages, config, correlation_id).await;
+            ignore_prefill(&mut request.config, "LlmBroker::generate_object_validated");
+            let messages = &*request.messages;
+            let correlation_id = &request.correlation_id;
+
+            if let Err(e) = self.check_context_length(messages, None, &request.config) {
+                yield Err(e);
+                return;
+            }
+
+            let mut schema = match serde_json::to_value(schemars::schema_for!(T)) {
+                Ok(schema) => schema,
+                Err(e) => {
+                    yield Err(e.into());
+                    return;
+                }
+            };
+            if let Some(transform) = &self.schema_transformer {
+                schema = transform(schema);
+            }
+            request.config.response_format = Some(ResponseFormat::JsonObject {
+                schema: Some(schema.clone()),
+            });
+
+            if let Some(tracer) = &self.tracer {
+                let messages_json = trace_messages(tracer, messages);
+                tracer.record_llm_call(
+                    self.model_for(&request.config),
+                    messages_json,
+                    request.config.temperature as f64,
+                    None,
+                    "LlmBroker::generate_object_validated",
+                    correlation_id,
+                );
+            }
+
+            let start = self.clock.now();
+            let mut upstream = self.gateway.complete_stream(self.model_for(&request.config), messages, None, &request.config);
+            let mut accumulated = String::new();
+
+            while let Some(chunk) = upstream.next().await {
+                match chunk {
+                    Ok(StreamChunk::Content(text)) => {
+                        accumulated.push_str(&text);
+                        yield Ok(ValidatedStreamItem::Text(text));
+
+                        let diverged = parse_partial_json(&accumulated)
+                            .and_then(|partial| validate_partial_against_schema(&partial, &schema).err());
+                        if let Some(violations) = diverged {
+                            yield Err(MojenticError::ParseError(format!(
+                                "Response diverged from the schema: {}",
+                                violations.join("; ")
+                            )));
+                            return;
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        yield Err(e);
+                        return;
+                    }
+                }
+            }
+
+            if let Some(tracer) = &self.tracer {
+                tracer.record_llm_response(
+                    self.model_for(&request.config),
+                    trace_content(tracer, &accumulated).to_string(),
+                    None,
+                    Some(self.clock.now().duration_since(start).as_secs_f64() * 1000.0),
+                    "LlmBroker::generate_object_validated",
+                    correlation_id,
+                );
+            }
+
+            let value = match serde_json::from_str::<Value>(&accumulated) {
+                Ok(value) => value,
+                Err(e) => {
+                    yield Err(e.into());
+                    return;
+                }
+            };
+            if let Err(violations) = validate_against_schema(&value, &schema) {
+                yield Err(MojenticError::ParseError(format!(
+                    "Response doesn't match the schema: {}",
+                    violations.join("; ")
+                )));
+                return;
+            }
+            match serde_json::from_value::<T>(value) {
+                Ok(object) => yield Ok(ValidatedStreamItem::Complete(object)),
+                Err(e) => yield Err(e.into()),
+            }
+        })
+    }
+
+    /// Extract structured data from text
+    ///
+    /// A thin wrapper over [`LlmBroker::generate_object`] tuned for extraction:
+    /// it runs at temperature 0 with a system prompt that includes the target
+    /// schema and asks the model to report only what the text states.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to extract from
+    /// * `instructions` - What to extract, e.g. "every person and city mentioned"
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// #[derive(Deserialize, Serialize, JsonSchema)]
+    /// struct People { names: Vec<String> }
+    ///
+    /// let people: People = broker.extract(&article, "the people mentioned").await?;
+    /// ```
+    pub async fn extract<T>(&self, text: &str, instructions: &str) -> Result<T>
+    where
+        T: DeserializeOwned + Serialize + schemars::JsonSchema + Send,
+    {
+        let schema = serde_json::to_string_pretty(&schemars::schema_for!(T))?;
+        let messages = [
+            LlmMessage::system(format!(
+                "You extract information from text. {instructions}\n\n\
+                 Only report what the text states. Do not infer, embellish, or invent \
+                 values; leave optional fields empty when the text doesn't supply them.\n\n\
+                 Respond with JSON matching this schema:\n{schema}"
+            )),
+            LlmMessage::user(text),
+        ];
+        let config = CompletionConfig {
+            temperature: 0.0,
+            ..Default::default()
+        };
+
+        self.generate_object(&messages, Some(config), None).await
+    }
+
+    /// Classify text into exactly one of the given labels
+    ///
+    /// The model is constrained to the labels with an enum schema. A response
+    /// that still falls outside the list is snapped to the closest label, so
+    /// the result is always one of `labels`.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to classify
+    /// * `labels` - The candidate labels; must not be empty
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let label = broker.classify("I love this product!", &["positive", "negative"]).await?;
+    /// ```
+    pub async fn classify(&self, text: &str, labels: &[&str]) -> Result<String> {
+        if labels.is_empty() {
+            return Err(MojenticError::InvalidArgument(
+                "classify requires at least one label".to_string(),
+            ));
+        }
+
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": {
+                "label": {"type": "string", "enum": labels}
+            },
+            "required": ["label"],
+            "additionalProperties": false
+        });
+        let messages = [
+            LlmMessage::system(format!(
+                "Classify the user's text into exactly one of these labels: {}. \
+                 Respond with the label only.",
+                labels.join(", ")
+            )),
+            LlmMessage::user(text),
+        ];
+
+        let response =
+            self.generate_json(&messages, schema, None, None, "LlmBroker::classify").await?;
+        let answer = response
+            .get("label")
+            .and_then(Value::as_str)
+            .or_else(|| response.as_str())
+            .unwrap_or_default();
+
+        let label = nearest_label(answer, labels);
+        if label != answer {
+            warn!(
+                answer = answer,
+                label = label,
+                "Snapped off-list classification to nearest label"
+            );
+        }
+        Ok(label.to_string())
+    }
+
+    /// Request JSON matching `schema`, applying the schema transformer and tracing the call
+    async fn generate_json(
+        &self,
+        messages: &[LlmMessage],
+        mut schema: Value,
+        config: Option<CompletionConfig>,
+        correlation_id: Option<String>,
+        source: &str,
+    ) -> Result<Value> {
+        let mut request = self.prepare(messages, config, correlation_id).await;
+        ignore_prefill(&mut request.config, source);
+        let (messages, config, correlation_id) =
+            (&*request.messages, &request.config, &request.correlation_id);
+
+        self.check_context_length(messages, None, config)?;
+
+        if let Some(transform) = &self.schema_transformer {
+            schema = transform(schema);
+        }
+        // Record LLM call
+        if let Some(tracer) = &self.tracer {
+            let messages_json = trace_messages(tracer, messages);
+
+            tracer.record_llm_call(
+                self.model_for(config),
+                messages_json,
+                config.temperature as f64,
+                None,
+                source,
+                correlation_id,
+            );
+        }
+
+        // Measure call duration
+        let start = self.clock.now();
+
+        // Call the gateway with the schema
+        let json_response = self
+            .retrying(correlation_id, || {
+                self.gateway
+                    .complete_json(self.model_for(config), messages, schema.clone(), config)
+            })
+            .await?;
+
+        let call_duration_ms = self.clock.now().duration_since(start).as_secs_f64() * 1000.0;
+
+        // Record LLM response
+        if let Some(tracer) = &self.tracer {
+            let content = match tracer.verbosity() {
+                TracerVerbosity::Full => format!(
+                    "Structured response: {}",
+                    serde_json::to_string_pretty(&json_response).unwrap_or_default()
+                ),
+                TracerVerbosity::Summary => String::new(),
+            };
+            tracer.record_llm_response(
+                self.model_for(config),
+                content,
+                None,
+                Some(call_duration_ms),
+                source,
+                correlation_id,
+            );
+        }
+
+        Ok(json_response)
+    }
+
+    /// Whether this broker's model can be used with [`LlmBroker::generate_stream`]
+    ///
+    /// Check this up front to fall back to [`LlmBroker::generate`] for models
+    /// that can't stream, rather than discovering it on the first poll.
+    pub fn supports_streaming(&self) -> bool {
+        self.gateway.supports_streaming(&self.model)
+    }
+
+    /// Generate streaming text response from LLM
+    ///
+    /// Returns a stream that yields content chunks as they arrive. When tool calls
+    /// are detected, the broker executes them and recursively streams the LLM's
+    /// follow-up response.
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - The messages to send to the LLM
+    /// * `tools` - Optional tools available to the LLM
+    /// * `config` - Optional completion configuration
+    /// * `correlation_id` - Optional correlation ID for tracing (broker default or new UUID if None)
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use futures::stream::StreamExt;
+    ///
+    /// let broker = LlmBroker::new("qwen3:32b", gateway, None);
+    /// let messages = vec![LlmMessage::user("Tell me a story")];
+    ///
+    /// let mut stream = broker.generate_stream(&messages, None, None, None);
+    /// while let Some(result) = stream.next().await {
+    ///     match result {
+    ///         Ok(chunk) => print!("{}", chunk),
+    ///         Err(e) => eprintln!("Error: {}", e),
+    ///     }
+    /// }
+    /// ```
+    pub fn generate_stream<'a>(
+        &'a self,
+        messages: &'a [LlmMessage],
+        tools: Option<&'a [Box<dyn LlmTool>]>,
+        config: Option<CompletionConfig>,
+        correlation_id: Option<String>,
+    ) -> Pin<Box<dyn Stream<Item = Result<String>> + 'a>> {
+        self.stream_messages(messages.to_vec(), tools, config, correlation_id)
+    }
+
+    /// Resume a streamed generation that was cut off partway through
+    ///
+    /// Sends `messages` followed by `partial_content` as an assistant turn and
+    /// a user turn asking the model to carry on from exactly where it stopped.
+    /// The returned stream yields only the new text, so appending it to
+    /// `partial_content` gives the whole response.
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - The messages the interrupted stream was generated from
+    /// * `partial_content` - The text received before the stream dropped
+    /// * `tools` - Optional tools available to the LLM
+    /// * `config` - Optional completion configuration
+    /// * `correlation_id` - Optional correlation ID for tracing (broker default or new UUID if None)
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut received = String::new();
+    /// let mut stream = broker.generate_stream(&messages, None, None, None);
+    /// while let Some(Ok(chunk)) = stream.next().await {
+    ///     received.push_str(&chunk);
+    /// }
+    ///
+    /// // The connection dropped; pick up where the model left off
+    /// let mut stream = broker.continue_stream(&messages, &received, None, None, None);
+    /// while let Some(chunk) = stream.next().await {
+    ///     received.push_str(&chunk?);
+    /// }
+    /// ```
+    pub fn continue_stream<'a>(
+        &'a self,
+        messages: &[LlmMessage],
+        partial_content: &str,
+        tools: Option<&'a [Box<dyn LlmTool>]>,
+        config: Option<CompletionConfig>,
+        correlation_id: Option<String>,
+    ) -> Pin<Box<dyn Stream<Item = Result<String>> + 'a>> {
+        let mut messages = messages.to_vec();
+        messages.push(LlmMessage::assistant(partial_content));
+        messages.push(LlmMessage::user(CONTINUE_PROMPT));
+        self.stream_messages(messages, tools, config, correlation_id)
+    }
+
+    /// Generate a streaming response, including progress from running tools
+    ///
+    /// Works like [`LlmBroker::generate_stream`], but yields [`StreamChunk`]s:
+    /// [`StreamChunk::Content`] for the response text, and
+    /// [`StreamChunk::ToolProgress`] for each update a tool sends with
+    /// [`ToolRunCtx::report_progress`](crate::llm::tools::ToolRunCtx::report_progress)
+    /// while it runs. Updates are forwarded as they are sent, so a slow tool
+    /// doesn't leave the stream silent.
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - The messages to send to the LLM
+    /// * `tools` - Optional tools available to the LLM
+    /// * `config` - Optional completion configuration
+    /// * `correlation_id` - Optional correlation ID for tracing (broker default or new UUID if None)
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut stream = broker.generate_stream_with_progress(&messages, Some(&tools), None, None);
+    /// while let Some(chunk) = stream.next().await {
+    ///     match chunk? {
+    ///         StreamChunk::Content(text) => print!("{}", text),
+    ///         StreamChunk::ToolProgress(progress) => {
+    ///             eprintln!("running {}... {}s", progress.tool_name, progress.elapsed.as_secs())
+    ///         }
+    ///         _ => {}
+    ///     }
+    /// }
+    /// ```
+    pub fn generate_stream_with_progress<'a>(
+        &'a self,
+        messages: &'a [LlmMessage],
+        tools: Option<&'a [Box<dyn LlmTool>]>,
+        config: Option<CompletionConfig>,
+        correlation_id: Option<String>,
+    ) -> Pin<Box<dyn Stream<Item = Result<StreamChunk>> + 'a>> {
+        self.stream_chunks(messages.to_vec(), tools, config, correlation_id)
+    }
+
+    fn stream_messages<'a>(
+        &'a self,
+        messages: Vec<LlmMessage>,
+        tools: Option<&'a [Box<dyn LlmTool>]>,
+        config: Option<CompletionConfig>,
+        correlation_id: Option<String>,
+    ) -> Pin<Box<dyn Stream<Item = Result<String>> + 'a>> {
+        Box::pin(self.stream_chunks(messages, tools, config, correlation_id).filter_map(
+            |chunk| async move {
+                match chunk {
+                    Ok(StreamChunk::Content(text)) => Some(Ok(text)),
+                    Ok(_) => None,
+                    Err(e) => Some(Err(e)),
+                }
+            },
+        ))
+    }
+
+    fn stream_chunks<'a>(
+        &'a self,
+        messages: Vec<LlmMessage>,
+        tools: Option<&'a [Box<dyn LlmTool>]>,
+        config: Option<CompletionConfig>,
+        correlation_id: Option<String>,
+    ) -> Pin<Box<dyn Stream<Item = Result<StreamChunk>> + 'a>> {
+        let mut stream: Pin<Box<dyn Stream<Item = Result<StreamChunk>> + 'a>> =
+            Box::pin(async_stream::stream! {
+                let request = self.prepare(&messages, config, correlation_id).await;
+                let chunks = self.generate_stream_with_depth(
+                    request.messages.into_owned(),
+                    tools,
+                    request.config,
+                    request.correlation_id,
+                    0,
+                );
+                tokio::pin!(chunks);
+                while let Some(chunk) = chunks.next().await {
+                    yield chunk;
+                }
+            });
+        if !self.stop_phrases.is_empty() {
+            stream = Box::pin(stop_at_phrases(stream, &self.stop_phrases));
+        }
+        match self.max_response_chars {
+            Some(max_chars) => Box::pin(cap_stream(stream, max_chars)),
+            None => stream,
+        }
+    }
+
+    fn generate_stream_with_depth<'a>(
+        &'a self,
+        current_messages: Vec<LlmMessage>,
+        tools: Option<&'a [Box<dyn LlmTool>]>,
+        config: CompletionConfig,
+        correlation_id: String,
+        depth: usize,
+    ) -> impl Stream<Item = Result<StreamChunk>> + 'a {
+        async_stream::stream! {
+            if depth >= config.max_tool_iterations {
+                yield Err(MojenticError::MaxToolIterationsExceeded {
+                    limit: config.max_tool_iterations,
+                });
+                return;
+            }
+
+            let sent = self.with_prefill(&current_messages, &config);
+            if let Err(e) = self.check_context_length(&sent, tools, &config) {
+                yield Err(e);
+                return;
+            }
+
+            // Record LLM call
+            if let Some(tracer) = &self.tracer {
+                let messages_json = trace_messages(tracer, &sent);
+
+                let tools_json = tools.map(|t| {
+                    t.iter()
+                        .map(|tool| {
+                            let desc = tool.descriptor();
+                            let mut map = std::collections::HashMap::new();
+                            map.insert("name".to_string(), serde_json::json!(desc.function.name));
+                            map.insert(
+                                "description".to_string(),
+                                serde_json::json!(desc.function.description),
+                            );
+                            map
+                        })
+                        .collect()
+                });
+
+                tracer.record_llm_call(
+                    self.model_for(&config),
+                    messages_json,
+                    config.temperature as f64,
+                    tools_json,
+                    "LlmBroker::generate_stream",
+                    &correlation_id,
+                );
+            }
+
+            let mut accumulated_content = String::new();
+            let mut accumulated_tool_calls = Vec::new();
+            let mut prefill = config
+                .assistant_prefill
+                .as_deref()
+                .map(|prefill| PrefillWriter::new(prefill, is_continued(&sent)));
+
+            // Measure stream duration
+            let start = self.clock.now();
+
+            // Stream from gateway
+            let mut stream =
+                self.gateway.complete_stream(self.model_for(&config), &sent, tools, &config);
+
+            while let Some(chunk_result) = stream.next().await {
+                match chunk_result {
+                    Ok(StreamChunk::Content(content)) => {
+                        accumulated_content.push_str(&content);
+                        let content = match &mut prefill {
+                            Some(prefill) => prefill.push(&content),
+                            None => Some(content),
+                        };
+                        if let Some(content) = content {
+                            yield Ok(StreamChunk::Content(content));
+                        }
+                    }
+                    Ok(StreamChunk::ToolCalls(tool_calls)) => {
+                        accumulated_tool_calls = if self.dedupe_tool_calls {
+                            without_duplicate_calls(tool_calls)
+                        } else {
+                            tool_calls
+                        };
+                    }
+                    Ok(StreamChunk::Thinking(_))
+                    | Ok(StreamChunk::Progress(_))
+                    | Ok(StreamChunk::Metrics(_))
+                    | Ok(StreamChunk::ToolProgress(_)) => {}
+                    Err(e) => {
+                        yield Err(e);
+                        return;
+                    }
+                }
+            }
+
+            if let Some(content) =
+                prefill.and_then(|prefill| prefill.finish(accumulated_tool_calls.is_empty()))
+            {
+                yield Ok(StreamChunk::Content(content));
+            }
+
+            let call_duration_ms = self.clock.now().duration_since(start).as_secs_f64() * 1000.0;
+
+            // Record LLM response
+            if let Some(tracer) = &self.tracer {
+                let tool_calls_json = if !accumulated_tool_calls.is_empty() {
+                    Some(
+                        accumulated_tool_calls
+                            .iter()
+                            .map(|tc| {
+                                let mut map = std::collections::HashMap::new();
+                                map.insert("name".to_string(), serde_json::json!(&tc.name));
+                                if let Some(id) = &tc.id {
+                                    map.insert("id".to_string(), serde_json::json!(id));
+                                }
+                                map
+                            })
+                            .collect(),
+                    )
+                } else {
+                    None
+                };
+
+                tracer.record_llm_response(
+                    self.model_for(&config),
+                    trace_content(tracer, &accumulated_content),
+                    tool_calls_json,
+                    Some(call_duration_ms),
+                    "LlmBroker::generate_stream",
+                    &correlation_id,
+                );
+            }
+
+            // Handle tool calls if present
+            if !accumulated_tool_calls.is_empty() {
+                if let Some(tools) = tools {
+                    info!("Processing {} tool call(s) in stream", accumulated_tool_calls.len());
+
+                    // Build new messages with tool results
+                    let mut new_messages = current_messages.clone();
+
+                    // Add assistant message with tool calls
+                    new_messages.push(LlmMessage {
+                        role: MessageRole::Assistant,
+                        content: Some(accumulated_content),
+                        tool_calls: Some(accumulated_tool_calls.clone()),
+                        image_paths: None,
+                        cache: false,
+                    });
+
+                    // Forward tool progress while the batch runs
+                    let (progress_tx, mut progress_rx) = tokio::sync::mpsc::unbounded_channel();
+                    let batch = self.run_tool_batch(
+                        &accumulated_tool_calls,
+                        tools,
+                        &correlation_id,
+                        "LlmBroker::generate_stream",
+                        Some(ToolProgressSender::new(progress_tx)),
+                    );
+                    tokio::pin!(batch);
+                    let batch_result = loop {
+                        let update = tokio::select! {
+                            result = &mut batch => break result,
+                            Some(update) = progress_rx.recv() => update,
+                        };
+                        yield Ok(StreamChunk::ToolProgress(update));
+                    };
+                    while let Ok(update) = progress_rx.try_recv() {
+                        yield Ok(StreamChunk::ToolProgress(update));
+                    }
+
+                    let outcomes = match batch_result {
+                        Ok(o) => o,
+                        Err(e) => {
+                            yield Err(e);
+                            return;
+                        }
+                    };
+
+                    if let Some(output) = terminal_output(&outcomes, tools) {
+                        info!("Terminal tool ran, returning its result");
+                        yield Ok(StreamChunk::Content(output));
+                        return;
+                    }
+
+                    new_messages.extend(tool_result_messages(
+                        &accumulated_tool_calls,
+                        &outcomes,
+                        config.tool_result_format,
+                    ));
+
+                    // Continue streaming with updated messages, incrementing depth
+                    let mut recursive_stream = Box::pin(self.generate_stream_with_depth(
+                        new_messages,
+                        Some(tools),
+                        config.clone(),
+                        correlation_id.clone(),
+                        depth + 1,
+                    ));
+
+                    while let Some(result) = recursive_stream.next().await {
+                        yield result;
+                    }
+                } else {
+                    warn!("LLM requested tool calls but no tools provided");
+                }
+            }
+        }
+    }
+}
+
+/// Whether `messages` end in an assistant turn for the model to continue
+fn is_continued(messages: &[LlmMessage]) -> bool {
+    messages.last().is_some_and(|m| m.role == MessageRole::Assistant)
+}
+
+/// Drop the assistant prefill from a structured request's config
+///
+/// The schema already shapes the output, and a partial turn would only break
+/// parsing the object.
+fn ignore_prefill(config: &mut CompletionConfig, source: &str) {
+    if config.assistant_prefill.take().is_some() {
+        warn!(source = source, "Ignoring assistant_prefill for a structured request");
+    }
+}
+
+/// `content` with the assistant prefill in front
+///
+/// A continued turn comes back as its continuation only; an instructed model
+/// usually repeats the prefix itself.
+fn prefixed(prefill: &str, continued: bool, content: String) -> String {
+    if continued || !content.starts_with(prefill) {
+        format!("{}{}", prefill, content)
+    } else {
+        content
+    }
+}
+
+/// Puts the assistant prefill in front of streamed content
+///
+/// Content is held back only until it's clear whether the model repeated the
+/// prefix, so an instructed stream doesn't yield it twice.
+struct PrefillWriter<'p> {
+    prefill: &'p str,
+    continued: bool,
+    /// Content seen before deciding, or `None` once decided
+    pending: Option<String>,
+}
+
+impl<'p> PrefillWriter<'p> {
+    fn new(prefill: &'p str, continued: bool) -> Self {
+        Self {
+            prefill,
+            continued,
+            pending: Some(String::new()),
+        }
+    }
+
+    /// The text to yield for a content chunk, if any yet
+    fn push(&mut self, content: &str) -> Option<String> {
+        let Some(pending) = &mut self.pending else {
+            return Some(content.to_string());
+        };
+        pending.push_str(content);
+        if !self.continued
+            && pending.len() < self.prefill.len()
+            && self.prefill.starts_with(&*pending)
+        {
+            return None;
+        }
+        self.pending
+            .take()
+            .map(|pending| prefixed(self.prefill, self.continued, pending))
+    }
+
+    /// The text still held back when the stream ends
+    ///
+    /// A response that only requests tools doesn't get the prefill.
+    fn finish(mut self, text_response: bool) -> Option<String> {
+        let pending = self.pending.take()?;
+        if text_response {
+            Some(prefixed(self.prefill, self.continued, pending))
+        } else {
+            Some(pending).filter(|pending| !pending.is_empty())
+        }
+    }
+}
+
+/// Build the tracer representation of a message history.
+///
+/// In [`TracerVerbosity::Summary`] mode only roles and sizes are recorded so that
+/// long histories aren't copied into every call event.
+fn trace_messages(
+    tracer: &TracerSystem,
+    messages: &[LlmMessage],
+) -> Vec<std::collections::HashMap<String, serde_json::Value>> {
+    messages
+        .iter()
+        .map(|m| {
+            let mut map = std::collections::HashMap::new();
+            map.insert("role".to_string(), serde_json::json!(format!("{:?}", m.role)));
+            match tracer.verbosity() {
+                TracerVerbosity::Full => {
+                    if let Some(content) = &m.content {
+                        map.insert("content".to_string(), serde_json::json!(content));
+                    }
+                }
+                TracerVerbosity::Summary => {
+                    let length = m.content.as_ref().map_or(0, |c| c.chars().count());
+                    map.insert("content_length".to_string(), serde_json::json!(length));
+                    if let Some(tool_calls) = &m.tool_calls {
+                        map.insert(
+                            "tool_call_count".to_string(),
+                            serde_json::json!(tool_calls.len()),
+                        );
+                    }
+                }
+            }
+            map
+        })
+        .collect()
+}
+
+/// `messages` without the oldest half of their non-system messages
+///
+/// The cut moves forward past tool results so none is kept without the
+/// assistant turn that asked for it. Returns `None` when nothing can be
+/// dropped without losing the last message.
+fn trim_oldest_history(messages: &[LlmMessage]) -> Option<Vec<LlmMessage>> {
+    let history: Vec<usize> = messages
+        .iter()
+        .enumerate()
+        .filter(|(_, m)| m.role != MessageRole::System)
+        .map(|(i, _)| i)
+        .collect();
+    let mut cut = history.len() / 2;
+    while cut < history.len() && messages[history[cut]].role == MessageRole::Tool {
+        cut += 1;
+    }
+    if cut == 0 || cut >= history.len() {
+        return None;
+    }
+
+    let dropped = &history[..cut];
+    Some(
+        messages
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !dropped.contains(i))
+            .map(|(_, m)| m.clone())
+            .collect(),
+    )
+}
+
+/// Cosine of the angle between two embeddings, or 0 if either is all zeros
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let denominator = norm(a) * norm(b);
+    if denominator == 0.0 {
+        0.0
+    } else {
+        dot / denominator
+    }
+}
+
+/// Remove calls that repeat the name and arguments of an earlier call
+fn without_duplicate_calls(tool_calls: Vec<LlmToolCall>) -> Vec<LlmToolCall> {
+    let mut unique: Vec<LlmToolCall> = Vec::with_capacity(tool_calls.len());
+    for call in tool_calls {
+        if unique
+            .iter()
+            .any(|kept| kept.name == call.name && kept.arguments == call.arguments)
+        {
+            warn!(
+                tool = %call.name,
+                id = call.id.as_deref().unwrap_or_default(),
+                "Dropping duplicate tool call"
+            );
+        } else {
+            unique.push(call);
+        }
+    }
+    unique
+}
+
+/// Arrange a runner's outcomes in the order the calls were made.
+///
+/// [`ToolRunner`] implementations must already return outcomes in call order;
+/// this pairs them by id so a runner that doesn't still can't shuffle results.
+/// Tool execution errors surface inside outcomes, so this only fails when the
+/// runner lost or invented an outcome.
+fn in_call_order(
+    executions: &[ToolCallExecution],
+    mut outcomes: Vec<ToolCallOutcome>,
+) -> Result<Vec<ToolCallOutcome>> {
+    if outcomes.len() != executions.len() {
+        return Err(MojenticError::ToolError(format!(
+            "tool runner returned {} outcomes for {} calls",
+            outcomes.len(),
+            executions.len()
+        )));
+    }
+    if executions.iter().zip(&outcomes).all(|(exec, outcome)| exec.id == outcome.id) {
+        return Ok(outcomes);
+    }
+
+    let mut ordered = Vec::with_capacity(executions.len());
+    for exec in executions {
+        match outcomes.iter().position(|outcome| outcome.id == exec.id) {
+            Some(pos) => ordered.push(outcomes.remove(pos)),
+            None => {
+                return Err(MojenticError::ToolError(format!(
+                    "tool runner returned no outcome for call '{}'",
+                    exec.id
+                )))
+            }
+        }
+    }
+    Ok(ordered)
+}
+
+/// Build the tool messages that report a batch of tool outcomes back to the LLM.
+///
+/// Failed calls are reported as `{"error": ...}` so the LLM can react to them.
+fn tool_result_messages(
+    calls: &[LlmToolCall],
+    outcomes: &[ToolCallOutcome],
+    format: ToolResultFormat,
+) -> Vec<LlmMessage> {
+    let results = outcomes.iter().map(|outcome| {
+        if outcome.ok {
+            outcome.result.clone().unwrap_or(serde_json::Value::Null)
+        } else {
+            serde_json::json!({ "error": outcome.error.clone().unwrap_or_default() })
+        }
+    });
+
+    if format == ToolResultFormat::Aggregated && calls.len() > 1 {
+        let aggregated: Vec<serde_json::Value> = outcomes
+            .iter()
+            .zip(results)
+            .map(|(outcome, result)| {
+                serde_json::json!({ "tool_call_id": outcome.id, "result": result })
+            })
+            .collect();
+        return vec![LlmMessage {
+            role: MessageRole::Tool,
+            content: Some(tool_result_content(&aggregated)),
+            tool_calls: Some(calls.to_vec()),
+            image_paths: None,
+            cache: false,
+        }];
+    }
+
+    calls
+        .iter()
+        .zip(results)
+        .map(|(call, result)| LlmMessage {
+            role: MessageRole::Tool,
+            content: Some(tool_result_content(&result)),
+            tool_calls: Some(vec![call.clone()]),
+            image_paths: None,
+            cache: false,
+        })
+        .collect()
+}
+
+/// Serialize a tool result for the LLM.
+///
+/// A result that can't be serialized becomes an `{"error": ...}` result, so
+/// one bad tool output doesn't abort the generation.
+fn tool_result_content(result: &impl Serialize) -> String {
+    serde_json::to_string(result).unwrap_or_else(|e| {
+        warn!(error = %e, "Tool result could not be serialized, reporting an error to the LLM");
+        serde_json::json!({ "error": format!("Tool result could not be serialized: {}", e) })
+            .to_string()
+    })
+}
+
+/// Asks the model to pick up an interrupted response (see [`LlmBroker::continue_stream`])
+const CONTINUE_PROMPT: &str =
+    "Your previous response was cut off. Continue it from exactly where it \
+     stopped, without repeating any of it or adding any preamble.";
+
+/// Image bytes written to a temporary file, removed when dropped
+struct SpilledImage {
+    path: String,
+}
+
+impl SpilledImage {
+    fn write(bytes: &[u8]) -> Result<Self> {
+        let extension = match bytes {
+            [0x89, b'P', b'N', b'G', ..] => "png",
+            [b'G', b'I', b'F', b'8', ..] => "gif",
+            [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => "webp",
+            _ => "jpg",
+        };
+        let path =
+            std::env::temp_dir().join(format!("mojentic-image-{}.{}", Uuid::new_v4(), extension));
+        std::fs::write(&path, bytes)?;
+        Ok(Self {
+            path: path.to_string_lossy().into_owned(),
+        })
+    }
+}
+
+impl Drop for SpilledImage {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            warn!(path = %self.path, error = %e, "Could not remove temporary image file");
+        }
+    }
+}
+
+/// The combined results of the terminal tools that succeeded in a batch, if any
+fn terminal_output(outcomes: &[ToolCallOutcome], tools: &[Box<dyn LlmTool>]) -> Option<String> {
+    let results: Vec<String> = outcomes
+        .iter()
+        .filter(|outcome| {
+            outcome.ok && tools.iter().any(|t| t.matches(&outcome.name) && t.terminal())
+        })
+        .map(|outcome| match &outcome.result {
+            Some(Value::String(text)) => text.clone(),
+            Some(value) => value.to_string(),
+            None => String::new(),
+        })
+        .collect();
+    (!results.is_empty()).then(|| results.join("\n"))
+}
+
+/// Approximate the prompt size of a request, in tokens.
+///
+/// Counts message content and tool definitions serialized as `provider`
+/// receives them, plus a small per-message allowance for role and framing
+/// tokens.
+pub(crate) fn estimate_prompt_tokens(
+    tokenizer: &TokenizerGateway,
+    messages: &[LlmMessage],
+    tools: Option<&[Box<dyn LlmTool>]>,
+    provider: ToolProvider,
+) -> usize {
+    const PER_MESSAGE_OVERHEAD: usize = 4;
+
+    let message_tokens: usize = messages
+        .iter()
+        .map(|m| {
+            let content = m.content.as_deref().map_or(0, |c| tokenizer.count_tokens(c));
+            let tool_calls = m.tool_calls.as_ref().map_or(0, |calls| {
+                tokenizer.count_tokens(&serde_json::to_string(calls).unwrap_or_default())
+            });
+            content + tool_calls + PER_MESSAGE_OVERHEAD
+        })
+        .sum();
+
+    let tool_tokens = tools.map_or(0, |tools| tokenizer.count_tool_tokens(tools, provider));
+
+    message_tokens + tool_tokens
+}
+
+/// Sum usage across calls, treating an unreported side as contributing nothing
+fn combine_usage(total: Option<TokenUsage>, next: Option<TokenUsage>) -> Option<TokenUsage> {
+    match (total, next) {
+        (Some(total), Some(next)) => Some(total + next),
+        (total, next) => total.or(next),
+    }
+}
+
+/// Cut a response at `max_chars` characters, appending the truncation marker
+fn truncate_response(content: String, max_chars: usize) -> String {
+    match content.char_indices().nth(max_chars) {
+        Some((cut, _)) => {
+            let mut truncated = content;
+            truncated.truncate(cut);
+            truncated.push_str(RESPONSE_TRUNCATION_MARKER);
+            truncated
+        }
+        None => content,
+    }
+}
+
+/// Forward text chunks until `max_chars` characters have been emitted.
+///
+/// Once the cap is hit, the rest of the chunk is dropped, the truncation marker
+/// is emitted, and the stream ends. Dropping `stream` cancels the upstream
+/// provider request. Chunks other than content pass through uncounted.
+fn cap_stream<'a>(
+    stream: impl Stream<Item = Result<StreamChunk>> + 'a,
+    max_chars: usize,
+) -> impl Stream<Item = Result<StreamChunk>> + 'a {
+    async_stream::stream! {
+        let mut stream = Box::pin(stream);
+        let mut remaining = max_chars;
+
+        while let Some(item) = stream.next().await {
+            let chunk = match item {
+                Ok(StreamChunk::Content(chunk)) => chunk,
+                Ok(other) => {
+                    yield Ok(other);
+                    continue;
+                }
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            };
+
+            let chunk_chars = chunk.chars().count();
+            if chunk_chars <= remaining {
+                remaining -= chunk_chars;
+                yield Ok(StreamChunk::Content(chunk));
+                continue;
+            }
+
+            let head: String = chunk.chars().take(remaining).collect();
+            if !head.is_empty() {
+                yield Ok(StreamChunk::Content(head));
+            }
+            info!("Response reached {} characters, stopping stream", max_chars);
+            yield Ok(StreamChunk::Content(RESPONSE_TRUNCATION_MARKER.to_string()));
+            return;
+        }
+    }
+}
+
+/// Pick the label that best matches a model's answer.
+///
+/// Prefers an exact match, then a case-insensitive one, then the longest label
+/// mentioned in the answer, and finally the label with the smallest edit distance.
+fn nearest_label<'l>(answer: &str, labels: &[&'l str]) -> &'l str {
+    if let Some(label) = labels.iter().find(|label| **label == answer) {
+        return label;
+    }
+
+    let answer = answer.trim().to_lowercase();
+    if let Some(label) = labels.iter().find(|label| label.to_lowercase() == answer) {
+        return label;
+    }
+
+    if let Some(label) = labels
+        .iter()
+        .filter(|label| answer.contains(&label.to_lowercase()))
+        .max_by_key(|label| label.len())
+    {
+        return label;
+    }
+
+    labels
+        .iter()
+        .min_by_key(|label| edit_distance(&answer, &label.to_lowercase()))
+        .copied()
+        .unwrap_or_default()
+}
+
+/// Levenshtein distance between two strings, counted in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// End a stream just before the first occurrence of any stop phrase.
+///
+/// Enough trailing text is held back to complete the longest phrase, so a
+/// phrase spanning chunk boundaries is never partially emitted. Chunks other
+/// than content pass through as they arrive.
+fn stop_at_phrases<'a>(
+    stream: impl Stream<Item = Result<StreamChunk>> + 'a,
+    phrases: &'a [String],
+) -> impl Stream<Item = Result<StreamChunk>> + 'a {
+    async_stream::stream! {
+        let mut stream = Box::pin(stream);
+        let hold_back = phrases.iter().map(String::len).max().unwrap_or(1) - 1;
+        let mut pending = String::new();
+
+        while let Some(item) = stream.next().await {
+            match item {
+                Ok(StreamChunk::Content(chunk)) => pending.push_str(&chunk),
+                Ok(other) => {
+                    yield Ok(other);
+                    continue;
+                }
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            }
+
+            let matched = phrases
+                .iter()
+                .filter_map(|phrase| pending.find(phrase.as_str()).map(|at| (at, phrase)))
+                .min_by_key(|(at, _)| *at);
+            if let Some((at, phrase)) = matched {
+                if at > 0 {
+                    yield Ok(StreamChunk::Content(pending[..at].to_string()));
+                }
+                info!("Stream produced stop phrase {:?}, stopping stream", phrase);
+                return;
+            }
+
+            let mut split = pending.len().saturating_sub(hold_back);
+            while !pending.is_char_boundary(split) {
+                split -= 1;
+            }
+            if split > 0 {
+                let tail = pending.split_off(split);
+                yield Ok(StreamChunk::Content(std::mem::replace(&mut pending, tail)));
+            }
+        }
+
+        if !pending.is_empty() {
+            yield Ok(StreamChunk::Content(pending));
+        }
+    }
+}
+
+/// Response content as it should be recorded for the tracer's verbosity.
+fn trace_content<'c>(tracer: &TracerSystem, content: &'c str) -> &'c str {
+    match tracer.verbosity() {
+        TracerVerbosity::Full => content,
+        TracerVerbosity::Summary => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::test_support::{
+        mock_tools, text_response, tool_call, tool_call_response, MockGateway, MockTool,
+    };
+    use crate::llm::tools::{FunctionDescriptor, ToolDescriptor};
+    use async_trait::async_trait;
+    use serde::{Deserialize, Serialize};
+    use serde_json::Value;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_broker_new() {
+        let gateway = Arc::new(MockGateway::new(vec![]));
+        let broker = LlmBroker::new("test-model", gateway, None);
+        assert_eq!(broker.model, "test-model");
+        assert_eq!(CompletionConfig::default().max_tool_iterations, 10);
+    }
+
+    #[tokio::test]
+    async fn test_generate_many_calls_gateway_once_per_choice_by_default() {
+        let responses = ["first", "second", "third"]
+            .iter()
+            .map(|content| text_response(content))
+            .collect();
+        let gateway = Arc::new(MockGateway::new(responses));
+        let broker = LlmBroker::new("test-model", gateway.clone(), None);
+
+        let choices = broker
+            .generate_many(
+                &[LlmMessage::user("Hello")],
+                None,
+                Some(CompletionConfig {
+                    n: Some(3),
+                    ..Default::default()
+                }),
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(choices, vec!["first", "second", "third"]);
+        assert_eq!(gateway.calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_generate_many_defaults_to_single_choice() {
+        let gateway = Arc::new(MockGateway::new(vec![]));
+        let broker = LlmBroker::new("test-model", gateway, None);
+
+        let choices = broker
+            .generate_many(&[LlmMessage::user("Hello")], None, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(choices, vec!["default response"]);
+    }
+
+    #[tokio::test]
+    async fn test_generate_many_retries_and_selects_tools() {
+        let gateway = Arc::new(MockGateway::scripted(vec![
+            Err(MojenticError::RateLimited {
+                retry_after: std::time::Duration::ZERO,
+            }),
+            Ok(text_response("Sunny.")),
+        ]));
+        let broker = LlmBroker::new("test-model", gateway.clone(), None)
+            .with_retries(1)
+            .with_embedding_gateway(Arc::new(KeywordEmbeddings))
+            .with_tool_selection(ToolSelection::TopK { k: 1 });
+        let tools = mock_tools(&["calculator", "get_weather"]);
+
+        let choices = broker
+            .generate_many(&[LlmMessage::user("What's the weather?")], Some(&tools), None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(choices, vec!["Sunny."]);
+        assert_eq!(gateway.calls(), 2);
+        assert_eq!(gateway.last_request().tool_names, vec!["get_weather"]);
+    }
+
+    #[tokio::test]
+    async fn test_broker_with_max_tool_iterations() {
+        assert_eq!(
+            CompletionConfig {
+                max_tool_iterations: 3,
+                ..Default::default()
+            }
+            .max_tool_iterations,
+            3
+        );
+    }
+
+    // Requests a nonexistent tool first, then echoes back the tool result it received
+    fn unknown_tool_gateway() -> Arc<MockGateway> {
+        Arc::new(MockGateway::new(vec![]).with_responder(|messages| {
+            let last = messages.last().unwrap();
+            Ok(if last.role == MessageRole::Tool {
+                text_response(last.content.as_deref().unwrap_or_default())
+            } else {
+                tool_call_response(vec![tool_call("call_1", "fly")])
+            })
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_unknown_tool_feeds_back_available_tools() {
+        let broker = LlmBroker::new("test-model", unknown_tool_gateway(), None);
+        let tools = mock_tools(&["walk", "swim"]);
+
+        let result = broker
+            .generate(&[LlmMessage::user("Get there")], Some(&tools), None, None)
+            .await
+            .unwrap();
+
+        assert!(result.contains(r#"Tool \"fly\" does not exist; available tools: walk, swim"#));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_tool_with_error_policy_fails() {
+        let broker = LlmBroker::new("test-model", unknown_tool_gateway(), None)
+            .with_tool_not_found_policy(ToolNotFoundPolicy::Error);
+        let tools = mock_tools(&["walk", "swim"]);
+
+        let result = broker
+            .generate(&[LlmMessage::user("Get there")], Some(&tools), None, None)
+            .await;
+
+        match result {
+            Err(MojenticError::ToolNotFound { name, available }) => {
+                assert_eq!(name, "fly");
+                assert_eq!(available, vec!["walk", "swim"]);
+            }
+            other => panic!("Expected ToolNotFound, got: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tracer_stats_count_tool_outcomes() {
+        struct BrokenTool;
+
+        #[async_trait]
+        impl LlmTool for BrokenTool {
+            async fn run(
+                &self,
+                _args: &HashMap<String, Value>,
+                _ctx: &crate::llm::tools::ToolRunCtx,
+            ) -> Result<Value> {
+                Err(MojenticError::ToolError("disk full".to_string()))
+            }
+
+            fn descriptor(&self) -> ToolDescriptor {
+                ToolDescriptor {
+                    r#type: "function".to_string(),
+                    function: FunctionDescriptor {
+                        name: "save".to_string(),
+                        description: "Always fails".to_string(),
+                        parameters: serde_json::json!({}),
+                        returns: None,
+                    },
+                }
+            }
+
+            fn clone_box(&self) -> Box<dyn LlmTool> {
+                Box::new(BrokenTool)
+            }
+        }
+        let gateway = Arc::new(MockGateway::new(vec![
+            tool_call_response(vec![tool_call("call_1", "lookup"), tool_call("call_2", "save")]),
+            text_response("Couldn't save"),
+        ]));
+        let tracer = Arc::new(TracerSystem::default());
+        let broker = LlmBroker::new("test-model", gateway, Some(tracer.clone()));
+        let tools: Vec<Box<dyn LlmTool>> = vec![
+            Box::new(MockTool::new("lookup", serde_json::json!("found"))),
+            Box::new(BrokenTool),
+        ];
+        broker
+            .generate(&[LlmMessage::user("Save it")], Some(&tools), None, None)
+            .await
+            .unwrap();
+
+        let strict = LlmBroker::new("test-model", unknown_tool_gateway(), Some(tracer.clone()))
+            .with_tool_not_found_policy(ToolNotFoundPolicy::Error);
+        let walk_and_swim = mock_tools(&["walk", "swim"]);
+        let result = strict
+            .generate(&[LlmMessage::user("Get there")], Some(&walk_and_swim), None, None)
+            .await;
+        assert!(result.is_err());
+
+        let stats = tracer.stats();
+        assert_eq!(
+            stats.tool("lookup"),
+            crate::tracer::ToolStats {
+                calls: 1,
+                ..Default::default()
+            }
+        );
+        assert_eq!(stats.tool("save").calls, 1);
+        assert_eq!(stats.tool("save").recoverable_errors, 1);
+        assert_eq!(stats.tool("save").hard_errors, 0);
+        assert_eq!(stats.tool("fly").hard_errors, 1);
+        assert_eq!(stats.tool("fly").failure_rate(), 1.0);
+        assert_eq!(stats.total_tool_calls(), 3);
+    }
+
+    fn two_tool_outcomes() -> (Vec<LlmToolCall>, Vec<ToolCallOutcome>) {
+        let calls = vec![
+            tool_call("call_1", "get_weather"),
+            tool_call("call_2", "get_time"),
+        ];
+        let outcomes = vec![
+            ToolCallOutcome {
+                id: "call_1".to_string(),
+                name: "get_weather".to_string(),
+                ok: true,
+                result: Some(serde_json::json!({"temp": 72})),
+                error: None,
+                duration_ms: 0,
+            },
+            ToolCallOutcome {
+                id: "call_2".to_string(),
+                name: "get_time".to_string(),
+                ok: false,
+                result: None,
+                error: Some("clock unavailable".to_string()),
+                duration_ms: 0,
+            },
+        ];
+        (calls, outcomes)
+    }
+
+    #[test]
+    fn test_tool_result_messages_separate() {
+        let (calls, outcomes) = two_tool_outcomes();
+
+        let messages = tool_result_messages(&calls, &outcomes, ToolResultFormat::Separate);
+
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].content.as_deref(), Some(r#"{"temp":72}"#));
+        assert_eq!(messages[1].content.as_deref(), Some(r#"{"error":"clock unavailable"}"#));
+        assert_eq!(messages[1].tool_calls.as_ref().unwrap()[0].name, "get_time");
+    }
+
+    #[test]
+    fn test_unserializable_tool_result_becomes_error_result() {
+        let result: HashMap<Vec<u8>, u32> = HashMap::from([(vec![1], 1)]);
+
+        let content: Value = serde_json::from_str(&tool_result_content(&result)).unwrap();
+
+        assert!(content["error"]
+            .as_str()
+            .unwrap()
+            .starts_with("Tool result could not be serialized"));
+    }
+
+    #[tokio::test]
+    async fn test_non_finite_tool_result_does_not_abort_generation() {
+        let tool_call = tool_call("call_1", "ratio");
+        let gateway = Arc::new(MockGateway::new(vec![tool_call_response(vec![tool_call])]));
+        let broker = LlmBroker::new("test-model", gateway.clone(), None);
+        let tools: Vec<Box<dyn LlmTool>> = vec![Box::new(MockTool::new(
+            "ratio",
+            serde_json::json!({"ratio": f64::NAN}),
+        ))];
+
+        let result = broker.generate(&[LlmMessage::user("Divide")], Some(&tools), None, None).await;
+
+        assert_eq!(result.unwrap(), "default response");
+        let messages = gateway.last_request().messages;
+        assert_eq!(messages.last().unwrap().content.as_deref(), Some(r#"{"ratio":null}"#));
+    }
+
+    #[test]
+    fn test_tool_result_messages_aggregated() {
+        let (calls, outcomes) = two_tool_outcomes();
+
+        let messages = tool_result_messages(&calls, &outcomes, ToolResultFormat::Aggregated);
+
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].role, MessageRole::Tool);
+        assert_eq!(messages[0].tool_calls.as_ref().unwrap().len(), 2);
+        let content: Value = serde_json::from_str(messages[0].content.as_deref().unwrap()).unwrap();
+        assert_eq!(
+            content,
+            serde_json::json!([
+                {"tool_call_id": "call_1", "result": {"temp": 72}},
+                {"tool_call_id": "call_2", "result": {"error": "clock unavailable"}}
+            ])
+        );
+    }
+
+    fn tiny_context_registry() -> OpenAIModelRegistry {
+        let mut registry = OpenAIModelRegistry::new();
+        let mut capabilities = registry.get_model_capabilities("gpt-4o");
+        capabilities.max_context_tokens = Some(32);
+        registry.register_model("tiny-model", capabilities);
+        registry
+    }
+
+    #[tokio::test]
+    async fn test_context_length_guard_rejects_oversized_prompt() {
+        let gateway = Arc::new(MockGateway::new(vec![]));
+        let broker = LlmBroker::new("tiny-model", gateway.clone(), None)
+            .with_context_length_guard(&tiny_context_registry());
+        let messages: Vec<LlmMessage> = (0..10)
+            .map(|i| LlmMessage::user(format!("This is message number {} of a long chat", i)))
+            .collect();
+
+        let result = broker.generate(&messages, None, None, None).await;
+
+        match result {
+            Err(MojenticError::ContextLengthExceeded { estimated, limit }) => {
+                assert_eq!(limit, 32);
+                assert!(estimated > limit);
+            }
+            other => panic!("Expected ContextLengthExceeded, got: {:?}", other),
+        }
+        assert_eq!(gateway.calls(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_context_length_guard_allows_prompt_within_limit() {
+        let gateway = Arc::new(MockGateway::new(vec![]));
+        let broker = LlmBroker::new("tiny-model", gateway, None)
+            .with_context_length_guard(&tiny_context_registry());
+
+        let result = broker.generate(&[LlmMessage::user("Hi")], None, None, None).await;
+
+        assert_eq!(result.unwrap(), "default response");
+    }
+
+    #[tokio::test]
+    async fn test_context_length_guard_checks_the_model_the_request_goes_to() {
+        let gateway = Arc::new(MockGateway::new(vec![]));
+        let broker = LlmBroker::new("gpt-4o", gateway.clone(), None)
+            .with_context_length_guard(&tiny_context_registry());
+        let messages: Vec<LlmMessage> = (0..10)
+            .map(|i| LlmMessage::user(format!("This is message number {} of a long chat", i)))
+            .collect();
+
+        broker.generate(&messages, None, None, None).await.unwrap();
+        let config = CompletionConfig {
+            model: Some("tiny-model".to_string()),
+            ..Default::default()
+        };
+        let result = broker.generate(&messages, None, Some(config), None).await;
+
+        assert!(matches!(result, Err(MojenticError::ContextLengthExceeded { limit: 32, .. })));
+        assert_eq!(gateway.calls(), 1);
+    }
+
+    #[test]
+    fn test_prompt_estimate_counts_tools_as_sent() {
+        let tokenizer = TokenizerGateway::default();
+        let messages = vec![LlmMessage::user("What's the weather?")];
+        let tools: Vec<Box<dyn LlmTool>> = (0..5)
+            .map(|i| {
+                Box::new(MockTool {
+                    name: format!("weather_tool_{}", i),
+                    result: serde_json::json!("ok"),
+                }) as Box<dyn LlmTool>
+            })
+            .collect();
+
+        let bare = estimate_prompt_tokens(&tokenizer, &messages, None, ToolProvider::OpenAI);
+        let one =
+            estimate_prompt_tokens(&tokenizer, &messages, Some(&tools[..1]), ToolProvider::OpenAI);
+        let five =
+            estimate_prompt_tokens(&tokenizer, &messages, Some(&tools), ToolProvider::OpenAI);
+
+        assert!(bare < one && one < five);
+        assert_eq!(five - bare, tokenizer.count_tool_tokens(&tools, ToolProvider::OpenAI));
+        let sent = crate::llm::tools::tools_to_provider_json(&tools, ToolProvider::OpenAI);
+        assert_eq!(five - bare, tokenizer.count_tokens(&sent.to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_generate_max_tool_iterations_exceeded() {
+        // Every response requests a tool call — should hit the limit
+        let tool_call = tool_call("call_loop", "loop_tool");
+
+        // Supply more responses than the limit so the gateway never runs dry
+        let responses: Vec<LlmGatewayResponse> =
+            (0..15).map(|_| tool_call_response(vec![tool_call.clone()])).collect();
+
+        let gateway = Arc::new(MockGateway::new(responses));
+        let broker = LlmBroker::new("test-model", gateway, None);
+
+        let tool = MockTool::new("loop_tool", serde_json::json!({"result": "loop"}));
+        let tools: Vec<Box<dyn LlmTool>> = vec![Box::new(tool)];
+
+        let messages = vec![LlmMessage::user("Loop forever")];
+        let result = broker
+            .generate(
+                &messages,
+                Some(&tools),
+                Some(CompletionConfig {
+                    max_tool_iterations: 3,
+                    ..Default::default()
+                }),
+                None,
+            )
+            .await;
+
+        assert!(result.is_err());
+        match result.unwrap_err() {
+            crate::error::MojenticError::MaxToolIterationsExceeded { limit } => {
+                assert_eq!(limit, 3);
+            }
+            e => panic!("Expected MaxToolIterationsExceeded, got: {:?}", e),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_generate_stream_max_tool_iterations_exceeded() {
+        // Every turn requests the tool again
+        let gateway = Arc::new((0..3).fold(MockGateway::new(vec![]), |gateway, _| {
+            gateway.with_stream(vec![StreamChunk::ToolCalls(vec![tool_call(
+                "call_loop",
+                "loop_tool",
+            )])])
+        }));
+        let broker = LlmBroker::new("test-model", gateway, None);
+
+        let tool = MockTool::new("loop_tool", serde_json::json!({"result": "loop"}));
+        let tools: Vec<Box<dyn LlmTool>> = vec![Box::new(tool)];
+
+        let messages = vec![LlmMessage::user("Loop forever")];
+        let mut stream = broker.generate_stream(
+            &messages,
+            Some(&tools),
+            Some(CompletionConfig {
+                max_tool_iterations: 2,
+                ..Default::default()
+            }),
+            None,
+        );
+
+        let mut found_error = false;
+        while let Some(result) = stream.next().await {
+            if let Err(crate::error::MojenticError::MaxToolIterationsExceeded { .. }) = result {
+                found_error = true;
+                break;
+            }
+        }
+        assert!(found_error, "Expected MaxToolIterationsExceeded error in stream");
+    }
+
+    struct MockEmbeddingGateway;
+
+    #[async_trait]
+    impl EmbeddingGateway for MockEmbeddingGateway {
+        async fn calculate_embeddings(&self, _text: &str, model: Option<&str>) -> Result<Vec<f32>> {
+            assert_eq!(model, Some("embed-model"));
+            Ok(vec![9.0, 8.0])
+        }
+    }
+
+    /// One dimension per topic, so similarity follows shared keywords
+    struct KeywordEmbeddings;
+
+    #[async_trait]
+    impl EmbeddingGateway for KeywordEmbeddings {
+        async fn calculate_embeddings(&self, text: &str, _model: Option<&str>) -> Result<Vec<f32>> {
+            let text = text.to_lowercase();
+            Ok(["weather", "time", "calculat", "email"]
+                .iter()
+                .map(|topic| if text.contains(topic) { 1.0 } else { 0.0 })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_selection_forwards_only_the_most_relevant_tools() {
+        let gateway = Arc::new(MockGateway::new(vec![]));
+        let broker = LlmBroker::new("test-model", gateway.clone(), None)
+            .with_embedding_gateway(Arc::new(KeywordEmbeddings))
+            .with_tool_selection(ToolSelection::TopK { k: 2 });
+        let tools: Vec<Box<dyn LlmTool>> = ["calculator", "get_time", "send_email", "get_weather"]
+            .into_iter()
+            .map(|name| -> Box<dyn LlmTool> {
+                Box::new(MockTool {
+                    name: name.to_string(),
+                    result: serde_json::json!("ok"),
+                })
+            })
+            .collect();
+        let messages = vec![LlmMessage::user(
+            "What's the weather and the time in Paris?",
+        )];
+
+        broker.generate(&messages, Some(&tools), None, None).await.unwrap();
+
+        let mut offered = gateway.last_request().tool_names;
+        offered.sort();
+        assert_eq!(offered, vec!["get_time", "get_weather"]);
+    }
+
+    #[tokio::test]
+    async fn test_calculate_embeddings_uses_distinct_embedding_gateway() {
+        let gateway = Arc::new(MockGateway::new(vec![text_response("chat response")]));
+        let broker = LlmBroker::new("test-model", gateway, None)
+            .with_embedding_gateway(Arc::new(MockEmbeddingGateway));
+
+        let embeddings = broker.calculate_embeddings("hello", Some("embed-model")).await.unwrap();
+        let response = broker.generate(&[LlmMessage::user("Hi")], None, None, None).await.unwrap();
+
+        assert_eq!(embeddings, vec![9.0, 8.0]);
+        assert_eq!(response, "chat response");
+    }
+
+    #[tokio::test]
+    async fn test_calculate_embeddings_falls_back_to_chat_gateway() {
+        let gateway = Arc::new(MockGateway::new(vec![]));
+        let broker = LlmBroker::new("test-model", gateway, None);
+
+        let embeddings = broker.calculate_embeddings("hello", None).await.unwrap();
+
+        assert_eq!(embeddings, vec![0.1, 0.2, 0.3]);
+    }
+
+    #[tokio::test]
+    async fn test_chat_only_gateway_reports_embeddings_unsupported() {
+        let gateway = Arc::new(MockGateway::new(vec![]).without_embeddings());
+        let broker = LlmBroker::new("test-model", gateway, None);
+
+        let result = broker.calculate_embeddings("hello", None).await;
+
+        assert!(matches!(result, Err(MojenticError::Unsupported(_))));
+    }
+
+    fn num_ctx_sent(gateway: &MockGateway) -> Vec<usize> {
+        gateway.requests().iter().map(|request| request.config.num_ctx).collect()
+    }
+
+    #[tokio::test]
+    async fn test_auto_context_length_sizes_num_ctx_to_model() {
+        let gateway = Arc::new(MockGateway::new(vec![]).with_context_length(2048));
+        let broker = LlmBroker::new("tiny-model", gateway.clone(), None).with_auto_context_length();
+        let messages = vec![LlmMessage::user("Hi")];
+
+        broker.generate(&messages, None, None, None).await.unwrap();
+        broker.generate_object::<Value>(&messages, None, None).await.unwrap();
+        broker.generate_stream(&messages, None, None, None).collect::<Vec<_>>().await;
+
+        assert_eq!(num_ctx_sent(&gateway), vec![2048, 2048, 2048]);
+        assert_eq!(gateway.context_lookups(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_auto_context_length_keeps_explicit_num_ctx() {
+        let gateway = Arc::new(MockGateway::new(vec![]).with_context_length(2048));
+        let broker = LlmBroker::new("tiny-model", gateway.clone(), None).with_auto_context_length();
+        let config = CompletionConfig {
+            num_ctx: 1024,
+            ..Default::default()
+        };
+
+        broker
+            .generate(&[LlmMessage::user("Hi")], None, Some(config), None)
+            .await
+            .unwrap();
+
+        assert_eq!(num_ctx_sent(&gateway), vec![1024]);
+    }
+
+    #[tokio::test]
+    async fn test_num_ctx_left_at_default_without_auto_context_length() {
+        let gateway = Arc::new(MockGateway::new(vec![]).with_context_length(2048));
+        let broker = LlmBroker::new("tiny-model", gateway.clone(), None);
+
+        broker.generate(&[LlmMessage::user("Hi")], None, None, None).await.unwrap();
+
+        assert_eq!(num_ctx_sent(&gateway), vec![DEFAULT_NUM_CTX]);
+        assert_eq!(gateway.context_lookups(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_trace_tags_are_queryable() {
+        let gateway = Arc::new(MockGateway::new(vec![]));
+        let tracer = Arc::new(TracerSystem::default());
+        let broker = LlmBroker::new("test-model", gateway, Some(tracer.clone()));
+        let tags = HashMap::from([("tenant".to_string(), "acme".to_string())]);
+        let messages = vec![LlmMessage::user("Hi")];
+
+        broker
+            .clone()
+            .with_trace_tags(tags)
+            .generate(&messages, None, None, Some("acme-request".to_string()))
+            .await
+            .unwrap();
+        broker
+            .generate(&messages, None, None, Some("other-request".to_string()))
+            .await
+            .unwrap();
+
+        let tagged = tracer.get_events_by_tag("tenant", "acme");
+        assert_eq!(tagged.len(), 2);
+        assert!(tagged.iter().all(|e| e.correlation_id() == "acme-request"));
+        assert_eq!(tagged[0].to_json()["tags"]["tenant"], "acme");
+        assert!(tracer.get_events_by_tag("tenant", "globex").is_empty());
+        assert_eq!(tracer.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_generate_once_returns_tool_calls_without_running_them() {
+        let tool_call = tool_call("call_1", "counting_tool");
+        let gateway = Arc::new(MockGateway::new(vec![LlmGatewayResponse {
+            content: None,
+            object: None,
+            tool_calls: vec![tool_call],
+            thinking: None,
+            usage: Some(TokenUsage::new(10, 2)),
+            payload_size: None,
+        }]));
+        let tracer = Arc::new(TracerSystem::default());
+        let broker = LlmBroker::new("test-model", gateway.clone(), Some(tracer.clone()));
+        let tools: Vec<Box<dyn LlmTool>> = vec![Box::new(MockTool::new(
+            "counting_tool",
+            serde_json::json!("ran"),
+        ))];
+
+        let response = broker
+            .generate_once(&[LlmMessage::user("Count")], Some(&tools), None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(response.tool_calls.len(), 1);
+        assert_eq!(response.tool_calls[0].name, "counting_tool");
+        assert_eq!(response.usage, Some(TokenUsage::new(10, 2)));
+        // One request and no tool call events: the tool never ran
+        assert_eq!(gateway.calls(), 1);
+        assert_eq!(tracer.len(), 2);
+        let tool_runs = tracer.count_events(
+            None,
+            None,
+            Some(&|e: &dyn crate::tracer::TracerEvent| {
+                e.printable_summary().contains("ToolCallTracerEvent")
+            }),
+        );
+        assert_eq!(tool_runs, 0);
+    }
+
+    #[tokio::test]
+    async fn test_generate_full_reports_tools_used() {
+        let turn = |tool_calls: Vec<LlmToolCall>, content: Option<&str>| LlmGatewayResponse {
+            content: content.map(String::from),
+            object: None,
+            tool_calls,
+            thinking: None,
+            usage: None,
+            payload_size: None,
+        };
+        let gateway = Arc::new(MockGateway::new(vec![
+            turn(
+                vec![
+                    tool_call("call_1", "search"),
+                    tool_call("call_2", "calculator"),
+                ],
+                None,
+            ),
+            turn(vec![tool_call("call_3", "search")], None),
+            turn(vec![], Some("42")),
+        ]));
+        let broker = LlmBroker::new("test-model", gateway, None);
+        let tools: Vec<Box<dyn LlmTool>> = ["search", "calculator"]
+            .into_iter()
+            .map(|name| {
+                Box::new(MockTool {
+                    name: name.to_string(),
+                    result: serde_json::json!("ok"),
+                }) as Box<dyn LlmTool>
+            })
+            .collect();
+
+        let result = broker
+            .generate_full(&[LlmMessage::user("Work it out")], Some(&tools), None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(result.content, "42");
+        assert_eq!(result.tools_used, vec!["search", "calculator"]);
+        assert_eq!(result.tool_call_counts["search"], 2);
+        assert_eq!(result.tool_call_counts["calculator"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_context_overflow_trims_history_and_retries() {
+        // Rejects any prompt longer than four messages, like a small context window
+        let gateway = Arc::new(MockGateway::new(vec![]).with_responder(|messages| {
+            if messages.len() > 4 {
+                return Err(MojenticError::ContextLengthExceeded {
+                    estimated: 9000,
+                    limit: 8192,
+                });
+            }
+            Ok(text_response("Still here."))
+        }));
+        let messages = vec![
+            LlmMessage::system("Be helpful."),
+            LlmMessage::user("Look something up"),
+            LlmMessage {
+                role: MessageRole::Assistant,
+                content: None,
+                tool_calls: Some(vec![tool_call("call_1", "lookup")]),
+                image_paths: None,
+                cache: false,
+            },
+            LlmMessage::tool_result("call_1", "lookup", &serde_json::json!("found it")),
+            LlmMessage::assistant("I found it."),
+            LlmMessage::user("Great, what next?"),
+        ];
+        let broker = LlmBroker::new("test-model", gateway.clone(), None);
+
+        let result = broker.generate(&messages, None, None, None).await;
+        assert!(matches!(result, Err(MojenticError::ContextLengthExceeded { .. })));
+
+        let broker = broker.with_context_overflow_trimming();
+        let content = broker.generate(&messages, None, None, None).await.unwrap();
+
+        assert_eq!(content, "Still here.");
+        let retried = gateway.last_request().messages;
+        let contents: Vec<Option<&str>> = retried.iter().map(|m| m.content.as_deref()).collect();
+        assert_eq!(
+            contents,
+            vec![
+                Some("Be helpful."),
+                Some("I found it."),
+                Some("Great, what next?")
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retry_budget_exhausted_mid_run_stops_retrying() {
+        let rate_limited = || {
+            Err(MojenticError::RateLimited {
+                retry_after: std::time::Duration::ZERO,
+            })
+        };
+        let gateway = Arc::new(
+            MockGateway::scripted(vec![
+                rate_limited(),
+                rate_limited(),
+                Ok(text_response("Done.")),
+                rate_limited(),
+            ])
+            .with_responder(|_| Ok(text_response("Done."))),
+        );
+        let budget = RetryBudget::new(2);
+        let broker = LlmBroker::new("test-model", gateway.clone(), None)
+            .with_retries(3)
+            .with_retry_budget(budget.clone());
+        let run = broker.child("run-1");
+        let messages = [LlmMessage::user("Hi")];
+
+        let first = run.generate(&messages, None, None, None).await.unwrap();
+        assert_eq!(first, "Done.");
+        assert_eq!(gateway.calls(), 3);
+        assert_eq!(budget.remaining("run-1"), 0);
+
+        let second = run.generate(&messages, None, None, None).await;
+        assert!(matches!(second, Err(MojenticError::RateLimited { .. })));
+        assert_eq!(gateway.calls(), 4);
+
+        // Another run has its own budget
+        let other = broker.child("run-2").generate(&messages, None, None, None).await.unwrap();
+        assert_eq!(other, "Done.");
+    }
+
+    #[tokio::test]
+    async fn test_tool_loop_follow_up_is_retried() {
+        let gateway = Arc::new(MockGateway::scripted(vec![
+            Ok(tool_call_response(vec![tool_call("call_1", "lookup")])),
+            Err(MojenticError::RateLimited {
+                retry_after: std::time::Duration::ZERO,
+            }),
+            Ok(text_response("Found it.")),
+        ]));
+        let tracer = Arc::new(TracerSystem::default());
+        let broker =
+            LlmBroker::new("test-model", gateway.clone(), Some(tracer.clone())).with_retries(1);
+        let tools = mock_tools(&["lookup"]);
+
+        let content = broker
+            .generate(&[LlmMessage::user("Look it up")], Some(&tools), None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(content, "Found it.");
+        assert_eq!(gateway.calls(), 3);
+        // Two traced calls and responses, plus the tool call
+        assert_eq!(tracer.len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_retry_budget_releases_calls_without_a_correlation_id() {
+        let rate_limited = || {
+            Err(MojenticError::RateLimited {
+                retry_after: std::time::Duration::ZERO,
+            })
+        };
+        let gateway = Arc::new(MockGateway::scripted(vec![
+            rate_limited(),
+            Ok(text_response("Hello.")),
+            rate_limited(),
+        ]));
+        let budget = RetryBudget::new(5);
+        let broker = LlmBroker::new("test-model", gateway.clone(), None)
+            .with_retries(1)
+            .with_retry_budget(budget.clone());
+        let messages = [LlmMessage::user("Hi")];
+
+        broker.generate(&messages, None, None, None).await.unwrap();
+        assert_eq!(budget.tracked_runs(), 0);
+
+        broker.generate(&messages, None, None, Some("run-1".to_string())).await.unwrap();
+        assert_eq!(budget.remaining("run-1"), 4);
+        assert_eq!(gateway.calls(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_default_system_prompt_is_prepended_only_when_missing() {
+        let gateway = Arc::new(MockGateway::new(vec![]));
+        let broker = LlmBroker::new("test-model", gateway.clone(), None)
+            .with_system_prompt("You are terse.");
+
+        broker.generate(&[LlmMessage::user("Hi")], None, None, None).await.unwrap();
+        let sent = gateway.last_request().messages;
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[0].role, MessageRole::System);
+        assert_eq!(sent[0].content.as_deref(), Some("You are terse."));
+
+        let messages = vec![
+            LlmMessage::system("You are verbose."),
+            LlmMessage::user("Hi"),
+        ];
+        broker.generate(&messages, None, None, None).await.unwrap();
+        let sent = gateway.last_request().messages;
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[0].content.as_deref(), Some("You are verbose."));
+        assert_eq!(sent.iter().filter(|m| m.role == MessageRole::System).count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_default_system_prompt_reaches_every_entry_point() {
+        #[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+        struct TestObject {
+            test: String,
+        }
+
+        let gateway = Arc::new(
+            MockGateway::new(vec![])
+                .with_stream_text(&["Hello"])
+                .with_stream_text(&[r#"{"test": "value"}"#]),
+        );
+        let broker = LlmBroker::new("test-model", gateway.clone(), None)
+            .with_system_prompt("You are terse.")
+            .with_response_language("French");
+        let messages = vec![LlmMessage::user("Hi")];
+
+        let _: TestObject = broker.generate_object(&messages, None, None).await.unwrap();
+        let config = CompletionConfig {
+            n: Some(2),
+            ..Default::default()
+        };
+        broker.generate_many(&messages, None, Some(config), None).await.unwrap();
+        let _: Vec<_> = broker.generate_stream(&messages, None, None, None).collect().await;
+        let _: Vec<_> = broker
+            .generate_object_partial::<TestObject, serde_json::Value>(&messages, None, None)
+            .collect()
+            .await;
+
+        let requests = gateway.requests();
+        assert_eq!(requests.len(), 5);
+        for request in requests {
+            assert_eq!(request.messages.len(), 2, "{:?}", request.kind);
+            assert_eq!(request.messages[0].role, MessageRole::System);
+            assert_eq!(
+                request.messages[0].content.as_deref(),
+                Some("You are terse.\n\nRespond in French.")
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_assistant_prefill_is_sent_as_partial_assistant_message() {
+        let gateway = Arc::new(
+            MockGateway::new(vec![text_response("\"answer\": 42}")]).with_prefill_support(),
+        );
+        let broker = LlmBroker::new("test-model", gateway.clone(), None);
+        let config = CompletionConfig {
+            assistant_prefill: Some("{".to_string()),
+            ..Default::default()
+        };
+
+        let content = broker
+            .generate(&[LlmMessage::user("Answer in JSON")], None, Some(config), None)
+            .await
+            .unwrap();
+
+        assert_eq!(content, "{\"answer\": 42}");
+        let sent = gateway.last_request().messages;
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[1].role, MessageRole::Assistant);
+        assert_eq!(sent[1].content.as_deref(), Some("{"));
+    }
+
+    #[tokio::test]
+    async fn test_assistant_prefill_leads_each_of_many_choices() {
+        let gateway = Arc::new(
+            MockGateway::new(vec![text_response("\"a\": 1}"), text_response("\"a\": 2}")])
+                .with_prefill_support(),
+        );
+        let broker = LlmBroker::new("test-model", gateway.clone(), None);
+        let config = CompletionConfig {
+            n: Some(2),
+            assistant_prefill: Some("{".to_string()),
+            ..Default::default()
+        };
+
+        let choices = broker
+            .generate_many(&[LlmMessage::user("Answer in JSON")], None, Some(config), None)
+            .await
+            .unwrap();
+
+        assert_eq!(choices, vec!["{\"a\": 1}", "{\"a\": 2}"]);
+        let sent = gateway.last_request().messages;
+        assert_eq!(sent.last().unwrap().role, MessageRole::Assistant);
+    }
+
+    #[tokio::test]
+    async fn test_structured_requests_ignore_assistant_prefill() {
+        let gateway = Arc::new(MockGateway::new(vec![]).with_prefill_support());
+        let broker = LlmBroker::new("test-model", gateway.clone(), None);
+        let config = CompletionConfig {
+            assistant_prefill: Some("{".to_string()),
+            ..Default::default()
+        };
+
+        let _: serde_json::Value = broker
+            .generate_object(&[LlmMessage::user("Answer in JSON")], Some(config), None)
+            .await
+            .unwrap();
+
+        let sent = gateway.last_request().messages;
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].role, MessageRole::User);
+    }
+
+    #[tokio::test]
+    async fn test_assistant_prefill_becomes_an_instruction_without_support() {
+        let gateway = Arc::new(MockGateway::new(vec![]));
+        let broker = LlmBroker::new("test-model", gateway.clone(), None);
+        let config = CompletionConfig {
+            assistant_prefill: Some("Sure,".to_string()),
+            ..Default::default()
+        };
+
+        let content = broker
+            .generate(&[LlmMessage::user("Help me")], None, Some(config), None)
+            .await
+            .unwrap();
+
+        assert_eq!(content, "Sure,default response");
+        let sent = gateway.last_request().messages;
+        assert_eq!(sent.len(), 1);
+        assert_eq!(
+            sent[0].content.as_deref(),
+            Some("Help me\n\nBegin your response with exactly: Sure,")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_assistant_prefill_leads_a_continued_stream() {
+        let gateway = Arc::new(
+            MockGateway::new(vec![])
+                .with_prefill_support()
+                .with_stream_text(&["\"answer\"", ": 42}"]),
+        );
+        let broker = LlmBroker::new("test-model", gateway.clone(), None);
+        let config = CompletionConfig {
+            assistant_prefill: Some("{".to_string()),
+            ..Default::default()
+        };
+        let messages = [LlmMessage::user("Answer in JSON")];
+
+        let chunks: Vec<String> = broker
+            .generate_stream(&messages, None, Some(config), None)
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(chunks.concat(), "{\"answer\": 42}");
+        let sent = gateway.last_request().messages;
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[1].role, MessageRole::Assistant);
+        assert_eq!(sent[1].content.as_deref(), Some("{"));
+    }
+
+    #[tokio::test]
+    async fn test_assistant_prefill_is_not_repeated_in_an_instructed_stream() {
+        let gateway = Arc::new(
+            MockGateway::new(vec![])
+                .with_stream_text(&["Su", "re, here", " it is"])
+                .with_stream_text(&["Hello"]),
+        );
+        let broker = LlmBroker::new("test-model", gateway.clone(), None);
+        let config = CompletionConfig {
+            assistant_prefill: Some("Sure,".to_string()),
+            ..Default::default()
+        };
+        let messages = [LlmMessage::user("Help me")];
+
+        for expected in ["Sure, here it is", "Sure,Hello"] {
+            let chunks: Vec<String> = broker
+                .generate_stream(&messages, None, Some(config.clone()), None)
+                .map(|chunk| chunk.unwrap())
+                .collect()
+                .await;
+            assert_eq!(chunks.concat(), expected);
+        }
+        let sent = gateway.last_request().messages;
+        assert_eq!(
+            sent[0].content.as_deref(),
+            Some("Help me\n\nBegin your response with exactly: Sure,")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_assistant_prefill_applies_to_tool_loop_follow_ups() {
+        let gateway = Arc::new(
+            MockGateway::new(vec![
+                tool_call_response(vec![tool_call("call_1", "lookup")]),
+                text_response("\"answer\": 42}"),
+            ])
+            .with_prefill_support(),
+        );
+        let broker = LlmBroker::new("test-model", gateway.clone(), None);
+        let tools = mock_tools(&["lookup"]);
+        let config = CompletionConfig {
+            assistant_prefill: Some("{".to_string()),
+            ..Default::default()
+        };
+
+        let content = broker
+            .generate(&[LlmMessage::user("Answer in JSON")], Some(&tools), Some(config), None)
+            .await
+            .unwrap();
+
+        assert_eq!(content, "{\"answer\": 42}");
+        let sent = gateway.last_request().messages;
+        assert_eq!(sent.last().unwrap().content.as_deref(), Some("{"));
+        let tool_turn = sent.iter().find(|m| m.tool_calls.is_some()).unwrap();
+        assert_ne!(tool_turn.content.as_deref(), Some("{"));
+    }
+
+    #[tokio::test]
+    async fn test_response_language_is_added_to_system_message() {
+        let gateway = Arc::new(MockGateway::new(vec![]));
+        let broker =
+            LlmBroker::new("test-model", gateway.clone(), None).with_response_language("French");
+        let messages = vec![LlmMessage::system("You are terse."), LlmMessage::user("Hi")];
+
+        broker.generate(&messages, None, None, None).await.unwrap();
+        let sent = gateway.last_request().messages;
+        assert_eq!(sent[0].content.as_deref(), Some("You are terse.\n\nRespond in French."));
+        assert_eq!(messages[0].content.as_deref(), Some("You are terse."));
+
+        let config = CompletionConfig {
+            response_language: Some("German".to_string()),
+            ..Default::default()
+        };
+        broker
+            .generate(&[LlmMessage::user("Hi")], None, Some(config), None)
+            .await
+            .unwrap();
+        let sent = gateway.last_request().messages;
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[0].role, MessageRole::System);
+        assert_eq!(sent[0].content.as_deref(), Some("Respond in German."));
+    }
+
+    #[tokio::test]
+    async fn test_describe_image_sends_image_bytes_to_vision_model() {
+        let gateway = Arc::new(MockGateway::new(vec![text_response("A red square.")]));
+        let broker = LlmBroker::new("vision-model", gateway.clone(), None);
+        let png = vec![0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+
+        let description = broker.describe_image(png, "Describe this image").await.unwrap();
+
+        assert_eq!(description, "A red square.");
+        let sent = gateway.last_request().messages;
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].content.as_deref(), Some("Describe this image"));
+        let path = &sent[0].image_paths.as_ref().unwrap()[0];
+        assert!(path.ends_with(".png"));
+        assert!(!std::path::Path::new(path).exists());
+    }
+
+    #[tokio::test]
+    async fn test_describe_image_rejects_models_without_vision() {
+        let gateway = Arc::new(MockGateway::new(vec![]).without_vision());
+        let broker = LlmBroker::new("text-model", gateway.clone(), None);
+
+        let result = broker.describe_image("photo.jpg", "Describe this image").await;
+
+        assert!(matches!(result, Err(MojenticError::ModelNotSupported(_))));
+        assert_eq!(gateway.calls(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_describe_model_asks_gateway_about_broker_model() {
+        let gateway = Arc::new(MockGateway::new(vec![]).without_vision());
+        let broker = LlmBroker::new("test-model", gateway, None);
+
+        let description = broker.describe_model().await.unwrap();
+
+        assert_eq!(description.name, "test-model");
+        assert!(description.available);
+        assert!(!description.supports_vision);
+    }
+
+    #[tokio::test]
+    async fn test_generate_object_with_tools_runs_tools_then_parses_object() {
+        #[derive(Debug, Deserialize, schemars::JsonSchema, PartialEq)]
+        struct Forecast {
+            city: String,
+            high: i32,
+        }
+
+        let gateway = Arc::new(MockGateway::new(vec![
+            LlmGatewayResponse {
+                content: None,
+                object: None,
+                tool_calls: vec![tool_call("call_1", "weather")],
+                thinking: None,
+                usage: None,
+                payload_size: None,
+            },
+            text_response(r#"{"city": "Oslo", "high": 21}"#),
+        ]));
+        let tracer = Arc::new(TracerSystem::default());
+        let broker = LlmBroker::new("test-model", gateway.clone(), Some(tracer.clone()));
+        let tools: Vec<Box<dyn LlmTool>> = vec![Box::new(MockTool::new(
+            "weather",
+            serde_json::json!({"high_c": 21}),
+        ))];
+
+        let forecast: Forecast = broker
+            .generate_object_with_tools(&[LlmMessage::user("Oslo forecast?")], &tools, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            forecast,
+            Forecast {
+                city: "Oslo".to_string(),
+                high: 21,
+            }
+        );
+        let tool_events = tracer.count_events(
+            None,
+            None,
+            Some(&|e: &dyn crate::tracer::TracerEvent| {
+                e.printable_summary().contains("ToolCallTracerEvent")
+            }),
+        );
+        assert_eq!(tool_events, 1);
+        let requests = gateway.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests
+            .iter()
+            .all(|request| request.schema.as_ref().unwrap()["properties"]["high"].is_object()));
+    }
+
+    #[tokio::test]
+    async fn test_generate_object_with_tools_sends_terminal_results_back_to_the_model() {
+        #[derive(Debug, Deserialize, schemars::JsonSchema, PartialEq)]
+        struct Report {
+            chart: String,
+        }
+
+        let gateway = Arc::new(MockGateway::new(vec![
+            tool_call_response(vec![tool_call("call_1", "render_chart")]),
+            text_response(r#"{"chart": "https://charts.example.com/sales.png"}"#),
+        ]));
+        let broker = LlmBroker::new("test-model", gateway.clone(), None);
+        let tools: Vec<Box<dyn LlmTool>> = vec![Box::new(ChartTool)];
+
+        let report: Report = broker
+            .generate_object_with_tools(&[LlmMessage::user("Chart our sales")], &tools, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(report.chart, "https://charts.example.com/sales.png");
+        assert_eq!(gateway.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_generate_object_with_tools_parses_unprocessed_content() {
+        #[derive(Debug, Deserialize, schemars::JsonSchema, PartialEq)]
+        struct Forecast {
+            city: String,
+            high: i32,
+        }
+
+        let gateway =
+            Arc::new(MockGateway::new(vec![text_response(r#"{"city": "Oslo", "high": 21}"#)]));
+        let broker = LlmBroker::new("test-model", gateway, None)
+            .with_response_post_processor(|content| content.to_uppercase())
+            .with_max_response_chars(10);
+
+        let forecast: Forecast = broker
+            .generate_object_with_tools(&[LlmMessage::user("Oslo forecast?")], &[], None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(forecast.city, "Oslo");
+        assert_eq!(forecast.high, 21);
+    }
+
+    /// A terminal tool whose result is a chart URL
+    struct ChartTool;
+
+    #[async_trait]
+    impl LlmTool for ChartTool {
+        async fn run(
+            &self,
+            _args: &HashMap<String, Value>,
+            _ctx: &crate::llm::tools::ToolRunCtx,
+        ) -> Result<Value> {
+            Ok(serde_json::json!("https://charts.example.com/sales.png"))
+        }
+
+        fn descriptor(&self) -> ToolDescriptor {
+            ToolDescriptor {
+                r#type: "function".to_string(),
+                function: FunctionDescriptor {
+                    name: "render_chart".to_string(),
+                    description: "Render a chart".to_string(),
+                    parameters: serde_json::json!({}),
+                    returns: None,
+                },
+            }
+        }
+
+        fn terminal(&self) -> bool {
+            true
+        }
+
+        fn clone_box(&self) -> Box<dyn LlmTool> {
+            Box::new(ChartTool)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_terminal_tool_result_ends_generation() {
+        let gateway = Arc::new(MockGateway::new(vec![LlmGatewayResponse {
+            content: None,
+            object: None,
+            tool_calls: vec![tool_call("call_1", "render_chart")],
+            thinking: None,
+            usage: None,
+            payload_size: None,
+        }]));
+        let broker = LlmBroker::new("test-model", gateway.clone(), None);
+        let tools: Vec<Box<dyn LlmTool>> = vec![Box::new(ChartTool)];
+
+        let result = broker
+            .generate_full(&[LlmMessage::user("Chart our sales")], Some(&tools), None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(result.content, "https://charts.example.com/sales.png");
+        assert_eq!(result.tools_used, vec!["render_chart"]);
+        assert_eq!(gateway.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_generate_full_without_tool_calls_reports_no_tools() {
+        let gateway = Arc::new(MockGateway::new(vec![]));
+        let broker = LlmBroker::new("test-model", gateway, None);
+
+        let result =
+            broker.generate_full(&[LlmMessage::user("Hi")], None, None, None).await.unwrap();
+
+        assert!(result.tools_used.is_empty());
+        assert!(result.tool_call_counts.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_broker_new_string_conversion() {
+        let gateway = Arc::new(MockGateway::new(vec![]));
+        let broker = LlmBroker::new(String::from("my-model"), gateway, None);
+        assert_eq!(broker.model, "my-model");
+    }
+
+    #[tokio::test]
+    async fn test_generate_simple_response() {
+        let response = text_response("Hello, World!");
+
+        let gateway = Arc::new(MockGateway::new(vec![response]));
+        let broker = LlmBroker::new("test-model", gateway, None);
+
+        let messages = vec![LlmMessage::user("Hi")];
+        let result = broker.generate(&messages, None, None, None).await.unwrap();
+
+        assert_eq!(result, "Hello, World!");
+    }
+
+    #[tokio::test]
+    async fn test_generate_with_custom_config() {
+        let response = text_response("Response");
+
+        let gateway = Arc::new(MockGateway::new(vec![response]));
+        let broker = LlmBroker::new("test-model", gateway, None);
+
+        let config = CompletionConfig {
+            temperature: 0.5,
+            num_ctx: 2048,
+            max_tokens: 100,
+            num_predict: Some(50),
+            top_p: None,
+            top_k: None,
+            response_format: None,
+            reasoning_effort: None,
+            thinking_budget_tokens: None,
+            max_tool_iterations: 10,
+            n: None,
+            tool_result_format: Default::default(),
+            logit_bias: None,
+            user: None,
+            model: None,
+            response_language: None,
+            assistant_prefill: None,
+            extra: HashMap::new(),
+        };
+
+        let messages = vec![LlmMessage::user("Hi")];
+        let result = broker.generate(&messages, None, Some(config), None).await.unwrap();
+
+        assert_eq!(result, "Response");
+    }
+
+    #[tokio::test]
+    async fn test_generate_empty_response_content() {
+        let response = tool_call_response(vec![]);
+
+        let gateway = Arc::new(MockGateway::new(vec![response]));
+        let broker = LlmBroker::new("test-model", gateway, None);
+
+        let messages = vec![LlmMessage::user("Hi")];
+        let result = broker.generate(&messages, None, None, None).await.unwrap();
+
+        assert_eq!(result, "");
+    }
+
+    #[tokio::test]
+    async fn test_generate_with_tool_call() {
+        let tool_call = tool_call("call_1", "test_tool");
+
+        let first_response = tool_call_response(vec![tool_call]);
+
+        let second_response = text_response("After tool execution");
+
+        let gateway = Arc::new(MockGateway::new(vec![first_response, second_response]));
+        let broker = LlmBroker::new("test-model", gateway, None);
+
+        let tool = MockTool::new("test_tool", serde_json::json!({"result": "success"}));
+
+        let tools: Vec<Box<dyn LlmTool>> = vec![Box::new(tool)];
+
+        let messages = vec![LlmMessage::user("Use the tool")];
+        let result = broker.generate(&messages, Some(&tools), None, None).await.unwrap();
+
+        assert_eq!(result, "After tool execution");
+    }
+
+    fn two_ordered_tool_calls() -> Vec<LlmGatewayResponse> {
+        vec![
+            tool_call_response(vec![
+                tool_call("call_1", "slow_tool"),
+                tool_call("call_2", "fast_tool"),
+            ]),
+            text_response("Done"),
+        ]
+    }
+
+    fn tool_message_ids(messages: &[LlmMessage]) -> Vec<(String, String)> {
+        messages
+            .iter()
+            .filter(|m| m.role == MessageRole::Tool)
+            .map(|m| {
+                let call = &m.tool_calls.as_ref().unwrap()[0];
+                (call.id.clone().unwrap(), m.content.clone().unwrap())
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_deduplication_runs_identical_calls_once() {
+        let call = |id: &str, query: &str| LlmToolCall {
+            id: Some(id.to_string()),
+            name: "lookup".to_string(),
+            arguments: HashMap::from([("q".to_string(), serde_json::json!(query))]),
+        };
+        let gateway = Arc::new(MockGateway::new(vec![
+            LlmGatewayResponse {
+                content: None,
+                object: None,
+                tool_calls: vec![
+                    call("call_1", "x"),
+                    call("call_2", "x"),
+                    call("call_3", "y"),
+                ],
+                thinking: None,
+                usage: None,
+                payload_size: None,
+            },
+            text_response("Done"),
+        ]));
+        let tracer = Arc::new(TracerSystem::default());
+        let broker = LlmBroker::new("test-model", gateway.clone(), Some(tracer.clone()))
+            .with_tool_call_deduplication(true);
+        let tools: Vec<Box<dyn LlmTool>> = vec![Box::new(MockTool::new(
+            "lookup",
+            serde_json::json!("found"),
+        ))];
+
+        let result = broker.generate(&[LlmMessage::user("Look")], Some(&tools), None, None).await;
+
+        assert_eq!(result.unwrap(), "Done");
+        let tool_runs = tracer.count_events(
+            None,
+            None,
+            Some(&|e: &dyn crate::tracer::TracerEvent| {
+                e.printable_summary().contains("ToolCallTracerEvent")
+            }),
+        );
+        assert_eq!(tool_runs, 2);
+        let messages = gateway.last_request().messages;
+        let ids: Vec<String> = tool_message_ids(&messages).into_iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec!["call_1", "call_3"]);
+        let assistant = messages.iter().find(|m| m.role == MessageRole::Assistant).unwrap();
+        assert_eq!(assistant.tool_calls.as_ref().unwrap().len(), 2);
     }
 
-    impl MockGateway {
-        fn new(responses: Vec<LlmGatewayResponse>) -> Self {
-            Self {
-                responses,
-                call_count: std::sync::Mutex::new(0),
-            }
+    #[tokio::test]
+    async fn test_max_concurrent_tools_bounds_parallel_execution() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        struct CountingTool {
+            running: Arc<AtomicUsize>,
+            max_seen: Arc<AtomicUsize>,
         }
-    }
 
-    #[async_trait::async_trait]
-    impl LlmGateway for MockGateway {
-        async fn complete(
-            &self,
-            _model: &str,
-            _messages: &[LlmMessage],
-            _tools: Option<&[Box<dyn LlmTool>]>,
-            _config: &CompletionConfig,
-        ) -> Result<LlmGatewayResponse> {
-            let mut count = self.call_count.lock().unwrap();
-            let idx = *count;
-            *count += 1;
+        #[async_trait]
+        impl LlmTool for CountingTool {
+            async fn run(
+                &self,
+                _args: &HashMap<String, Value>,
+                _ctx: &crate::llm::tools::ToolRunCtx,
+            ) -> Result<Value> {
+                let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+                self.max_seen.fetch_max(running, Ordering::SeqCst);
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                self.running.fetch_sub(1, Ordering::SeqCst);
+                Ok(serde_json::json!("ok"))
+            }
 
-            if idx < self.responses.len() {
-                Ok(self.responses[idx].clone())
-            } else {
-                Ok(LlmGatewayResponse {
-                    content: Some("default response".to_string()),
-                    object: None,
-                    tool_calls: vec![],
-                    thinking: None,
+            fn descriptor(&self) -> ToolDescriptor {
+                ToolDescriptor {
+                    r#type: "function".to_string(),
+                    function: FunctionDescriptor {
+                        name: "counting_tool".to_string(),
+                        description: "Records concurrency".to_string(),
+                        parameters: serde_json::json!({}),
+                        returns: None,
+                    },
+                }
+            }
+
+            fn clone_box(&self) -> Box<dyn LlmTool> {
+                Box::new(CountingTool {
+                    running: self.running.clone(),
+                    max_seen: self.max_seen.clone(),
                 })
             }
         }
 
-        async fn complete_json(
-            &self,
-            _model: &str,
-            _messages: &[LlmMessage],
-            _schema: Value,
-            _config: &CompletionConfig,
-        ) -> Result<Value> {
-            Ok(serde_json::json!({"test": "value"}))
-        }
+        let calls = (0..6)
+            .map(|i| LlmToolCall {
+                id: Some(format!("call_{i}")),
+                name: "counting_tool".to_string(),
+                arguments: HashMap::new(),
+            })
+            .collect();
+        let gateway =
+            Arc::new(MockGateway::new(vec![tool_call_response(calls), text_response("Done")]));
+        let broker = LlmBroker::with_tool_runner(
+            "test-model",
+            gateway,
+            None,
+            Arc::new(crate::llm::tools::ParallelToolRunner::new(16)),
+        )
+        .with_max_concurrent_tools(2);
+        let max_seen = Arc::new(AtomicUsize::new(0));
+        let tools: Vec<Box<dyn LlmTool>> = vec![Box::new(CountingTool {
+            running: Arc::new(AtomicUsize::new(0)),
+            max_seen: max_seen.clone(),
+        })];
+
+        let result = broker.generate(&[LlmMessage::user("Go")], Some(&tools), None, None).await;
+
+        assert_eq!(result.unwrap(), "Done");
+        assert_eq!(max_seen.load(Ordering::SeqCst), 2);
+    }
 
-        async fn get_available_models(&self) -> Result<Vec<String>> {
-            Ok(vec!["test-model".to_string()])
+    #[tokio::test]
+    async fn test_tool_results_follow_provider_call_order() {
+        // The first call finishes last, so completion order differs from call order
+        struct DelayedTool {
+            name: &'static str,
+            delay_ms: u64,
         }
 
-        async fn calculate_embeddings(
-            &self,
-            _text: &str,
-            _model: Option<&str>,
-        ) -> Result<Vec<f32>> {
-            Ok(vec![0.1, 0.2, 0.3])
-        }
+        #[async_trait]
+        impl LlmTool for DelayedTool {
+            async fn run(
+                &self,
+                _args: &HashMap<String, Value>,
+                _ctx: &crate::llm::tools::ToolRunCtx,
+            ) -> Result<Value> {
+                tokio::time::sleep(std::time::Duration::from_millis(self.delay_ms)).await;
+                Ok(serde_json::json!(self.name))
+            }
+
+            fn descriptor(&self) -> ToolDescriptor {
+                ToolDescriptor {
+                    r#type: "function".to_string(),
+                    function: FunctionDescriptor {
+                        name: self.name.to_string(),
+                        description: "A delayed tool".to_string(),
+                        parameters: serde_json::json!({}),
+                        returns: None,
+                    },
+                }
+            }
 
-        fn complete_stream<'a>(
-            &'a self,
-            _model: &'a str,
-            _messages: &'a [LlmMessage],
-            _tools: Option<&'a [Box<dyn LlmTool>]>,
-            _config: &'a CompletionConfig,
-        ) -> Pin<Box<dyn Stream<Item = Result<StreamChunk>> + Send + 'a>> {
-            use futures::stream;
-            Box::pin(stream::iter(vec![Ok(StreamChunk::Content("test".to_string()))]))
+            fn clone_box(&self) -> Box<dyn LlmTool> {
+                Box::new(DelayedTool {
+                    name: self.name,
+                    delay_ms: self.delay_ms,
+                })
+            }
         }
-    }
 
-    // Mock tool for testing
-    struct MockTool {
-        name: String,
-        result: Value,
-    }
+        let gateway = Arc::new(MockGateway::new(two_ordered_tool_calls()));
+        let broker = LlmBroker::with_tool_runner(
+            "test-model",
+            gateway.clone(),
+            None,
+            Arc::new(crate::llm::tools::ParallelToolRunner::default()),
+        );
+        let tools: Vec<Box<dyn LlmTool>> = vec![
+            Box::new(DelayedTool {
+                name: "slow_tool",
+                delay_ms: 50,
+            }),
+            Box::new(DelayedTool {
+                name: "fast_tool",
+                delay_ms: 0,
+            }),
+        ];
 
-    #[async_trait]
+        let result =
+            broker.generate(&[LlmMessage::user("Use both")], Some(&tools), None, None).await;
 
-    impl LlmTool for MockTool {
-        async fn run(
-            &self,
-            _args: &HashMap<String, Value>,
-            _ctx: &crate::llm::tools::ToolRunCtx,
-        ) -> Result<Value> {
-            Ok(self.result.clone())
-        }
+        assert_eq!(result.unwrap(), "Done");
+        assert_eq!(
+            tool_message_ids(&gateway.last_request().messages),
+            vec![
+                ("call_1".to_string(), "\"slow_tool\"".to_string()),
+                ("call_2".to_string(), "\"fast_tool\"".to_string()),
+            ]
+        );
+    }
 
-        fn descriptor(&self) -> ToolDescriptor {
-            ToolDescriptor {
-                r#type: "function".to_string(),
-                function: FunctionDescriptor {
-                    name: self.name.clone(),
-                    description: "A mock tool".to_string(),
-                    parameters: serde_json::json!({}),
-                },
+    #[tokio::test]
+    async fn test_tool_results_reordered_when_runner_returns_them_out_of_order() {
+        struct ReversingRunner;
+
+        #[async_trait]
+        impl ToolRunner for ReversingRunner {
+            async fn run_batch(
+                &self,
+                calls: &[ToolCallExecution],
+                tools: &[Box<dyn LlmTool>],
+                ctx: &ToolRunCtx,
+            ) -> Vec<ToolCallOutcome> {
+                let mut outcomes = SerialToolRunner.run_batch(calls, tools, ctx).await;
+                outcomes.reverse();
+                outcomes
             }
         }
 
-        fn clone_box(&self) -> Box<dyn LlmTool> {
-            Box::new(MockTool {
-                name: self.name.clone(),
-                result: self.result.clone(),
-            })
-        }
-    }
+        let gateway = Arc::new(MockGateway::new(two_ordered_tool_calls()));
+        let broker = LlmBroker::with_tool_runner(
+            "test-model",
+            gateway.clone(),
+            None,
+            Arc::new(ReversingRunner),
+        );
+        let tools: Vec<Box<dyn LlmTool>> = vec![
+            Box::new(MockTool::new("slow_tool", serde_json::json!("first"))),
+            Box::new(MockTool::new("fast_tool", serde_json::json!("second"))),
+        ];
 
-    #[tokio::test]
-    async fn test_broker_new() {
-        let gateway = Arc::new(MockGateway::new(vec![]));
-        let broker = LlmBroker::new("test-model", gateway, None);
-        assert_eq!(broker.model, "test-model");
-        assert_eq!(CompletionConfig::default().max_tool_iterations, 10);
-    }
+        broker
+            .generate(&[LlmMessage::user("Use both")], Some(&tools), None, None)
+            .await
+            .unwrap();
 
-    #[tokio::test]
-    async fn test_broker_with_max_tool_iterations() {
         assert_eq!(
-            CompletionConfig {
-                max_tool_iterations: 3,
-                ..Default::default()
-            }
-            .max_tool_iterations,
-            3
+            tool_message_ids(&gateway.last_request().messages),
+            vec![
+                ("call_1".to_string(), "\"first\"".to_string()),
+                ("call_2".to_string(), "\"second\"".to_string()),
+            ]
         );
     }
 
     #[tokio::test]
-    async fn test_generate_max_tool_iterations_exceeded() {
-        // Every response requests a tool call — should hit the limit
-        let tool_call = LlmToolCall {
-            id: Some("call_loop".to_string()),
-            name: "loop_tool".to_string(),
-            arguments: HashMap::new(),
-        };
-
-        // Supply more responses than the limit so the gateway never runs dry
-        let responses: Vec<LlmGatewayResponse> = (0..15)
-            .map(|_| LlmGatewayResponse {
+    async fn test_generate_full_sums_usage_across_tool_loop() {
+        let tool_call = tool_call("call_1", "test_tool");
+        let gateway = Arc::new(MockGateway::new(vec![
+            LlmGatewayResponse {
                 content: None,
                 object: None,
-                tool_calls: vec![tool_call.clone()],
+                tool_calls: vec![tool_call],
                 thinking: None,
-            })
-            .collect();
-
-        let gateway = Arc::new(MockGateway::new(responses));
+                usage: Some(TokenUsage::new(10, 2)),
+                payload_size: None,
+            },
+            LlmGatewayResponse {
+                content: Some("Done".to_string()),
+                object: None,
+                tool_calls: vec![],
+                thinking: None,
+                usage: Some(TokenUsage::new(15, 3)),
+                payload_size: None,
+            },
+        ]));
         let broker = LlmBroker::new("test-model", gateway, None);
+        let tools: Vec<Box<dyn LlmTool>> = vec![Box::new(MockTool::new(
+            "test_tool",
+            serde_json::json!({"ok": true}),
+        ))];
 
-        let tool = MockTool {
-            name: "loop_tool".to_string(),
-            result: serde_json::json!({"result": "loop"}),
-        };
-        let tools: Vec<Box<dyn LlmTool>> = vec![Box::new(tool)];
-
-        let messages = vec![LlmMessage::user("Loop forever")];
         let result = broker
-            .generate(
-                &messages,
-                Some(&tools),
-                Some(CompletionConfig {
-                    max_tool_iterations: 3,
-                    ..Default::default()
-                }),
-                None,
-            )
-            .await;
+            .generate_full(&[LlmMessage::user("Use tool")], Some(&tools), None, None)
+            .await
+            .unwrap();
 
-        assert!(result.is_err());
-        match result.unwrap_err() {
-            crate::error::MojenticError::MaxToolIterationsExceeded { limit } => {
-                assert_eq!(limit, 3);
-            }
-            e => panic!("Expected MaxToolIterationsExceeded, got: {:?}", e),
-        }
+        assert_eq!(result.content, "Done");
+        assert_eq!(result.usage, Some(TokenUsage::new(25, 5)));
     }
 
     #[tokio::test]
-    async fn test_generate_stream_max_tool_iterations_exceeded() {
-        use futures::stream;
+    async fn test_generate_with_tool_call_no_tools_provided() {
+        let tool_call = tool_call("call_1", "test_tool");
 
-        struct InfiniteToolGateway;
+        let response = LlmGatewayResponse {
+            content: Some("fallback".to_string()),
+            object: None,
+            tool_calls: vec![tool_call],
+            thinking: None,
+            usage: None,
+            payload_size: None,
+        };
 
-        #[async_trait::async_trait]
-        impl LlmGateway for InfiniteToolGateway {
-            async fn complete(
-                &self,
-                _model: &str,
-                _messages: &[LlmMessage],
-                _tools: Option<&[Box<dyn LlmTool>]>,
-                _config: &CompletionConfig,
-            ) -> Result<LlmGatewayResponse> {
-                Ok(LlmGatewayResponse {
-                    content: None,
-                    object: None,
-                    tool_calls: vec![],
-                    thinking: None,
-                })
-            }
+        let gateway = Arc::new(MockGateway::new(vec![response]));
+        let broker = LlmBroker::new("test-model", gateway, None);
 
-            async fn complete_json(
-                &self,
-                _model: &str,
-                _messages: &[LlmMessage],
-                _schema: serde_json::Value,
-                _config: &CompletionConfig,
-            ) -> Result<serde_json::Value> {
-                Ok(serde_json::json!({}))
-            }
+        let messages = vec![LlmMessage::user("Use the tool")];
+        let result = broker.generate(&messages, None, None, None).await.unwrap();
 
-            async fn get_available_models(&self) -> Result<Vec<String>> {
-                Ok(vec![])
-            }
+        assert_eq!(result, "fallback");
+    }
 
-            async fn calculate_embeddings(
-                &self,
-                _text: &str,
-                _model: Option<&str>,
-            ) -> Result<Vec<f32>> {
-                Ok(vec![])
-            }
-
-            fn complete_stream<'a>(
-                &'a self,
-                _model: &'a str,
-                _messages: &'a [LlmMessage],
-                _tools: Option<&'a [Box<dyn LlmTool>]>,
-                _config: &'a CompletionConfig,
-            ) -> std::pin::Pin<
-                Box<dyn futures::stream::Stream<Item = Result<StreamChunk>> + Send + 'a>,
-            > {
-                Box::pin(stream::iter(vec![Ok(StreamChunk::ToolCalls(vec![LlmToolCall {
-                    id: Some("call_loop".to_string()),
-                    name: "loop_tool".to_string(),
-                    arguments: HashMap::new(),
-                }]))]))
-            }
-        }
-
-        let gateway = Arc::new(InfiniteToolGateway);
+    #[tokio::test]
+    async fn test_generate_object() {
+        #[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+        struct TestObject {
+            test: String,
+        }
+
+        let gateway = Arc::new(MockGateway::new(vec![]));
         let broker = LlmBroker::new("test-model", gateway, None);
 
-        let tool = MockTool {
-            name: "loop_tool".to_string(),
-            result: serde_json::json!({"result": "loop"}),
-        };
-        let tools: Vec<Box<dyn LlmTool>> = vec![Box::new(tool)];
+        let messages = vec![LlmMessage::user("Generate object")];
+        let result: TestObject = broker.generate_object(&messages, None, None).await.unwrap();
 
-        let messages = vec![LlmMessage::user("Loop forever")];
-        let mut stream = broker.generate_stream(
-            &messages,
-            Some(&tools),
-            Some(CompletionConfig {
-                max_tool_iterations: 2,
-                ..Default::default()
-            }),
-            None,
-        );
+        assert_eq!(result.test, "value");
+    }
 
-        let mut found_error = false;
-        while let Some(result) = stream.next().await {
-            if let Err(crate::error::MojenticError::MaxToolIterationsExceeded { .. }) = result {
-                found_error = true;
-                break;
-            }
+    #[tokio::test]
+    async fn test_generate_object_applies_schema_transformer() {
+        #[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+        struct Inner {
+            value: String,
         }
-        assert!(found_error, "Expected MaxToolIterationsExceeded error in stream");
+
+        #[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+        struct TestObject {
+            test: String,
+            inner: Option<Inner>,
+        }
+
+        let gateway = Arc::new(MockGateway::new(vec![]));
+        let broker = LlmBroker::new("test-model", gateway.clone(), None)
+            .with_schema_transformer(crate::llm::schema_transform::openai_strict);
+
+        let messages = vec![LlmMessage::user("Generate object")];
+        let _: TestObject = broker.generate_object(&messages, None, None).await.unwrap();
+
+        let schema = gateway.last_request().schema.unwrap();
+        assert_eq!(schema["additionalProperties"], serde_json::json!(false));
+        assert_eq!(schema["$defs"]["Inner"]["additionalProperties"], serde_json::json!(false));
+        assert!(schema.get("$schema").is_none());
     }
 
     #[tokio::test]
-    async fn test_broker_new_string_conversion() {
-        let gateway = Arc::new(MockGateway::new(vec![]));
-        let broker = LlmBroker::new(String::from("my-model"), gateway, None);
-        assert_eq!(broker.model, "my-model");
+    async fn test_extract_returns_typed_struct() {
+        #[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+        struct Meeting {
+            attendees: Vec<String>,
+            city: Option<String>,
+        }
+
+        let gateway = Arc::new(MockGateway::new(vec![]).with_json_response(
+            serde_json::json!({"attendees": ["Ada", "Grace"], "city": "London"}),
+        ));
+        let broker = LlmBroker::new("test-model", gateway.clone(), None);
+
+        let meeting: Meeting = broker
+            .extract("Ada met Grace in London.", "Extract the meeting details.")
+            .await
+            .unwrap();
+
+        assert_eq!(meeting.attendees, vec!["Ada", "Grace"]);
+        assert_eq!(meeting.city.as_deref(), Some("London"));
+
+        let request = gateway.last_request();
+        let (messages, temperature) = (request.messages, request.config.temperature);
+        assert_eq!(temperature, 0.0);
+        let system = messages[0].content.as_deref().unwrap();
+        assert!(system.contains("Extract the meeting details."));
+        assert!(system.contains("\"attendees\""));
+        assert_eq!(messages[1].content.as_deref(), Some("Ada met Grace in London."));
     }
 
     #[tokio::test]
-    async fn test_generate_simple_response() {
-        let response = LlmGatewayResponse {
-            content: Some("Hello, World!".to_string()),
-            object: None,
-            tool_calls: vec![],
-            thinking: None,
-        };
+    async fn test_generate_object_partial_yields_progressive_views() {
+        #[derive(Debug, Serialize, Deserialize, schemars::JsonSchema, PartialEq)]
+        struct Profile {
+            name: String,
+            languages: Vec<String>,
+            years: u32,
+        }
 
-        let gateway = Arc::new(MockGateway::new(vec![response]));
+        #[derive(Debug, Default, Deserialize, PartialEq)]
+        #[serde(default)]
+        struct PartialProfile {
+            name: Option<String>,
+            languages: Option<Vec<String>>,
+            years: Option<u32>,
+        }
+
+        let gateway = Arc::new(MockGateway::new(vec![]).with_stream_text(&[
+            r#"{"name": "A"#,
+            r#"da", "langu"#,
+            r#"ages": ["Rust", "Py"#,
+            r#"thon"], "years": 1"#,
+            r#"2}"#,
+        ]));
         let broker = LlmBroker::new("test-model", gateway, None);
+        let messages = vec![LlmMessage::user("Describe Ada")];
 
-        let messages = vec![LlmMessage::user("Hi")];
-        let result = broker.generate(&messages, None, None, None).await.unwrap();
+        let items: Vec<ObjectStreamItem<Profile, PartialProfile>> = broker
+            .generate_object_partial(&messages, None, None)
+            .map(|item| item.unwrap())
+            .collect()
+            .await;
 
-        assert_eq!(result, "Hello, World!");
+        let partial = |name: &str, languages: Option<&[&str]>, years: Option<u32>| {
+            ObjectStreamItem::Partial(PartialProfile {
+                name: Some(name.to_string()),
+                languages: languages.map(|l| l.iter().map(|s| s.to_string()).collect()),
+                years,
+            })
+        };
+        assert_eq!(
+            items,
+            vec![
+                partial("A", None, None),
+                partial("Ada", None, None),
+                partial("Ada", Some(&["Rust", "Py"]), None),
+                partial("Ada", Some(&["Rust", "Python"]), Some(1)),
+                partial("Ada", Some(&["Rust", "Python"]), Some(12)),
+                ObjectStreamItem::Complete(Profile {
+                    name: "Ada".to_string(),
+                    languages: vec!["Rust".to_string(), "Python".to_string()],
+                    years: 12,
+                }),
+            ]
+        );
     }
 
     #[tokio::test]
-    async fn test_generate_with_custom_config() {
-        let response = LlmGatewayResponse {
-            content: Some("Response".to_string()),
-            object: None,
-            tool_calls: vec![],
-            thinking: None,
-        };
+    async fn test_generate_object_partial_fails_on_incomplete_response() {
+        #[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+        struct Profile {
+            name: String,
+        }
 
-        let gateway = Arc::new(MockGateway::new(vec![response]));
+        let gateway = Arc::new(MockGateway::new(vec![]).with_stream_text(&[r#"{"name": "Ad"#]));
         let broker = LlmBroker::new("test-model", gateway, None);
+        let messages = vec![LlmMessage::user("Describe Ada")];
 
-        let config = CompletionConfig {
-            temperature: 0.5,
-            num_ctx: 2048,
-            max_tokens: 100,
-            num_predict: Some(50),
-            top_p: None,
-            top_k: None,
-            response_format: None,
-            reasoning_effort: None,
-            max_tool_iterations: 10,
+        let items: Vec<Result<ObjectStreamItem<Profile, Value>>> =
+            broker.generate_object_partial(&messages, None, None).collect().await;
+
+        assert!(matches!(items.last(), Some(Err(MojenticError::SerializationError(_)))));
+    }
+
+    #[tokio::test]
+    async fn test_config_model_overrides_broker_model_per_request() {
+        let gateway = Arc::new(MockGateway::new(vec![]));
+        let broker = LlmBroker::new("small-model", gateway.clone(), None);
+        let messages = vec![LlmMessage::user("Hard question")];
+        let escalate = || {
+            Some(CompletionConfig {
+                model: Some("large-model".to_string()),
+                ..Default::default()
+            })
         };
 
-        let messages = vec![LlmMessage::user("Hi")];
-        let result = broker.generate(&messages, None, Some(config), None).await.unwrap();
+        broker.generate(&messages, None, escalate(), None).await.unwrap();
+        broker.generate_object::<Value>(&messages, escalate(), None).await.unwrap();
+        let _: Vec<_> = broker.generate_stream(&messages, None, escalate(), None).collect().await;
+        broker.generate(&messages, None, None, None).await.unwrap();
 
-        assert_eq!(result, "Response");
+        assert_eq!(
+            gateway.models(),
+            vec!["large-model", "large-model", "large-model", "small-model"]
+        );
     }
 
     #[tokio::test]
-    async fn test_generate_empty_response_content() {
-        let response = LlmGatewayResponse {
-            content: None,
-            object: None,
-            tool_calls: vec![],
-            thinking: None,
-        };
+    async fn test_generate_object_validated_streams_text_then_object() {
+        #[derive(Debug, Serialize, Deserialize, schemars::JsonSchema, PartialEq)]
+        struct Profile {
+            name: String,
+            years: u32,
+        }
 
-        let gateway = Arc::new(MockGateway::new(vec![response]));
+        let gateway = Arc::new(
+            MockGateway::new(vec![]).with_stream_text(&[r#"{"name": "Ada", "#, r#""years": 12}"#]),
+        );
         let broker = LlmBroker::new("test-model", gateway, None);
+        let messages = vec![LlmMessage::user("Describe Ada")];
 
-        let messages = vec![LlmMessage::user("Hi")];
-        let result = broker.generate(&messages, None, None, None).await.unwrap();
-
-        assert_eq!(result, "");
+        let items: Vec<ValidatedStreamItem<Profile>> = broker
+            .generate_object_validated(&messages, None, None)
+            .map(|item| item.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(
+            items,
+            vec![
+                ValidatedStreamItem::Text(r#"{"name": "Ada", "#.to_string()),
+                ValidatedStreamItem::Text(r#""years": 12}"#.to_string()),
+                ValidatedStreamItem::Complete(Profile {
+                    name: "Ada".to_string(),
+                    years: 12,
+                }),
+            ]
+        );
     }
 
     #[tokio::test]
-    async fn test_generate_with_tool_call() {
-        let tool_call = LlmToolCall {
-            id: Some("call_1".to_string()),
-            name: "test_tool".to_string(),
-            arguments: HashMap::new(),
-        };
+    async fn test_generate_object_validated_stops_when_response_diverges() {
+        #[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+        #[serde(deny_unknown_fields)]
+        struct Profile {
+            name: String,
+            years: u32,
+        }
 
-        let first_response = LlmGatewayResponse {
-            content: None,
-            object: None,
-            tool_calls: vec![tool_call],
-            thinking: None,
-        };
+        let gateway = Arc::new(MockGateway::new(vec![]).with_stream_text(&[
+            r#"{"name": "Ada", "#,
+            r#""nickname": "A"#,
+            r#"da", "#,
+            r#""years": 12}"#,
+        ]));
+        let broker = LlmBroker::new("test-model", gateway.clone(), None);
+        let messages = vec![LlmMessage::user("Describe Ada")];
+
+        let items: Vec<Result<ValidatedStreamItem<Profile>>> =
+            broker.generate_object_validated(&messages, None, None).collect().await;
+
+        assert_eq!(gateway.chunks_produced(), 2);
+        assert_eq!(items.len(), 3);
+        match items.last() {
+            Some(Err(MojenticError::ParseError(message))) => {
+                assert!(message.contains("$.nickname: unexpected property"), "{}", message)
+            }
+            other => panic!("expected the stream to stop on divergence, got {:?}", other),
+        }
+    }
 
-        let second_response = LlmGatewayResponse {
-            content: Some("After tool execution".to_string()),
-            object: None,
-            tool_calls: vec![],
-            thinking: None,
-        };
+    #[tokio::test]
+    async fn test_generate_object_validated_fails_on_schema_violation() {
+        #[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+        struct Profile {
+            name: String,
+            years: u32,
+        }
 
-        let gateway = Arc::new(MockGateway::new(vec![first_response, second_response]));
+        let gateway = Arc::new(
+            MockGateway::new(vec![])
+                .with_stream_text(&[r#"{"name": "Ada", "#, r#""years": "twelve"}"#]),
+        );
         let broker = LlmBroker::new("test-model", gateway, None);
+        let messages = vec![LlmMessage::user("Describe Ada")];
 
-        let tool = MockTool {
-            name: "test_tool".to_string(),
-            result: serde_json::json!({"result": "success"}),
-        };
-
-        let tools: Vec<Box<dyn LlmTool>> = vec![Box::new(tool)];
-
-        let messages = vec![LlmMessage::user("Use the tool")];
-        let result = broker.generate(&messages, Some(&tools), None, None).await.unwrap();
+        let items: Vec<Result<ValidatedStreamItem<Profile>>> =
+            broker.generate_object_validated(&messages, None, None).collect().await;
 
-        assert_eq!(result, "After tool execution");
+        assert_eq!(items.len(), 3);
+        assert!(matches!(&items[0], Ok(ValidatedStreamItem::Text(_))));
+        assert!(matches!(&items[1], Ok(ValidatedStreamItem::Text(_))));
+        match items.last() {
+            Some(Err(MojenticError::ParseError(message))) => {
+                assert!(message.contains("$.years"), "{}", message)
+            }
+            other => panic!("expected a schema violation, got {:?}", other),
+        }
     }
 
     #[tokio::test]
-    async fn test_generate_with_tool_call_no_tools_provided() {
-        let tool_call = LlmToolCall {
-            id: Some("call_1".to_string()),
-            name: "test_tool".to_string(),
-            arguments: HashMap::new(),
-        };
+    async fn test_classify_returns_one_of_the_labels() {
+        let gateway = Arc::new(
+            MockGateway::new(vec![]).with_json_response(serde_json::json!({"label": "negative"})),
+        );
+        let broker = LlmBroker::new("test-model", gateway.clone(), None);
 
-        let response = LlmGatewayResponse {
-            content: Some("fallback".to_string()),
-            object: None,
-            tool_calls: vec![tool_call],
-            thinking: None,
-        };
+        let label = broker.classify("This was awful", &["positive", "negative"]).await.unwrap();
 
-        let gateway = Arc::new(MockGateway::new(vec![response]));
+        assert_eq!(label, "negative");
+        let schema = gateway.last_request().schema.unwrap();
+        assert_eq!(
+            schema["properties"]["label"]["enum"],
+            serde_json::json!(["positive", "negative"])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_classify_snaps_off_list_answer_to_nearest_label() {
+        let gateway = Arc::new(
+            MockGateway::new(vec![])
+                .with_json_response(serde_json::json!({"label": "Positive sentiment"})),
+        );
         let broker = LlmBroker::new("test-model", gateway, None);
 
-        let messages = vec![LlmMessage::user("Use the tool")];
-        let result = broker.generate(&messages, None, None, None).await.unwrap();
+        let label = broker.classify("I love it", &["positive", "negative"]).await.unwrap();
 
-        assert_eq!(result, "fallback");
+        assert_eq!(label, "positive");
+        assert_eq!(nearest_label("negatve", &["positive", "negative"]), "negative");
     }
 
     #[tokio::test]
-    async fn test_generate_object() {
-        #[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
-        struct TestObject {
-            test: String,
-        }
-
-        let gateway = Arc::new(MockGateway::new(vec![]));
-        let broker = LlmBroker::new("test-model", gateway, None);
+    async fn test_classify_requires_labels() {
+        let broker = LlmBroker::new("test-model", Arc::new(MockGateway::new(vec![])), None);
 
-        let messages = vec![LlmMessage::user("Generate object")];
-        let result: TestObject = broker.generate_object(&messages, None, None).await.unwrap();
+        let result = broker.classify("anything", &[]).await;
 
-        assert_eq!(result.test, "value");
+        assert!(matches!(result, Err(MojenticError::InvalidArgument(_))));
     }
 
     #[tokio::test]
@@ -1165,7 +4802,16 @@ mod tests {
             top_k: None,
             response_format: None,
             reasoning_effort: None,
+            thinking_budget_tokens: None,
             max_tool_iterations: 10,
+            n: None,
+            tool_result_format: Default::default(),
+            logit_bias: None,
+            user: None,
+            model: None,
+            response_language: None,
+            assistant_prefill: None,
+            extra: HashMap::new(),
         };
 
         let messages = vec![LlmMessage::user("Generate")];
@@ -1176,12 +4822,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_multiple_messages() {
-        let response = LlmGatewayResponse {
-            content: Some("Response to conversation".to_string()),
-            object: None,
-            tool_calls: vec![],
-            thinking: None,
-        };
+        let response = text_response("Response to conversation");
 
         let gateway = Arc::new(MockGateway::new(vec![response]));
         let broker = LlmBroker::new("test-model", gateway, None);
@@ -1197,68 +4838,22 @@ mod tests {
         assert_eq!(result, "Response to conversation");
     }
 
-    #[tokio::test]
-    async fn test_generate_stream_basic() {
-        use futures::stream;
-
-        // Mock gateway that returns a simple stream
-        struct StreamingMockGateway;
-
-        #[async_trait::async_trait]
-        impl LlmGateway for StreamingMockGateway {
-            async fn complete(
-                &self,
-                _model: &str,
-                _messages: &[LlmMessage],
-                _tools: Option<&[Box<dyn LlmTool>]>,
-                _config: &CompletionConfig,
-            ) -> Result<LlmGatewayResponse> {
-                Ok(LlmGatewayResponse {
-                    content: Some("test".to_string()),
-                    object: None,
-                    tool_calls: vec![],
-                    thinking: None,
-                })
-            }
-
-            async fn complete_json(
-                &self,
-                _model: &str,
-                _messages: &[LlmMessage],
-                _schema: Value,
-                _config: &CompletionConfig,
-            ) -> Result<Value> {
-                Ok(serde_json::json!({}))
-            }
-
-            async fn get_available_models(&self) -> Result<Vec<String>> {
-                Ok(vec![])
-            }
+    #[test]
+    fn test_supports_streaming_consults_gateway() {
+        let gateway = Arc::new(crate::llm::gateways::OpenAIGateway::with_api_key("test-key"));
 
-            async fn calculate_embeddings(
-                &self,
-                _text: &str,
-                _model: Option<&str>,
-            ) -> Result<Vec<f32>> {
-                Ok(vec![])
-            }
+        let instruct = LlmBroker::new("gpt-3.5-turbo-instruct", gateway.clone(), None);
+        let chat = LlmBroker::new("gpt-4o", gateway, None);
+        let mock = LlmBroker::new("test-model", Arc::new(MockGateway::new(vec![])), None);
 
-            fn complete_stream<'a>(
-                &'a self,
-                _model: &'a str,
-                _messages: &'a [LlmMessage],
-                _tools: Option<&'a [Box<dyn LlmTool>]>,
-                _config: &'a CompletionConfig,
-            ) -> Pin<Box<dyn Stream<Item = Result<StreamChunk>> + Send + 'a>> {
-                Box::pin(stream::iter(vec![
-                    Ok(StreamChunk::Content("Hello".to_string())),
-                    Ok(StreamChunk::Content(" ".to_string())),
-                    Ok(StreamChunk::Content("World".to_string())),
-                ]))
-            }
-        }
+        assert!(!instruct.supports_streaming());
+        assert!(chat.supports_streaming());
+        assert!(mock.supports_streaming());
+    }
 
-        let gateway = Arc::new(StreamingMockGateway);
+    #[tokio::test]
+    async fn test_generate_stream_basic() {
+        let gateway = Arc::new(MockGateway::new(vec![]).with_stream_text(&["Hello", " ", "World"]));
         let broker = LlmBroker::new("test-model", gateway, None);
         let messages = vec![LlmMessage::user("Hello")];
 
@@ -1273,100 +4868,178 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_generate_stream_with_tool_calls() {
-        use futures::stream;
+    async fn test_generate_records_payload_sizes() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("POST", "/api/chat")
+            .with_status(200)
+            .with_body(r#"{"message":{"role":"assistant","content":"Hello!"}}"#)
+            .create();
+        let gateway = Arc::new(crate::llm::gateways::OllamaGateway::with_host(server.url()));
+        let tracer = Arc::new(TracerSystem::default());
+        let broker = LlmBroker::new("llama2", gateway, Some(tracer.clone()));
 
-        // Mock gateway that returns tool calls
-        struct ToolCallMockGateway {
-            call_count: std::sync::Mutex<usize>,
-        }
+        broker.generate(&[LlmMessage::user("Hi")], None, None, None).await.unwrap();
 
-        impl ToolCallMockGateway {
-            fn new() -> Self {
-                Self {
-                    call_count: std::sync::Mutex::new(0),
-                }
-            }
-        }
+        let responses = tracer.get_event_json(None, None, None);
+        let response = responses.iter().find(|e| e["type"] == "LlmResponseTracerEvent").unwrap();
+        assert!(response["request_bytes"].as_u64().unwrap() > 0);
+        assert!(response["response_bytes"].as_u64().unwrap() > 0);
+    }
 
-        #[async_trait::async_trait]
-        impl LlmGateway for ToolCallMockGateway {
-            async fn complete(
-                &self,
-                _model: &str,
-                _messages: &[LlmMessage],
-                _tools: Option<&[Box<dyn LlmTool>]>,
-                _config: &CompletionConfig,
-            ) -> Result<LlmGatewayResponse> {
-                Ok(LlmGatewayResponse {
-                    content: Some("test".to_string()),
-                    object: None,
-                    tool_calls: vec![],
-                    thinking: None,
-                })
-            }
+    #[tokio::test]
+    async fn test_call_duration_is_measured_on_the_broker_clock() {
+        // Takes a second and a half of manual time to answer
+        let clock = crate::clock::ManualClock::new();
+        let gateway_clock = clock.clone();
+        let gateway = Arc::new(MockGateway::new(vec![]).with_responder(move |_| {
+            gateway_clock.advance(std::time::Duration::from_millis(1500));
+            Ok(text_response("Eventually."))
+        }));
+        let tracer = Arc::new(TracerSystem::default());
+        let broker =
+            LlmBroker::new("test-model", gateway, Some(tracer.clone())).with_clock(Arc::new(clock));
 
-            async fn complete_json(
-                &self,
-                _model: &str,
-                _messages: &[LlmMessage],
-                _schema: Value,
-                _config: &CompletionConfig,
-            ) -> Result<Value> {
-                Ok(serde_json::json!({}))
-            }
+        broker.generate(&[LlmMessage::user("Hi")], None, None, None).await.unwrap();
 
-            async fn get_available_models(&self) -> Result<Vec<String>> {
-                Ok(vec![])
-            }
+        let events = tracer.get_event_json(None, None, None);
+        let response = events.iter().find(|e| e["type"] == "LlmResponseTracerEvent").unwrap();
+        assert_eq!(response["call_duration_ms"], 1500.0);
+    }
 
-            async fn calculate_embeddings(
-                &self,
-                _text: &str,
-                _model: Option<&str>,
-            ) -> Result<Vec<f32>> {
-                Ok(vec![])
-            }
-
-            fn complete_stream<'a>(
-                &'a self,
-                _model: &'a str,
-                _messages: &'a [LlmMessage],
-                _tools: Option<&'a [Box<dyn LlmTool>]>,
-                _config: &'a CompletionConfig,
-            ) -> Pin<Box<dyn Stream<Item = Result<StreamChunk>> + Send + 'a>> {
-                let mut count = self.call_count.lock().unwrap();
-                let call_num = *count;
-                *count += 1;
-
-                if call_num == 0 {
-                    // First call: return content with tool call
-                    Box::pin(stream::iter(vec![
-                        Ok(StreamChunk::Content("Initial ".to_string())),
-                        Ok(StreamChunk::Content("response".to_string())),
-                        Ok(StreamChunk::ToolCalls(vec![LlmToolCall {
-                            id: Some("call_1".to_string()),
-                            name: "test_tool".to_string(),
-                            arguments: HashMap::new(),
-                        }])),
-                    ]))
-                } else {
-                    // Second call (after tool execution): return final content
-                    Box::pin(stream::iter(vec![
-                        Ok(StreamChunk::Content("After ".to_string())),
-                        Ok(StreamChunk::Content("tool".to_string())),
-                    ]))
-                }
-            }
-        }
+    #[tokio::test]
+    async fn test_generate_truncates_at_max_response_chars() {
+        let gateway =
+            Arc::new(MockGateway::new(vec![text_response("héllo wörld, this is far too long")]));
+        let broker = LlmBroker::new("test-model", gateway, None).with_max_response_chars(11);
+
+        let result = broker.generate(&[LlmMessage::user("Hi")], None, None, None).await.unwrap();
+
+        assert_eq!(result, format!("héllo wörld{}", RESPONSE_TRUNCATION_MARKER));
+    }
+
+    #[tokio::test]
+    async fn test_generate_leaves_short_responses_untouched() {
+        let gateway = Arc::new(MockGateway::new(vec![]));
+        let broker = LlmBroker::new("test-model", gateway, None).with_max_response_chars(100);
+
+        let result = broker.generate(&[LlmMessage::user("Hi")], None, None, None).await.unwrap();
+
+        assert_eq!(result, "default response");
+    }
+
+    #[tokio::test]
+    async fn test_post_processor_strips_reasoning_but_tracer_keeps_it() {
+        let gateway = Arc::new(MockGateway::new(vec![text_response(
+            "<think>The user is testing me.</think>\nParis",
+        )]));
+        let tracer = Arc::new(TracerSystem::default());
+        let broker = LlmBroker::new("test-model", gateway, Some(tracer.clone()))
+            .with_response_post_processor(crate::llm::reasoning::strip_reasoning);
+
+        let result = broker
+            .generate(&[LlmMessage::user("Capital of France?")], None, None, None)
+            .await
+            .unwrap();
 
-        let gateway = Arc::new(ToolCallMockGateway::new());
+        assert_eq!(result, "Paris");
+        let events = tracer.get_event_json(None, None, None);
+        let response = events.iter().find(|e| e["type"] == "LlmResponseTracerEvent").unwrap();
+        assert!(response.to_string().contains("The user is testing me."));
+    }
+
+    #[tokio::test]
+    async fn test_generate_stream_stops_at_max_response_chars() {
+        let gateway = Arc::new(MockGateway::new(vec![]).with_endless_stream("abcd"));
+        let broker =
+            LlmBroker::new("test-model", gateway.clone(), None).with_max_response_chars(10);
+        let messages = vec![LlmMessage::user("Go on forever")];
+
+        let chunks: Vec<String> = broker
+            .generate_stream(&messages, None, None, None)
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(chunks, vec!["abcd", "abcd", "ab", RESPONSE_TRUNCATION_MARKER]);
+        assert_eq!(gateway.chunks_produced(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_generate_stream_stops_at_phrase_split_across_chunks() {
+        let gateway = Arc::new(MockGateway::new(vec![]).with_stream_text(&[
+            "Sure thing. As an AI lan",
+            "guage model, I",
+            " must decline.",
+            "More",
+        ]));
+        let broker = LlmBroker::new("test-model", gateway.clone(), None)
+            .with_stop_phrases(["AI language model", "never matches"]);
+        let messages = vec![LlmMessage::user("Hello")];
+
+        let text: String = broker
+            .generate_stream(&messages, None, None, None)
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(text, "Sure thing. As an ");
+        assert_eq!(gateway.chunks_produced(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_generate_stream_flushes_held_back_text_without_stop_phrase() {
+        let gateway = Arc::new(MockGateway::new(vec![]));
+        let broker =
+            LlmBroker::new("test-model", gateway, None).with_stop_phrases(["a long stop phrase"]);
+        let messages = vec![LlmMessage::user("Hello")];
+
+        let text: String = broker
+            .generate_stream(&messages, None, None, None)
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(text, "test");
+    }
+
+    #[tokio::test]
+    async fn test_continue_stream_resumes_from_partial_content() {
+        let gateway = Arc::new(MockGateway::new(vec![]));
+        let broker = LlmBroker::new("test-model", gateway.clone(), None);
+        let messages = vec![LlmMessage::user("Say something short")];
+        let partial = "This is a ".to_string();
+
+        let resumed: String = broker
+            .continue_stream(&messages, &partial, None, None, None)
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(partial + &resumed, "This is a test");
+        let sent = gateway.last_request().messages;
+        assert_eq!(sent.len(), 3);
+        assert_eq!(sent[0].content.as_deref(), Some("Say something short"));
+        assert_eq!(sent[1].role, MessageRole::Assistant);
+        assert_eq!(sent[1].content.as_deref(), Some("This is a "));
+        assert_eq!(sent[2].role, MessageRole::User);
+        assert_eq!(sent[2].content.as_deref(), Some(CONTINUE_PROMPT));
+    }
+
+    #[tokio::test]
+    async fn test_generate_stream_with_tool_calls() {
+        let gateway = Arc::new(
+            MockGateway::new(vec![])
+                .with_stream(vec![
+                    StreamChunk::Content("Initial ".to_string()),
+                    StreamChunk::Content("response".to_string()),
+                    StreamChunk::ToolCalls(vec![tool_call("call_1", "test_tool")]),
+                ])
+                .with_stream_text(&["After ", "tool"]),
+        );
         let broker = LlmBroker::new("test-model", gateway, None);
 
-        let tool = MockTool {
-            name: "test_tool".to_string(),
-            result: serde_json::json!({"result": "success"}),
-        };
+        let tool = MockTool::new("test_tool", serde_json::json!({"result": "success"}));
         let tools: Vec<Box<dyn LlmTool>> = vec![Box::new(tool)];
 
         let messages = vec![LlmMessage::user("Use the tool")];
@@ -1383,66 +5056,69 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_generate_stream_without_tools() {
-        use futures::stream;
-
-        struct SimpleStreamGateway;
-
-        #[async_trait::async_trait]
-        impl LlmGateway for SimpleStreamGateway {
-            async fn complete(
-                &self,
-                _model: &str,
-                _messages: &[LlmMessage],
-                _tools: Option<&[Box<dyn LlmTool>]>,
-                _config: &CompletionConfig,
-            ) -> Result<LlmGatewayResponse> {
-                Ok(LlmGatewayResponse {
-                    content: Some("test".to_string()),
-                    object: None,
-                    tool_calls: vec![],
-                    thinking: None,
-                })
+    async fn test_generate_stream_with_progress_forwards_tool_updates() {
+        struct SlowFetchTool;
+
+        #[async_trait]
+        impl LlmTool for SlowFetchTool {
+            async fn run(&self, _args: &HashMap<String, Value>, ctx: &ToolRunCtx) -> Result<Value> {
+                ctx.report_progress("fetch", "connecting");
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                ctx.report_progress("fetch", "downloading");
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                Ok(serde_json::json!({"bytes": 1024}))
             }
 
-            async fn complete_json(
-                &self,
-                _model: &str,
-                _messages: &[LlmMessage],
-                _schema: Value,
-                _config: &CompletionConfig,
-            ) -> Result<Value> {
-                Ok(serde_json::json!({}))
+            fn descriptor(&self) -> ToolDescriptor {
+                ToolDescriptor {
+                    r#type: "function".to_string(),
+                    function: FunctionDescriptor {
+                        name: "fetch".to_string(),
+                        description: "Fetch a page".to_string(),
+                        parameters: serde_json::json!({"type": "object", "properties": {}}),
+                        returns: None,
+                    },
+                }
             }
 
-            async fn get_available_models(&self) -> Result<Vec<String>> {
-                Ok(vec![])
+            fn clone_box(&self) -> Box<dyn LlmTool> {
+                Box::new(SlowFetchTool)
             }
+        }
 
-            async fn calculate_embeddings(
-                &self,
-                _text: &str,
-                _model: Option<&str>,
-            ) -> Result<Vec<f32>> {
-                Ok(vec![])
-            }
+        let gateway = Arc::new(
+            MockGateway::new(vec![])
+                .with_stream(vec![StreamChunk::ToolCalls(vec![tool_call("call_1", "fetch")])])
+                .with_stream_text(&["Fetched."]),
+        );
+        let broker = LlmBroker::new("test-model", gateway, None);
+        let tools: Vec<Box<dyn LlmTool>> = vec![Box::new(SlowFetchTool)];
+        let messages = vec![LlmMessage::user("Fetch the page")];
 
-            fn complete_stream<'a>(
-                &'a self,
-                _model: &'a str,
-                _messages: &'a [LlmMessage],
-                _tools: Option<&'a [Box<dyn LlmTool>]>,
-                _config: &'a CompletionConfig,
-            ) -> Pin<Box<dyn Stream<Item = Result<StreamChunk>> + Send + 'a>> {
-                // Simple stream with no tool calls
-                Box::pin(stream::iter(vec![
-                    Ok(StreamChunk::Content("Simple ".to_string())),
-                    Ok(StreamChunk::Content("stream".to_string())),
-                ]))
-            }
-        }
+        let chunks: Vec<StreamChunk> = broker
+            .generate_stream_with_progress(&messages, Some(&tools), None, None)
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
 
-        let gateway = Arc::new(SimpleStreamGateway);
+        let updates: Vec<&crate::llm::tools::ToolProgress> = chunks
+            .iter()
+            .filter_map(|chunk| match chunk {
+                StreamChunk::ToolProgress(progress) => Some(progress),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(updates.len(), 2);
+        assert_eq!(updates[0].tool_name, "fetch");
+        assert_eq!(updates[0].message, "connecting");
+        assert_eq!(updates[1].message, "downloading");
+        assert!(updates[1].elapsed >= std::time::Duration::from_millis(20));
+        assert!(matches!(chunks.last(), Some(StreamChunk::Content(text)) if text == "Fetched."));
+    }
+
+    #[tokio::test]
+    async fn test_generate_stream_without_tools() {
+        let gateway = Arc::new(MockGateway::new(vec![]).with_stream_text(&["Simple ", "stream"]));
         let broker = LlmBroker::new("test-model", gateway, None);
 
         let messages = vec![LlmMessage::user("Test")];
@@ -1460,12 +5136,7 @@ mod tests {
     async fn test_tracer_integration() {
         use crate::tracer::TracerSystem;
 
-        let response = LlmGatewayResponse {
-            content: Some("Test response".to_string()),
-            object: None,
-            tool_calls: vec![],
-            thinking: None,
-        };
+        let response = text_response("Test response");
 
         let gateway = Arc::new(MockGateway::new(vec![response]));
         let tracer = Arc::new(TracerSystem::default());
@@ -1490,38 +5161,167 @@ mod tests {
         assert!(summaries[1].contains(correlation_id));
     }
 
+    #[test]
+    fn test_trace_messages_summary_omits_content() {
+        let tracer = TracerSystem::default().with_verbosity(TracerVerbosity::Summary);
+        let messages = vec![
+            LlmMessage::system("Be brief"),
+            LlmMessage::user("Secret question"),
+        ];
+
+        let traced = trace_messages(&tracer, &messages);
+
+        assert_eq!(traced.len(), 2);
+        assert!(traced.iter().all(|m| !m.contains_key("content")));
+        assert_eq!(traced[1]["role"], serde_json::json!("User"));
+        assert_eq!(traced[1]["content_length"], serde_json::json!(15));
+    }
+
+    #[test]
+    fn test_trace_messages_full_includes_content() {
+        let tracer = TracerSystem::default();
+        let messages = vec![LlmMessage::user("Secret question")];
+
+        let traced = trace_messages(&tracer, &messages);
+
+        assert_eq!(traced[0]["content"], serde_json::json!("Secret question"));
+    }
+
+    #[tokio::test]
+    async fn test_summary_tracer_omits_response_content() {
+        let response = text_response("Sensitive answer");
+        let gateway = Arc::new(MockGateway::new(vec![response]));
+        let tracer = Arc::new(TracerSystem::default().with_verbosity(TracerVerbosity::Summary));
+        let broker = LlmBroker::new("test-model", gateway, Some(tracer.clone()));
+
+        let result = broker.generate(&[LlmMessage::user("Test")], None, None, None).await.unwrap();
+
+        assert_eq!(result, "Sensitive answer");
+        assert_eq!(tracer.len(), 2);
+        let summaries = tracer.get_event_summaries(None, None, None);
+        assert!(summaries.iter().all(|s| !s.contains("Sensitive answer")));
+    }
+
+    #[tokio::test]
+    async fn test_summary_tracer_omits_tool_values_and_records_tokens() {
+        let mut call = tool_call("call_1", "lookup");
+        call.arguments.insert("account".to_string(), serde_json::json!("acct-4471"));
+        let gateway = Arc::new(MockGateway::new(vec![
+            tool_call_response(vec![call]),
+            LlmGatewayResponse::builder()
+                .content("Done")
+                .usage(TokenUsage::new(42, 7))
+                .build(),
+        ]));
+        let tracer = Arc::new(TracerSystem::default().with_verbosity(TracerVerbosity::Summary));
+        let broker = LlmBroker::new("test-model", gateway, Some(tracer.clone()));
+        let tools: Vec<Box<dyn LlmTool>> = vec![Box::new(MockTool::new(
+            "lookup",
+            serde_json::json!({"balance": 1234}),
+        ))];
+
+        broker
+            .generate(&[LlmMessage::user("Check")], Some(&tools), None, None)
+            .await
+            .unwrap();
+
+        let events = tracer.get_event_json(None, None, None);
+        let dump = serde_json::to_string(&events).unwrap();
+        assert!(!dump.contains("acct-4471"));
+        assert!(!dump.contains("1234"));
+        let tool_event = events.iter().find(|e| e["type"] == "ToolCallTracerEvent").unwrap();
+        assert_eq!(tool_event["arguments"]["account"], serde_json::json!({"length": 11}));
+        assert!(events.iter().any(|e| e["prompt_tokens"] == 42 && e["completion_tokens"] == 7));
+    }
+
+    #[tokio::test]
+    async fn test_child_broker_tags_events_with_parent_correlation_id() {
+        use crate::tracer::TracerSystem;
+
+        let gateway = Arc::new(MockGateway::new(vec![]));
+        let tracer = Arc::new(TracerSystem::default());
+        let parent = LlmBroker::new("test-model", gateway, Some(tracer.clone()));
+        let child = parent.child("parent-correlation-789");
+
+        child.generate(&[LlmMessage::user("Test")], None, None, None).await.unwrap();
+        let _: serde_json::Value =
+            child.generate_object(&[LlmMessage::user("Test")], None, None).await.unwrap();
+
+        assert_eq!(tracer.len(), 4);
+        let summaries = tracer.get_event_summaries(None, None, None);
+        assert!(summaries.iter().all(|s| s.contains("parent-correlation-789")));
+    }
+
+    #[tokio::test]
+    async fn test_child_broker_events_link_to_parent_span() {
+        use crate::tracer::TracerSystem;
+
+        let gateway = Arc::new(MockGateway::new(vec![]));
+        let tracer = Arc::new(TracerSystem::default());
+        let parent = LlmBroker::new("test-model", gateway, Some(tracer.clone()));
+        let child = parent.child("run-1");
+        let grandchild = child.child("run-1");
+        let messages = [LlmMessage::user("Test")];
+
+        parent.generate(&messages, None, None, None).await.unwrap();
+        child.generate(&messages, None, None, None).await.unwrap();
+        grandchild.generate(&messages, None, None, None).await.unwrap();
+
+        let spans: Vec<serde_json::Value> = tracer
+            .get_event_json(None, None, None)
+            .into_iter()
+            .map(|event| event["parent_span_id"].clone())
+            .collect();
+        let (parent_span, child_span) =
+            (serde_json::json!(parent.span_id()), serde_json::json!(child.span_id()));
+        assert_eq!(
+            spans,
+            vec![
+                serde_json::Value::Null,
+                serde_json::Value::Null,
+                parent_span.clone(),
+                parent_span,
+                child_span.clone(),
+                child_span,
+            ]
+        );
+        assert_ne!(child.span_id(), parent.span_id());
+        let summaries = tracer.get_event_summaries(None, None, None);
+        assert!(summaries[2].contains(&format!("Parent span: {}", parent.span_id())));
+    }
+
+    #[tokio::test]
+    async fn test_child_broker_explicit_correlation_id_wins() {
+        use crate::tracer::TracerSystem;
+
+        let gateway = Arc::new(MockGateway::new(vec![]));
+        let tracer = Arc::new(TracerSystem::default());
+        let child = LlmBroker::new("test-model", gateway, Some(tracer.clone())).child("parent-id");
+
+        child
+            .generate(&[LlmMessage::user("Test")], None, None, Some("explicit-id".to_string()))
+            .await
+            .unwrap();
+
+        let summaries = tracer.get_event_summaries(None, None, None);
+        assert!(summaries.iter().all(|s| s.contains("explicit-id")));
+    }
+
     #[tokio::test]
     async fn test_tracer_with_tool_calls() {
         use crate::tracer::TracerSystem;
 
-        let tool_call = LlmToolCall {
-            id: Some("call_1".to_string()),
-            name: "test_tool".to_string(),
-            arguments: HashMap::new(),
-        };
+        let tool_call = tool_call("call_1", "test_tool");
 
-        let first_response = LlmGatewayResponse {
-            content: None,
-            object: None,
-            tool_calls: vec![tool_call],
-            thinking: None,
-        };
+        let first_response = tool_call_response(vec![tool_call]);
 
-        let second_response = LlmGatewayResponse {
-            content: Some("After tool".to_string()),
-            object: None,
-            tool_calls: vec![],
-            thinking: None,
-        };
+        let second_response = text_response("After tool");
 
         let gateway = Arc::new(MockGateway::new(vec![first_response, second_response]));
         let tracer = Arc::new(TracerSystem::default());
         let broker = LlmBroker::new("test-model", gateway, Some(tracer.clone()));
 
-        let tool = MockTool {
-            name: "test_tool".to_string(),
-            result: serde_json::json!({"result": "success"}),
-        };
+        let tool = MockTool::new("test_tool", serde_json::json!({"result": "success"}));
         let tools: Vec<Box<dyn LlmTool>> = vec![Box::new(tool)];
 
         let messages = vec![LlmMessage::user("Use tool")];
@@ -3,12 +3,12 @@
 //! This module provides a chat session abstraction that manages conversation history
 //! and automatically handles context window limits using token counting.
 
-use crate::error::Result;
-use crate::llm::broker::LlmBroker;
-use crate::llm::gateway::CompletionConfig;
+use crate::error::{MojenticError, Result};
+use crate::llm::broker::{GenerationResult, LlmBroker};
+use crate::llm::gateway::{CompletionConfig, ResponseFormat};
 use crate::llm::gateways::TokenizerGateway;
-use crate::llm::models::{LlmMessage, MessageRole};
-use crate::llm::tools::LlmTool;
+use crate::llm::models::{LlmMessage, MessageRole, TokenUsage};
+use crate::llm::tools::{LlmTool, ToolResultCache};
 use futures::stream::{Stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use std::pin::Pin;
@@ -44,12 +44,34 @@ impl SizedLlmMessage {
     }
 }
 
+/// Identifies a point in a [`ChatSession`]'s history to roll back to.
+///
+/// Returned by [`ChatSession::checkpoint`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CheckpointId(u64);
+
+/// What a [`ChatSession`] does when the model returns an empty response.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmptyResponseBehavior {
+    /// Record the empty assistant message in history
+    #[default]
+    Record,
+    /// Leave the history without an assistant message for the empty turn
+    Skip,
+    /// Generate once more; if the retry is also empty, behave like `Skip`
+    RetryOnce,
+}
+
 /// A chat session that manages conversation history with context window limits.
 ///
 /// `ChatSession` maintains a list of messages and automatically trims old messages
 /// when the total token count exceeds the configured maximum context size. The system
 /// prompt (first message) is always preserved.
 ///
+/// Each session keeps its own [`ToolResultCache`], so a
+/// [cacheable](LlmTool::cacheable) tool called again with the same arguments
+/// later in the conversation returns its earlier result without running.
+///
 /// # Examples
 ///
 /// ```ignore
@@ -76,6 +98,11 @@ pub struct ChatSession {
     max_context: usize,
     tokenizer_gateway: TokenizerGateway,
     temperature: f32,
+    config: CompletionConfig,
+    empty_response_behavior: EmptyResponseBehavior,
+    cumulative_usage: TokenUsage,
+    checkpoints: Vec<(CheckpointId, Vec<SizedLlmMessage>)>,
+    next_checkpoint: u64,
 }
 
 impl ChatSession {
@@ -129,6 +156,9 @@ impl ChatSession {
     /// 3. Adds the assistant's response to the history
     /// 4. Automatically trims old messages if context window is exceeded
     ///
+    /// Empty responses are handled according to the session's
+    /// [`EmptyResponseBehavior`].
+    ///
     /// # Arguments
     ///
     /// * `query` - The user's message
@@ -144,6 +174,26 @@ impl ChatSession {
     /// println!("Answer: {}", response);
     /// ```
     pub async fn send(&mut self, query: &str) -> Result<String> {
+        self.send_turn(query, true).await
+    }
+
+    /// Send a message to the LLM without offering the session's tools for this turn.
+    ///
+    /// Behaves exactly like [`ChatSession::send`], except that no tools are sent
+    /// with the request, forcing the LLM to answer in prose. The session's tools
+    /// remain registered and are offered again on the next `send`. This suits
+    /// final summaries and other turns where a tool call would be unwanted.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let summary = session.send_no_tools("Summarize what you found.").await?;
+    /// ```
+    pub async fn send_no_tools(&mut self, query: &str) -> Result<String> {
+        self.send_turn(query, false).await
+    }
+
+    async fn send_turn(&mut self, query: &str, offer_tools: bool) -> Result<String> {
         // Add user message
         self.insert_message(LlmMessage::user(query));
 
@@ -151,19 +201,48 @@ impl ChatSession {
         let messages: Vec<LlmMessage> = self.messages.iter().map(|m| m.message.clone()).collect();
         let config = CompletionConfig {
             temperature: self.temperature,
-            ..Default::default()
+            ..self.config.clone()
         };
 
-        let response = self
+        let prompt_tokens = self.total_tokens();
+
+        let result = self
             .broker
-            .generate(&messages, self.tools.as_deref(), Some(config), None)
+            .generate_full(
+                &messages,
+                self.tools.as_deref().filter(|_| offer_tools),
+                Some(config.clone()),
+                None,
+            )
             .await?;
+        self.record_usage(prompt_tokens, &result);
+        let mut response = result.content;
+
+        if response.trim().is_empty()
+            && self.empty_response_behavior == EmptyResponseBehavior::RetryOnce
+        {
+            let result = self
+                .broker
+                .generate_full(
+                    &messages,
+                    self.tools.as_deref().filter(|_| offer_tools),
+                    Some(config),
+                    None,
+                )
+                .await?;
+            self.record_usage(prompt_tokens, &result);
+            response = result.content;
+        }
 
         // Ensure all messages in history have token counts
         self.ensure_all_messages_are_sized();
 
         // Add assistant response
-        self.insert_message(LlmMessage::assistant(&response));
+        if !response.trim().is_empty()
+            || self.empty_response_behavior == EmptyResponseBehavior::Record
+        {
+            self.insert_message(LlmMessage::assistant(&response));
+        }
 
         Ok(response)
     }
@@ -176,6 +255,12 @@ impl ChatSession {
     /// 3. After the stream is fully consumed, adds the assembled response to history
     /// 4. Automatically trims old messages if context window is exceeded
     ///
+    /// Dropping the stream before it completes interrupts the generation. Any
+    /// partial response produced so far is recorded as the assistant turn, so the
+    /// conversation stays coherent and the next `send` can follow up on it. If
+    /// the stream is dropped (or fails) before any content arrives, including
+    /// when it is never polled, the user message is removed again instead.
+    ///
     /// # Arguments
     ///
     /// * `query` - The user's message
@@ -201,22 +286,29 @@ impl ChatSession {
         // Add user message
         self.insert_message(LlmMessage::user(query));
 
-        // Clone messages for the broker call
+        // Clone messages, broker, and tools so the session itself stays free to
+        // record the response if the stream is dropped part-way through
         let messages: Vec<LlmMessage> = self.messages.iter().map(|m| m.message.clone()).collect();
         let config = CompletionConfig {
             temperature: self.temperature,
-            ..Default::default()
+            ..self.config.clone()
         };
+        let broker = self.broker.clone();
+        let tools: Option<Vec<Box<dyn LlmTool>>> =
+            self.tools.as_ref().map(|tools| tools.iter().map(|t| t.clone_box()).collect());
+        let prompt_tokens = self.total_tokens();
+        // Created before the stream is first polled, so dropping an unpolled
+        // stream still undoes the user message
+        let mut finalizer = StreamFinalizer::new(self, prompt_tokens);
 
         Box::pin(async_stream::stream! {
-            let mut accumulated = Vec::new();
-            let tools_ref = self.tools.as_deref();
-            let mut inner_stream = self.broker.generate_stream(&messages, tools_ref, Some(config), None);
+            let mut inner_stream =
+                broker.generate_stream(&messages, tools.as_deref(), Some(config), None);
 
             while let Some(result) = inner_stream.next().await {
                 match &result {
                     Ok(chunk) => {
-                        accumulated.push(chunk.clone());
+                        finalizer.accumulated.push_str(chunk);
                         yield result;
                     }
                     Err(_) => {
@@ -228,9 +320,7 @@ impl ChatSession {
 
             // Stream consumed — finalize
             drop(inner_stream);
-            self.ensure_all_messages_are_sized();
-            let full_response = accumulated.join("");
-            self.insert_message(LlmMessage::assistant(&full_response));
+            finalizer.finalize();
         })
     }
 
@@ -238,7 +328,9 @@ impl ChatSession {
     ///
     /// If the total token count exceeds `max_context`, the oldest messages
     /// are removed until the total is under the limit. The system prompt
-    /// (index 0) is always preserved.
+    /// (index 0) is always preserved, and an assistant message that made tool
+    /// calls is removed together with its tool results so no tool message is
+    /// left without the call it answers.
     ///
     /// # Arguments
     ///
@@ -251,10 +343,138 @@ impl ChatSession {
         let mut total_length: usize = self.messages.iter().map(|m| m.token_length).sum();
 
         while total_length > self.max_context && self.messages.len() > 1 {
-            // Remove the oldest message (index 1 to preserve system prompt at 0)
-            let removed = self.messages.remove(1);
-            total_length -= removed.token_length;
+            // Remove the oldest turn (from index 1 to preserve system prompt at 0)
+            let end = 1 + self.oldest_turn_len();
+            let removed: usize = self.messages.drain(1..end).map(|m| m.token_length).sum();
+            total_length -= removed;
+        }
+    }
+
+    /// The oldest turns that [`ChatSession::remove_oldest_turns`] would remove.
+    ///
+    /// Whole turns, starting after the system prompt, up to the point where at
+    /// most `keep_recent` messages remain.
+    pub fn oldest_turns(&self, keep_recent: usize) -> &[SizedLlmMessage] {
+        let mut end = 1;
+        while self.messages.len() - end > keep_recent {
+            end += self.turn_len_at(end);
+        }
+        &self.messages[1..end]
+    }
+
+    /// Remove the oldest turns, keeping the system prompt and recent messages.
+    ///
+    /// Whole turns are removed from the front of the history, as in
+    /// [`ChatSession::insert_message`], until at most `keep_recent` messages
+    /// follow the system prompt. Used to fold older history into a summary
+    /// (see [`MemoryConsolidationAgent`](crate::agents::MemoryConsolidationAgent)).
+    ///
+    /// # Returns
+    ///
+    /// The removed messages, oldest first
+    pub fn remove_oldest_turns(&mut self, keep_recent: usize) -> Vec<LlmMessage> {
+        let end = 1 + self.oldest_turns(keep_recent).len();
+        self.messages.drain(1..end).map(|m| m.message).collect()
+    }
+
+    /// Number of messages, starting at index 1, that must be removed together.
+    ///
+    /// An assistant tool-call message takes its following tool results with it,
+    /// as does a run of tool results whose call is already gone.
+    fn oldest_turn_len(&self) -> usize {
+        self.turn_len_at(1)
+    }
+
+    /// Number of messages, starting at `start`, that form one turn.
+    fn turn_len_at(&self, start: usize) -> usize {
+        let is_tool_result = |m: &SizedLlmMessage| m.role() == MessageRole::Tool;
+        let first = &self.messages[start];
+        let makes_tool_calls = first.role() == MessageRole::Assistant
+            && first.message.tool_calls.as_ref().is_some_and(|calls| !calls.is_empty());
+
+        if makes_tool_calls || is_tool_result(first) {
+            1 + self.messages[start + 1..].iter().take_while(|m| is_tool_result(m)).count()
+        } else {
+            1
+        }
+    }
+
+    /// Snapshot the conversation history so it can be restored later.
+    ///
+    /// Checkpoints nest: take one, explore, take another, and roll back to
+    /// either. See [`ChatSession::rollback`].
+    pub fn checkpoint(&mut self) -> CheckpointId {
+        let id = CheckpointId(self.next_checkpoint);
+        self.next_checkpoint += 1;
+        self.checkpoints.push((id, self.messages.clone()));
+        id
+    }
+
+    /// Restore the conversation history captured by a checkpoint.
+    ///
+    /// Messages and their token counts return to the snapshot. Checkpoints
+    /// taken after this one are discarded, while this one remains available
+    /// to roll back to again. [`ChatSession::cumulative_usage`] is not rewound,
+    /// since those tokens were still consumed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the checkpoint doesn't belong to this session or
+    /// was discarded by rolling back to an earlier one.
+    pub fn rollback(&mut self, id: CheckpointId) -> Result<()> {
+        let position = self
+            .checkpoints
+            .iter()
+            .position(|(checkpoint, _)| *checkpoint == id)
+            .ok_or_else(|| {
+                MojenticError::InvalidArgument(format!("Unknown checkpoint: {:?}", id))
+            })?;
+
+        self.checkpoints.truncate(position + 1);
+        self.messages = self.checkpoints[position].1.clone();
+        Ok(())
+    }
+
+    /// Make a tool available to the LLM for subsequent turns.
+    ///
+    /// A tool with the same name as an existing one replaces it, so descriptors
+    /// offered to the model stay unique.
+    ///
+    /// # Arguments
+    ///
+    /// * `tool` - The tool to add
+    pub fn add_tool(&mut self, tool: Box<dyn LlmTool>) {
+        let name = tool.descriptor().function.name;
+        let tools = self.tools.get_or_insert_with(Vec::new);
+        tools.retain(|t| t.descriptor().function.name != name);
+        tools.push(tool);
+    }
+
+    /// Remove a tool by name so it is no longer offered on subsequent turns.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The function name of the tool to remove
+    ///
+    /// # Returns
+    ///
+    /// `true` if a tool with that name was removed
+    pub fn remove_tool(&mut self, name: &str) -> bool {
+        let Some(tools) = self.tools.as_mut() else {
+            return false;
+        };
+        let before = tools.len();
+        tools.retain(|t| t.descriptor().function.name != name);
+        let removed = tools.len() != before;
+        if tools.is_empty() {
+            self.tools = None;
         }
+        removed
+    }
+
+    /// Get the tools currently offered to the LLM
+    pub fn tools(&self) -> &[Box<dyn LlmTool>] {
+        self.tools.as_deref().unwrap_or_default()
     }
 
     /// Get the current conversation history
@@ -267,6 +487,31 @@ impl ChatSession {
         self.messages.iter().map(|m| m.token_length).sum()
     }
 
+    /// Get the tokens consumed across every turn of this session.
+    ///
+    /// Unlike [`ChatSession::total_tokens`], which measures the messages currently
+    /// retained in the context window, this accumulates prompt and completion
+    /// tokens for each generation. Provider-reported usage is used when the
+    /// gateway supplies it; otherwise usage is estimated with the session's
+    /// tokenizer.
+    pub fn cumulative_usage(&self) -> TokenUsage {
+        self.cumulative_usage
+    }
+
+    /// Add one generation's usage to the running total
+    fn record_usage(&mut self, prompt_tokens: usize, result: &GenerationResult) {
+        let usage = result
+            .usage
+            .unwrap_or_else(|| self.estimate_usage(prompt_tokens, &result.content));
+        self.cumulative_usage += usage;
+    }
+
+    /// Estimate usage for a generation whose provider didn't report it
+    fn estimate_usage(&self, prompt_tokens: usize, response: &str) -> TokenUsage {
+        let completion_tokens = self.tokenizer_gateway.encode(response).len();
+        TokenUsage::new(prompt_tokens as u64, completion_tokens as u64)
+    }
+
     /// Build a sized message from a regular message
     fn build_sized_message(&self, message: LlmMessage) -> SizedLlmMessage {
         let token_length = if let Some(content) = &message.content {
@@ -290,6 +535,62 @@ impl ChatSession {
     }
 }
 
+/// Records a streamed response into the session's history.
+///
+/// `send_stream` finalizes explicitly once the stream is exhausted. If the stream
+/// is dropped first, `Drop` records whatever partial content was produced, or
+/// removes the turn's user message when nothing was produced.
+struct StreamFinalizer<'a> {
+    session: &'a mut ChatSession,
+    prompt_tokens: usize,
+    accumulated: String,
+    finalized: bool,
+}
+
+impl<'a> StreamFinalizer<'a> {
+    fn new(session: &'a mut ChatSession, prompt_tokens: usize) -> Self {
+        Self {
+            session,
+            prompt_tokens,
+            accumulated: String::new(),
+            finalized: false,
+        }
+    }
+
+    fn finalize(&mut self) {
+        if self.finalized {
+            return;
+        }
+        self.finalized = true;
+        // Streams don't surface provider usage, so estimate it
+        let usage = self.session.estimate_usage(self.prompt_tokens, &self.accumulated);
+        self.session.cumulative_usage += usage;
+        self.session.ensure_all_messages_are_sized();
+        // A stream can't be retried once handed out, so any non-default
+        // behavior simply leaves an empty turn out of the history
+        if !self.accumulated.trim().is_empty()
+            || self.session.empty_response_behavior == EmptyResponseBehavior::Record
+        {
+            self.session.insert_message(LlmMessage::assistant(&self.accumulated));
+        }
+    }
+}
+
+impl Drop for StreamFinalizer<'_> {
+    fn drop(&mut self) {
+        if self.finalized {
+            return;
+        }
+        if !self.accumulated.is_empty() {
+            self.finalize();
+        } else if self.session.messages.last().is_some_and(|m| m.role() == MessageRole::User) {
+            // Nothing was produced, so take back the user message rather than
+            // leave a turn without an answer
+            self.session.messages.pop();
+        }
+    }
+}
+
 /// Builder for constructing a `ChatSession` with custom configuration.
 pub struct ChatSessionBuilder {
     broker: LlmBroker,
@@ -297,7 +598,9 @@ pub struct ChatSessionBuilder {
     tools: Option<Vec<Box<dyn LlmTool>>>,
     max_context: usize,
     tokenizer_gateway: Option<TokenizerGateway>,
-    temperature: f32,
+    temperature: Option<f32>,
+    config: CompletionConfig,
+    empty_response_behavior: EmptyResponseBehavior,
 }
 
 impl ChatSessionBuilder {
@@ -309,7 +612,9 @@ impl ChatSessionBuilder {
             tools: None,
             max_context: 32768,
             tokenizer_gateway: None,
-            temperature: 1.0,
+            temperature: None,
+            config: CompletionConfig::default(),
+            empty_response_behavior: EmptyResponseBehavior::default(),
         }
     }
 
@@ -338,8 +643,38 @@ impl ChatSessionBuilder {
     }
 
     /// Set the temperature for generation (default: 1.0)
+    ///
+    /// Takes precedence over the temperature in [`ChatSessionBuilder::completion_config`].
     pub fn temperature(mut self, temperature: f32) -> Self {
-        self.temperature = temperature;
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// Set the completion configuration used for every turn (default: `CompletionConfig::default()`)
+    ///
+    /// Use this to pin a session to settings such as a JSON `response_format`.
+    pub fn completion_config(mut self, config: CompletionConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Set the response format requested on every turn (default: provider default)
+    pub fn response_format(mut self, response_format: ResponseFormat) -> Self {
+        self.config.response_format = Some(response_format);
+        self
+    }
+
+    /// Set the language the model responds in on every turn (default: the broker's)
+    ///
+    /// See [`LlmBroker::with_response_language`].
+    pub fn response_language(mut self, language: impl Into<String>) -> Self {
+        self.config.response_language = Some(language.into());
+        self
+    }
+
+    /// Set how empty model responses are handled (default: [`EmptyResponseBehavior::Record`])
+    pub fn empty_response_behavior(mut self, behavior: EmptyResponseBehavior) -> Self {
+        self.empty_response_behavior = behavior;
         self
     }
 
@@ -350,12 +685,17 @@ impl ChatSessionBuilder {
         let token_length = tokenizer_gateway.encode(&self.system_prompt).len();
 
         ChatSession {
-            broker: self.broker,
+            broker: self.broker.with_tool_result_cache(ToolResultCache::new()),
             messages: vec![SizedLlmMessage::new(system_message, token_length)],
             tools: self.tools,
             max_context: self.max_context,
             tokenizer_gateway,
-            temperature: self.temperature,
+            temperature: self.temperature.unwrap_or(self.config.temperature),
+            config: self.config,
+            empty_response_behavior: self.empty_response_behavior,
+            cumulative_usage: TokenUsage::default(),
+            checkpoints: Vec::new(),
+            next_checkpoint: 0,
         }
     }
 }
@@ -364,7 +704,8 @@ impl ChatSessionBuilder {
 mod tests {
     use super::*;
     use crate::llm::gateway::{LlmGateway, StreamChunk};
-    use crate::llm::models::LlmGatewayResponse;
+    use crate::llm::models::{LlmGatewayResponse, LlmToolCall};
+    use crate::llm::test_support;
     use crate::llm::tools::{FunctionDescriptor, ToolDescriptor};
     use async_trait::async_trait;
     use futures::stream::{self, Stream};
@@ -412,6 +753,8 @@ mod tests {
                 object: None,
                 tool_calls: vec![],
                 thinking: None,
+                usage: None,
+                payload_size: None,
             })
         }
 
@@ -472,6 +815,7 @@ mod tests {
                     name: self.name.clone(),
                     description: "A mock tool".to_string(),
                     parameters: json!({}),
+                    returns: None,
                 },
             }
         }
@@ -511,6 +855,43 @@ mod tests {
         assert_eq!(session.temperature, 0.5);
     }
 
+    #[tokio::test]
+    async fn test_builder_response_format_reaches_gateway() {
+        let gateway = Arc::new(test_support::MockGateway::new(vec![]));
+        let broker = LlmBroker::new("test-model", gateway.clone(), None);
+        let mut session = ChatSession::builder(broker)
+            .response_format(ResponseFormat::JsonObject { schema: None })
+            .build();
+
+        session.send("Give me JSON").await.unwrap();
+
+        let config = gateway.last_request().config;
+        assert!(matches!(
+            config.response_format,
+            Some(ResponseFormat::JsonObject { schema: None })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_builder_completion_config_with_temperature_override() {
+        let gateway = Arc::new(test_support::MockGateway::new(vec![]));
+        let broker = LlmBroker::new("test-model", gateway.clone(), None);
+        let mut session = ChatSession::builder(broker)
+            .temperature(0.3)
+            .completion_config(CompletionConfig {
+                temperature: 0.9,
+                response_format: Some(ResponseFormat::Text),
+                ..Default::default()
+            })
+            .build();
+
+        session.send("Hi").await.unwrap();
+
+        let config = gateway.last_request().config;
+        assert!(matches!(config.response_format, Some(ResponseFormat::Text)));
+        assert_eq!(config.temperature, 0.3);
+    }
+
     #[tokio::test]
     async fn test_builder_custom_max_context() {
         let gateway = Arc::new(MockGateway::new(vec![]));
@@ -555,6 +936,143 @@ mod tests {
         assert_eq!(session.messages[4].content(), Some("Second response"));
     }
 
+    #[tokio::test]
+    async fn test_empty_response_recorded_by_default() {
+        let gateway = Arc::new(MockGateway::new(vec!["".to_string()]));
+        let broker = LlmBroker::new("test-model", gateway, None);
+        let mut session = ChatSession::new(broker);
+
+        session.send("Hi").await.unwrap();
+
+        assert_eq!(session.messages.len(), 3);
+        assert_eq!(session.messages[2].content(), Some(""));
+    }
+
+    #[tokio::test]
+    async fn test_empty_response_skipped_when_configured() {
+        let gateway = Arc::new(MockGateway::new(vec!["".to_string(), "Real answer".to_string()]));
+        let broker = LlmBroker::new("test-model", gateway, None);
+        let mut session = ChatSession::builder(broker)
+            .empty_response_behavior(EmptyResponseBehavior::Skip)
+            .build();
+
+        let response = session.send("Hi").await.unwrap();
+
+        assert_eq!(response, "");
+        // system + user only
+        assert_eq!(session.messages.len(), 2);
+        assert!(session.messages.iter().all(|m| m.role() != MessageRole::Assistant));
+    }
+
+    #[tokio::test]
+    async fn test_empty_response_retried_once_when_configured() {
+        let gateway = Arc::new(MockGateway::new(vec!["".to_string(), "Real answer".to_string()]));
+        let broker = LlmBroker::new("test-model", gateway, None);
+        let mut session = ChatSession::builder(broker)
+            .empty_response_behavior(EmptyResponseBehavior::RetryOnce)
+            .build();
+
+        let response = session.send("Hi").await.unwrap();
+
+        assert_eq!(response, "Real answer");
+        assert_eq!(session.messages.len(), 3);
+        assert_eq!(session.messages[2].content(), Some("Real answer"));
+    }
+
+    #[tokio::test]
+    async fn test_cumulative_usage_sums_reported_usage_across_turns() {
+        // Reports fixed provider usage on every completion
+        let gateway = test_support::MockGateway::new(vec![]).with_responder(|_| {
+            Ok(LlmGatewayResponse::builder()
+                .content("ok")
+                .usage(TokenUsage::new(100, 20))
+                .build())
+        });
+        let broker = LlmBroker::new("test-model", Arc::new(gateway), None);
+        let mut session = ChatSession::new(broker);
+
+        assert_eq!(session.cumulative_usage(), TokenUsage::default());
+
+        session.send("First").await.unwrap();
+        session.send("Second").await.unwrap();
+
+        assert_eq!(session.cumulative_usage(), TokenUsage::new(200, 40));
+        assert_eq!(session.cumulative_usage().total_tokens(), 240);
+    }
+
+    #[tokio::test]
+    async fn test_cumulative_usage_estimates_when_not_reported() {
+        let gateway = Arc::new(MockGateway::new(vec![
+            "First answer".to_string(),
+            "Second answer".to_string(),
+        ]));
+        let broker = LlmBroker::new("test-model", gateway, None);
+        let mut session = ChatSession::new(broker);
+
+        session.send("First").await.unwrap();
+        let after_first = session.cumulative_usage();
+        session.send("Second").await.unwrap();
+        let after_second = session.cumulative_usage();
+
+        assert!(after_first.prompt_tokens > 0);
+        assert!(after_first.completion_tokens > 0);
+        // The second prompt re-sends the whole history, so usage outgrows the window
+        assert!(after_second.prompt_tokens > after_first.prompt_tokens * 2);
+        assert!(after_second.total_tokens() as usize > session.total_tokens());
+    }
+
+    #[tokio::test]
+    async fn test_rollback_restores_history_at_checkpoint() {
+        let gateway = Arc::new(MockGateway::new(vec![
+            "First".to_string(),
+            "Second".to_string(),
+            "Third".to_string(),
+        ]));
+        let broker = LlmBroker::new("test-model", gateway, None);
+        let mut session = ChatSession::new(broker);
+
+        session.send("Hello").await.unwrap();
+        let checkpoint = session.checkpoint();
+        let snapshot: Vec<Option<String>> =
+            session.messages().iter().map(|m| m.content().map(str::to_string)).collect();
+        let tokens = session.total_tokens();
+
+        session.send("Try something").await.unwrap();
+        session.send("And something else").await.unwrap();
+        session.rollback(checkpoint).unwrap();
+
+        let restored: Vec<Option<String>> =
+            session.messages().iter().map(|m| m.content().map(str::to_string)).collect();
+        assert_eq!(restored, snapshot);
+        assert_eq!(session.total_tokens(), tokens);
+    }
+
+    #[tokio::test]
+    async fn test_nested_checkpoints() {
+        let gateway = Arc::new(MockGateway::new(vec![]));
+        let broker = LlmBroker::new("test-model", gateway, None);
+        let mut session = ChatSession::new(broker);
+
+        let outer = session.checkpoint();
+        session.insert_message(LlmMessage::user("one"));
+        let inner = session.checkpoint();
+        session.insert_message(LlmMessage::user("two"));
+
+        session.rollback(inner).unwrap();
+        assert_eq!(session.messages().len(), 2);
+
+        // The inner checkpoint can be reused after rolling back to it
+        session.insert_message(LlmMessage::user("three"));
+        session.rollback(inner).unwrap();
+        assert_eq!(session.messages().last().unwrap().content(), Some("one"));
+
+        session.rollback(outer).unwrap();
+        assert_eq!(session.messages().len(), 1);
+
+        // Rolling back past a checkpoint discards it
+        assert!(matches!(session.rollback(inner), Err(MojenticError::InvalidArgument(_))));
+    }
+
     #[tokio::test]
     async fn test_insert_message_calculates_token_length() {
         let gateway = Arc::new(MockGateway::new(vec![]));
@@ -593,6 +1111,81 @@ mod tests {
         assert!(session.total_tokens() <= 50);
     }
 
+    #[tokio::test]
+    async fn test_context_window_trimming_keeps_tool_calls_with_results() {
+        let gateway = Arc::new(MockGateway::new(vec![]));
+        let broker = LlmBroker::new("test-model", gateway, None);
+        let mut session = ChatSession::new(broker);
+
+        let call = LlmToolCall {
+            id: Some("call_1".to_string()),
+            name: "lookup".to_string(),
+            arguments: HashMap::new(),
+        };
+        session.insert_message(LlmMessage {
+            role: MessageRole::Assistant,
+            content: Some("Let me look that up".to_string()),
+            tool_calls: Some(vec![call.clone()]),
+            image_paths: None,
+            cache: false,
+        });
+        session.insert_message(LlmMessage {
+            role: MessageRole::Tool,
+            content: Some("The answer is forty-two".to_string()),
+            tool_calls: Some(vec![call]),
+            image_paths: None,
+            cache: false,
+        });
+
+        // Exceed the limit by one token, so dropping the assistant message alone would fit
+        let follow_up = LlmMessage::user("Thanks, what else?");
+        let follow_up_len = session.build_sized_message(follow_up.clone()).token_length;
+        session.max_context = session.total_tokens() + follow_up_len - 1;
+        session.insert_message(follow_up);
+
+        let roles: Vec<MessageRole> = session.messages.iter().map(|m| m.role()).collect();
+        assert_eq!(roles, vec![MessageRole::System, MessageRole::User]);
+    }
+
+    #[tokio::test]
+    async fn test_remove_oldest_turns_keeps_tool_calls_with_results() {
+        let gateway = Arc::new(MockGateway::new(vec![]));
+        let broker = LlmBroker::new("test-model", gateway, None);
+        let mut session = ChatSession::new(broker);
+
+        let call = LlmToolCall {
+            id: Some("call_1".to_string()),
+            name: "lookup".to_string(),
+            arguments: HashMap::new(),
+        };
+        session.insert_message(LlmMessage::user("Look it up"));
+        session.insert_message(LlmMessage {
+            role: MessageRole::Assistant,
+            content: None,
+            tool_calls: Some(vec![call.clone()]),
+            image_paths: None,
+            cache: false,
+        });
+        session.insert_message(LlmMessage {
+            role: MessageRole::Tool,
+            content: Some("forty-two".to_string()),
+            tool_calls: Some(vec![call]),
+            image_paths: None,
+            cache: false,
+        });
+        session.insert_message(LlmMessage::assistant("It's forty-two"));
+
+        // Keeping two would split the tool call from its result
+        assert_eq!(session.oldest_turns(2).len(), 3);
+        let removed = session.remove_oldest_turns(2);
+
+        assert_eq!(removed.len(), 3);
+        assert_eq!(removed[0].content.as_deref(), Some("Look it up"));
+        let roles: Vec<MessageRole> = session.messages.iter().map(|m| m.role()).collect();
+        assert_eq!(roles, vec![MessageRole::System, MessageRole::Assistant]);
+        assert!(session.remove_oldest_turns(1).is_empty());
+    }
+
     #[tokio::test]
     async fn test_context_window_preserves_system_prompt() {
         let gateway = Arc::new(MockGateway::new(vec![]));
@@ -656,6 +1249,83 @@ mod tests {
         assert_eq!(session.tools.as_ref().unwrap().len(), 1);
     }
 
+    fn offered_tools(gateway: &test_support::MockGateway) -> Vec<Vec<String>> {
+        gateway.requests().into_iter().map(|request| request.tool_names).collect()
+    }
+
+    #[tokio::test]
+    async fn test_add_tool_mid_session_is_offered_on_next_send() {
+        let gateway = Arc::new(test_support::MockGateway::new(vec![]));
+        let broker = LlmBroker::new("test-model", gateway.clone(), None);
+        let mut session = ChatSession::new(broker);
+
+        session.send("First").await.unwrap();
+        session.add_tool(Box::new(MockTool {
+            name: "granted_tool".to_string(),
+        }));
+        session.send("Second").await.unwrap();
+
+        let offered = offered_tools(&gateway);
+        assert!(offered[0].is_empty());
+        assert_eq!(offered[1], vec!["granted_tool".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_add_tool_replaces_tool_with_same_name() {
+        let gateway = Arc::new(MockGateway::new(vec![]));
+        let broker = LlmBroker::new("test-model", gateway, None);
+        let mut session = ChatSession::new(broker);
+
+        session.add_tool(Box::new(MockTool {
+            name: "tool".to_string(),
+        }));
+        session.add_tool(Box::new(MockTool {
+            name: "tool".to_string(),
+        }));
+
+        assert_eq!(session.tools().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_remove_tool_stops_offering_it() {
+        let gateway = Arc::new(test_support::MockGateway::new(vec![]));
+        let broker = LlmBroker::new("test-model", gateway.clone(), None);
+        let tool: Box<dyn LlmTool> = Box::new(MockTool {
+            name: "revoked_tool".to_string(),
+        });
+        let mut session = ChatSession::builder(broker).tools(vec![tool]).build();
+
+        session.send("First").await.unwrap();
+        assert!(session.remove_tool("revoked_tool"));
+        assert!(!session.remove_tool("revoked_tool"));
+        session.send("Second").await.unwrap();
+
+        let offered = offered_tools(&gateway);
+        assert_eq!(offered[0], vec!["revoked_tool".to_string()]);
+        assert!(offered[1].is_empty());
+        assert!(session.tools.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_send_no_tools_omits_tools_for_that_turn_only() {
+        let gateway = Arc::new(test_support::MockGateway::new(vec![]));
+        let broker = LlmBroker::new("test-model", gateway.clone(), None);
+        let tool: Box<dyn LlmTool> = Box::new(MockTool {
+            name: "search".to_string(),
+        });
+        let mut session = ChatSession::builder(broker).tools(vec![tool]).build();
+
+        session.send("Research this").await.unwrap();
+        session.send_no_tools("Summarize").await.unwrap();
+        session.send("Keep going").await.unwrap();
+
+        let offered = offered_tools(&gateway);
+        assert_eq!(offered[0], vec!["search".to_string()]);
+        assert!(offered[1].is_empty());
+        assert_eq!(offered[2], vec!["search".to_string()]);
+        assert_eq!(session.tools().len(), 1);
+    }
+
     #[tokio::test]
     async fn test_sized_message_creation() {
         let message = LlmMessage::user("Test content");
@@ -695,6 +1365,8 @@ mod tests {
                 object: None,
                 tool_calls: vec![],
                 thinking: None,
+                usage: None,
+                payload_size: None,
             })
         }
 
@@ -809,6 +1481,77 @@ mod tests {
         assert_eq!(session.messages[1].content(), Some("My question"));
     }
 
+    #[tokio::test]
+    async fn test_send_stream_dropped_early_records_partial_response() {
+        let gateway = Arc::new(StreamingMockGateway::new(vec![
+            vec!["Once upon".to_string(), " a time".to_string()],
+            vec!["Sure, a poem instead".to_string()],
+        ]));
+        let broker = LlmBroker::new("test-model", gateway, None);
+        let mut session = ChatSession::new(broker);
+
+        {
+            let mut stream = session.send_stream("Tell me a story");
+            let first = stream.next().await.unwrap().unwrap();
+            assert_eq!(first, "Once upon");
+        }
+
+        // system + user + partial assistant
+        assert_eq!(session.messages.len(), 3);
+        assert_eq!(session.messages[2].role(), MessageRole::Assistant);
+        assert_eq!(session.messages[2].content(), Some("Once upon"));
+
+        {
+            let mut stream = session.send_stream("Stop, I meant a poem");
+            while stream.next().await.is_some() {}
+        }
+
+        assert_eq!(session.messages.len(), 5);
+        assert_eq!(session.messages[3].content(), Some("Stop, I meant a poem"));
+        assert_eq!(session.messages[4].content(), Some("Sure, a poem instead"));
+    }
+
+    #[tokio::test]
+    async fn test_send_after_stream_dropped_early_alternates_turns() {
+        let gateway = Arc::new(StreamingMockGateway::new(vec![vec![
+            "Once upon".to_string(),
+            " a time".to_string(),
+        ]]));
+        let broker = LlmBroker::new("test-model", gateway, None);
+        let mut session = ChatSession::new(broker);
+
+        {
+            let mut stream = session.send_stream("Tell me a story");
+            stream.next().await.unwrap().unwrap();
+        }
+        let reply = session.send("Never mind").await.unwrap();
+
+        assert_eq!(reply, "default");
+        let roles: Vec<MessageRole> = session.messages.iter().map(|m| m.role()).collect();
+        assert_eq!(
+            roles,
+            vec![
+                MessageRole::System,
+                MessageRole::User,
+                MessageRole::Assistant,
+                MessageRole::User,
+                MessageRole::Assistant,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_stream_dropped_before_content_removes_user_message() {
+        let gateway = Arc::new(StreamingMockGateway::new(vec![vec!["Hello".to_string()]]));
+        let broker = LlmBroker::new("test-model", gateway, None);
+        let mut session = ChatSession::new(broker);
+
+        drop(session.send_stream("Are you there?"));
+
+        assert_eq!(session.messages.len(), 1);
+        assert_eq!(session.messages[0].role(), MessageRole::System);
+    }
+
     #[tokio::test]
     async fn test_send_stream_respects_context_capacity() {
         let gateway = Arc::new(StreamingMockGateway::new(vec![
@@ -844,6 +1587,7 @@ mod tests {
             content: None,
             tool_calls: None,
             image_paths: None,
+            cache: false,
         };
 
         session.insert_message(message);
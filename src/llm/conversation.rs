@@ -0,0 +1,220 @@
+//! Helpers for working directly with conversation histories.
+//!
+//! Where the tracer's `printable_summary` describes individual events, the functions
+//! in this module operate on a slice of [`LlmMessage`]s, which is handy for CLI
+//! debugging of a whole conversation.
+
+use crate::llm::models::{LlmMessage, LlmToolCall, MessageRole};
+
+const ANSI_RESET: &str = "\x1b[0m";
+const ANSI_DIM: &str = "\x1b[2m";
+
+/// Options controlling how [`pretty_print`] renders a conversation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PrettyOptions {
+    /// Wrap role headers and tool details in ANSI color codes
+    pub color: bool,
+    /// Truncate message content and tool results to this many characters
+    pub max_content_chars: Option<usize>,
+}
+
+impl PrettyOptions {
+    /// Enable or disable ANSI color output
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Truncate content longer than `max_chars` characters
+    pub fn with_max_content_chars(mut self, max_chars: usize) -> Self {
+        self.max_content_chars = Some(max_chars);
+        self
+    }
+}
+
+/// Render a conversation as human-readable text.
+///
+/// Each message is printed with its role, followed by its content. Assistant tool
+/// requests are listed with their arguments, and tool messages are rendered as the
+/// result of the call they answer.
+///
+/// # Examples
+///
+/// ```
+/// use mojentic::llm::conversation::{pretty_print, PrettyOptions};
+/// use mojentic::llm::LlmMessage;
+///
+/// let messages = vec![LlmMessage::user("Hello"), LlmMessage::assistant("Hi there!")];
+/// let text = pretty_print(&messages, PrettyOptions::default());
+///
+/// assert!(text.contains("[user]"));
+/// assert!(text.contains("Hi there!"));
+/// ```
+pub fn pretty_print(messages: &[LlmMessage], options: PrettyOptions) -> String {
+    messages
+        .iter()
+        .map(|message| format_message(message, &options))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn format_message(message: &LlmMessage, options: &PrettyOptions) -> String {
+    let mut lines = Vec::new();
+
+    if message.role == MessageRole::Tool {
+        let call_name = message
+            .tool_calls
+            .as_ref()
+            .and_then(|calls| calls.first())
+            .map(|call| call.name.as_str())
+            .unwrap_or("unknown");
+        lines.push(paint(
+            &format!("[tool result: {}]", call_name),
+            role_color(message.role),
+            options,
+        ));
+        if let Some(content) = &message.content {
+            lines.push(format!("  {}", truncate(content, options.max_content_chars)));
+        }
+        return lines.join("\n");
+    }
+
+    lines.push(paint(
+        &format!("[{}]", role_label(message.role)),
+        role_color(message.role),
+        options,
+    ));
+
+    if let Some(content) = message.content.as_deref().filter(|c| !c.is_empty()) {
+        lines.push(truncate(content, options.max_content_chars));
+    }
+
+    if let Some(images) = &message.image_paths {
+        for path in images {
+            lines.push(paint(&format!("  (image: {})", path), ANSI_DIM, options));
+        }
+    }
+
+    if let Some(tool_calls) = &message.tool_calls {
+        for call in tool_calls {
+            lines.push(paint(&format_tool_call(call, options), ANSI_DIM, options));
+        }
+    }
+
+    lines.join("\n")
+}
+
+fn format_tool_call(call: &LlmToolCall, options: &PrettyOptions) -> String {
+    let args = serde_json::to_string(&call.arguments).unwrap_or_default();
+    let id = call.id.as_deref().map(|id| format!(" ({})", id)).unwrap_or_default();
+    format!("  -> {}{}: {}", call.name, id, truncate(&args, options.max_content_chars))
+}
+
+fn role_label(role: MessageRole) -> &'static str {
+    match role {
+        MessageRole::System => "system",
+        MessageRole::User => "user",
+        MessageRole::Assistant => "assistant",
+        MessageRole::Tool => "tool",
+    }
+}
+
+fn role_color(role: MessageRole) -> &'static str {
+    match role {
+        MessageRole::System => "\x1b[35m",
+        MessageRole::User => "\x1b[36m",
+        MessageRole::Assistant => "\x1b[32m",
+        MessageRole::Tool => "\x1b[33m",
+    }
+}
+
+fn paint(text: &str, color: &str, options: &PrettyOptions) -> String {
+    if options.color {
+        format!("{}{}{}", color, text, ANSI_RESET)
+    } else {
+        text.to_string()
+    }
+}
+
+fn truncate(text: &str, max_chars: Option<usize>) -> String {
+    match max_chars {
+        Some(max) if text.chars().count() > max => {
+            let truncated: String = text.chars().take(max).collect();
+            format!("{}...", truncated)
+        }
+        _ => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn tool_call_conversation() -> Vec<LlmMessage> {
+        let mut args = HashMap::new();
+        args.insert("date".to_string(), serde_json::json!("tomorrow"));
+        let call = LlmToolCall {
+            id: Some("call_1".to_string()),
+            name: "resolve_date".to_string(),
+            arguments: args,
+        };
+
+        vec![
+            LlmMessage::system("You are a helpful assistant."),
+            LlmMessage::user("What is the date tomorrow?"),
+            LlmMessage {
+                role: MessageRole::Assistant,
+                content: None,
+                tool_calls: Some(vec![call.clone()]),
+                image_paths: None,
+                cache: false,
+            },
+            LlmMessage {
+                role: MessageRole::Tool,
+                content: Some(r#"{"resolved_date":"2026-10-19"}"#.to_string()),
+                tool_calls: Some(vec![call]),
+                image_paths: None,
+                cache: false,
+            },
+            LlmMessage::assistant("Tomorrow is 2026-10-19."),
+        ]
+    }
+
+    #[test]
+    fn test_pretty_print_conversation_with_tool_call() {
+        let text = pretty_print(&tool_call_conversation(), PrettyOptions::default());
+
+        assert_eq!(
+            text,
+            "[system]\nYou are a helpful assistant.\n\n\
+             [user]\nWhat is the date tomorrow?\n\n\
+             [assistant]\n  -> resolve_date (call_1): {\"date\":\"tomorrow\"}\n\n\
+             [tool result: resolve_date]\n  {\"resolved_date\":\"2026-10-19\"}\n\n\
+             [assistant]\nTomorrow is 2026-10-19."
+        );
+    }
+
+    #[test]
+    fn test_pretty_print_truncates_content() {
+        let messages = vec![LlmMessage::user("abcdefghij")];
+        let text = pretty_print(&messages, PrettyOptions::default().with_max_content_chars(4));
+
+        assert_eq!(text, "[user]\nabcd...");
+    }
+
+    #[test]
+    fn test_pretty_print_with_color() {
+        let messages = vec![LlmMessage::user("Hello")];
+        let text = pretty_print(&messages, PrettyOptions::default().with_color(true));
+
+        assert!(text.starts_with("\x1b[36m[user]\x1b[0m"));
+    }
+
+    #[test]
+    fn test_pretty_print_without_color_has_no_escape_codes() {
+        let text = pretty_print(&tool_call_conversation(), PrettyOptions::default());
+
+        assert!(!text.contains('\x1b'));
+    }
+}
@@ -0,0 +1,22 @@
+//! Embeddings provider abstraction.
+//!
+//! Embeddings are a separate capability from chat completion: some chat providers
+//! don't offer them at all, and applications often pair a chat model from one
+//! provider with an embedding model from another. [`EmbeddingGateway`] lets an
+//! [`LlmBroker`](crate::llm::LlmBroker) be configured with its own embeddings
+//! provider, independent of the gateway used for completions.
+
+use crate::error::Result;
+use async_trait::async_trait;
+
+/// Abstract interface for embedding providers
+#[async_trait]
+pub trait EmbeddingGateway: Send + Sync {
+    /// Calculate embeddings for text
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to embed
+    /// * `model` - Optional embedding model (the provider's default is used if None)
+    async fn calculate_embeddings(&self, text: &str, model: Option<&str>) -> Result<Vec<f32>>;
+}
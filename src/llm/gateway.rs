@@ -1,10 +1,12 @@
-use crate::error::Result;
+use crate::error::{MojenticError, Result};
+use crate::llm::gateways::ModelType;
 use crate::llm::models::{LlmGatewayResponse, LlmMessage};
-use crate::llm::tools::LlmTool;
+use crate::llm::tools::{LlmTool, ToolProvider};
 use async_trait::async_trait;
 use futures::stream::Stream;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
 use std::pin::Pin;
 
 /// Format specification for LLM responses
@@ -25,6 +27,19 @@ pub enum ReasoningEffort {
     High,
 }
 
+/// How the broker feeds the results of several tool calls from one turn back to the LLM
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ToolResultFormat {
+    /// One tool message per tool call
+    #[default]
+    Separate,
+    /// A single tool message whose content is an array of `{tool_call_id, result}` objects
+    Aggregated,
+}
+
+/// Context window requested by [`CompletionConfig::default`], in tokens
+pub const DEFAULT_NUM_CTX: usize = 32768;
+
 /// Configuration for LLM completion
 #[derive(Debug, Clone)]
 pub struct CompletionConfig {
@@ -36,21 +51,81 @@ pub struct CompletionConfig {
     pub top_k: Option<u32>,
     pub response_format: Option<ResponseFormat>,
     pub reasoning_effort: Option<ReasoningEffort>,
+    /// Tokens a reasoning model may spend thinking before it answers.
+    ///
+    /// For providers that take a thinking budget. Ollama has no budget and
+    /// enables thinking (`think: true`) when one is set; OpenAI reasoning
+    /// models are steered with [`reasoning_effort`](Self::reasoning_effort)
+    /// instead, so the budget is ignored there.
+    pub thinking_budget_tokens: Option<u32>,
     pub max_tool_iterations: usize,
+    /// Number of response choices to request (see [`LlmGateway::complete_many`])
+    pub n: Option<u32>,
+    /// How results from several tool calls in one turn are added to the conversation
+    pub tool_result_format: ToolResultFormat,
+    /// Bias applied to tokens, keyed by token text, in the range -100 to 100.
+    ///
+    /// OpenAI resolves each string to the model's token ids; text spanning
+    /// several tokens biases each of them. Ollama has no equivalent and
+    /// ignores it.
+    pub logit_bias: Option<HashMap<String, f32>>,
+    /// Identifier for the end user on whose behalf the request is made.
+    ///
+    /// Sent as OpenAI's `user` field, which OpenAI uses to detect abuse; use
+    /// an opaque id rather than a name or email. Ollama ignores it.
+    pub user: Option<String>,
+    /// Model to use for this request instead of the broker's model.
+    ///
+    /// Lets one [`LlmBroker`](crate::llm::LlmBroker) escalate a single call
+    /// to another model on the same gateway. The broker's context guard checks
+    /// the request against this model's limit; the auto-sized context length
+    /// still describes the broker's own model.
+    pub model: Option<String>,
+    /// Language to respond in for this request, overriding the broker's
+    /// (see [`LlmBroker::with_response_language`](crate::llm::LlmBroker::with_response_language)).
+    pub response_language: Option<String>,
+    /// Text a text response must start with, such as `{` to force JSON.
+    ///
+    /// Gateways that can continue an assistant turn (see
+    /// [`LlmGateway::supports_assistant_prefill`]) receive it as a partial
+    /// assistant message; for the rest the prompt asks the model to begin with
+    /// it. Either way the text from `generate`, `generate_many`, and the
+    /// streaming methods starts with the prefill, including after a tool loop;
+    /// a response that only requests tools doesn't get it. Structured requests
+    /// (`generate_object` and its variants) ignore it, since their schema
+    /// already constrains the output.
+    pub assistant_prefill: Option<String>,
+    /// Provider-specific options this config doesn't model, such as Ollama's
+    /// `mirostat` or OpenAI's `service_tier`.
+    ///
+    /// Merged into Ollama's `options` object and into the top level of OpenAI's
+    /// request body. Options set by the fields above take precedence over an
+    /// entry with the same name, and entries never replace request structure
+    /// such as the model, messages, or tools.
+    pub extra: HashMap<String, Value>,
 }
 
 impl Default for CompletionConfig {
     fn default() -> Self {
         Self {
             temperature: 1.0,
-            num_ctx: 32768,
+            num_ctx: DEFAULT_NUM_CTX,
             max_tokens: 16384,
             num_predict: None,
             top_p: None,
             top_k: None,
             response_format: None,
             reasoning_effort: None,
+            thinking_budget_tokens: None,
             max_tool_iterations: 10,
+            n: None,
+            tool_result_format: ToolResultFormat::default(),
+            logit_bias: None,
+            user: None,
+            model: None,
+            response_language: None,
+            assistant_prefill: None,
+            extra: HashMap::new(),
         }
     }
 }
@@ -76,11 +151,99 @@ pub trait LlmGateway: Send + Sync {
         config: &CompletionConfig,
     ) -> Result<Value>;
 
+    /// Complete an LLM request, returning several independent response choices
+    ///
+    /// The number of choices comes from `config.n` (one if unset). Gateways whose
+    /// provider can return several choices in one round-trip should override this;
+    /// the default calls [`LlmGateway::complete`] once per requested choice.
+    async fn complete_many(
+        &self,
+        model: &str,
+        messages: &[LlmMessage],
+        tools: Option<&[Box<dyn LlmTool>]>,
+        config: &CompletionConfig,
+    ) -> Result<Vec<LlmGatewayResponse>> {
+        let count = config.n.unwrap_or(1).max(1);
+        let mut responses = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            responses.push(self.complete(model, messages, tools, config).await?);
+        }
+        Ok(responses)
+    }
+
     /// Get list of available models
     async fn get_available_models(&self) -> Result<Vec<String>>;
 
     /// Calculate embeddings for text
-    async fn calculate_embeddings(&self, text: &str, model: Option<&str>) -> Result<Vec<f32>>;
+    ///
+    /// Gateways that don't offer embeddings can rely on the default, which returns
+    /// [`MojenticError::Unsupported`]. Prefer configuring a dedicated
+    /// [`crate::llm::EmbeddingGateway`] on the broker for new code.
+    async fn calculate_embeddings(&self, _text: &str, _model: Option<&str>) -> Result<Vec<f32>> {
+        Err(MojenticError::Unsupported(
+            "embeddings are not supported by this gateway".to_string(),
+        ))
+    }
+
+    /// Whether [`LlmGateway::complete_stream`] can be used with the given model
+    ///
+    /// Lets callers choose between streaming and non-streaming completion before
+    /// building a stream. The default assumes every model can stream.
+    fn supports_streaming(&self, _model: &str) -> bool {
+        true
+    }
+
+    /// Whether the given model accepts image input
+    ///
+    /// Checked by [`crate::llm::LlmBroker::describe_image`] before sending an
+    /// image. The default assumes every model can see images.
+    async fn supports_vision(&self, _model: &str) -> bool {
+        true
+    }
+
+    /// Whether the given model continues a trailing assistant message
+    ///
+    /// When true, [`CompletionConfig::assistant_prefill`] is sent as a partial
+    /// assistant turn for the model to continue. Otherwise the broker asks for
+    /// the prefix in the prompt instead. The default assumes no support.
+    fn supports_assistant_prefill(&self, _model: &str) -> bool {
+        false
+    }
+
+    /// The format this gateway sends tool definitions in
+    ///
+    /// Token estimates serialize tools in this format, so they count what the
+    /// provider actually receives. The default is the OpenAI function-tool
+    /// format, which most providers accept.
+    fn tool_provider(&self) -> ToolProvider {
+        ToolProvider::OpenAI
+    }
+
+    /// Longest context the given model accepts, in tokens, when the gateway knows it
+    ///
+    /// Used by [`crate::llm::LlmBroker::with_auto_context_length`] to size
+    /// `num_ctx` to the model. The default knows no limits.
+    async fn context_length(&self, _model: &str) -> Option<usize> {
+        None
+    }
+
+    /// What the gateway knows about a model: whether it offers it, and what it can do
+    ///
+    /// Backs [`crate::llm::LlmBroker::describe_model`]. The default combines
+    /// [`LlmGateway::get_available_models`] with the other capability methods,
+    /// assuming a chat model that supports tools.
+    async fn describe_model(&self, model: &str) -> Result<ModelDescription> {
+        let available = self.get_available_models().await?.iter().any(|name| name == model);
+        Ok(ModelDescription {
+            name: model.to_string(),
+            available,
+            model_type: ModelType::Chat,
+            supports_tools: true,
+            supports_streaming: self.supports_streaming(model),
+            supports_vision: self.supports_vision(model).await,
+            context_length: self.context_length(model).await,
+        })
+    }
 
     /// Stream LLM responses chunk by chunk
     ///
@@ -95,6 +258,21 @@ pub trait LlmGateway: Send + Sync {
     ) -> Pin<Box<dyn Stream<Item = Result<StreamChunk>> + Send + 'a>>;
 }
 
+/// A model's availability and capabilities, from [`LlmGateway::describe_model`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelDescription {
+    pub name: String,
+    /// Whether the gateway lists the model among those it offers
+    pub available: bool,
+    /// The kind of model, inferred from its name or reported capabilities
+    pub model_type: ModelType,
+    pub supports_tools: bool,
+    pub supports_streaming: bool,
+    pub supports_vision: bool,
+    /// Longest context the model accepts, in tokens, when known
+    pub context_length: Option<usize>,
+}
+
 /// Streaming response chunk
 #[derive(Debug, Clone)]
 pub enum StreamChunk {
@@ -108,6 +286,11 @@ pub enum StreamChunk {
     Progress(StreamProgress),
     /// Provider-neutral final generation metrics when the provider exposes them.
     Metrics(StreamMetrics),
+    /// Progress from a tool the broker is running between model turns.
+    ///
+    /// Only produced by [`crate::llm::LlmBroker::generate_stream_with_progress`],
+    /// never by gateways.
+    ToolProgress(crate::llm::tools::ToolProgress),
 }
 
 /// Provider-neutral progress for a streamed provider frame.
@@ -151,7 +334,11 @@ mod tests {
         assert_eq!(config.top_k, None);
         assert!(config.response_format.is_none());
         assert!(config.reasoning_effort.is_none());
+        assert_eq!(config.thinking_budget_tokens, None);
+        assert_eq!(config.user, None);
         assert_eq!(config.max_tool_iterations, 10);
+        assert_eq!(config.n, None);
+        assert_eq!(config.tool_result_format, ToolResultFormat::Separate);
     }
 
     #[test]
@@ -165,7 +352,16 @@ mod tests {
             top_k: Some(40),
             response_format: Some(ResponseFormat::Text),
             reasoning_effort: None,
+            thinking_budget_tokens: None,
             max_tool_iterations: 10,
+            n: None,
+            tool_result_format: ToolResultFormat::default(),
+            logit_bias: None,
+            user: None,
+            model: None,
+            response_language: None,
+            assistant_prefill: None,
+            extra: HashMap::new(),
         };
 
         assert_eq!(config.temperature, 0.5);
@@ -188,7 +384,16 @@ mod tests {
             top_k: Some(50),
             response_format: Some(ResponseFormat::JsonObject { schema: None }),
             reasoning_effort: None,
+            thinking_budget_tokens: None,
             max_tool_iterations: 10,
+            n: None,
+            tool_result_format: ToolResultFormat::default(),
+            logit_bias: None,
+            user: None,
+            model: None,
+            response_language: None,
+            assistant_prefill: None,
+            extra: HashMap::new(),
         };
 
         let config2 = config1.clone();
@@ -246,7 +451,16 @@ mod tests {
                 schema: Some(serde_json::json!({"type": "object"})),
             }),
             reasoning_effort: None,
+            thinking_budget_tokens: None,
             max_tool_iterations: 10,
+            n: None,
+            tool_result_format: ToolResultFormat::default(),
+            logit_bias: None,
+            user: None,
+            model: None,
+            response_language: None,
+            assistant_prefill: None,
+            extra: HashMap::new(),
         };
 
         assert_eq!(config.temperature, 0.8);
@@ -289,7 +503,16 @@ mod tests {
             top_k: None,
             response_format: None,
             reasoning_effort: Some(ReasoningEffort::High),
+            thinking_budget_tokens: None,
             max_tool_iterations: 10,
+            n: None,
+            tool_result_format: ToolResultFormat::default(),
+            logit_bias: None,
+            user: None,
+            model: None,
+            response_language: None,
+            assistant_prefill: None,
+            extra: HashMap::new(),
         };
 
         assert_eq!(config.reasoning_effort, Some(ReasoningEffort::High));
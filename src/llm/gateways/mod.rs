@@ -1,12 +1,70 @@
+use crate::error::{MojenticError, Result};
+use reqwest::{Client, NoProxy, Proxy, StatusCode};
+use std::time::Duration;
+
 pub mod ollama;
 pub mod openai;
 pub mod openai_messages_adapter;
 pub mod openai_model_registry;
+pub mod openai_tool_call_accumulator;
+pub mod rate_limited;
 pub mod tokenizer_gateway;
 
-pub use ollama::{OllamaConfig, OllamaGateway};
-pub use openai::{OpenAIConfig, OpenAIGateway};
+pub use ollama::{ModelInfo, OllamaConfig, OllamaConfigBuilder, OllamaGateway};
+pub use openai::{OpenAIConfig, OpenAIConfigBuilder, OpenAIGateway};
 pub use openai_model_registry::{
     get_model_registry, ModelCapabilities, ModelType, OpenAIModelRegistry,
 };
+pub use openai_tool_call_accumulator::ToolCallStreamAccumulator;
+pub use rate_limited::{RateLimitedGateway, DEFAULT_RATE_LIMIT_MAX_WAIT};
 pub use tokenizer_gateway::TokenizerGateway;
+
+/// The error for a provider response with an unsuccessful status
+///
+/// Rejected credentials become [`MojenticError::Unauthorized`] so a caller can
+/// tell a bad API key apart from other failures.
+pub(crate) fn status_error(action: &str, status: StatusCode) -> MojenticError {
+    match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+            MojenticError::Unauthorized(format!("{}: {}", action, status))
+        }
+        _ => MojenticError::GatewayError(format!("{}: {}", action, status)),
+    }
+}
+
+/// Build a gateway's HTTP client with its timeout and proxy settings
+///
+/// Requests go through `proxy`, except for the `no_proxy` hosts. Without a
+/// proxy, reqwest falls back to the `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY`
+/// environment variables.
+///
+/// # Errors
+///
+/// Returns [`MojenticError::HttpError`] if `proxy` isn't a valid proxy URL or
+/// the TLS backend fails to initialize.
+pub(crate) fn build_client(
+    timeout: Option<Duration>,
+    proxy: Option<&str>,
+    no_proxy: &[String],
+) -> Result<Client> {
+    let mut builder = Client::builder();
+    if let Some(timeout) = timeout {
+        builder = builder.timeout(timeout);
+    }
+    if let Some(proxy) = proxy {
+        builder =
+            builder.proxy(Proxy::all(proxy)?.no_proxy(NoProxy::from_string(&no_proxy.join(","))));
+    }
+    Ok(builder.build()?)
+}
+
+/// The error for a request that never got a response
+///
+/// Connection failures and timeouts become [`MojenticError::NetworkError`].
+pub(crate) fn send_error(error: reqwest::Error) -> MojenticError {
+    if error.is_connect() || error.is_timeout() {
+        MojenticError::NetworkError(error.to_string())
+    } else {
+        MojenticError::HttpError(error)
+    }
+}
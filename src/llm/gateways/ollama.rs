@@ -1,9 +1,12 @@
 use crate::error::{MojenticError, Result};
 use crate::llm::gateway::{
-    CompletionConfig, LlmGateway, StreamChunk, StreamMetrics, StreamProgress,
+    CompletionConfig, LlmGateway, ModelDescription, StreamChunk, StreamMetrics, StreamProgress,
 };
-use crate::llm::models::{LlmGatewayResponse, LlmMessage, LlmToolCall, MessageRole};
-use crate::llm::tools::LlmTool;
+use crate::llm::gateways::{build_client, send_error, status_error, ModelType};
+use crate::llm::models::{
+    LlmGatewayResponse, LlmMessage, LlmToolCall, MessageRole, PayloadSize, TokenUsage,
+};
+use crate::llm::tools::{tools_to_provider_json, LlmTool, ToolProvider};
 use async_trait::async_trait;
 use futures::stream::{Stream, StreamExt};
 use reqwest::Client;
@@ -13,11 +16,18 @@ use std::pin::Pin;
 use tracing::{debug, info, warn};
 
 /// Configuration for connecting to Ollama server
+///
+/// Build one with [`OllamaConfig::builder`], since new settings may be added.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct OllamaConfig {
     pub host: String,
     pub timeout: Option<std::time::Duration>,
     pub headers: HashMap<String, String>,
+    /// Proxy for every request, such as `http://proxy.corp:3128`
+    pub proxy: Option<String>,
+    /// Hosts, domains or IP ranges reached directly instead of through `proxy`
+    pub no_proxy: Vec<String>,
 }
 
 impl Default for OllamaConfig {
@@ -27,8 +37,136 @@ impl Default for OllamaConfig {
                 .unwrap_or_else(|_| "http://localhost:11434".to_string()),
             timeout: None,
             headers: HashMap::new(),
+            proxy: None,
+            no_proxy: Vec::new(),
+        }
+    }
+}
+
+impl OllamaConfig {
+    /// Start from the default configuration
+    pub fn builder() -> OllamaConfigBuilder {
+        OllamaConfigBuilder {
+            config: Self::default(),
+        }
+    }
+}
+
+/// Builder for constructing an `OllamaConfig`.
+#[derive(Debug, Clone)]
+pub struct OllamaConfigBuilder {
+    config: OllamaConfig,
+}
+
+impl OllamaConfigBuilder {
+    /// Set the Ollama server URL (default: `OLLAMA_HOST` or `http://localhost:11434`)
+    pub fn host(mut self, host: impl Into<String>) -> Self {
+        self.config.host = host.into();
+        self
+    }
+
+    /// Set the timeout for each request
+    pub fn timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.config.timeout = Some(timeout);
+        self
+    }
+
+    /// Add a header sent with every request
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.config.headers.insert(name.into(), value.into());
+        self
+    }
+
+    /// Send every request through `proxy`, such as `http://proxy.corp:3128`
+    pub fn proxy(mut self, proxy: impl Into<String>) -> Self {
+        self.config.proxy = Some(proxy.into());
+        self
+    }
+
+    /// Set the hosts, domains or IP ranges reached directly instead of through the proxy
+    pub fn no_proxy(mut self, hosts: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.config.no_proxy = hosts.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Build the configuration
+    pub fn build(self) -> OllamaConfig {
+        self.config
+    }
+}
+
+/// Details Ollama reports about a local model, from `/api/show`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModelInfo {
+    /// Model family, such as `llama` or `qwen2`
+    pub family: Option<String>,
+    /// Human-readable parameter size, such as `8.0B`
+    pub parameter_size: Option<String>,
+    /// Exact parameter count, when the model metadata includes it
+    pub parameter_count: Option<u64>,
+    /// Quantization level, such as `Q4_K_M`
+    pub quantization_level: Option<String>,
+    /// Longest context the model was trained for, in tokens
+    pub context_length: Option<u64>,
+    /// Modelfile parameters such as `num_ctx` or `stop`; repeated keys keep every value
+    pub parameters: HashMap<String, Vec<String>>,
+    /// The model's prompt template
+    pub template: Option<String>,
+    /// Capabilities Ollama reports, such as `completion`, `tools`, or `vision`
+    pub capabilities: Vec<String>,
+}
+
+impl ModelInfo {
+    /// Parse the body of an `/api/show` response
+    pub fn from_show_response(body: &Value) -> Self {
+        let details = &body["details"];
+        let model_info = &body["model_info"];
+        let architecture = model_info["general.architecture"].as_str();
+
+        Self {
+            family: details["family"].as_str().map(String::from),
+            parameter_size: details["parameter_size"].as_str().map(String::from),
+            parameter_count: model_info["general.parameter_count"].as_u64(),
+            quantization_level: details["quantization_level"].as_str().map(String::from),
+            context_length: architecture
+                .and_then(|arch| model_info[format!("{}.context_length", arch)].as_u64()),
+            parameters: body["parameters"]
+                .as_str()
+                .map(parse_modelfile_parameters)
+                .unwrap_or_default(),
+            template: body["template"].as_str().map(String::from),
+            capabilities: body["capabilities"]
+                .as_array()
+                .map(|caps| caps.iter().filter_map(|c| c.as_str().map(String::from)).collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The context window Ollama uses for this model unless a request overrides it
+    ///
+    /// This is the Modelfile's `num_ctx` when it sets one; otherwise Ollama
+    /// falls back to its server default, so this returns `None`.
+    pub fn default_num_ctx(&self) -> Option<u64> {
+        self.parameters.get("num_ctx")?.last()?.parse().ok()
+    }
+}
+
+/// Whether the config asks for extended thinking, which Ollama enables without a budget
+fn wants_thinking(config: &CompletionConfig) -> bool {
+    config.reasoning_effort.is_some() || config.thinking_budget_tokens.is_some()
+}
+
+/// Parse the `parameters` text of `/api/show`, one `key value` pair per line
+fn parse_modelfile_parameters(text: &str) -> HashMap<String, Vec<String>> {
+    let mut parameters: HashMap<String, Vec<String>> = HashMap::new();
+    for line in text.lines() {
+        if let Some((key, value)) = line.trim().split_once(char::is_whitespace) {
+            let value = value.trim();
+            let value = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')).unwrap_or(value);
+            parameters.entry(key.to_string()).or_default().push(value.to_string());
         }
     }
+    parameters
 }
 
 /// Gateway for Ollama local LLM service
@@ -47,15 +185,37 @@ impl OllamaGateway {
     }
 
     /// Create a new Ollama gateway with custom configuration
+    ///
+    /// # Panics
+    ///
+    /// Panics if the HTTP client can't be built, for example because the proxy
+    /// URL is invalid, rather than sending requests without the configured
+    /// proxy and timeout. Use [`try_with_config`](Self::try_with_config) to get
+    /// the error instead.
     pub fn with_config(config: OllamaConfig) -> Self {
-        let mut client_builder = Client::builder();
-
-        if let Some(timeout) = config.timeout {
-            client_builder = client_builder.timeout(timeout);
-        }
+        let client = build_client(config.timeout, config.proxy.as_deref(), &config.no_proxy)
+            .unwrap_or_else(|e| panic!("Could not build the Ollama HTTP client: {}", e));
+        Self::with_client(client, config)
+    }
 
-        let client = client_builder.build().unwrap();
+    /// Create a new Ollama gateway with custom configuration, reporting client errors
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MojenticError::HttpError`] if the HTTP client can't be built,
+    /// for example when the proxy URL is invalid or the TLS backend fails to
+    /// initialize.
+    pub fn try_with_config(config: OllamaConfig) -> Result<Self> {
+        let client = build_client(config.timeout, config.proxy.as_deref(), &config.no_proxy)?;
+        Ok(Self::with_client(client, config))
+    }
 
+    /// Create a gateway that sends its requests through a client you built
+    ///
+    /// For mutual TLS, proxies, or custom connectors. The client is used as
+    /// given, so `config.timeout` and the proxy settings are not applied; set
+    /// them on the client.
+    pub fn with_client(client: Client, config: OllamaConfig) -> Self {
         Self { client, config }
     }
 
@@ -90,6 +250,65 @@ impl OllamaGateway {
 
         Ok(())
     }
+
+    /// Look up a local model's details with Ollama's `/api/show`
+    ///
+    /// Useful for choosing `num_ctx`: [`ModelInfo::context_length`] is the
+    /// longest context the model supports, and [`ModelInfo::default_num_ctx`]
+    /// what its Modelfile configures.
+    pub async fn model_info(&self, model: &str) -> Result<ModelInfo> {
+        debug!("Fetching Ollama model info: {}", model);
+
+        let response = self
+            .client
+            .post(format!("{}/api/show", self.config.host))
+            .json(&serde_json::json!({ "model": model }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(MojenticError::GatewayError(format!(
+                "Failed to get model info for {}: {}",
+                model,
+                response.status()
+            )));
+        }
+
+        let body: Value = response.json().await?;
+        Ok(ModelInfo::from_show_response(&body))
+    }
+
+    /// POST an arbitrary request body to Ollama's chat endpoint and return the raw response.
+    ///
+    /// This is an escape hatch for power users who need provider features the
+    /// crate doesn't model yet. The body is sent exactly as given: no message
+    /// adaptation, option mapping, or validation is applied, and the response is
+    /// not parsed into an [`LlmGatewayResponse`]. Prefer [`LlmGateway::complete`]
+    /// whenever it covers your needs.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MojenticError::GatewayError`] for a non-success HTTP status and
+    /// [`MojenticError::HttpError`] if the request fails or the response isn't JSON.
+    pub async fn complete_raw_body(&self, body: Value) -> Result<Value> {
+        info!("Sending raw request body to Ollama");
+
+        let response = self
+            .client
+            .post(format!("{}/api/chat", self.config.host))
+            .json(&body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(MojenticError::GatewayError(format!(
+                "Ollama API error: {}",
+                response.status()
+            )));
+        }
+
+        Ok(response.json().await?)
+    }
 }
 
 impl Default for OllamaGateway {
@@ -98,6 +317,13 @@ impl Default for OllamaGateway {
     }
 }
 
+#[async_trait]
+impl crate::llm::embedding_gateway::EmbeddingGateway for OllamaGateway {
+    async fn calculate_embeddings(&self, text: &str, model: Option<&str>) -> Result<Vec<f32>> {
+        LlmGateway::calculate_embeddings(self, text, model).await
+    }
+}
+
 #[async_trait]
 impl LlmGateway for OllamaGateway {
     async fn complete(
@@ -122,12 +348,11 @@ impl LlmGateway for OllamaGateway {
 
         // Add tools if provided
         if let Some(tools) = tools {
-            let tool_defs: Vec<_> = tools.iter().map(|t| t.descriptor()).collect();
-            body["tools"] = serde_json::to_value(tool_defs)?;
+            body["tools"] = tools_to_provider_json(tools, ToolProvider::Ollama);
         }
 
-        // Add reasoning effort if specified (Ollama uses "think" parameter)
-        if config.reasoning_effort.is_some() {
+        // Reasoning effort and thinking budgets both map to Ollama's "think" parameter
+        if wants_thinking(config) {
             body["think"] = serde_json::json!(true);
         }
 
@@ -135,10 +360,13 @@ impl LlmGateway for OllamaGateway {
         add_response_format(&mut body, config);
 
         // Make API request
+        let request_body = serde_json::to_vec(&body)?;
+        let request_bytes = request_body.len() as u64;
         let response = self
             .client
             .post(format!("{}/api/chat", self.config.host))
-            .json(&body)
+            .header("Content-Type", "application/json")
+            .body(request_body)
             .send()
             .await?;
 
@@ -149,7 +377,12 @@ impl LlmGateway for OllamaGateway {
             )));
         }
 
-        let response_body: Value = response.json().await?;
+        let raw_body = response.bytes().await?;
+        let payload_size = PayloadSize {
+            request_bytes,
+            response_bytes: raw_body.len() as u64,
+        };
+        let response_body: Value = serde_json::from_slice(&raw_body)?;
 
         // Parse content
         let content = response_body["message"]["content"].as_str().map(String::from);
@@ -162,28 +395,35 @@ impl LlmGateway for OllamaGateway {
             calls
                 .iter()
                 .filter_map(|call| {
-                    let name = call["function"]["name"].as_str()?.to_string();
-                    let args = call["function"]["arguments"].as_object()?;
-
-                    let arguments: HashMap<String, Value> =
-                        args.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
-
-                    Some(LlmToolCall {
-                        id: call["id"].as_str().map(String::from),
+                    let name = call["function"]["name"].as_str()?;
+                    Some(LlmToolCall::from_raw_arguments(
+                        call["id"].as_str().map(String::from),
                         name,
-                        arguments,
-                    })
+                        &call["function"]["arguments"],
+                    ))
                 })
-                .collect()
+                .collect::<Result<Vec<_>>>()?
         } else {
             vec![]
         };
 
+        let usage = match (
+            response_body["prompt_eval_count"].as_u64(),
+            response_body["eval_count"].as_u64(),
+        ) {
+            (None, None) => None,
+            (prompt, completion) => {
+                Some(TokenUsage::new(prompt.unwrap_or(0), completion.unwrap_or(0)))
+            }
+        };
+
         Ok(LlmGatewayResponse {
             content,
             object: None,
             tool_calls,
             thinking,
+            usage,
+            payload_size: Some(payload_size),
         })
     }
 
@@ -235,13 +475,15 @@ impl LlmGateway for OllamaGateway {
     async fn get_available_models(&self) -> Result<Vec<String>> {
         debug!("Fetching available Ollama models");
 
-        let response = self.client.get(format!("{}/api/tags", self.config.host)).send().await?;
+        let response = self
+            .client
+            .get(format!("{}/api/tags", self.config.host))
+            .send()
+            .await
+            .map_err(send_error)?;
 
         if !response.status().is_success() {
-            return Err(MojenticError::GatewayError(format!(
-                "Failed to get models: {}",
-                response.status()
-            )));
+            return Err(status_error("Failed to get models", response.status()));
         }
 
         let body: Value = response.json().await?;
@@ -291,6 +533,75 @@ impl LlmGateway for OllamaGateway {
         Ok(embeddings)
     }
 
+    fn tool_provider(&self) -> ToolProvider {
+        ToolProvider::Ollama
+    }
+
+    async fn supports_vision(&self, model: &str) -> bool {
+        match self.model_info(model).await {
+            Ok(info) => info.capabilities.iter().any(|capability| capability == "vision"),
+            Err(e) => {
+                warn!("Could not read capabilities for {}: {}", model, e);
+                true
+            }
+        }
+    }
+
+    fn supports_assistant_prefill(&self, _model: &str) -> bool {
+        // Ollama continues a conversation that ends with an assistant message
+        true
+    }
+
+    async fn describe_model(&self, model: &str) -> Result<ModelDescription> {
+        // Ollama lists models with their tag, so `llama3.1` is `llama3.1:latest`
+        let tagged = format!("{}:latest", model);
+        let available = self
+            .get_available_models()
+            .await?
+            .iter()
+            .any(|name| name == model || *name == tagged);
+        if !available {
+            return Ok(ModelDescription {
+                name: model.to_string(),
+                available,
+                model_type: ModelType::Chat,
+                supports_tools: false,
+                supports_streaming: false,
+                supports_vision: false,
+                context_length: None,
+            });
+        }
+
+        let info = self.model_info(model).await?;
+        let has = |capability: &str| info.capabilities.iter().any(|c| c == capability);
+        let model_type = if has("embedding") {
+            ModelType::Embedding
+        } else if has("thinking") {
+            ModelType::Reasoning
+        } else {
+            ModelType::Chat
+        };
+        Ok(ModelDescription {
+            name: model.to_string(),
+            available,
+            model_type,
+            supports_tools: has("tools"),
+            supports_streaming: model_type != ModelType::Embedding,
+            supports_vision: has("vision"),
+            context_length: info.context_length.map(|length| length as usize),
+        })
+    }
+
+    async fn context_length(&self, model: &str) -> Option<usize> {
+        match self.model_info(model).await {
+            Ok(info) => info.context_length.map(|length| length as usize),
+            Err(e) => {
+                warn!("Could not read context length for {}: {}", model, e);
+                None
+            }
+        }
+    }
+
     fn complete_stream<'a>(
         &'a self,
         model: &'a str,
@@ -321,14 +632,11 @@ impl LlmGateway for OllamaGateway {
 
             // Add tools if provided
             if let Some(tools) = tools {
-                let tool_defs: Vec<_> = tools.iter().map(|t| t.descriptor()).collect();
-                if let Ok(tools_value) = serde_json::to_value(tool_defs) {
-                    body["tools"] = tools_value;
-                }
+                body["tools"] = tools_to_provider_json(tools, ToolProvider::Ollama);
             }
 
-            // Add reasoning effort if specified (Ollama uses "think" parameter)
-            if config.reasoning_effort.is_some() {
+            // Reasoning effort and thinking budgets both map to Ollama's "think" parameter
+            if wants_thinking(config) {
                 body["think"] = serde_json::json!(true);
             }
 
@@ -428,23 +736,17 @@ impl LlmGateway for OllamaGateway {
                                             // Extract tool calls
                                             if let Some(calls) = message.get("tool_calls").and_then(|v| v.as_array()) {
                                                 for call in calls {
-                                                    if let Some(function) = call.get("function").and_then(|v| v.as_object()) {
-                                                        if let (Some(name), Some(args)) = (
-                                                            function.get("name").and_then(|v| v.as_str()),
-                                                            function.get("arguments").and_then(|v| v.as_object()),
+                                                    if let Some(name) = call["function"]["name"].as_str() {
+                                                        match LlmToolCall::from_raw_arguments(
+                                                            call["id"].as_str().map(String::from),
+                                                            name,
+                                                            &call["function"]["arguments"],
                                                         ) {
-                                                            let arguments: HashMap<String, Value> = args
-                                                                .iter()
-                                                                .map(|(k, v)| (k.clone(), v.clone()))
-                                                                .collect();
-
-                                                            let tool_call = LlmToolCall {
-                                                                id: call.get("id").and_then(|v| v.as_str()).map(String::from),
-                                                                name: name.to_string(),
-                                                                arguments,
-                                                            };
-
-                                                            accumulated_tool_calls.push(tool_call);
+                                                            Ok(tool_call) => accumulated_tool_calls.push(tool_call),
+                                                            Err(e) => {
+                                                                yield Err(e);
+                                                                return;
+                                                            }
                                                         }
                                                     }
                                                 }
@@ -598,6 +900,17 @@ fn extract_ollama_options(config: &CompletionConfig) -> Value {
         options["top_k"] = serde_json::json!(top_k);
     }
 
+    if config.logit_bias.is_some() {
+        debug!("Ollama does not support logit_bias, ignoring");
+    }
+
+    // Provider-specific extras fill in anything the known fields didn't set
+    for (key, value) in &config.extra {
+        if options.get(key).is_none() {
+            options[key] = value.clone();
+        }
+    }
+
     options
 }
 
@@ -655,6 +968,8 @@ mod tests {
             host: "http://test:9999".to_string(),
             timeout: Some(std::time::Duration::from_secs(30)),
             headers,
+            proxy: None,
+            no_proxy: Vec::new(),
         };
 
         assert_eq!(config.host, "http://test:9999");
@@ -662,6 +977,23 @@ mod tests {
         assert_eq!(config.headers.get("X-Custom"), Some(&"value".to_string()));
     }
 
+    #[test]
+    fn test_ollama_config_builder() {
+        let config = OllamaConfig::builder()
+            .host("http://test:9999")
+            .timeout(std::time::Duration::from_secs(30))
+            .header("X-Custom", "value")
+            .proxy("http://proxy.corp.example:3128")
+            .no_proxy(["localhost"])
+            .build();
+
+        assert_eq!(config.host, "http://test:9999");
+        assert_eq!(config.timeout, Some(std::time::Duration::from_secs(30)));
+        assert_eq!(config.headers.get("X-Custom"), Some(&"value".to_string()));
+        assert_eq!(config.proxy.as_deref(), Some("http://proxy.corp.example:3128"));
+        assert_eq!(config.no_proxy, vec!["localhost"]);
+    }
+
     #[test]
     fn test_gateway_new() {
         let gateway = OllamaGateway::new();
@@ -680,12 +1012,65 @@ mod tests {
             host: "http://custom:5000".to_string(),
             timeout: Some(std::time::Duration::from_secs(60)),
             headers: HashMap::new(),
+            proxy: None,
+            no_proxy: Vec::new(),
         };
 
         let gateway = OllamaGateway::with_config(config);
         assert_eq!(gateway.config.host, "http://custom:5000");
     }
 
+    #[test]
+    fn test_gateway_with_proxy_config() {
+        let proxied = OllamaGateway::try_with_config(OllamaConfig {
+            proxy: Some("http://proxy.corp.example:3128".to_string()),
+            no_proxy: vec!["localhost".to_string(), "10.0.0.0/8".to_string()],
+            ..Default::default()
+        });
+        let invalid = OllamaGateway::try_with_config(OllamaConfig {
+            proxy: Some("not a proxy url".to_string()),
+            ..Default::default()
+        });
+
+        assert!(proxied.is_ok());
+        assert!(matches!(invalid, Err(MojenticError::HttpError(_))));
+    }
+
+    #[test]
+    #[should_panic(expected = "Could not build the Ollama HTTP client")]
+    fn test_with_config_panics_on_invalid_proxy() {
+        OllamaGateway::with_config(OllamaConfig {
+            proxy: Some("not a proxy url".to_string()),
+            ..Default::default()
+        });
+    }
+
+    #[tokio::test]
+    async fn test_gateway_sends_requests_through_injected_client() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/api/tags")
+            .match_header("X-Proxy-Token", "secret")
+            .with_status(200)
+            .with_body(r#"{"models":[{"name":"llama3.2"}]}"#)
+            .create();
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("X-Proxy-Token", "secret".parse().unwrap());
+        let client = Client::builder().default_headers(headers).build().unwrap();
+
+        let gateway = OllamaGateway::with_client(
+            client,
+            OllamaConfig {
+                host: server.url(),
+                ..Default::default()
+            },
+        );
+        let models = gateway.get_available_models().await.unwrap();
+
+        mock.assert();
+        assert_eq!(models, vec!["llama3.2"]);
+    }
+
     #[test]
     fn test_gateway_default() {
         let gateway = OllamaGateway::default();
@@ -711,6 +1096,19 @@ mod tests {
         assert_eq!(result[2]["content"], "Hi there");
     }
 
+    #[test]
+    fn test_adapt_messages_ignores_cache_marker() {
+        let marked = vec![LlmMessage::system("Large context").with_cache()];
+
+        let result = adapt_messages_to_ollama(&marked).unwrap();
+
+        assert_eq!(
+            result,
+            adapt_messages_to_ollama(&[LlmMessage::system("Large context")]).unwrap()
+        );
+        assert!(result[0].get("cache").is_none());
+    }
+
     #[test]
     fn test_adapt_messages_with_images() {
         use std::io::Write;
@@ -764,6 +1162,7 @@ mod tests {
             content: None,
             tool_calls: Some(vec![tool_call]),
             image_paths: None,
+            cache: false,
         }];
 
         let result = adapt_messages_to_ollama(&messages).unwrap();
@@ -781,6 +1180,7 @@ mod tests {
             content: None,
             tool_calls: None,
             image_paths: None,
+            cache: false,
         }];
 
         let result = adapt_messages_to_ollama(&messages).unwrap();
@@ -796,6 +1196,7 @@ mod tests {
             content: Some("Tool result".to_string()),
             tool_calls: None,
             image_paths: None,
+            cache: false,
         }];
 
         let result = adapt_messages_to_ollama(&messages).unwrap();
@@ -805,6 +1206,53 @@ mod tests {
         assert_eq!(result[0]["content"], "Tool result");
     }
 
+    #[test]
+    fn test_adapt_messages_separate_and_aggregated_tool_results() {
+        let call = |id: &str, name: &str| LlmToolCall {
+            id: Some(id.to_string()),
+            name: name.to_string(),
+            arguments: HashMap::new(),
+        };
+        let separate = vec![
+            LlmMessage {
+                role: MessageRole::Tool,
+                content: Some(r#"{"temp":72}"#.to_string()),
+                tool_calls: Some(vec![call("call_1", "get_weather")]),
+                image_paths: None,
+                cache: false,
+            },
+            LlmMessage {
+                role: MessageRole::Tool,
+                content: Some(r#"{"time":"noon"}"#.to_string()),
+                tool_calls: Some(vec![call("call_2", "get_time")]),
+                image_paths: None,
+                cache: false,
+            },
+        ];
+        let aggregated_content = serde_json::json!([
+            {"tool_call_id": "call_1", "result": {"temp": 72}},
+            {"tool_call_id": "call_2", "result": {"time": "noon"}}
+        ])
+        .to_string();
+        let aggregated = vec![LlmMessage {
+            role: MessageRole::Tool,
+            content: Some(aggregated_content.clone()),
+            tool_calls: Some(vec![call("call_1", "get_weather"), call("call_2", "get_time")]),
+            image_paths: None,
+            cache: false,
+        }];
+
+        let separate = adapt_messages_to_ollama(&separate).unwrap();
+        let aggregated = adapt_messages_to_ollama(&aggregated).unwrap();
+
+        assert_eq!(separate.len(), 2);
+        assert_eq!(separate[0]["content"], r#"{"temp":72}"#);
+        assert_eq!(separate[1]["content"], r#"{"time":"noon"}"#);
+        assert_eq!(aggregated.len(), 1);
+        assert_eq!(aggregated[0]["role"], "tool");
+        assert_eq!(aggregated[0]["content"], aggregated_content);
+    }
+
     #[test]
     fn test_extract_ollama_options_basic() {
         let config = CompletionConfig {
@@ -816,7 +1264,16 @@ mod tests {
             top_k: None,
             response_format: None,
             reasoning_effort: None,
+            thinking_budget_tokens: None,
             max_tool_iterations: 10,
+            n: None,
+            tool_result_format: Default::default(),
+            logit_bias: None,
+            user: None,
+            model: None,
+            response_language: None,
+            assistant_prefill: None,
+            extra: HashMap::new(),
         };
 
         let options = extract_ollama_options(&config);
@@ -839,7 +1296,16 @@ mod tests {
             top_k: None,
             response_format: None,
             reasoning_effort: None,
+            thinking_budget_tokens: None,
             max_tool_iterations: 10,
+            n: None,
+            tool_result_format: Default::default(),
+            logit_bias: None,
+            user: None,
+            model: None,
+            response_language: None,
+            assistant_prefill: None,
+            extra: HashMap::new(),
         };
 
         let options = extract_ollama_options(&config);
@@ -861,7 +1327,16 @@ mod tests {
             top_k: None,
             response_format: None,
             reasoning_effort: None,
+            thinking_budget_tokens: None,
             max_tool_iterations: 10,
+            n: None,
+            tool_result_format: Default::default(),
+            logit_bias: None,
+            user: None,
+            model: None,
+            response_language: None,
+            assistant_prefill: None,
+            extra: HashMap::new(),
         };
 
         let options = extract_ollama_options(&config);
@@ -884,7 +1359,16 @@ mod tests {
             top_k: None,
             response_format: None,
             reasoning_effort: None,
+            thinking_budget_tokens: None,
             max_tool_iterations: 10,
+            n: None,
+            tool_result_format: Default::default(),
+            logit_bias: None,
+            user: None,
+            model: None,
+            response_language: None,
+            assistant_prefill: None,
+            extra: HashMap::new(),
         };
 
         let options = extract_ollama_options(&config);
@@ -908,7 +1392,16 @@ mod tests {
             top_k: None,
             response_format: None,
             reasoning_effort: None,
+            thinking_budget_tokens: None,
             max_tool_iterations: 10,
+            n: None,
+            tool_result_format: Default::default(),
+            logit_bias: None,
+            user: None,
+            model: None,
+            response_language: None,
+            assistant_prefill: None,
+            extra: HashMap::new(),
         };
 
         let options = extract_ollama_options(&config);
@@ -930,7 +1423,16 @@ mod tests {
             top_k: Some(40),
             response_format: None,
             reasoning_effort: None,
+            thinking_budget_tokens: None,
             max_tool_iterations: 10,
+            n: None,
+            tool_result_format: Default::default(),
+            logit_bias: None,
+            user: None,
+            model: None,
+            response_language: None,
+            assistant_prefill: None,
+            extra: HashMap::new(),
         };
 
         let options = extract_ollama_options(&config);
@@ -951,7 +1453,16 @@ mod tests {
             top_k: Some(50),
             response_format: None,
             reasoning_effort: None,
+            thinking_budget_tokens: None,
             max_tool_iterations: 10,
+            n: None,
+            tool_result_format: Default::default(),
+            logit_bias: None,
+            user: None,
+            model: None,
+            response_language: None,
+            assistant_prefill: None,
+            extra: HashMap::new(),
         };
 
         let options = extract_ollama_options(&config);
@@ -976,7 +1487,16 @@ mod tests {
             top_k: None,
             response_format: Some(ResponseFormat::Text),
             reasoning_effort: None,
+            thinking_budget_tokens: None,
             max_tool_iterations: 10,
+            n: None,
+            tool_result_format: Default::default(),
+            logit_bias: None,
+            user: None,
+            model: None,
+            response_language: None,
+            assistant_prefill: None,
+            extra: HashMap::new(),
         };
 
         let mut body = serde_json::json!({
@@ -1003,7 +1523,16 @@ mod tests {
             top_k: None,
             response_format: Some(ResponseFormat::JsonObject { schema: None }),
             reasoning_effort: None,
+            thinking_budget_tokens: None,
             max_tool_iterations: 10,
+            n: None,
+            tool_result_format: Default::default(),
+            logit_bias: None,
+            user: None,
+            model: None,
+            response_language: None,
+            assistant_prefill: None,
+            extra: HashMap::new(),
         };
 
         let mut body = serde_json::json!({
@@ -1039,7 +1568,16 @@ mod tests {
                 schema: Some(schema.clone()),
             }),
             reasoning_effort: None,
+            thinking_budget_tokens: None,
             max_tool_iterations: 10,
+            n: None,
+            tool_result_format: Default::default(),
+            logit_bias: None,
+            user: None,
+            model: None,
+            response_language: None,
+            assistant_prefill: None,
+            extra: HashMap::new(),
         };
 
         let mut body = serde_json::json!({
@@ -1063,7 +1601,16 @@ mod tests {
             top_k: None,
             response_format: None,
             reasoning_effort: None,
+            thinking_budget_tokens: None,
             max_tool_iterations: 10,
+            n: None,
+            tool_result_format: Default::default(),
+            logit_bias: None,
+            user: None,
+            model: None,
+            response_language: None,
+            assistant_prefill: None,
+            extra: HashMap::new(),
         };
 
         let mut body = serde_json::json!({
@@ -1105,6 +1652,177 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_model_info_parses_show_response() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/api/show")
+            .match_body(mockito::Matcher::Json(serde_json::json!({"model": "llama3.1:8b"})))
+            .with_status(200)
+            .with_body(
+                r#"{
+                    "modelfile": "FROM llama3.1",
+                    "parameters": "num_ctx                        8192\nstop                           \"<|start_header_id|>\"\nstop                           \"<|eot_id|>\"",
+                    "template": "{{ .Prompt }}",
+                    "details": {
+                        "parent_model": "",
+                        "format": "gguf",
+                        "family": "llama",
+                        "families": ["llama"],
+                        "parameter_size": "8.0B",
+                        "quantization_level": "Q4_K_M"
+                    },
+                    "model_info": {
+                        "general.architecture": "llama",
+                        "general.parameter_count": 8030261248,
+                        "llama.context_length": 131072,
+                        "llama.embedding_length": 4096
+                    },
+                    "capabilities": ["completion", "tools"]
+                }"#,
+            )
+            .create();
+
+        let gateway = OllamaGateway::with_host(server.url());
+        let info = gateway.model_info("llama3.1:8b").await.unwrap();
+
+        mock.assert();
+        assert_eq!(info.family.as_deref(), Some("llama"));
+        assert_eq!(info.parameter_size.as_deref(), Some("8.0B"));
+        assert_eq!(info.parameter_count, Some(8_030_261_248));
+        assert_eq!(info.quantization_level.as_deref(), Some("Q4_K_M"));
+        assert_eq!(info.context_length, Some(131_072));
+        assert_eq!(info.default_num_ctx(), Some(8192));
+        assert_eq!(info.parameters["stop"], vec!["<|start_header_id|>", "<|eot_id|>"]);
+        assert_eq!(info.template.as_deref(), Some("{{ .Prompt }}"));
+        assert_eq!(info.capabilities, vec!["completion", "tools"]);
+    }
+
+    #[tokio::test]
+    async fn test_model_info_unknown_model() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server.mock("POST", "/api/show").with_status(404).create();
+
+        let gateway = OllamaGateway::with_host(server.url());
+        let result = gateway.model_info("missing").await;
+
+        assert!(matches!(result, Err(MojenticError::GatewayError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_context_length_comes_from_model_info() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("POST", "/api/show")
+            .with_status(200)
+            .with_body(
+                r#"{"model_info": {"general.architecture": "qwen2", "qwen2.context_length": 4096}}"#,
+            )
+            .create();
+
+        let gateway = OllamaGateway::with_host(server.url());
+
+        assert_eq!(gateway.context_length("qwen2:0.5b").await, Some(4096));
+    }
+
+    #[tokio::test]
+    async fn test_describe_model_reports_capabilities_from_show() {
+        let mut server = mockito::Server::new_async().await;
+        let _tags = server
+            .mock("GET", "/api/tags")
+            .with_status(200)
+            .with_body(r#"{"models":[{"name":"qwen3:latest"}]}"#)
+            .create();
+        let _show = server
+            .mock("POST", "/api/show")
+            .with_status(200)
+            .with_body(
+                r#"{
+                    "model_info": {"general.architecture": "qwen3", "qwen3.context_length": 40960},
+                    "capabilities": ["completion", "tools", "thinking"]
+                }"#,
+            )
+            .create();
+
+        let gateway = OllamaGateway::with_host(server.url());
+        let description = gateway.describe_model("qwen3").await.unwrap();
+
+        assert!(description.available);
+        assert_eq!(description.model_type, ModelType::Reasoning);
+        assert!(description.supports_tools);
+        assert!(description.supports_streaming);
+        assert!(!description.supports_vision);
+        assert_eq!(description.context_length, Some(40960));
+    }
+
+    #[tokio::test]
+    async fn test_describe_model_not_pulled() {
+        let mut server = mockito::Server::new_async().await;
+        let _tags = server
+            .mock("GET", "/api/tags")
+            .with_status(200)
+            .with_body(r#"{"models":[{"name":"llama2"}]}"#)
+            .create();
+
+        let gateway = OllamaGateway::with_host(server.url());
+        let description = gateway.describe_model("mistral").await.unwrap();
+
+        assert!(!description.available);
+        assert!(!description.supports_tools);
+        assert_eq!(description.context_length, None);
+    }
+
+    #[tokio::test]
+    async fn test_context_length_unknown_when_show_fails() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server.mock("POST", "/api/show").with_status(404).create();
+
+        let gateway = OllamaGateway::with_host(server.url());
+
+        assert_eq!(gateway.context_length("missing").await, None);
+    }
+
+    #[tokio::test]
+    async fn test_complete_raw_body_posts_custom_body() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/api/chat")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "model": "llama2",
+                "options": {"mirostat": 2},
+                "stream": false
+            })))
+            .with_status(200)
+            .with_body(r#"{"message":{"role":"assistant","content":"raw"},"done":true}"#)
+            .create();
+
+        let gateway = OllamaGateway::with_host(server.url());
+        let body = serde_json::json!({
+            "model": "llama2",
+            "messages": [{"role": "user", "content": "Hi"}],
+            "options": {"mirostat": 2},
+            "stream": false
+        });
+
+        let response = gateway.complete_raw_body(body).await.unwrap();
+
+        mock.assert();
+        assert_eq!(response["message"]["content"], "raw");
+        assert_eq!(response["done"], true);
+    }
+
+    #[tokio::test]
+    async fn test_complete_raw_body_error_status() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server.mock("POST", "/api/chat").with_status(400).create();
+
+        let gateway = OllamaGateway::with_host(server.url());
+
+        let result = gateway.complete_raw_body(serde_json::json!({})).await;
+
+        assert!(matches!(result, Err(MojenticError::GatewayError(_))));
+    }
+
     #[tokio::test]
     async fn test_complete_simple() {
         let mut server = mockito::Server::new_async().await;
@@ -1127,6 +1845,78 @@ mod tests {
         assert_eq!(response.thinking, None);
     }
 
+    #[tokio::test]
+    async fn test_complete_reports_token_usage() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("POST", "/api/chat")
+            .with_status(200)
+            .with_body(
+                r#"{"message":{"role":"assistant","content":"Hello!"},"prompt_eval_count":12,"eval_count":4}"#,
+            )
+            .create();
+
+        let gateway = OllamaGateway::with_host(server.url());
+        let messages = vec![LlmMessage::user("Hi")];
+
+        let response = gateway
+            .complete("llama2", &messages, None, &CompletionConfig::default())
+            .await
+            .unwrap();
+
+        assert_eq!(response.usage, Some(TokenUsage::new(12, 4)));
+    }
+
+    #[tokio::test]
+    async fn test_complete_reports_payload_size() {
+        let body = r#"{"message":{"role":"assistant","content":"Hello!"}}"#;
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server.mock("POST", "/api/chat").with_status(200).with_body(body).create();
+
+        let gateway = OllamaGateway::with_host(server.url());
+        let messages = vec![LlmMessage::user("Hi")];
+
+        let response = gateway
+            .complete("llama2", &messages, None, &CompletionConfig::default())
+            .await
+            .unwrap();
+
+        let size = response.payload_size.unwrap();
+        assert!(size.request_bytes > 0);
+        assert_eq!(size.response_bytes, body.len() as u64);
+    }
+
+    #[tokio::test]
+    async fn test_complete_merges_extra_options() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/api/chat")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "options": {"mirostat": 2, "tfs_z": 1.5, "temperature": 0.5}
+            })))
+            .with_status(200)
+            .with_body(r#"{"message":{"role":"assistant","content":"Hello!"}}"#)
+            .create();
+
+        let gateway = OllamaGateway::with_host(server.url());
+        let config = CompletionConfig {
+            temperature: 0.5,
+            extra: HashMap::from([
+                ("mirostat".to_string(), serde_json::json!(2)),
+                ("tfs_z".to_string(), serde_json::json!(1.5)),
+                ("temperature".to_string(), serde_json::json!(0.9)),
+            ]),
+            ..Default::default()
+        };
+
+        gateway
+            .complete("llama2", &[LlmMessage::user("Hi")], None, &config)
+            .await
+            .unwrap();
+
+        mock.assert();
+    }
+
     #[tokio::test]
     async fn test_complete_with_tools() {
         let mut server = mockito::Server::new_async().await;
@@ -1163,6 +1953,7 @@ mod tests {
                         name: "test_tool".to_string(),
                         description: "A test".to_string(),
                         parameters: serde_json::json!({}),
+                        returns: None,
                     },
                 }
             }
@@ -1178,6 +1969,56 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_complete_accepts_stringified_tool_arguments() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/api/chat")
+            .with_status(200)
+            .with_body(r#"{"message":{"role":"assistant","content":"","tool_calls":[{"function":{"name":"search","arguments":{"query":"rust"}}},{"function":{"name":"search","arguments":"{\"query\":\"ollama\"}"}}]}}"#)
+            .create();
+
+        let gateway = OllamaGateway::with_host(server.url());
+        let response = gateway
+            .complete("llama2", &[LlmMessage::user("Hi")], None, &CompletionConfig::default())
+            .await
+            .unwrap();
+
+        mock.assert();
+        assert_eq!(response.tool_calls.len(), 2);
+        assert_eq!(response.tool_calls[0].arguments["query"], "rust");
+        assert_eq!(response.tool_calls[1].arguments["query"], "ollama");
+    }
+
+    #[tokio::test]
+    async fn test_complete_sends_exported_tool_json() {
+        use crate::llm::tools::simple_date_tool::SimpleDateTool;
+
+        let tools: Vec<Box<dyn LlmTool>> = vec![Box::new(SimpleDateTool)];
+        let exported = tools_to_provider_json(&tools, ToolProvider::Ollama);
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/api/chat")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({"tools": exported})))
+            .with_status(200)
+            .with_body(r#"{"message":{"role":"assistant","content":"Result"}}"#)
+            .create();
+
+        let gateway = OllamaGateway::with_host(server.url());
+        gateway
+            .complete(
+                "llama2",
+                &[LlmMessage::user("Hi")],
+                Some(&tools),
+                &CompletionConfig::default(),
+            )
+            .await
+            .unwrap();
+
+        mock.assert();
+        assert_eq!(exported[0]["function"]["name"], "resolve_date");
+    }
+
     #[tokio::test]
     async fn test_complete_error() {
         let mut server = mockito::Server::new_async().await;
@@ -1236,6 +2077,18 @@ mod tests {
         assert!(models.contains(&"mistral".to_string()));
     }
 
+    #[tokio::test]
+    async fn test_get_available_models_reports_unreachable_server_as_network_error() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let host = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let gateway = OllamaGateway::with_host(host);
+        let result = gateway.get_available_models().await;
+
+        assert!(matches!(result, Err(MojenticError::NetworkError(_))));
+    }
+
     #[tokio::test]
     async fn test_calculate_embeddings() {
         let mut server = mockito::Server::new_async().await;
@@ -1305,6 +2158,30 @@ mod tests {
         assert_eq!(response.thinking, Some("Internal reasoning...".to_string()));
     }
 
+    #[tokio::test]
+    async fn test_complete_with_thinking_budget_enables_think() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/api/chat")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({"think": true})))
+            .with_status(200)
+            .with_body(r#"{"message":{"role":"assistant","content":"Response"}}"#)
+            .create();
+
+        let gateway = OllamaGateway::with_host(server.url());
+        let config = CompletionConfig {
+            thinking_budget_tokens: Some(1024),
+            ..Default::default()
+        };
+
+        gateway
+            .complete("qwen3:32b", &[LlmMessage::user("Test")], None, &config)
+            .await
+            .unwrap();
+
+        mock.assert();
+    }
+
     #[tokio::test]
     async fn test_complete_stream_surfaces_thinking_chunks() {
         use crate::llm::gateway::ReasoningEffort;
@@ -4,25 +4,61 @@
 //! including chat completions, streaming, and embeddings.
 
 use crate::error::{MojenticError, Result};
-use crate::llm::gateway::{CompletionConfig, LlmGateway, StreamChunk};
-use crate::llm::gateways::openai_messages_adapter::{adapt_messages_to_openai, convert_tool_calls};
+use crate::llm::gateway::{
+    CompletionConfig, LlmGateway, ModelDescription, ResponseFormat, StreamChunk,
+};
+use crate::llm::gateways::openai_messages_adapter::{
+    adapt_messages_to_openai, adapt_messages_with_cache_control, convert_tool_calls,
+};
 use crate::llm::gateways::openai_model_registry::{get_model_registry, ModelType};
-use crate::llm::models::{LlmGatewayResponse, LlmMessage, LlmToolCall};
-use crate::llm::tools::LlmTool;
+use crate::llm::gateways::openai_tool_call_accumulator::ToolCallStreamAccumulator;
+use crate::llm::gateways::TokenizerGateway;
+use crate::llm::gateways::{build_client, send_error, status_error};
+use crate::llm::models::{LlmGatewayResponse, LlmMessage, PayloadSize, TokenUsage};
+use crate::llm::tools::{tools_to_provider_json, LlmTool, ToolProvider};
 use async_trait::async_trait;
 use futures::stream::{Stream, StreamExt};
+use regex::Regex;
+use reqwest::header::{HeaderMap, HeaderValue};
 use reqwest::Client;
 use serde_json::Value;
 use std::collections::HashMap;
 use std::pin::Pin;
+use std::sync::LazyLock;
 use tracing::{debug, info, warn};
 
+/// Request body keys that `CompletionConfig::extra` may not set
+const RESERVED_BODY_KEYS: &[&str] = &[
+    "model",
+    "messages",
+    "tools",
+    "stream",
+    "stream_options",
+    "response_format",
+];
+
 /// Configuration for connecting to OpenAI API.
+///
+/// Build one with [`OpenAIConfig::builder`], since new settings may be added.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct OpenAIConfig {
     pub api_key: String,
     pub base_url: String,
     pub timeout: Option<std::time::Duration>,
+    /// Sent as the `OpenAI-Organization` header, for billing attribution
+    pub organization: Option<String>,
+    /// Sent as the `OpenAI-Project` header, for billing attribution
+    pub project: Option<String>,
+    /// Proxy for every request, such as `http://proxy.corp:3128`
+    pub proxy: Option<String>,
+    /// Hosts, domains or IP ranges reached directly instead of through `proxy`
+    pub no_proxy: Vec<String>,
+    /// Send a `cache_control` breakpoint at the last message marked with
+    /// [`LlmMessage::with_cache`], for OpenAI-compatible endpoints in front of
+    /// providers with explicit prompt caching. OpenAI itself caches prefixes
+    /// automatically and rejects the field, so this is off by default.
+    pub cache_control: bool,
 }
 
 impl Default for OpenAIConfig {
@@ -32,10 +68,139 @@ impl Default for OpenAIConfig {
             base_url: std::env::var("OPENAI_API_ENDPOINT")
                 .unwrap_or_else(|_| "https://api.openai.com/v1".to_string()),
             timeout: None,
+            organization: std::env::var("OPENAI_ORG_ID").ok(),
+            project: std::env::var("OPENAI_PROJECT_ID").ok(),
+            proxy: None,
+            no_proxy: Vec::new(),
+            cache_control: false,
+        }
+    }
+}
+
+impl OpenAIConfig {
+    /// Start from the default configuration, which reads the environment.
+    pub fn builder() -> OpenAIConfigBuilder {
+        OpenAIConfigBuilder {
+            config: Self::default(),
+        }
+    }
+}
+
+/// Builder for constructing an `OpenAIConfig`.
+#[derive(Debug, Clone)]
+pub struct OpenAIConfigBuilder {
+    config: OpenAIConfig,
+}
+
+impl OpenAIConfigBuilder {
+    /// Set the API key (default: `OPENAI_API_KEY`)
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.config.api_key = api_key.into();
+        self
+    }
+
+    /// Set the API base URL (default: `OPENAI_API_ENDPOINT` or `https://api.openai.com/v1`)
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.config.base_url = base_url.into();
+        self
+    }
+
+    /// Set the timeout for each request
+    pub fn timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.config.timeout = Some(timeout);
+        self
+    }
+
+    /// Set the `OpenAI-Organization` header (default: `OPENAI_ORG_ID`)
+    pub fn organization(mut self, organization: impl Into<String>) -> Self {
+        self.config.organization = Some(organization.into());
+        self
+    }
+
+    /// Set the `OpenAI-Project` header (default: `OPENAI_PROJECT_ID`)
+    pub fn project(mut self, project: impl Into<String>) -> Self {
+        self.config.project = Some(project.into());
+        self
+    }
+
+    /// Send every request through `proxy`, such as `http://proxy.corp:3128`
+    pub fn proxy(mut self, proxy: impl Into<String>) -> Self {
+        self.config.proxy = Some(proxy.into());
+        self
+    }
+
+    /// Set the hosts, domains or IP ranges reached directly instead of through the proxy
+    pub fn no_proxy(mut self, hosts: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.config.no_proxy = hosts.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Send `cache_control` breakpoints at marked messages (default: off)
+    ///
+    /// See [`OpenAIConfig::cache_control`].
+    pub fn cache_control(mut self, enabled: bool) -> Self {
+        self.config.cache_control = enabled;
+        self
+    }
+
+    /// Build the configuration
+    pub fn build(self) -> OpenAIConfig {
+        self.config
+    }
+}
+
+/// The organization and project headers sent with every request
+///
+/// A value that can't be sent as a header is skipped with a warning.
+fn attribution_headers(config: &OpenAIConfig) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let configured = [
+        ("OpenAI-Organization", &config.organization),
+        ("OpenAI-Project", &config.project),
+    ];
+    for (name, value) in configured {
+        let Some(value) = value else { continue };
+        match HeaderValue::from_str(value) {
+            Ok(value) => {
+                headers.insert(name, value);
+            }
+            Err(e) => warn!("Not sending {} header: {}", name, e),
         }
     }
+    headers
+}
+
+/// The error for an unsuccessful chat completions response
+///
+/// A prompt too long for the model becomes
+/// [`MojenticError::ContextLengthExceeded`], with the token counts taken from
+/// the provider's message when it gives them (zero otherwise).
+fn api_error(status: reqwest::StatusCode, error_text: &str) -> MojenticError {
+    let error: Value = serde_json::from_str(error_text).unwrap_or(Value::Null);
+    if error["error"]["code"] == "context_length_exceeded" {
+        let message = error["error"]["message"].as_str().unwrap_or_default();
+        let counts = CONTEXT_LENGTH_COUNTS.captures(message);
+        let count = |group: usize| {
+            counts
+                .as_ref()
+                .and_then(|c| c.get(group))
+                .and_then(|m| m.as_str().parse().ok())
+                .unwrap_or(0)
+        };
+        return MojenticError::ContextLengthExceeded {
+            estimated: count(2),
+            limit: count(1),
+        };
+    }
+    MojenticError::GatewayError(format!("OpenAI API error: {} - {}", status, error_text))
 }
 
+/// "maximum context length is 8192 tokens. However, your messages resulted in 9000 tokens"
+static CONTEXT_LENGTH_COUNTS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"maximum context length is (\d+) tokens.*?resulted in (\d+) tokens")
+        .expect("context length pattern is valid")
+});
+
 /// Gateway for OpenAI LLM service.
 ///
 /// This gateway provides access to OpenAI models through their API,
@@ -43,6 +208,8 @@ impl Default for OpenAIConfig {
 pub struct OpenAIGateway {
     client: Client,
     config: OpenAIConfig,
+    /// Sent with every request, see [`attribution_headers`]
+    attribution: HeaderMap,
 }
 
 impl OpenAIGateway {
@@ -52,16 +219,43 @@ impl OpenAIGateway {
     }
 
     /// Create a new OpenAI gateway with custom configuration.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the HTTP client can't be built, for example because the proxy
+    /// URL is invalid, rather than sending requests without the configured
+    /// proxy and timeout. Use [`try_with_config`](Self::try_with_config) to get
+    /// the error instead.
     pub fn with_config(config: OpenAIConfig) -> Self {
-        let mut client_builder = Client::builder();
-
-        if let Some(timeout) = config.timeout {
-            client_builder = client_builder.timeout(timeout);
-        }
+        let client = build_client(config.timeout, config.proxy.as_deref(), &config.no_proxy)
+            .unwrap_or_else(|e| panic!("Could not build the OpenAI HTTP client: {}", e));
+        Self::with_client(client, config)
+    }
 
-        let client = client_builder.build().unwrap();
+    /// Create a new OpenAI gateway with custom configuration, reporting client errors.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MojenticError::HttpError`] if the HTTP client can't be built,
+    /// for example when the proxy URL is invalid or the TLS backend fails to
+    /// initialize.
+    pub fn try_with_config(config: OpenAIConfig) -> Result<Self> {
+        let client = build_client(config.timeout, config.proxy.as_deref(), &config.no_proxy)?;
+        Ok(Self::with_client(client, config))
+    }
 
-        Self { client, config }
+    /// Create a gateway that sends its requests through a client you built.
+    ///
+    /// For mutual TLS, proxies, or custom connectors. The API key and the
+    /// organization and project headers are still added to each request, but
+    /// `config.timeout` and the proxy settings are not applied; set them on
+    /// the client.
+    pub fn with_client(client: Client, config: OpenAIConfig) -> Self {
+        Self {
+            client,
+            attribution: attribution_headers(&config),
+            config,
+        }
     }
 
     /// Create gateway with custom API key.
@@ -84,6 +278,114 @@ impl OpenAIGateway {
         })
     }
 
+    /// POST an arbitrary request body to the chat completions endpoint and return the raw response.
+    ///
+    /// This is an escape hatch for power users who need provider features the
+    /// crate doesn't model yet. The body is sent exactly as given: no message
+    /// adaptation, model-specific parameter mapping, or validation is applied,
+    /// and the response is not parsed into an [`LlmGatewayResponse`]. Prefer
+    /// [`LlmGateway::complete`] whenever it covers your needs.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MojenticError::GatewayError`] for a non-success HTTP status and
+    /// [`MojenticError::HttpError`] if the request fails or the response isn't JSON.
+    pub async fn complete_raw_body(&self, body: Value) -> Result<Value> {
+        info!("Sending raw request body to OpenAI");
+
+        let response = self
+            .client
+            .post(format!("{}/chat/completions", self.config.base_url))
+            .header("Authorization", format!("Bearer {}", self.config.api_key))
+            .headers(self.attribution.clone())
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(api_error(status, &error_text));
+        }
+
+        Ok(response.json().await?)
+    }
+
+    /// Adapt messages to OpenAI format, with a cache breakpoint when configured
+    fn adapt_messages(&self, messages: &[LlmMessage]) -> Result<Vec<Value>> {
+        if self.config.cache_control {
+            adapt_messages_with_cache_control(messages)
+        } else {
+            adapt_messages_to_openai(messages)
+        }
+    }
+
+    /// Build and send a chat completion request, returning the raw response body
+    /// and the size of both bodies.
+    ///
+    /// `choices` is only sent when more than one is asked for.
+    async fn post_chat_completion(
+        &self,
+        model: &str,
+        messages: &[LlmMessage],
+        tools: Option<&[Box<dyn LlmTool>]>,
+        config: &CompletionConfig,
+        choices: Option<u32>,
+    ) -> Result<(Value, PayloadSize)> {
+        let openai_messages = self.adapt_messages(messages)?;
+        let (adapted_params, supports_tools) = self.adapt_parameters_for_model(model, config);
+
+        let mut body = serde_json::json!({
+            "model": model,
+            "messages": openai_messages,
+        });
+
+        // Add adapted parameters
+        for (key, value) in adapted_params {
+            body[key] = value;
+        }
+
+        if let Some(n) = choices.filter(|n| *n > 1) {
+            body["n"] = serde_json::json!(n);
+        }
+
+        // Add tools if provided and supported
+        if let Some(tools) = tools {
+            if supports_tools {
+                body["tools"] = tools_to_provider_json(tools, ToolProvider::OpenAI);
+            } else {
+                warn!(model = model, "Model does not support tools, ignoring tool configuration");
+            }
+        }
+
+        // Make API request
+        let request_body = serde_json::to_vec(&body)?;
+        let request_bytes = request_body.len() as u64;
+        let response = self
+            .client
+            .post(format!("{}/chat/completions", self.config.base_url))
+            .header("Authorization", format!("Bearer {}", self.config.api_key))
+            .headers(self.attribution.clone())
+            .header("Content-Type", "application/json")
+            .body(request_body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(api_error(status, &error_text));
+        }
+
+        let response_body = response.bytes().await?;
+        let payload_size = PayloadSize {
+            request_bytes,
+            response_bytes: response_body.len() as u64,
+        };
+        Ok((serde_json::from_slice(&response_body)?, payload_size))
+    }
+
     /// Adapt parameters based on model type and capabilities.
     fn adapt_parameters_for_model(
         &self,
@@ -162,6 +464,34 @@ impl OpenAIGateway {
             }
         }
 
+        // OpenAI has no thinking budget; reasoning models take reasoning_effort instead
+        if config.thinking_budget_tokens.is_some() {
+            debug!(model = model, "thinking_budget_tokens is not supported by OpenAI, ignoring");
+        }
+
+        if let Some(response_format) =
+            config.response_format.as_ref().and_then(openai_response_format)
+        {
+            params.insert("response_format".to_string(), response_format);
+        }
+
+        if let Some(bias) = config.logit_bias.as_ref().filter(|b| !b.is_empty()) {
+            params.insert("logit_bias".to_string(), resolve_logit_bias(model, bias));
+        }
+
+        if let Some(user) = &config.user {
+            params.insert("user".to_string(), serde_json::json!(user));
+        }
+
+        // Provider-specific extras fill in anything the known fields didn't set
+        for (key, value) in &config.extra {
+            if RESERVED_BODY_KEYS.contains(&key.as_str()) {
+                warn!(option = %key, "Ignoring extra option that would replace request structure");
+            } else if !params.contains_key(key) {
+                params.insert(key.clone(), value.clone());
+            }
+        }
+
         (params, capabilities.supports_tools)
     }
 
@@ -228,6 +558,13 @@ impl Default for OpenAIGateway {
     }
 }
 
+#[async_trait]
+impl crate::llm::embedding_gateway::EmbeddingGateway for OpenAIGateway {
+    async fn calculate_embeddings(&self, text: &str, model: Option<&str>) -> Result<Vec<f32>> {
+        LlmGateway::calculate_embeddings(self, text, model).await
+    }
+}
+
 #[async_trait]
 impl LlmGateway for OpenAIGateway {
     async fn complete(
@@ -240,69 +577,48 @@ impl LlmGateway for OpenAIGateway {
         info!("Delegating to OpenAI for completion");
         debug!("Model: {}, Message count: {}", model, messages.len());
 
-        let openai_messages = adapt_messages_to_openai(messages)?;
-        let (adapted_params, supports_tools) = self.adapt_parameters_for_model(model, config);
-
-        let mut body = serde_json::json!({
-            "model": model,
-            "messages": openai_messages,
-        });
-
-        // Add adapted parameters
-        for (key, value) in adapted_params {
-            body[key] = value;
-        }
-
-        // Add tools if provided and supported
-        if let Some(tools) = tools {
-            if supports_tools {
-                let tool_defs: Vec<_> = tools.iter().map(|t| t.descriptor()).collect();
-                body["tools"] = serde_json::to_value(tool_defs)?;
-            } else {
-                warn!(model = model, "Model does not support tools, ignoring tool configuration");
-            }
-        }
-
-        // Make API request
-        let response = self
-            .client
-            .post(format!("{}/chat/completions", self.config.base_url))
-            .header("Authorization", format!("Bearer {}", self.config.api_key))
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(MojenticError::GatewayError(format!(
-                "OpenAI API error: {} - {}",
-                status, error_text
-            )));
-        }
-
-        let response_body: Value = response.json().await?;
-
-        // Parse content
-        let content = response_body["choices"][0]["message"]["content"].as_str().map(String::from);
-
-        // Parse tool calls if present
-        let tool_calls =
-            if let Some(calls) = response_body["choices"][0]["message"]["tool_calls"].as_array() {
-                convert_tool_calls(calls)
-            } else {
-                vec![]
-            };
+        let (response_body, payload_size) =
+            self.post_chat_completion(model, messages, tools, config, None).await?;
 
         Ok(LlmGatewayResponse {
-            content,
-            object: None,
-            tool_calls,
-            thinking: None,
+            payload_size: Some(payload_size),
+            ..parse_choice(&response_body["choices"][0], parse_usage(&response_body))?
         })
     }
 
+    async fn complete_many(
+        &self,
+        model: &str,
+        messages: &[LlmMessage],
+        tools: Option<&[Box<dyn LlmTool>]>,
+        config: &CompletionConfig,
+    ) -> Result<Vec<LlmGatewayResponse>> {
+        info!("Delegating to OpenAI for multi-choice completion");
+        debug!("Model: {}, Message count: {}, Choices: {:?}", model, messages.len(), config.n);
+
+        let (response_body, payload_size) =
+            self.post_chat_completion(model, messages, tools, config, config.n).await?;
+
+        // Usage and payload size cover the whole request, so they are reported
+        // once on the first choice
+        let mut usage = parse_usage(&response_body);
+        let mut payload_size = Some(payload_size);
+        response_body["choices"]
+            .as_array()
+            .map(|choices| {
+                choices
+                    .iter()
+                    .map(|choice| {
+                        Ok(LlmGatewayResponse {
+                            payload_size: payload_size.take(),
+                            ..parse_choice(choice, usage.take())?
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_else(|| Ok(Vec::new()))
+    }
+
     async fn complete_json(
         &self,
         model: &str,
@@ -312,8 +628,10 @@ impl LlmGateway for OpenAIGateway {
     ) -> Result<Value> {
         info!("Requesting structured output from OpenAI");
 
-        let openai_messages = adapt_messages_to_openai(messages)?;
-        let (adapted_params, _) = self.adapt_parameters_for_model(model, config);
+        let openai_messages = self.adapt_messages(messages)?;
+        let (mut adapted_params, _) = self.adapt_parameters_for_model(model, config);
+        // The schema passed here takes precedence over any configured response format
+        adapted_params.remove("response_format");
 
         let mut body = serde_json::json!({
             "model": model,
@@ -336,6 +654,7 @@ impl LlmGateway for OpenAIGateway {
             .client
             .post(format!("{}/chat/completions", self.config.base_url))
             .header("Authorization", format!("Bearer {}", self.config.api_key))
+            .headers(self.attribution.clone())
             .header("Content-Type", "application/json")
             .json(&body)
             .send()
@@ -344,10 +663,7 @@ impl LlmGateway for OpenAIGateway {
         if !response.status().is_success() {
             let status = response.status();
             let error_text = response.text().await.unwrap_or_default();
-            return Err(MojenticError::GatewayError(format!(
-                "OpenAI API error: {} - {}",
-                status, error_text
-            )));
+            return Err(api_error(status, &error_text));
         }
 
         let response_body: Value = response.json().await?;
@@ -368,14 +684,13 @@ impl LlmGateway for OpenAIGateway {
             .client
             .get(format!("{}/models", self.config.base_url))
             .header("Authorization", format!("Bearer {}", self.config.api_key))
+            .headers(self.attribution.clone())
             .send()
-            .await?;
+            .await
+            .map_err(send_error)?;
 
         if !response.status().is_success() {
-            return Err(MojenticError::GatewayError(format!(
-                "Failed to get models: {}",
-                response.status()
-            )));
+            return Err(status_error("Failed to get models", response.status()));
         }
 
         let body: Value = response.json().await?;
@@ -415,6 +730,7 @@ impl LlmGateway for OpenAIGateway {
                 .client
                 .post(format!("{}/embeddings", self.config.base_url))
                 .header("Authorization", format!("Bearer {}", self.config.api_key))
+                .headers(self.attribution.clone())
                 .header("Content-Type", "application/json")
                 .json(&body)
                 .send()
@@ -451,6 +767,35 @@ impl LlmGateway for OpenAIGateway {
         Ok(self.weighted_average_embeddings(&all_embeddings, &weights))
     }
 
+    fn supports_streaming(&self, model: &str) -> bool {
+        get_model_registry().get_model_capabilities(model).supports_streaming
+    }
+
+    async fn supports_vision(&self, model: &str) -> bool {
+        get_model_registry().get_model_capabilities(model).supports_vision
+    }
+
+    async fn describe_model(&self, model: &str) -> Result<ModelDescription> {
+        let available = self.get_available_models().await?.iter().any(|name| name == model);
+        let capabilities = get_model_registry().get_model_capabilities(model);
+        Ok(ModelDescription {
+            name: model.to_string(),
+            available,
+            model_type: capabilities.model_type,
+            supports_tools: capabilities.supports_tools,
+            supports_streaming: capabilities.supports_streaming,
+            supports_vision: capabilities.supports_vision,
+            context_length: capabilities.max_context_tokens.map(|tokens| tokens as usize),
+        })
+    }
+
+    async fn context_length(&self, model: &str) -> Option<usize> {
+        get_model_registry()
+            .get_model_capabilities(model)
+            .max_context_tokens
+            .map(|tokens| tokens as usize)
+    }
+
     fn complete_stream<'a>(
         &'a self,
         model: &'a str,
@@ -462,10 +807,7 @@ impl LlmGateway for OpenAIGateway {
             info!("Starting OpenAI streaming completion");
             debug!("Model: {}, Message count: {}", model, messages.len());
 
-            // Check if model supports streaming
-            let registry = get_model_registry();
-            let capabilities = registry.get_model_capabilities(model);
-            if !capabilities.supports_streaming {
+            if !self.supports_streaming(model) {
                 yield Err(MojenticError::GatewayError(format!(
                     "Model {} does not support streaming",
                     model
@@ -473,7 +815,7 @@ impl LlmGateway for OpenAIGateway {
                 return;
             }
 
-            let openai_messages = match adapt_messages_to_openai(messages) {
+            let openai_messages = match self.adapt_messages(messages) {
                 Ok(msgs) => msgs,
                 Err(e) => {
                     yield Err(e);
@@ -497,10 +839,7 @@ impl LlmGateway for OpenAIGateway {
             // Add tools if provided and supported
             if let Some(tools) = tools {
                 if supports_tools {
-                    let tool_defs: Vec<_> = tools.iter().map(|t| t.descriptor()).collect();
-                    if let Ok(tools_value) = serde_json::to_value(tool_defs) {
-                        body["tools"] = tools_value;
-                    }
+                    body["tools"] = tools_to_provider_json(tools, ToolProvider::OpenAI);
                 }
             }
 
@@ -509,6 +848,7 @@ impl LlmGateway for OpenAIGateway {
                 .client
                 .post(format!("{}/chat/completions", self.config.base_url))
                 .header("Authorization", format!("Bearer {}", self.config.api_key))
+                .headers(self.attribution.clone())
                 .header("Content-Type", "application/json")
                 .json(&body)
                 .send()
@@ -534,7 +874,7 @@ impl LlmGateway for OpenAIGateway {
             let mut buffer = String::new();
 
             // Accumulate tool calls as they stream in
-            let mut tool_calls_accumulator: HashMap<usize, ToolCallAccumulator> = HashMap::new();
+            let mut tool_calls_accumulator = ToolCallStreamAccumulator::new();
 
             while let Some(chunk_result) = stream.next().await {
                 match chunk_result {
@@ -556,9 +896,13 @@ impl LlmGateway for OpenAIGateway {
                                 if data == "[DONE]" {
                                     // Final chunk - yield accumulated tool calls if any
                                     if !tool_calls_accumulator.is_empty() {
-                                        let complete_tool_calls = build_complete_tool_calls(&tool_calls_accumulator);
-                                        if !complete_tool_calls.is_empty() {
-                                            yield Ok(StreamChunk::ToolCalls(complete_tool_calls));
+                                        match tool_calls_accumulator.finish() {
+                                            Ok(calls) if calls.is_empty() => {}
+                                            Ok(calls) => yield Ok(StreamChunk::ToolCalls(calls)),
+                                            Err(e) => {
+                                                yield Err(e);
+                                                return;
+                                            }
                                         }
                                     }
                                     continue;
@@ -585,41 +929,20 @@ impl LlmGateway for OpenAIGateway {
                                             // Accumulate tool call chunks
                                             if let Some(tool_calls) = delta["tool_calls"].as_array() {
                                                 for tc in tool_calls {
-                                                    if let Some(index) = tc["index"].as_u64() {
-                                                        let index = index as usize;
-
-                                                        // Initialize accumulator if needed
-                                                        let acc = tool_calls_accumulator.entry(index).or_insert_with(|| ToolCallAccumulator {
-                                                            id: None,
-                                                            name: None,
-                                                            arguments: String::new(),
-                                                        });
-
-                                                        // First chunk has id
-                                                        if let Some(id) = tc["id"].as_str() {
-                                                            acc.id = Some(id.to_string());
-                                                        }
-
-                                                        // First chunk has function name
-                                                        if let Some(name) = tc["function"]["name"].as_str() {
-                                                            acc.name = Some(name.to_string());
-                                                        }
-
-                                                        // All chunks may have argument fragments
-                                                        if let Some(args) = tc["function"]["arguments"].as_str() {
-                                                            acc.arguments.push_str(args);
-                                                        }
-                                                    }
+                                                    tool_calls_accumulator.push_delta(tc.clone());
                                                 }
                                             }
 
                                             // When stream completes with tool_calls, yield accumulated tool calls
                                             if finish_reason == Some("tool_calls") && !tool_calls_accumulator.is_empty() {
-                                                let complete_tool_calls = build_complete_tool_calls(&tool_calls_accumulator);
-                                                if !complete_tool_calls.is_empty() {
-                                                    yield Ok(StreamChunk::ToolCalls(complete_tool_calls));
+                                                match tool_calls_accumulator.finish() {
+                                                    Ok(calls) if calls.is_empty() => {}
+                                                    Ok(calls) => yield Ok(StreamChunk::ToolCalls(calls)),
+                                                    Err(e) => {
+                                                        yield Err(e);
+                                                        return;
+                                                    }
                                                 }
-                                                tool_calls_accumulator.clear();
                                             }
                                         }
                                     }
@@ -640,42 +963,81 @@ impl LlmGateway for OpenAIGateway {
     }
 }
 
-/// Accumulator for streaming tool calls.
-struct ToolCallAccumulator {
-    id: Option<String>,
-    name: Option<String>,
-    arguments: String,
+/// Convert a single chat completion choice into a gateway response.
+fn parse_choice(choice: &Value, usage: Option<TokenUsage>) -> Result<LlmGatewayResponse> {
+    let content = choice["message"]["content"].as_str().map(String::from);
+
+    let tool_calls = if let Some(calls) = choice["message"]["tool_calls"].as_array() {
+        convert_tool_calls(calls)?
+    } else {
+        vec![]
+    };
+
+    Ok(LlmGatewayResponse {
+        content,
+        object: None,
+        tool_calls,
+        thinking: None,
+        usage,
+        payload_size: None,
+    })
 }
 
-/// Build complete tool calls from accumulators.
-fn build_complete_tool_calls(
-    accumulators: &HashMap<usize, ToolCallAccumulator>,
-) -> Vec<LlmToolCall> {
-    let mut indices: Vec<_> = accumulators.keys().collect();
-    indices.sort();
-
-    indices
-        .iter()
-        .filter_map(|&&index| {
-            let acc = accumulators.get(&index)?;
-            let name = acc.name.clone()?;
-
-            // Parse arguments
-            let arguments: HashMap<String, Value> =
-                serde_json::from_str(&acc.arguments).unwrap_or_default();
-
-            Some(LlmToolCall {
-                id: acc.id.clone(),
-                name,
-                arguments,
-            })
-        })
-        .collect()
+/// Extract token usage from a chat completion response body.
+fn parse_usage(response_body: &Value) -> Option<TokenUsage> {
+    response_body["usage"].as_object().map(|usage| {
+        TokenUsage::new(
+            usage.get("prompt_tokens").and_then(Value::as_u64).unwrap_or(0),
+            usage.get("completion_tokens").and_then(Value::as_u64).unwrap_or(0),
+        )
+    })
+}
+
+/// Map a configured response format onto OpenAI's `response_format` parameter
+fn openai_response_format(response_format: &ResponseFormat) -> Option<Value> {
+    match response_format {
+        ResponseFormat::Text => None,
+        ResponseFormat::JsonObject { schema: None } => {
+            Some(serde_json::json!({"type": "json_object"}))
+        }
+        ResponseFormat::JsonObject {
+            schema: Some(schema),
+        } => Some(serde_json::json!({
+            "type": "json_schema",
+            "json_schema": {
+                "name": "response",
+                "schema": schema
+            }
+        })),
+    }
+}
+
+/// Convert a token-text bias map into OpenAI's token-id keyed `logit_bias`.
+fn resolve_logit_bias(model: &str, bias: &HashMap<String, f32>) -> Value {
+    let tokenizer = TokenizerGateway::for_model(model);
+    let mut resolved = serde_json::Map::new();
+
+    for (text, weight) in bias {
+        let tokens = tokenizer.encode(text);
+        if tokens.is_empty() {
+            warn!(token = %text, "logit_bias entry encodes to no tokens, ignoring");
+            continue;
+        }
+        if tokens.len() > 1 {
+            debug!(token = %text, count = tokens.len(), "logit_bias entry spans several tokens");
+        }
+        for id in tokens {
+            resolved.insert(id.to_string(), serde_json::json!(weight.clamp(-100.0, 100.0)));
+        }
+    }
+
+    Value::Object(resolved)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::llm::gateway::ReasoningEffort;
     use std::sync::Mutex;
 
     static ENV_MUTEX: Mutex<()> = Mutex::new(());
@@ -703,6 +1065,24 @@ mod tests {
         std::env::remove_var("OPENAI_API_ENDPOINT");
     }
 
+    #[test]
+    fn test_openai_config_builder() {
+        let config = OpenAIConfig::builder()
+            .api_key("test-key")
+            .base_url("https://custom.openai.com")
+            .organization("org-acme")
+            .project("proj_billing")
+            .no_proxy(["localhost"])
+            .build();
+
+        assert_eq!(config.api_key, "test-key");
+        assert_eq!(config.base_url, "https://custom.openai.com");
+        assert_eq!(config.organization.as_deref(), Some("org-acme"));
+        assert_eq!(config.project.as_deref(), Some("proj_billing"));
+        assert!(config.proxy.is_none());
+        assert_eq!(config.no_proxy, vec!["localhost"]);
+    }
+
     #[test]
     fn test_gateway_new() {
         let gateway = OpenAIGateway::new();
@@ -780,51 +1160,6 @@ mod tests {
         assert!(result.is_empty());
     }
 
-    #[test]
-    fn test_build_complete_tool_calls() {
-        let mut accumulators = HashMap::new();
-        accumulators.insert(
-            0,
-            ToolCallAccumulator {
-                id: Some("call_123".to_string()),
-                name: Some("get_weather".to_string()),
-                arguments: r#"{"location": "NYC"}"#.to_string(),
-            },
-        );
-        accumulators.insert(
-            1,
-            ToolCallAccumulator {
-                id: Some("call_456".to_string()),
-                name: Some("search".to_string()),
-                arguments: r#"{"query": "test"}"#.to_string(),
-            },
-        );
-
-        let result = build_complete_tool_calls(&accumulators);
-
-        assert_eq!(result.len(), 2);
-        assert_eq!(result[0].id, Some("call_123".to_string()));
-        assert_eq!(result[0].name, "get_weather");
-        assert_eq!(result[1].id, Some("call_456".to_string()));
-        assert_eq!(result[1].name, "search");
-    }
-
-    #[test]
-    fn test_build_complete_tool_calls_missing_name() {
-        let mut accumulators = HashMap::new();
-        accumulators.insert(
-            0,
-            ToolCallAccumulator {
-                id: Some("call_123".to_string()),
-                name: None, // Missing name
-                arguments: r#"{}"#.to_string(),
-            },
-        );
-
-        let result = build_complete_tool_calls(&accumulators);
-        assert!(result.is_empty()); // Should be filtered out
-    }
-
     #[test]
     fn test_adapt_parameters_chat_model() {
         let gateway = OpenAIGateway::new();
@@ -857,31 +1192,415 @@ mod tests {
         assert!(supports_tools); // o1 now supports tools (audit 2026-02-04)
     }
 
-    #[tokio::test]
-    async fn test_complete_success() {
-        let mut server = mockito::Server::new_async().await;
-        let mock = server
-            .mock("POST", "/chat/completions")
-            .with_status(200)
-            .with_body(r#"{"choices":[{"message":{"role":"assistant","content":"Hello!"}}]}"#)
-            .create();
-
-        let gateway = OpenAIGateway::with_api_key_and_base_url("test-key", server.url());
-        let messages = vec![LlmMessage::user("Hi")];
-        let config = CompletionConfig::default();
+    #[test]
+    fn test_adapt_parameters_sends_reasoning_effort_to_reasoning_model() {
+        let gateway = OpenAIGateway::new();
+        let config = CompletionConfig {
+            reasoning_effort: Some(ReasoningEffort::High),
+            ..Default::default()
+        };
 
-        let result = gateway.complete("gpt-4", &messages, None, &config).await;
+        let (params, _) = gateway.adapt_parameters_for_model("o1", &config);
 
-        mock.assert();
-        assert!(result.is_ok());
-        let response = result.unwrap();
-        assert_eq!(response.content, Some("Hello!".to_string()));
+        assert_eq!(params.get("reasoning_effort"), Some(&serde_json::json!("high")));
     }
 
-    #[tokio::test]
-    async fn test_complete_with_tool_calls() {
-        let mut server = mockito::Server::new_async().await;
-        let mock = server
+    #[test]
+    fn test_adapt_parameters_omits_reasoning_effort_for_chat_model() {
+        let gateway = OpenAIGateway::new();
+        let config = CompletionConfig {
+            reasoning_effort: Some(ReasoningEffort::High),
+            ..Default::default()
+        };
+
+        let (params, _) = gateway.adapt_parameters_for_model("gpt-4", &config);
+
+        assert!(!params.contains_key("reasoning_effort"));
+    }
+
+    #[test]
+    fn test_adapt_parameters_ignores_thinking_budget() {
+        let gateway = OpenAIGateway::new();
+        let config = CompletionConfig {
+            thinking_budget_tokens: Some(2048),
+            ..Default::default()
+        };
+
+        let (params, _) = gateway.adapt_parameters_for_model("o1", &config);
+
+        assert!(!params.keys().any(|key| key.contains("budget") || key == "thinking"));
+    }
+
+    #[tokio::test]
+    async fn test_complete_raw_body_posts_custom_body() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/chat/completions")
+            .match_header("authorization", "Bearer test-key")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "model": "gpt-4",
+                "logprobs": true,
+                "top_logprobs": 2
+            })))
+            .with_status(200)
+            .with_body(r#"{"choices":[{"message":{"content":"raw"},"logprobs":{"content":[]}}]}"#)
+            .create();
+
+        let gateway = OpenAIGateway::with_api_key_and_base_url("test-key", server.url());
+        let body = serde_json::json!({
+            "model": "gpt-4",
+            "messages": [{"role": "user", "content": "Hi"}],
+            "logprobs": true,
+            "top_logprobs": 2
+        });
+
+        let response = gateway.complete_raw_body(body).await.unwrap();
+
+        mock.assert();
+        assert_eq!(response["choices"][0]["message"]["content"], "raw");
+        assert!(response["choices"][0]["logprobs"].is_object());
+    }
+
+    #[tokio::test]
+    async fn test_complete_success() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/chat/completions")
+            .with_status(200)
+            .with_body(r#"{"choices":[{"message":{"role":"assistant","content":"Hello!"}}]}"#)
+            .create();
+
+        let gateway = OpenAIGateway::with_api_key_and_base_url("test-key", server.url());
+        let messages = vec![LlmMessage::user("Hi")];
+        let config = CompletionConfig::default();
+
+        let result = gateway.complete("gpt-4", &messages, None, &config).await;
+
+        mock.assert();
+        assert!(result.is_ok());
+        let response = result.unwrap();
+        assert_eq!(response.content, Some("Hello!".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_complete_reports_token_usage() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("POST", "/chat/completions")
+            .with_status(200)
+            .with_body(
+                r#"{"choices":[{"message":{"role":"assistant","content":"Hello!"}}],"usage":{"prompt_tokens":9,"completion_tokens":3,"total_tokens":12}}"#,
+            )
+            .create();
+
+        let gateway = OpenAIGateway::with_api_key_and_base_url("test-key", server.url());
+        let messages = vec![LlmMessage::user("Hi")];
+
+        let response = gateway
+            .complete("gpt-4", &messages, None, &CompletionConfig::default())
+            .await
+            .unwrap();
+
+        assert_eq!(response.usage, Some(TokenUsage::new(9, 3)));
+    }
+
+    #[tokio::test]
+    async fn test_complete_reports_payload_size() {
+        let body = r#"{"choices":[{"message":{"role":"assistant","content":"Hello!"}}]}"#;
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("POST", "/chat/completions")
+            .with_status(200)
+            .with_body(body)
+            .create();
+
+        let gateway = OpenAIGateway::with_api_key_and_base_url("test-key", server.url());
+        let messages = vec![LlmMessage::user("Hi")];
+
+        let response = gateway
+            .complete("gpt-4", &messages, None, &CompletionConfig::default())
+            .await
+            .unwrap();
+
+        let size = response.payload_size.unwrap();
+        assert!(size.request_bytes > 0);
+        assert_eq!(size.response_bytes, body.len() as u64);
+    }
+
+    #[tokio::test]
+    async fn test_complete_merges_extra_options_at_top_level() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/chat/completions")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "model": "gpt-4",
+                "user": "user-42",
+                "logit_bias": {"50256": -100},
+                "temperature": 0.5
+            })))
+            .with_status(200)
+            .with_body(r#"{"choices":[{"message":{"role":"assistant","content":"Hello!"}}]}"#)
+            .create();
+
+        let gateway = OpenAIGateway::with_api_key_and_base_url("test-key", server.url());
+        let config = CompletionConfig {
+            temperature: 0.5,
+            extra: HashMap::from([
+                ("user".to_string(), serde_json::json!("user-42")),
+                ("logit_bias".to_string(), serde_json::json!({"50256": -100})),
+                ("temperature".to_string(), serde_json::json!(0.9)),
+                ("model".to_string(), serde_json::json!("other-model")),
+            ]),
+            ..Default::default()
+        };
+
+        gateway
+            .complete("gpt-4", &[LlmMessage::user("Hi")], None, &config)
+            .await
+            .unwrap();
+
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn test_complete_sends_end_user_id() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/chat/completions")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({"user": "user-7f3a"})))
+            .with_status(200)
+            .with_body(r#"{"choices":[{"message":{"role":"assistant","content":"Hello!"}}]}"#)
+            .create();
+
+        let gateway = OpenAIGateway::with_api_key_and_base_url("test-key", server.url());
+        let config = CompletionConfig {
+            user: Some("user-7f3a".to_string()),
+            ..Default::default()
+        };
+
+        gateway
+            .complete("gpt-4", &[LlmMessage::user("Hi")], None, &config)
+            .await
+            .unwrap();
+
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn test_complete_reports_unparseable_tool_arguments() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/chat/completions")
+            .with_status(200)
+            .with_body(r#"{"choices":[{"message":{"role":"assistant","content":null,"tool_calls":[{"id":"call_1","type":"function","function":{"name":"get_weather","arguments":"{\"location\": "}}]}}]}"#)
+            .create();
+
+        let gateway = OpenAIGateway::with_api_key_and_base_url("test-key", server.url());
+        let result = gateway
+            .complete("gpt-4", &[LlmMessage::user("Weather?")], None, &CompletionConfig::default())
+            .await;
+
+        mock.assert();
+        assert!(
+            matches!(&result, Err(MojenticError::ParseError(message)) if message.contains("get_weather"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_complete_sends_exported_tool_json() {
+        use crate::llm::tools::simple_date_tool::SimpleDateTool;
+
+        let tools: Vec<Box<dyn LlmTool>> = vec![Box::new(SimpleDateTool)];
+        let exported = tools_to_provider_json(&tools, ToolProvider::OpenAI);
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/chat/completions")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({"tools": exported})))
+            .with_status(200)
+            .with_body(r#"{"choices":[{"message":{"role":"assistant","content":"Hello!"}}]}"#)
+            .create();
+
+        let gateway = OpenAIGateway::with_api_key_and_base_url("test-key", server.url());
+        gateway
+            .complete(
+                "gpt-4",
+                &[LlmMessage::user("Hi")],
+                Some(&tools),
+                &CompletionConfig::default(),
+            )
+            .await
+            .unwrap();
+
+        mock.assert();
+        assert_eq!(exported[0]["type"], "function");
+    }
+
+    #[test]
+    fn test_adapt_parameters_leaves_choice_count_to_complete_many() {
+        let gateway = OpenAIGateway::new();
+        let config = CompletionConfig {
+            n: Some(3),
+            ..Default::default()
+        };
+
+        let (params, _) = gateway.adapt_parameters_for_model("gpt-4", &config);
+
+        assert!(!params.contains_key("n"));
+    }
+
+    #[test]
+    fn test_adapt_parameters_omits_user_when_unset() {
+        let gateway = OpenAIGateway::new();
+
+        let (params, _) = gateway.adapt_parameters_for_model("gpt-4", &CompletionConfig::default());
+
+        assert!(!params.contains_key("user"));
+    }
+
+    #[tokio::test]
+    async fn test_complete_sends_configured_response_format() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/chat/completions")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "response_format": {
+                    "type": "json_schema",
+                    "json_schema": {"name": "response", "schema": {"type": "object"}}
+                }
+            })))
+            .with_status(200)
+            .with_body(r#"{"choices":[{"message":{"role":"assistant","content":"{}"}}]}"#)
+            .create();
+
+        let gateway = OpenAIGateway::with_api_key_and_base_url("test-key", server.url());
+        let config = CompletionConfig {
+            response_format: Some(ResponseFormat::JsonObject {
+                schema: Some(serde_json::json!({"type": "object"})),
+            }),
+            ..Default::default()
+        };
+
+        gateway
+            .complete("gpt-4", &[LlmMessage::user("Hi")], None, &config)
+            .await
+            .unwrap();
+
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn test_complete_sends_cache_control_at_marked_message_when_enabled() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/chat/completions")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "messages": [
+                    {
+                        "role": "system",
+                        "content": [{
+                            "type": "text",
+                            "text": "Large context",
+                            "cache_control": {"type": "ephemeral"}
+                        }]
+                    },
+                    {"role": "user", "content": "Question"}
+                ]
+            })))
+            .with_status(200)
+            .with_body(r#"{"choices":[{"message":{"role":"assistant","content":"Answer"}}]}"#)
+            .create();
+
+        let gateway = OpenAIGateway::with_config(
+            OpenAIConfig::builder()
+                .api_key("test-key")
+                .base_url(server.url())
+                .cache_control(true)
+                .build(),
+        );
+        let messages = vec![
+            LlmMessage::system("Large context").with_cache(),
+            LlmMessage::user("Question"),
+        ];
+
+        gateway
+            .complete("gpt-4", &messages, None, &CompletionConfig::default())
+            .await
+            .unwrap();
+
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn test_complete_resolves_logit_bias_to_token_ids() {
+        let tokenizer = TokenizerGateway::for_model("gpt-4o");
+        let yes = tokenizer.encode("yes");
+        let no = tokenizer.encode("no");
+        assert_eq!((yes.len(), no.len()), (1, 1));
+
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/chat/completions")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "logit_bias": {yes[0].to_string(): 100.0, no[0].to_string(): -100.0}
+            })))
+            .with_status(200)
+            .with_body(r#"{"choices":[{"message":{"role":"assistant","content":"yes"}}]}"#)
+            .create();
+
+        let gateway = OpenAIGateway::with_api_key_and_base_url("test-key", server.url());
+        let config = CompletionConfig {
+            logit_bias: Some(HashMap::from([
+                ("yes".to_string(), 100.0),
+                ("no".to_string(), -250.0),
+            ])),
+            ..Default::default()
+        };
+
+        gateway
+            .complete("gpt-4o", &[LlmMessage::user("Hi")], None, &config)
+            .await
+            .unwrap();
+
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn test_complete_many_returns_all_choices() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/chat/completions")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({"n": 3})))
+            .with_status(200)
+            .with_body(
+                r#"{"choices":[
+                    {"index":0,"message":{"role":"assistant","content":"one"}},
+                    {"index":1,"message":{"role":"assistant","content":"two"}},
+                    {"index":2,"message":{"role":"assistant","content":"three"}}
+                ],"usage":{"prompt_tokens":9,"completion_tokens":6,"total_tokens":15}}"#,
+            )
+            .expect(1)
+            .create();
+
+        let gateway = OpenAIGateway::with_api_key_and_base_url("test-key", server.url());
+        let messages = vec![LlmMessage::user("Count")];
+        let config = CompletionConfig {
+            n: Some(3),
+            ..Default::default()
+        };
+
+        let responses = gateway.complete_many("gpt-4", &messages, None, &config).await.unwrap();
+
+        mock.assert();
+        let contents: Vec<_> = responses.iter().map(|r| r.content.as_deref().unwrap()).collect();
+        assert_eq!(contents, vec!["one", "two", "three"]);
+        assert_eq!(responses[0].usage, Some(TokenUsage::new(9, 6)));
+        assert!(responses[1].usage.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_complete_with_tool_calls() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
             .mock("POST", "/chat/completions")
             .with_status(200)
             .with_body(r#"{"choices":[{"message":{"role":"assistant","content":null,"tool_calls":[{"id":"call_1","type":"function","function":{"name":"get_weather","arguments":"{\"location\": \"NYC\"}"}}]}}]}"#)
@@ -919,6 +1638,33 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_complete_reports_context_length_exceeded() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/chat/completions")
+            .with_status(400)
+            .with_body(
+                r#"{"error":{"message":"This model's maximum context length is 8192 tokens. However, your messages resulted in 9000 tokens. Please reduce the length of the messages.","type":"invalid_request_error","param":"messages","code":"context_length_exceeded"}}"#,
+            )
+            .create();
+
+        let gateway = OpenAIGateway::with_api_key_and_base_url("test-key", server.url());
+        let messages = vec![LlmMessage::user("Hi")];
+        let config = CompletionConfig::default();
+
+        let result = gateway.complete("gpt-4", &messages, None, &config).await;
+
+        mock.assert();
+        assert!(matches!(
+            result,
+            Err(MojenticError::ContextLengthExceeded {
+                estimated: 9000,
+                limit: 8192
+            })
+        ));
+    }
+
     #[tokio::test]
     async fn test_complete_json() {
         let mut server = mockito::Server::new_async().await;
@@ -965,6 +1711,130 @@ mod tests {
         assert_eq!(models[1], "gpt-4");
     }
 
+    #[tokio::test]
+    async fn test_describe_model_known_model() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("GET", "/models")
+            .with_status(200)
+            .with_body(r#"{"data":[{"id":"gpt-4o"}]}"#)
+            .create();
+
+        let gateway = OpenAIGateway::with_api_key_and_base_url("test-key", server.url());
+        let description = gateway.describe_model("gpt-4o").await.unwrap();
+
+        assert_eq!(description.name, "gpt-4o");
+        assert!(description.available);
+        assert_eq!(description.model_type, ModelType::Chat);
+        assert!(description.supports_tools);
+        assert!(description.supports_streaming);
+        assert!(description.supports_vision);
+        assert!(description.context_length.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_requests_carry_organization_and_project_headers() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/models")
+            .match_header("OpenAI-Organization", "org-acme")
+            .match_header("OpenAI-Project", "proj_billing")
+            .with_status(200)
+            .with_body(r#"{"data":[]}"#)
+            .create();
+
+        let gateway = OpenAIGateway::with_config(OpenAIConfig {
+            api_key: "test-key".to_string(),
+            base_url: server.url(),
+            organization: Some("org-acme".to_string()),
+            project: Some("proj_billing".to_string()),
+            ..Default::default()
+        });
+        let result = gateway.get_available_models().await;
+
+        mock.assert();
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_no_proxy_hosts_bypass_the_proxy() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/models")
+            .with_status(200)
+            .with_body(r#"{"data":[{"id":"gpt-4o"}]}"#)
+            .create();
+        let host = server.host_with_port();
+        let host = host.split(':').next().unwrap();
+
+        // Nothing listens on the discard port, so only a bypassed request succeeds
+        let gateway = OpenAIGateway::with_config(OpenAIConfig {
+            api_key: "test-key".to_string(),
+            base_url: server.url(),
+            proxy: Some("http://127.0.0.1:9".to_string()),
+            no_proxy: vec![host.to_string()],
+            ..Default::default()
+        });
+        let models = gateway.get_available_models().await.unwrap();
+
+        mock.assert();
+        assert_eq!(models, vec!["gpt-4o"]);
+    }
+
+    #[tokio::test]
+    async fn test_injected_client_requests_keep_credentials_and_attribution() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/models")
+            .match_header("Authorization", "Bearer test-key")
+            .match_header("OpenAI-Project", "proj_billing")
+            .match_header("X-Proxy-Token", "secret")
+            .with_status(200)
+            .with_body(r#"{"data":[{"id":"gpt-4o"}]}"#)
+            .create();
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Proxy-Token", HeaderValue::from_static("secret"));
+        let client = Client::builder().default_headers(headers).build().unwrap();
+
+        let gateway = OpenAIGateway::with_client(
+            client,
+            OpenAIConfig {
+                api_key: "test-key".to_string(),
+                base_url: server.url(),
+                project: Some("proj_billing".to_string()),
+                ..Default::default()
+            },
+        );
+        let models = gateway.get_available_models().await.unwrap();
+
+        mock.assert();
+        assert_eq!(models, vec!["gpt-4o"]);
+    }
+
+    #[tokio::test]
+    async fn test_get_available_models_reports_rejected_key_as_unauthorized() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server.mock("GET", "/models").with_status(401).create();
+
+        let gateway = OpenAIGateway::with_api_key_and_base_url("bad-key", server.url());
+        let result = gateway.get_available_models().await;
+
+        mock.assert();
+        assert!(matches!(result, Err(MojenticError::Unauthorized(_))));
+    }
+
+    #[tokio::test]
+    async fn test_get_available_models_reports_unreachable_server_as_network_error() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let gateway = OpenAIGateway::with_api_key_and_base_url("test-key", url);
+        let result = gateway.get_available_models().await;
+
+        assert!(matches!(result, Err(MojenticError::NetworkError(_))));
+    }
+
     #[tokio::test]
     async fn test_calculate_embeddings() {
         let mut server = mockito::Server::new_async().await;
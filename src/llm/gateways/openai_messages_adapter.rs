@@ -1,7 +1,9 @@
 //! Adapter for converting LLM messages to OpenAI format.
 
 use crate::error::Result;
-use crate::llm::models::{LlmMessage, LlmToolCall, MessageRole};
+use crate::llm::models::{
+    cache_breakpoint, normalize_tool_call_ids, LlmMessage, LlmToolCall, MessageRole,
+};
 use base64::Engine;
 use serde_json::Value;
 use std::path::Path;
@@ -71,10 +73,37 @@ fn encode_image_as_base64(file_path: &str) -> Result<String> {
 }
 
 /// Adapt LLM messages to OpenAI format.
+///
+/// Tool calls without ids, as recorded from Ollama, are given ids first (see
+/// [`normalize_tool_call_ids`]) so OpenAI can match each result to its call.
 pub fn adapt_messages_to_openai(messages: &[LlmMessage]) -> Result<Vec<Value>> {
+    adapt_messages(messages, false)
+}
+
+/// Adapt LLM messages to OpenAI format, with a cache breakpoint at the last marked message.
+///
+/// The content of the message found by [`cache_breakpoint`] is sent as text
+/// parts, the last carrying `cache_control: {"type": "ephemeral"}`. This is the
+/// form OpenAI-compatible endpoints in front of providers with explicit prompt
+/// caching (such as Anthropic through OpenRouter or LiteLLM) accept; OpenAI
+/// itself caches automatically and rejects the field.
+pub fn adapt_messages_with_cache_control(messages: &[LlmMessage]) -> Result<Vec<Value>> {
+    adapt_messages(messages, true)
+}
+
+fn adapt_messages(messages: &[LlmMessage], cache_control: bool) -> Result<Vec<Value>> {
     let mut result = Vec::new();
+    let breakpoint = cache_breakpoint(messages).filter(|_| cache_control);
+
+    for (index, msg) in normalize_tool_call_ids(messages).iter().enumerate() {
+        if let Some(expanded) = expand_aggregated_tool_results(msg) {
+            result.extend(expanded);
+            if breakpoint == Some(index) {
+                result.last_mut().into_iter().for_each(add_cache_control);
+            }
+            continue;
+        }
 
-    for msg in messages {
         let openai_msg = match msg.role {
             MessageRole::System => {
                 serde_json::json!({
@@ -179,29 +208,85 @@ pub fn adapt_messages_to_openai(messages: &[LlmMessage]) -> Result<Vec<Value>> {
         };
 
         result.push(openai_msg);
+        if breakpoint == Some(index) {
+            result.last_mut().into_iter().for_each(add_cache_control);
+        }
     }
 
     Ok(result)
 }
 
+/// Mark the end of an adapted message's content as a cache breakpoint
+///
+/// A message without content, such as an assistant turn that only calls
+/// tools, is left as it is.
+fn add_cache_control(message: &mut Value) {
+    let cache_control = serde_json::json!({"type": "ephemeral"});
+    match &mut message["content"] {
+        Value::String(text) => {
+            message["content"] = serde_json::json!([{
+                "type": "text",
+                "text": text,
+                "cache_control": cache_control
+            }]);
+        }
+        Value::Array(parts) => {
+            if let Some(last) = parts.last_mut() {
+                last["cache_control"] = cache_control;
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Split an aggregated tool-result message into one OpenAI tool message per call.
+///
+/// OpenAI requires a separate `tool` message answering each `tool_call_id`, so a
+/// message built with [`crate::llm::gateway::ToolResultFormat::Aggregated`]
+/// (content is an array of `{tool_call_id, result}`) is expanded here. Returns
+/// `None` for any other message.
+fn expand_aggregated_tool_results(msg: &LlmMessage) -> Option<Vec<Value>> {
+    if msg.role != MessageRole::Tool || msg.tool_calls.as_ref().is_none_or(|c| c.len() < 2) {
+        return None;
+    }
+
+    let calls = msg.tool_calls.as_ref()?;
+    let entries: Vec<Value> = serde_json::from_str(msg.content.as_deref()?).ok()?;
+    entries
+        .iter()
+        .enumerate()
+        .map(|(position, entry)| {
+            // Entries recorded without ids answer the calls in order
+            let tool_call_id = entry
+                .get("tool_call_id")
+                .and_then(Value::as_str)
+                .filter(|id| !id.is_empty())
+                .or_else(|| calls.get(position)?.id.as_deref())?;
+            let result = entry.get("result")?;
+            Some(serde_json::json!({
+                "role": "tool",
+                "content": serde_json::to_string(result).ok()?,
+                "tool_call_id": tool_call_id
+            }))
+        })
+        .collect()
+}
+
 /// Convert tool calls from OpenAI format to internal format.
-pub fn convert_tool_calls(tool_calls: &[Value]) -> Vec<LlmToolCall> {
+///
+/// Calls without a function name are skipped.
+///
+/// # Errors
+///
+/// Returns [`crate::error::MojenticError::ParseError`] if a call's arguments
+/// aren't a JSON object (see [`LlmToolCall::from_raw_arguments`]).
+pub fn convert_tool_calls(tool_calls: &[Value]) -> crate::error::Result<Vec<LlmToolCall>> {
     tool_calls
         .iter()
         .filter_map(|tc| {
             let id = tc["id"].as_str().map(String::from);
-            let name = tc["function"]["name"].as_str()?.to_string();
-            let args_str = tc["function"]["arguments"].as_str().unwrap_or("{}");
-
-            // Parse arguments as JSON object
-            let arguments: std::collections::HashMap<String, Value> =
-                serde_json::from_str(args_str).unwrap_or_default();
-
-            Some(LlmToolCall {
-                id,
-                name,
-                arguments,
-            })
+            let name = tc["function"]["name"].as_str()?;
+            Some(LlmToolCall::from_raw_arguments(id, name, &tc["function"]["arguments"]))
         })
         .collect()
 }
@@ -318,6 +403,7 @@ mod tests {
             content: None,
             tool_calls: Some(vec![tool_call]),
             image_paths: None,
+            cache: false,
         }];
 
         let result = adapt_messages_to_openai(&messages).unwrap();
@@ -346,6 +432,7 @@ mod tests {
                 arguments: HashMap::new(),
             }]),
             image_paths: None,
+            cache: false,
         }];
 
         let result = adapt_messages_to_openai(&messages).unwrap();
@@ -356,6 +443,183 @@ mod tests {
         assert_eq!(result[0]["tool_call_id"], "call_123");
     }
 
+    #[test]
+    fn test_adapt_ignores_cache_marker() {
+        // OpenAI caches prompt prefixes automatically, so nothing is sent for the marker
+        let marked = vec![
+            LlmMessage::system("Large context").with_cache(),
+            LlmMessage::user("Question"),
+        ];
+        let unmarked = vec![
+            LlmMessage::system("Large context"),
+            LlmMessage::user("Question"),
+        ];
+
+        assert_eq!(
+            adapt_messages_to_openai(&marked).unwrap(),
+            adapt_messages_to_openai(&unmarked).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_adapt_with_cache_control_marks_last_marked_message() {
+        let messages = vec![
+            LlmMessage::system("Instructions").with_cache(),
+            LlmMessage::user("Large retrieved context").with_cache(),
+            LlmMessage::user("Question"),
+        ];
+
+        let result = adapt_messages_with_cache_control(&messages).unwrap();
+
+        assert_eq!(result[0]["content"], "Instructions");
+        assert_eq!(
+            result[1]["content"],
+            serde_json::json!([{
+                "type": "text",
+                "text": "Large retrieved context",
+                "cache_control": {"type": "ephemeral"}
+            }])
+        );
+        assert_eq!(result[2]["content"], "Question");
+    }
+
+    #[test]
+    fn test_adapt_with_cache_control_sends_nothing_without_marker() {
+        let messages = vec![
+            LlmMessage::system("Instructions"),
+            LlmMessage::user("Question"),
+        ];
+
+        assert_eq!(
+            adapt_messages_with_cache_control(&messages).unwrap(),
+            adapt_messages_to_openai(&messages).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_adapt_tool_result_constructor() {
+        let messages = vec![LlmMessage::tool_result(
+            "call_123",
+            "get_weather",
+            &serde_json::json!({"f": 72}),
+        )];
+
+        let result = adapt_messages_to_openai(&messages).unwrap();
+
+        assert_eq!(result[0]["role"], "tool");
+        assert_eq!(result[0]["content"], r#"{"f":72}"#);
+        assert_eq!(result[0]["tool_call_id"], "call_123");
+    }
+
+    fn weather_and_time_calls() -> Vec<LlmToolCall> {
+        vec![
+            LlmToolCall {
+                id: Some("call_1".to_string()),
+                name: "get_weather".to_string(),
+                arguments: HashMap::new(),
+            },
+            LlmToolCall {
+                id: Some("call_2".to_string()),
+                name: "get_time".to_string(),
+                arguments: HashMap::new(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_adapt_separate_tool_results() {
+        let calls = weather_and_time_calls();
+        let messages: Vec<LlmMessage> = calls
+            .iter()
+            .zip([r#"{"temp":72}"#, r#"{"time":"noon"}"#])
+            .map(|(call, content)| LlmMessage {
+                role: MessageRole::Tool,
+                content: Some(content.to_string()),
+                tool_calls: Some(vec![call.clone()]),
+                image_paths: None,
+                cache: false,
+            })
+            .collect();
+
+        let result = adapt_messages_to_openai(&messages).unwrap();
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0]["tool_call_id"], "call_1");
+        assert_eq!(result[0]["content"], r#"{"temp":72}"#);
+        assert_eq!(result[1]["tool_call_id"], "call_2");
+        assert_eq!(result[1]["content"], r#"{"time":"noon"}"#);
+    }
+
+    #[test]
+    fn test_adapt_aggregated_tool_results_expands_per_call() {
+        let messages = vec![LlmMessage {
+            role: MessageRole::Tool,
+            content: Some(
+                serde_json::json!([
+                    {"tool_call_id": "call_1", "result": {"temp": 72}},
+                    {"tool_call_id": "call_2", "result": {"time": "noon"}}
+                ])
+                .to_string(),
+            ),
+            tool_calls: Some(weather_and_time_calls()),
+            image_paths: None,
+            cache: false,
+        }];
+
+        let result = adapt_messages_to_openai(&messages).unwrap();
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0]["role"], "tool");
+        assert_eq!(result[0]["tool_call_id"], "call_1");
+        assert_eq!(result[0]["content"], r#"{"temp":72}"#);
+        assert_eq!(result[1]["tool_call_id"], "call_2");
+        assert_eq!(result[1]["content"], r#"{"time":"noon"}"#);
+    }
+
+    #[test]
+    fn test_adapt_replays_ollama_tool_conversation_with_matching_ids() {
+        let call = |name: &str| LlmToolCall {
+            id: None,
+            name: name.to_string(),
+            arguments: HashMap::new(),
+        };
+        let assistant = |calls: Vec<LlmToolCall>| LlmMessage {
+            role: MessageRole::Assistant,
+            content: None,
+            tool_calls: Some(calls),
+            image_paths: None,
+            cache: false,
+        };
+        let result = |call: LlmToolCall, content: &str| LlmMessage {
+            role: MessageRole::Tool,
+            content: Some(content.to_string()),
+            tool_calls: Some(vec![call]),
+            image_paths: None,
+            cache: false,
+        };
+        let messages = vec![
+            LlmMessage::user("Weather and time in Paris, then in Rome?"),
+            assistant(vec![call("get_weather"), call("get_time")]),
+            result(call("get_time"), r#"{"time":"noon"}"#),
+            result(call("get_weather"), r#"{"temp":21}"#),
+            assistant(vec![call("get_weather")]),
+            LlmMessage::tool_result("", "get_weather", &serde_json::json!({"temp": 25})),
+        ];
+
+        let adapted = adapt_messages_to_openai(&messages).unwrap();
+
+        let first_calls = adapted[1]["tool_calls"].as_array().unwrap();
+        let (weather_id, time_id) = (&first_calls[0]["id"], &first_calls[1]["id"]);
+        assert_ne!(weather_id, "");
+        assert_ne!(weather_id, time_id);
+        assert_eq!(&adapted[2]["tool_call_id"], time_id);
+        assert_eq!(&adapted[3]["tool_call_id"], weather_id);
+        let second_id = &adapted[4]["tool_calls"][0]["id"];
+        assert_ne!(second_id, weather_id);
+        assert_eq!(&adapted[5]["tool_call_id"], second_id);
+        assert_eq!(adapt_messages_to_openai(&messages).unwrap(), adapted);
+    }
+
     #[test]
     fn test_convert_tool_calls() {
         let tool_calls = vec![serde_json::json!({
@@ -367,7 +631,7 @@ mod tests {
             }
         })];
 
-        let result = convert_tool_calls(&tool_calls);
+        let result = convert_tool_calls(&tool_calls).unwrap();
 
         assert_eq!(result.len(), 1);
         assert_eq!(result[0].id, Some("call_abc".to_string()));
@@ -386,7 +650,7 @@ mod tests {
             }
         })];
 
-        let result = convert_tool_calls(&tool_calls);
+        let result = convert_tool_calls(&tool_calls).unwrap();
 
         assert_eq!(result.len(), 1);
         assert_eq!(result[0].name, "no_args_tool");
@@ -77,6 +77,7 @@ impl Default for ModelCapabilities {
 ///
 /// This struct provides a centralized way to manage model-specific configurations,
 /// parameter mappings, and capabilities for OpenAI models.
+#[derive(Clone)]
 pub struct OpenAIModelRegistry {
     models: HashMap<String, ModelCapabilities>,
     pattern_mappings: HashMap<String, ModelType>,
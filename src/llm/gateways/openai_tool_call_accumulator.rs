@@ -0,0 +1,170 @@
+//! Reassembly of tool calls from OpenAI streaming deltas.
+//!
+//! A streamed chat completion sends each tool call in pieces: the first delta
+//! for an `index` carries the call's id and function name, and later deltas
+//! append fragments of the JSON arguments. [`ToolCallStreamAccumulator`] collects
+//! those pieces, independent of any HTTP stream, so it can be fed and tested
+//! one delta at a time.
+
+use crate::error::Result;
+use crate::llm::models::LlmToolCall;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use tracing::warn;
+
+/// The pieces of one tool call received so far
+#[derive(Debug, Default, Clone)]
+struct PartialToolCall {
+    id: Option<String>,
+    name: Option<String>,
+    arguments: String,
+}
+
+/// Collects streamed tool-call deltas into complete [`LlmToolCall`]s
+///
+/// # Examples
+///
+/// ```
+/// use mojentic::llm::gateways::ToolCallStreamAccumulator;
+/// use serde_json::json;
+///
+/// let mut accumulator = ToolCallStreamAccumulator::new();
+/// accumulator.push_delta(json!({"index": 0, "id": "call_1", "function": {"name": "add", "arguments": "{\"a\": "}}));
+/// accumulator.push_delta(json!({"index": 0, "function": {"arguments": "1}"}}));
+///
+/// let calls = accumulator.finish().unwrap();
+/// assert_eq!(calls[0].name, "add");
+/// assert_eq!(calls[0].arguments["a"], 1);
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct ToolCallStreamAccumulator {
+    calls: BTreeMap<u64, PartialToolCall>,
+    last_index: Option<u64>,
+}
+
+impl ToolCallStreamAccumulator {
+    /// Create an empty accumulator
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add one entry of a delta's `tool_calls` array
+    ///
+    /// Deltas may arrive for any index in any order. A delta without an
+    /// `index` continues the call most recently added to, or starts call 0.
+    pub fn push_delta(&mut self, delta: Value) {
+        let index = delta["index"].as_u64().or(self.last_index).unwrap_or(0);
+        self.last_index = Some(index);
+        let call = self.calls.entry(index).or_default();
+
+        if let Some(id) = delta["id"].as_str().filter(|id| !id.is_empty()) {
+            call.id = Some(id.to_string());
+        }
+        if let Some(name) = delta["function"]["name"].as_str().filter(|name| !name.is_empty()) {
+            call.name = Some(name.to_string());
+        }
+        if let Some(arguments) = delta["function"]["arguments"].as_str() {
+            call.arguments.push_str(arguments);
+        }
+    }
+
+    /// Whether no deltas have been added since the last [`finish`](Self::finish)
+    pub fn is_empty(&self) -> bool {
+        self.calls.is_empty()
+    }
+
+    /// The complete tool calls in index order, leaving the accumulator empty
+    ///
+    /// Calls whose name never arrived can't be run and are dropped with a
+    /// warning.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::error::MojenticError::ParseError`] naming the tool if a
+    /// call's arguments don't form a JSON object.
+    pub fn finish(&mut self) -> Result<Vec<LlmToolCall>> {
+        self.last_index = None;
+        std::mem::take(&mut self.calls)
+            .into_iter()
+            .filter_map(|(index, call)| {
+                let Some(name) = call.name else {
+                    warn!("Dropping streamed tool call {} without a function name", index);
+                    return None;
+                };
+                Some(LlmToolCall::from_raw_arguments(call.id, name, &Value::String(call.arguments)))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::MojenticError;
+    use serde_json::json;
+
+    #[test]
+    fn test_reassembles_calls_from_out_of_order_deltas() {
+        let mut accumulator = ToolCallStreamAccumulator::new();
+        accumulator.push_delta(
+            json!({"index": 2, "id": "call_c", "function": {"name": "search", "arguments": ""}}),
+        );
+        accumulator.push_delta(
+            json!({"index": 0, "id": "call_a", "function": {"name": "get_weather", "arguments": "{\"location\""}}),
+        );
+        accumulator
+            .push_delta(json!({"index": 2, "function": {"arguments": "{\"query\": \"rust\"}"}}));
+        accumulator.push_delta(
+            json!({"index": 1, "id": "call_b", "function": {"name": "get_time", "arguments": "{}"}}),
+        );
+        accumulator.push_delta(json!({"index": 0, "function": {"arguments": ": \"NYC\"}"}}));
+
+        let calls = accumulator.finish().unwrap();
+
+        let names: Vec<&str> = calls.iter().map(|call| call.name.as_str()).collect();
+        assert_eq!(names, vec!["get_weather", "get_time", "search"]);
+        assert_eq!(calls[0].id.as_deref(), Some("call_a"));
+        assert_eq!(calls[0].arguments["location"], "NYC");
+        assert_eq!(calls[2].arguments["query"], "rust");
+        assert!(accumulator.is_empty());
+    }
+
+    #[test]
+    fn test_drops_calls_without_a_name() {
+        let mut accumulator = ToolCallStreamAccumulator::new();
+        accumulator.push_delta(json!({"index": 1, "function": {"arguments": "{}"}}));
+        accumulator.push_delta(json!({"index": 0, "id": "call_a", "function": {"name": "ping"}}));
+
+        let calls = accumulator.finish().unwrap();
+
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].name, "ping");
+        assert!(calls[0].arguments.is_empty());
+    }
+
+    #[test]
+    fn test_delta_without_index_continues_the_last_call() {
+        let mut accumulator = ToolCallStreamAccumulator::new();
+        accumulator
+            .push_delta(json!({"index": 3, "function": {"name": "add", "arguments": "{\"a\":"}}));
+        accumulator.push_delta(json!({"function": {"arguments": " 2}"}}));
+
+        let calls = accumulator.finish().unwrap();
+
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].arguments["a"], 2);
+    }
+
+    #[test]
+    fn test_reports_unparseable_arguments() {
+        let mut accumulator = ToolCallStreamAccumulator::new();
+        accumulator
+            .push_delta(json!({"index": 0, "function": {"name": "add", "arguments": "{\"a\":"}}));
+
+        let result = accumulator.finish();
+
+        assert!(
+            matches!(result, Err(MojenticError::ParseError(message)) if message.contains("add"))
+        );
+    }
+}
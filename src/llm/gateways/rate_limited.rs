@@ -0,0 +1,441 @@
+use crate::error::{MojenticError, Result};
+use crate::llm::broker::estimate_prompt_tokens;
+use crate::llm::gateway::{CompletionConfig, LlmGateway, ModelDescription, StreamChunk};
+use crate::llm::gateways::TokenizerGateway;
+use crate::llm::models::{LlmGatewayResponse, LlmMessage, TokenUsage};
+use crate::llm::tools::{LlmTool, ToolProvider};
+use async_trait::async_trait;
+use futures::stream::{Stream, StreamExt};
+use serde_json::Value;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Longest a request waits for capacity unless configured otherwise
+pub const DEFAULT_RATE_LIMIT_MAX_WAIT: Duration = Duration::from_secs(60);
+
+/// Gateway wrapper that paces requests to stay under provider quotas
+///
+/// Wraps another gateway with token buckets for requests per second and/or
+/// tokens per minute. Each call reserves capacity before it is forwarded and
+/// waits until that capacity is available, so concurrent callers are served in
+/// the order they arrived. A call that would have to wait longer than the
+/// maximum wait fails with [`MojenticError::RateLimited`] instead.
+///
+/// Token costs are estimated from the prompt before the call and corrected
+/// from the provider's reported usage afterwards, when it reports any. Clones
+/// share the same buckets, so wrap the provider gateway once and hand the
+/// result to every broker that should count against the same quota.
+///
+/// # Examples
+///
+/// ```ignore
+/// use mojentic::llm::gateways::{OpenAIGateway, RateLimitedGateway};
+///
+/// let gateway = RateLimitedGateway::new(Arc::new(OpenAIGateway::default()))
+///     .with_requests_per_second(5.0)
+///     .with_tokens_per_minute(90_000)
+///     .with_max_wait(Duration::from_secs(30));
+/// let broker = LlmBroker::new("gpt-4o", Arc::new(gateway), None);
+/// ```
+#[derive(Clone)]
+pub struct RateLimitedGateway {
+    inner: Arc<dyn LlmGateway>,
+    limiter: Arc<Mutex<Limiter>>,
+    max_wait: Duration,
+}
+
+impl RateLimitedGateway {
+    /// Wrap `inner` with no limits; add them with the `with_*` methods
+    pub fn new(inner: Arc<dyn LlmGateway>) -> Self {
+        Self {
+            inner,
+            limiter: Arc::new(Mutex::new(Limiter {
+                requests: None,
+                tokens: None,
+                last_refill: Instant::now(),
+            })),
+            max_wait: DEFAULT_RATE_LIMIT_MAX_WAIT,
+        }
+    }
+
+    /// Allow this many requests per second, with bursts of up to one second's worth
+    ///
+    /// # Panics
+    ///
+    /// Panics if `requests_per_second` is not positive.
+    pub fn with_requests_per_second(self, requests_per_second: f64) -> Self {
+        assert!(requests_per_second > 0.0, "requests_per_second must be positive");
+        self.limiter.lock().unwrap().requests =
+            Some(Bucket::new(requests_per_second.max(1.0), requests_per_second));
+        self
+    }
+
+    /// Allow this many prompt and completion tokens per minute
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tokens_per_minute` is zero.
+    pub fn with_tokens_per_minute(self, tokens_per_minute: u64) -> Self {
+        assert!(tokens_per_minute > 0, "tokens_per_minute must be positive");
+        let capacity = tokens_per_minute as f64;
+        self.limiter.lock().unwrap().tokens = Some(Bucket::new(capacity, capacity / 60.0));
+        self
+    }
+
+    /// Set how long a request may wait for capacity before failing
+    pub fn with_max_wait(mut self, max_wait: Duration) -> Self {
+        self.max_wait = max_wait;
+        self
+    }
+
+    /// Reserve one request and `tokens` tokens, waiting until they are available
+    async fn acquire(&self, tokens: u64) -> Result<()> {
+        let wait = self.limiter.lock().unwrap().reserve(tokens as f64, self.max_wait)?;
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+        Ok(())
+    }
+
+    /// Estimate the prompt tokens a request will be charged for
+    fn estimate_tokens(
+        &self,
+        model: &str,
+        messages: &[LlmMessage],
+        tools: Option<&[Box<dyn LlmTool>]>,
+    ) -> u64 {
+        let tokenizer = TokenizerGateway::for_model(model);
+        estimate_prompt_tokens(&tokenizer, messages, tools, self.inner.tool_provider()) as u64
+    }
+
+    /// Correct a reservation once the provider reports what the call actually used
+    fn settle(&self, estimated: u64, usage: Option<&TokenUsage>) {
+        if let Some(usage) = usage {
+            let actual = usage.prompt_tokens + usage.completion_tokens;
+            self.limiter.lock().unwrap().adjust_tokens(actual as f64 - estimated as f64);
+        }
+    }
+}
+
+#[async_trait]
+impl LlmGateway for RateLimitedGateway {
+    async fn complete(
+        &self,
+        model: &str,
+        messages: &[LlmMessage],
+        tools: Option<&[Box<dyn LlmTool>]>,
+        config: &CompletionConfig,
+    ) -> Result<LlmGatewayResponse> {
+        let estimated = self.estimate_tokens(model, messages, tools);
+        self.acquire(estimated).await?;
+        let response = self.inner.complete(model, messages, tools, config).await?;
+        self.settle(estimated, response.usage.as_ref());
+        Ok(response)
+    }
+
+    async fn complete_json(
+        &self,
+        model: &str,
+        messages: &[LlmMessage],
+        schema: Value,
+        config: &CompletionConfig,
+    ) -> Result<Value> {
+        self.acquire(self.estimate_tokens(model, messages, None)).await?;
+        self.inner.complete_json(model, messages, schema, config).await
+    }
+
+    async fn complete_many(
+        &self,
+        model: &str,
+        messages: &[LlmMessage],
+        tools: Option<&[Box<dyn LlmTool>]>,
+        config: &CompletionConfig,
+    ) -> Result<Vec<LlmGatewayResponse>> {
+        let estimated = self.estimate_tokens(model, messages, tools);
+        self.acquire(estimated).await?;
+        let responses = self.inner.complete_many(model, messages, tools, config).await?;
+        let usage = responses.iter().filter_map(|response| response.usage.as_ref()).fold(
+            None,
+            |total: Option<TokenUsage>, usage| {
+                let total = total.unwrap_or_default();
+                Some(TokenUsage::new(
+                    total.prompt_tokens + usage.prompt_tokens,
+                    total.completion_tokens + usage.completion_tokens,
+                ))
+            },
+        );
+        self.settle(estimated, usage.as_ref());
+        Ok(responses)
+    }
+
+    async fn get_available_models(&self) -> Result<Vec<String>> {
+        self.inner.get_available_models().await
+    }
+
+    async fn calculate_embeddings(&self, text: &str, model: Option<&str>) -> Result<Vec<f32>> {
+        let tokenizer = TokenizerGateway::for_model(model.unwrap_or_default());
+        self.acquire(tokenizer.count_tokens(text) as u64).await?;
+        self.inner.calculate_embeddings(text, model).await
+    }
+
+    fn supports_streaming(&self, model: &str) -> bool {
+        self.inner.supports_streaming(model)
+    }
+
+    fn supports_assistant_prefill(&self, model: &str) -> bool {
+        self.inner.supports_assistant_prefill(model)
+    }
+
+    async fn context_length(&self, model: &str) -> Option<usize> {
+        self.inner.context_length(model).await
+    }
+
+    async fn supports_vision(&self, model: &str) -> bool {
+        self.inner.supports_vision(model).await
+    }
+
+    async fn describe_model(&self, model: &str) -> Result<ModelDescription> {
+        self.inner.describe_model(model).await
+    }
+
+    fn tool_provider(&self) -> ToolProvider {
+        self.inner.tool_provider()
+    }
+
+    fn complete_stream<'a>(
+        &'a self,
+        model: &'a str,
+        messages: &'a [LlmMessage],
+        tools: Option<&'a [Box<dyn LlmTool>]>,
+        config: &'a CompletionConfig,
+    ) -> Pin<Box<dyn Stream<Item = Result<StreamChunk>> + Send + 'a>> {
+        Box::pin(async_stream::stream! {
+            let estimated = self.estimate_tokens(model, messages, tools);
+            if let Err(e) = self.acquire(estimated).await {
+                yield Err(e);
+                return;
+            }
+
+            let mut inner = self.inner.complete_stream(model, messages, tools, config);
+            while let Some(chunk) = inner.next().await {
+                if let Ok(StreamChunk::Metrics(metrics)) = &chunk {
+                    if let (Some(prompt), Some(completion)) =
+                        (metrics.prompt_eval_count, metrics.eval_count)
+                    {
+                        self.settle(estimated, Some(&TokenUsage::new(prompt, completion)));
+                    }
+                }
+                yield chunk;
+            }
+        })
+    }
+}
+
+/// Capacity shared by every clone of a [`RateLimitedGateway`]
+struct Limiter {
+    requests: Option<Bucket>,
+    tokens: Option<Bucket>,
+    last_refill: Instant,
+}
+
+impl Limiter {
+    /// Reserve capacity for one request, returning how long to wait before sending it
+    ///
+    /// Reservations may drive a bucket negative, which makes later callers wait
+    /// behind earlier ones. Nothing is reserved when the wait would exceed `max_wait`.
+    fn reserve(&mut self, tokens: f64, max_wait: Duration) -> Result<Duration> {
+        self.refill();
+
+        let wait = [(&self.requests, 1.0), (&self.tokens, tokens)]
+            .into_iter()
+            .filter_map(|(bucket, cost)| bucket.as_ref().map(|bucket| bucket.wait_for(cost)))
+            .max()
+            .unwrap_or(Duration::ZERO);
+        if wait > max_wait {
+            return Err(MojenticError::RateLimited { retry_after: wait });
+        }
+
+        if let Some(bucket) = &mut self.requests {
+            bucket.available -= 1.0;
+        }
+        if let Some(bucket) = &mut self.tokens {
+            bucket.available -= tokens;
+        }
+        Ok(wait)
+    }
+
+    /// Charge (or refund, when negative) tokens after the fact
+    fn adjust_tokens(&mut self, tokens: f64) {
+        self.refill();
+        if let Some(bucket) = &mut self.tokens {
+            bucket.available = (bucket.available - tokens).min(bucket.capacity);
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.last_refill = now;
+        for bucket in [&mut self.requests, &mut self.tokens].into_iter().flatten() {
+            bucket.available =
+                (bucket.available + elapsed * bucket.per_second).min(bucket.capacity);
+        }
+    }
+}
+
+/// A token bucket refilled continuously at a fixed rate
+struct Bucket {
+    capacity: f64,
+    per_second: f64,
+    available: f64,
+}
+
+impl Bucket {
+    fn new(capacity: f64, per_second: f64) -> Self {
+        Self {
+            capacity,
+            per_second,
+            available: capacity,
+        }
+    }
+
+    /// How long until `cost` units are available
+    fn wait_for(&self, cost: f64) -> Duration {
+        if self.available >= cost {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((cost - self.available) / self.per_second)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct CountingGateway {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl LlmGateway for CountingGateway {
+        async fn complete(
+            &self,
+            _model: &str,
+            _messages: &[LlmMessage],
+            _tools: Option<&[Box<dyn LlmTool>]>,
+            _config: &CompletionConfig,
+        ) -> Result<LlmGatewayResponse> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(LlmGatewayResponse {
+                content: Some("ok".to_string()),
+                object: None,
+                tool_calls: vec![],
+                thinking: None,
+                usage: None,
+                payload_size: None,
+            })
+        }
+
+        async fn complete_json(
+            &self,
+            _model: &str,
+            _messages: &[LlmMessage],
+            _schema: Value,
+            _config: &CompletionConfig,
+        ) -> Result<Value> {
+            Ok(Value::Null)
+        }
+
+        async fn get_available_models(&self) -> Result<Vec<String>> {
+            Ok(vec![])
+        }
+
+        fn complete_stream<'a>(
+            &'a self,
+            _model: &'a str,
+            _messages: &'a [LlmMessage],
+            _tools: Option<&'a [Box<dyn LlmTool>]>,
+            _config: &'a CompletionConfig,
+        ) -> Pin<Box<dyn Stream<Item = Result<StreamChunk>> + Send + 'a>> {
+            Box::pin(futures::stream::empty())
+        }
+    }
+
+    fn counting_gateway() -> Arc<CountingGateway> {
+        Arc::new(CountingGateway {
+            calls: AtomicUsize::new(0),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_burst_is_paced_to_request_rate() {
+        let inner = counting_gateway();
+        let gateway = RateLimitedGateway::new(inner.clone()).with_requests_per_second(20.0);
+        let messages = [LlmMessage::user("Hi")];
+        let config = CompletionConfig::default();
+        let start = Instant::now();
+
+        // One second's worth goes out at once; the other ten are spaced 50ms apart
+        let calls = (0..30).map(|_| gateway.complete("gpt-4o", &messages, None, &config));
+        let results = futures::future::join_all(calls).await;
+
+        let elapsed = start.elapsed();
+        assert!(results.iter().all(|result| result.is_ok()));
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 30);
+        assert!(elapsed >= Duration::from_millis(450), "finished in {:?}", elapsed);
+        assert!(elapsed < Duration::from_secs(3), "finished in {:?}", elapsed);
+    }
+
+    #[tokio::test]
+    async fn test_fails_when_wait_exceeds_maximum() {
+        let inner = counting_gateway();
+        let gateway = RateLimitedGateway::new(inner.clone())
+            .with_requests_per_second(1.0)
+            .with_max_wait(Duration::from_millis(100));
+        let messages = [LlmMessage::user("Hi")];
+        let config = CompletionConfig::default();
+
+        gateway.complete("gpt-4o", &messages, None, &config).await.unwrap();
+        let result = gateway.complete("gpt-4o", &messages, None, &config).await;
+
+        assert!(matches!(
+            result,
+            Err(MojenticError::RateLimited { retry_after }) if retry_after > Duration::from_millis(800)
+        ));
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_limits_tokens_per_minute() {
+        let inner = counting_gateway();
+        let gateway = RateLimitedGateway::new(inner.clone())
+            .with_tokens_per_minute(60)
+            .with_max_wait(Duration::from_secs(1));
+        let messages = [LlmMessage::user("word ".repeat(40))];
+        let config = CompletionConfig::default();
+
+        gateway.complete("gpt-4o", &messages, None, &config).await.unwrap();
+        let result = gateway.complete("gpt-4o", &messages, None, &config).await;
+
+        assert!(matches!(result, Err(MojenticError::RateLimited { .. })));
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_clones_share_capacity() {
+        let inner = counting_gateway();
+        let gateway = RateLimitedGateway::new(inner)
+            .with_requests_per_second(1.0)
+            .with_max_wait(Duration::ZERO);
+        let clone = gateway.clone();
+        let messages = [LlmMessage::user("Hi")];
+        let config = CompletionConfig::default();
+
+        gateway.complete("gpt-4o", &messages, None, &config).await.unwrap();
+
+        assert!(clone.complete("gpt-4o", &messages, None, &config).await.is_err());
+    }
+}
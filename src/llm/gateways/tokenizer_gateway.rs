@@ -6,6 +6,7 @@
 //! - Debugging tokenization issues
 //! - Optimizing prompt engineering
 
+use crate::llm::tools::{tools_to_provider_json, LlmTool, ToolProvider};
 use tiktoken_rs::CoreBPE;
 
 /// Gateway for tokenizing and detokenizing text using tiktoken.
@@ -26,7 +27,7 @@ use tiktoken_rs::CoreBPE;
 /// assert_eq!(text, decoded);
 /// ```
 pub struct TokenizerGateway {
-    tokenizer: CoreBPE,
+    tokenizer: &'static CoreBPE,
 }
 
 impl TokenizerGateway {
@@ -35,6 +36,7 @@ impl TokenizerGateway {
     /// # Arguments
     ///
     /// * `model` - The encoding model to use. Common options:
+    ///   - "o200k_base" - Used by GPT-4o, GPT-4.1 and the o-series models
     ///   - "cl100k_base" - Used by GPT-4 and GPT-3.5-turbo (default)
     ///   - "p50k_base" - Used by older GPT-3 models
     ///   - "r50k_base" - Used by even older models
@@ -52,14 +54,35 @@ impl TokenizerGateway {
     /// ```
     pub fn new(model: &str) -> Result<Self, Box<dyn std::error::Error>> {
         let tokenizer = match model {
-            "cl100k_base" => tiktoken_rs::cl100k_base()?,
-            "p50k_base" => tiktoken_rs::p50k_base()?,
-            "r50k_base" => tiktoken_rs::r50k_base()?,
+            "o200k_base" => tiktoken_rs::o200k_base_singleton(),
+            "cl100k_base" => tiktoken_rs::cl100k_base_singleton(),
+            "p50k_base" => tiktoken_rs::p50k_base_singleton(),
+            "r50k_base" => tiktoken_rs::r50k_base_singleton(),
             _ => return Err(format!("Unsupported encoding model: {}", model).into()),
         };
         Ok(Self { tokenizer })
     }
 
+    /// Creates a TokenizerGateway using the encoding an OpenAI model uses.
+    ///
+    /// Models tiktoken doesn't recognise fall back to "cl100k_base".
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mojentic::llm::gateways::TokenizerGateway;
+    ///
+    /// let tokenizer = TokenizerGateway::for_model("gpt-4o");
+    /// assert!(tokenizer.count_tokens("Hello, world!") > 0);
+    /// ```
+    pub fn for_model(model: &str) -> Self {
+        let tokenizer = tiktoken_rs::bpe_for_model(model).unwrap_or_else(|_| {
+            tracing::debug!("No tokenizer known for model {}, using cl100k_base", model);
+            tiktoken_rs::cl100k_base_singleton()
+        });
+        Self { tokenizer }
+    }
+
     /// Encodes text into tokens.
     ///
     /// # Arguments
@@ -137,6 +160,27 @@ impl TokenizerGateway {
     pub fn count_tokens(&self, text: &str) -> usize {
         self.encode(text).len()
     }
+
+    /// Counts the tokens taken by tool definitions as a provider receives them.
+    ///
+    /// The tools are serialized as the `tools` array the provider is sent
+    /// (see [`tools_to_provider_json`]), including usage hints and priorities,
+    /// so the count reflects the real overhead of offering them.
+    ///
+    /// # Arguments
+    ///
+    /// * `tools` - The tools offered to the model
+    /// * `provider` - The provider whose format they are sent in
+    ///
+    /// # Returns
+    ///
+    /// The number of tokens, or 0 when there are no tools.
+    pub fn count_tool_tokens(&self, tools: &[Box<dyn LlmTool>], provider: ToolProvider) -> usize {
+        if tools.is_empty() {
+            return 0;
+        }
+        self.count_tokens(&tools_to_provider_json(tools, provider).to_string())
+    }
 }
 
 impl Default for TokenizerGateway {
@@ -161,6 +205,17 @@ mod tests {
         assert!(!tokens.is_empty());
     }
 
+    #[test]
+    fn test_for_model_selects_model_encoding() {
+        let gpt4o = TokenizerGateway::for_model("gpt-4o");
+        let o200k = TokenizerGateway::new("o200k_base").unwrap();
+        let text = "Tokenizers differ between model generations";
+        assert_eq!(gpt4o.encode(text), o200k.encode(text));
+
+        let unknown = TokenizerGateway::for_model("not-a-real-model");
+        assert_eq!(unknown.encode(text), TokenizerGateway::default().encode(text));
+    }
+
     #[test]
     fn test_encode_empty() {
         let tokenizer = TokenizerGateway::default();
@@ -1,12 +1,40 @@
 pub mod broker;
 pub mod chat_session;
+pub mod conversation;
+pub mod embedding_gateway;
 pub mod gateway;
 pub mod gateways;
 pub mod models;
+pub mod partial_json;
+pub mod reasoning;
+pub mod replay;
+pub mod reranker;
+pub mod retry;
+pub mod schema_transform;
+pub mod schema_validation;
+pub mod stream_merge;
+#[cfg(test)]
+pub(crate) mod test_support;
 pub mod tools;
 
-pub use broker::LlmBroker;
-pub use chat_session::{ChatSession, ChatSessionBuilder, SizedLlmMessage};
-pub use gateway::{CompletionConfig, LlmGateway};
-pub use models::{LlmGatewayResponse, LlmMessage, LlmToolCall, MessageRole};
+pub use broker::{
+    GenerationResult, LlmBroker, ToolNotFoundPolicy, ToolSelection, DEFAULT_MAX_CONCURRENT_TOOLS,
+    RESPONSE_TRUNCATION_MARKER,
+};
+pub use chat_session::{
+    ChatSession, ChatSessionBuilder, CheckpointId, EmptyResponseBehavior, SizedLlmMessage,
+};
+pub use embedding_gateway::EmbeddingGateway;
+pub use gateway::{CompletionConfig, LlmGateway, ModelDescription, DEFAULT_NUM_CTX};
+pub use models::{
+    normalize_tool_call_ids, ImageInput, LlmGatewayResponse, LlmGatewayResponseBuilder, LlmMessage,
+    LlmToolCall, MessageRole, PayloadSize, TokenUsage,
+};
+pub use partial_json::{
+    ObjectStream, ObjectStreamItem, ValidatedObjectStream, ValidatedStreamItem,
+};
+pub use replay::{replay_turn, ReplayedTurn};
+pub use reranker::{LlmReranker, Reranker};
+pub use retry::RetryBudget;
+pub use stream_merge::merge_streams;
 pub use tools::{FunctionDescriptor, LlmTool, ToolDescriptor, ToolWrapper};
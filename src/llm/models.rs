@@ -1,3 +1,4 @@
+use crate::error::{MojenticError, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -20,6 +21,75 @@ pub struct LlmToolCall {
     pub arguments: HashMap<String, serde_json::Value>,
 }
 
+impl LlmToolCall {
+    /// Build a tool call from the arguments a provider sent
+    ///
+    /// Providers encode arguments differently: OpenAI sends a string holding
+    /// a JSON object, Ollama sends the object itself. Both are accepted, as is
+    /// a missing value or an empty string (no arguments).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MojenticError::ParseError`] naming the tool if the arguments
+    /// are neither a JSON object nor a string containing one.
+    pub fn from_raw_arguments(
+        id: Option<String>,
+        name: impl Into<String>,
+        raw: &serde_json::Value,
+    ) -> Result<Self> {
+        let name = name.into();
+        let invalid = |reason: String| {
+            MojenticError::ParseError(format!(
+                "Invalid arguments for tool call {:?}: {}",
+                name, reason
+            ))
+        };
+        let arguments = match raw {
+            serde_json::Value::Null => HashMap::new(),
+            serde_json::Value::Object(fields) => fields.clone().into_iter().collect(),
+            serde_json::Value::String(text) if text.trim().is_empty() => HashMap::new(),
+            serde_json::Value::String(text) => match serde_json::from_str(text) {
+                Ok(serde_json::Value::Object(fields)) => fields.into_iter().collect(),
+                Ok(other) => return Err(invalid(format!("expected a JSON object, got {}", other))),
+                Err(e) => return Err(invalid(format!("{} in {:?}", e, text))),
+            },
+            other => return Err(invalid(format!("expected a JSON object, got {}", other))),
+        };
+        Ok(Self {
+            id,
+            name,
+            arguments,
+        })
+    }
+}
+
+/// An image to send to a vision model (see [`LlmBroker::describe_image`](crate::llm::LlmBroker::describe_image))
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageInput {
+    /// An image file on disk
+    Path(String),
+    /// Encoded image data: PNG, JPEG, GIF or WebP
+    Bytes(Vec<u8>),
+}
+
+impl From<&str> for ImageInput {
+    fn from(path: &str) -> Self {
+        ImageInput::Path(path.to_string())
+    }
+}
+
+impl From<String> for ImageInput {
+    fn from(path: String) -> Self {
+        ImageInput::Path(path)
+    }
+}
+
+impl From<Vec<u8>> for ImageInput {
+    fn from(bytes: Vec<u8>) -> Self {
+        ImageInput::Bytes(bytes)
+    }
+}
+
 /// Message in LLM conversation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LlmMessage {
@@ -31,19 +101,216 @@ pub struct LlmMessage {
     pub tool_calls: Option<Vec<LlmToolCall>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub image_paths: Option<Vec<String>>,
+    /// Marks this message as the end of a cacheable prompt prefix.
+    ///
+    /// Place it after large, stable content (a system prompt, retrieved
+    /// documents) and before the parts that change between requests. Only the
+    /// last marked message counts (see [`cache_breakpoint`]). The OpenAI
+    /// gateway sends a `cache_control` breakpoint here when
+    /// [`OpenAIConfig::cache_control`](crate::llm::gateways::OpenAIConfig::cache_control)
+    /// is on, for compatible endpoints in front of providers with explicit
+    /// prompt caching; OpenAI itself caches prefixes automatically and Ollama
+    /// has no prompt cache, so by default the marker is not sent.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cache: bool,
+}
+
+/// Index of the message a provider's cache breakpoint belongs after.
+///
+/// This is the last message marked with [`LlmMessage::with_cache`], or `None`
+/// when no message is marked.
+pub fn cache_breakpoint(messages: &[LlmMessage]) -> Option<usize> {
+    messages.iter().rposition(|message| message.cache)
+}
+
+/// Give every tool call in a conversation an id its result can be matched by.
+///
+/// Providers disagree on tool call ids: OpenAI requires them, while Ollama
+/// leaves them out, so a conversation recorded through one can't be replayed
+/// through the other as is. Each assistant tool call without an id gets a
+/// stable one derived from its position, `call_<message>_<call>`. Each tool
+/// result without an id takes the id of the earliest unanswered call with the
+/// same name from the preceding assistant turn. Ids that are already present
+/// are kept.
+pub fn normalize_tool_call_ids(messages: &[LlmMessage]) -> Vec<LlmMessage> {
+    let mut unanswered: Vec<LlmToolCall> = Vec::new();
+
+    messages
+        .iter()
+        .enumerate()
+        .map(|(index, message)| {
+            let mut message = message.clone();
+            let Some(calls) = message.tool_calls.as_mut() else {
+                return message;
+            };
+
+            match message.role {
+                MessageRole::Assistant => {
+                    for (position, call) in calls.iter_mut().enumerate() {
+                        if !has_tool_call_id(call) {
+                            call.id = Some(format!("call_{}_{}", index, position));
+                        }
+                    }
+                    unanswered = calls.clone();
+                }
+                MessageRole::Tool => {
+                    for call in calls.iter_mut() {
+                        let answered = if has_tool_call_id(call) {
+                            unanswered.iter().position(|pending| pending.id == call.id)
+                        } else {
+                            unanswered.iter().position(|pending| pending.name == call.name)
+                        };
+                        if let Some(answered) = answered {
+                            call.id = unanswered.remove(answered).id;
+                        }
+                    }
+                }
+                MessageRole::System | MessageRole::User => {}
+            }
+            message
+        })
+        .collect()
+}
+
+fn has_tool_call_id(call: &LlmToolCall) -> bool {
+    call.id.as_deref().is_some_and(|id| !id.is_empty())
 }
 
 fn default_role() -> MessageRole {
     MessageRole::User
 }
 
+/// Token consumption reported by a provider for one or more completions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+impl TokenUsage {
+    /// Create a usage record
+    pub fn new(prompt_tokens: u64, completion_tokens: u64) -> Self {
+        Self {
+            prompt_tokens,
+            completion_tokens,
+        }
+    }
+
+    /// Prompt and completion tokens combined
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+}
+
+impl std::ops::Add for TokenUsage {
+    type Output = TokenUsage;
+
+    fn add(self, other: TokenUsage) -> TokenUsage {
+        TokenUsage::new(
+            self.prompt_tokens + other.prompt_tokens,
+            self.completion_tokens + other.completion_tokens,
+        )
+    }
+}
+
+impl std::ops::AddAssign for TokenUsage {
+    fn add_assign(&mut self, other: TokenUsage) {
+        *self = *self + other;
+    }
+}
+
+/// Bytes sent and received for one provider request
+///
+/// Useful for capacity planning and for spotting context bloat, since the
+/// request size grows with the conversation history.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PayloadSize {
+    /// Size of the serialized request body
+    pub request_bytes: u64,
+    /// Size of the response body
+    pub response_bytes: u64,
+}
+
 /// Response from LLM gateway
+///
+/// Gateways outside this crate build one with [`LlmGatewayResponse::builder`],
+/// since new fields may be added.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct LlmGatewayResponse<T = ()> {
     pub content: Option<String>,
     pub object: Option<T>,
     pub tool_calls: Vec<LlmToolCall>,
     pub thinking: Option<String>,
+    /// Token usage, when the provider reports it
+    pub usage: Option<TokenUsage>,
+    /// Serialized request and response body sizes, when the gateway measures them
+    pub payload_size: Option<PayloadSize>,
+}
+
+impl<T> LlmGatewayResponse<T> {
+    /// Start an empty response
+    pub fn builder() -> LlmGatewayResponseBuilder<T> {
+        LlmGatewayResponseBuilder {
+            response: Self {
+                content: None,
+                object: None,
+                tool_calls: Vec::new(),
+                thinking: None,
+                usage: None,
+                payload_size: None,
+            },
+        }
+    }
+}
+
+/// Builder for constructing an `LlmGatewayResponse`.
+#[derive(Debug, Clone)]
+pub struct LlmGatewayResponseBuilder<T = ()> {
+    response: LlmGatewayResponse<T>,
+}
+
+impl<T> LlmGatewayResponseBuilder<T> {
+    /// Set the text content
+    pub fn content(mut self, content: impl Into<String>) -> Self {
+        self.response.content = Some(content.into());
+        self
+    }
+
+    /// Set the structured object
+    pub fn object(mut self, object: T) -> Self {
+        self.response.object = Some(object);
+        self
+    }
+
+    /// Set the tool calls the model requested
+    pub fn tool_calls(mut self, tool_calls: Vec<LlmToolCall>) -> Self {
+        self.response.tool_calls = tool_calls;
+        self
+    }
+
+    /// Set the model's reasoning text
+    pub fn thinking(mut self, thinking: impl Into<String>) -> Self {
+        self.response.thinking = Some(thinking.into());
+        self
+    }
+
+    /// Set the token usage the provider reported
+    pub fn usage(mut self, usage: TokenUsage) -> Self {
+        self.response.usage = Some(usage);
+        self
+    }
+
+    /// Set the measured request and response body sizes
+    pub fn payload_size(mut self, payload_size: PayloadSize) -> Self {
+        self.response.payload_size = Some(payload_size);
+        self
+    }
+
+    /// Build the response
+    pub fn build(self) -> LlmGatewayResponse<T> {
+        self.response
+    }
 }
 
 impl LlmMessage {
@@ -54,6 +321,7 @@ impl LlmMessage {
             content: Some(content.into()),
             tool_calls: None,
             image_paths: None,
+            cache: false,
         }
     }
 
@@ -64,6 +332,7 @@ impl LlmMessage {
             content: Some(content.into()),
             tool_calls: None,
             image_paths: None,
+            cache: false,
         }
     }
 
@@ -74,9 +343,37 @@ impl LlmMessage {
             content: Some(content.into()),
             tool_calls: None,
             image_paths: None,
+            cache: false,
         }
     }
 
+    /// Create a tool message carrying a tool's output back to the model
+    ///
+    /// `tool_call_id` must be the id of the assistant's tool call this answers;
+    /// providers such as OpenAI reject tool messages that don't reference one.
+    /// The output is serialized as JSON for the message content.
+    pub fn tool_result(tool_call_id: &str, name: &str, output: &serde_json::Value) -> Self {
+        Self {
+            role: MessageRole::Tool,
+            content: Some(output.to_string()),
+            tool_calls: Some(vec![LlmToolCall {
+                id: Some(tool_call_id.to_string()),
+                name: name.to_string(),
+                arguments: HashMap::new(),
+            }]),
+            image_paths: None,
+            cache: false,
+        }
+    }
+
+    /// Mark this message as the end of a cacheable prompt prefix
+    ///
+    /// See [`LlmMessage::cache`].
+    pub fn with_cache(mut self) -> Self {
+        self.cache = true;
+        self
+    }
+
     /// Add image paths to this message
     pub fn with_images(mut self, paths: Vec<String>) -> Self {
         self.image_paths = Some(paths);
@@ -88,6 +385,31 @@ impl LlmMessage {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_normalize_tool_call_ids_keeps_existing_ids() {
+        let call = LlmToolCall {
+            id: Some("toolu_01".to_string()),
+            name: "get_weather".to_string(),
+            arguments: HashMap::new(),
+        };
+        let messages = vec![
+            LlmMessage {
+                role: MessageRole::Assistant,
+                content: None,
+                tool_calls: Some(vec![call]),
+                image_paths: None,
+                cache: false,
+            },
+            LlmMessage::tool_result("toolu_01", "get_weather", &serde_json::json!(72)),
+        ];
+
+        let normalized = normalize_tool_call_ids(&messages);
+
+        for message in &normalized {
+            assert_eq!(message.tool_calls.as_ref().unwrap()[0].id.as_deref(), Some("toolu_01"));
+        }
+    }
+
     #[test]
     fn test_message_role_serialization() {
         assert_eq!(serde_json::to_string(&MessageRole::System).unwrap(), "\"system\"");
@@ -107,6 +429,20 @@ mod tests {
         assert_eq!(serde_json::from_str::<MessageRole>("\"tool\"").unwrap(), MessageRole::Tool);
     }
 
+    #[test]
+    fn test_gateway_response_builder() {
+        let response = LlmGatewayResponse::<()>::builder()
+            .content("Hello")
+            .usage(TokenUsage::new(3, 1))
+            .build();
+
+        assert_eq!(response.content.as_deref(), Some("Hello"));
+        assert!(response.object.is_none());
+        assert!(response.tool_calls.is_empty());
+        assert_eq!(response.usage, Some(TokenUsage::new(3, 1)));
+        assert!(response.payload_size.is_none());
+    }
+
     #[test]
     fn test_user_message() {
         let msg = LlmMessage::user("Hello");
@@ -134,6 +470,42 @@ mod tests {
         assert!(msg.image_paths.is_none());
     }
 
+    #[test]
+    fn test_tool_result_message() {
+        let msg = LlmMessage::tool_result("call_7", "lookup", &serde_json::json!({"answer": 42}));
+
+        assert_eq!(msg.role, MessageRole::Tool);
+        assert_eq!(msg.content.as_deref(), Some(r#"{"answer":42}"#));
+        let calls = msg.tool_calls.unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].id.as_deref(), Some("call_7"));
+        assert_eq!(calls[0].name, "lookup");
+        assert!(msg.image_paths.is_none());
+    }
+
+    #[test]
+    fn test_cache_marker_is_opt_in_and_omitted_when_unset() {
+        let plain = LlmMessage::user("Question");
+        let marked = LlmMessage::system("Large context").with_cache();
+
+        assert!(!plain.cache);
+        assert!(marked.cache);
+        assert!(serde_json::to_value(&plain).unwrap().get("cache").is_none());
+        assert_eq!(serde_json::to_value(&marked).unwrap()["cache"], true);
+    }
+
+    #[test]
+    fn test_cache_breakpoint_is_last_marked_message() {
+        let messages = vec![
+            LlmMessage::system("Instructions").with_cache(),
+            LlmMessage::user("Retrieved documents").with_cache(),
+            LlmMessage::user("The question"),
+        ];
+
+        assert_eq!(cache_breakpoint(&messages), Some(1));
+        assert_eq!(cache_breakpoint(&messages[2..]), None);
+    }
+
     #[test]
     fn test_message_with_images() {
         let msg = LlmMessage::user("Describe this image")
@@ -143,6 +515,52 @@ mod tests {
         assert_eq!(msg.image_paths, Some(vec!["/path/to/image.jpg".to_string()]));
     }
 
+    #[test]
+    fn test_tool_call_arguments_accept_string_and_object_encodings() {
+        let from_string = LlmToolCall::from_raw_arguments(
+            Some("call_1".to_string()),
+            "search",
+            &serde_json::json!(r#"{"query": "rust", "limit": 3}"#),
+        )
+        .unwrap();
+        let from_object = LlmToolCall::from_raw_arguments(
+            None,
+            "search",
+            &serde_json::json!({"query": "rust", "limit": 3}),
+        )
+        .unwrap();
+
+        assert_eq!(from_string.arguments, from_object.arguments);
+        assert_eq!(from_string.arguments["limit"], serde_json::json!(3));
+        assert_eq!(from_string.id.as_deref(), Some("call_1"));
+        for empty in [
+            serde_json::Value::Null,
+            serde_json::json!(""),
+            serde_json::json!("{}"),
+        ] {
+            assert!(LlmToolCall::from_raw_arguments(None, "now", &empty)
+                .unwrap()
+                .arguments
+                .is_empty());
+        }
+    }
+
+    #[test]
+    fn test_tool_call_arguments_reject_non_objects() {
+        for raw in [
+            serde_json::json!(r#"{"query": "rust""#),
+            serde_json::json!("[1, 2]"),
+            serde_json::json!(42),
+        ] {
+            let err = LlmToolCall::from_raw_arguments(None, "search", &raw).unwrap_err();
+            assert!(
+                matches!(&err, MojenticError::ParseError(message) if message.contains("\"search\"")),
+                "{}",
+                err
+            );
+        }
+    }
+
     #[test]
     fn test_llm_tool_call_serialization() {
         let mut args = HashMap::new();
@@ -191,6 +609,15 @@ mod tests {
         assert_eq!(msg.content, Some("response".to_string()));
     }
 
+    #[test]
+    fn test_token_usage_addition() {
+        let mut usage = TokenUsage::new(10, 5);
+        usage += TokenUsage::new(20, 7);
+
+        assert_eq!(usage, TokenUsage::new(30, 12));
+        assert_eq!(usage.total_tokens(), 42);
+    }
+
     #[test]
     fn test_llm_message_default_role() {
         let json = r#"{"content":"test"}"#;
@@ -0,0 +1,158 @@
+//! Reading JSON that is still being streamed.
+//!
+//! [`LlmBroker::generate_object_partial`](super::LlmBroker::generate_object_partial)
+//! accumulates a structured response chunk by chunk. [`parse_partial_json`]
+//! turns an incomplete document into the most complete valid value it can,
+//! closing open strings, arrays and objects and dropping any trailing key or
+//! literal that isn't finished yet.
+
+use crate::error::Result;
+use futures::stream::Stream;
+use serde_json::Value;
+use std::pin::Pin;
+
+/// An item from [`LlmBroker::generate_object_partial`](super::LlmBroker::generate_object_partial)
+#[derive(Debug, Clone, PartialEq)]
+pub enum ObjectStreamItem<T, P> {
+    /// The latest view of the object, with whatever fields have arrived so far
+    Partial(P),
+    /// The finished object, deserialized from the complete response
+    Complete(T),
+}
+
+/// The stream returned by [`LlmBroker::generate_object_partial`](super::LlmBroker::generate_object_partial)
+pub type ObjectStream<'a, T, P> = Pin<Box<dyn Stream<Item = Result<ObjectStreamItem<T, P>>> + 'a>>;
+
+/// An item from [`LlmBroker::generate_object_validated`](super::LlmBroker::generate_object_validated)
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidatedStreamItem<T> {
+    /// A raw chunk of the response text, for display as it arrives
+    Text(String),
+    /// The finished object, validated against its schema and deserialized
+    Complete(T),
+}
+
+/// The stream returned by [`LlmBroker::generate_object_validated`](super::LlmBroker::generate_object_validated)
+pub type ValidatedObjectStream<'a, T> =
+    Pin<Box<dyn Stream<Item = Result<ValidatedStreamItem<T>>> + 'a>>;
+
+/// Parse the longest valid JSON value from the start of an incomplete document.
+///
+/// Returns `None` until an object or array has begun. Text before the first
+/// `{` or `[` is ignored, an unterminated string is closed where it stops, and
+/// anything that can't be completed (a key without a value, a half-written
+/// `true`) is dropped.
+///
+/// # Examples
+///
+/// ```
+/// use mojentic::llm::partial_json::parse_partial_json;
+/// use serde_json::json;
+///
+/// let value = parse_partial_json(r#"{"name": "Ada", "tags": ["rust", "ma"#).unwrap();
+/// assert_eq!(value, json!({"name": "Ada", "tags": ["rust", "ma"]}));
+/// ```
+pub fn parse_partial_json(text: &str) -> Option<Value> {
+    let text = &text[text.find(['{', '['])?..];
+
+    if let Ok(value) = serde_json::from_str(text) {
+        return Some(value);
+    }
+
+    // Positions outside strings where the document could be cut and closed
+    let mut candidates: Vec<(usize, String)> = Vec::new();
+    let mut closers: Vec<char> = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (i, c) in text.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else {
+            match c {
+                '"' => in_string = true,
+                '{' => closers.push('}'),
+                '[' => closers.push(']'),
+                '}' | ']' => {
+                    closers.pop();
+                }
+                _ => {}
+            }
+        }
+
+        if !in_string {
+            candidates.push((i + c.len_utf8(), closers.iter().rev().collect()));
+        }
+    }
+
+    // An unfinished string value is kept as far as it has been written
+    if in_string {
+        let end = if escaped { text.len() - 1 } else { text.len() };
+        let closing: String = std::iter::once('"').chain(closers.iter().rev().copied()).collect();
+        if let Some(value) = close_at(text, end, &closing) {
+            return Some(value);
+        }
+    }
+
+    candidates.iter().rev().find_map(|(end, closing)| close_at(text, *end, closing))
+}
+
+/// Parse `text[..end]` with trailing separators removed and `closing` appended
+fn close_at(text: &str, end: usize, closing: &str) -> Option<Value> {
+    let prefix = text[..end].trim_end_matches(|c: char| c == ',' || c.is_whitespace());
+    serde_json::from_str(&format!("{}{}", prefix, closing)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_complete_document_parses_as_is() {
+        assert_eq!(parse_partial_json(r#"{"a": [1, 2]}"#), Some(json!({"a": [1, 2]})));
+    }
+
+    #[test]
+    fn test_nothing_before_an_object_starts() {
+        assert_eq!(parse_partial_json(""), None);
+        assert_eq!(parse_partial_json("  "), None);
+        assert_eq!(parse_partial_json("{"), Some(json!({})));
+    }
+
+    #[test]
+    fn test_closes_open_strings_and_containers() {
+        assert_eq!(parse_partial_json(r#"{"name": "Ad"#), Some(json!({"name": "Ad"})));
+        assert_eq!(
+            parse_partial_json(r#"{"a": {"b": [1, {"c": "x"#),
+            Some(json!({"a": {"b": [1, {"c": "x"}]}}))
+        );
+    }
+
+    #[test]
+    fn test_drops_unfinished_keys_and_literals() {
+        assert_eq!(parse_partial_json(r#"{"a": 1, "b"#), Some(json!({"a": 1})));
+        assert_eq!(parse_partial_json(r#"{"a": 1, "b":"#), Some(json!({"a": 1})));
+        assert_eq!(parse_partial_json(r#"{"a": 1, "b": tr"#), Some(json!({"a": 1})));
+        assert_eq!(parse_partial_json(r#"[1, 2,"#), Some(json!([1, 2])));
+    }
+
+    #[test]
+    fn test_handles_escapes_in_unfinished_strings() {
+        assert_eq!(
+            parse_partial_json(r#"{"q": "say \"hi\" to"#),
+            Some(json!({"q": "say \"hi\" to"}))
+        );
+        assert_eq!(parse_partial_json(r#"{"q": "line\"#), Some(json!({"q": "line"})));
+    }
+
+    #[test]
+    fn test_ignores_text_before_the_document() {
+        assert_eq!(parse_partial_json("```json\n{\"a\": 1"), Some(json!({"a": 1})));
+    }
+}
@@ -0,0 +1,120 @@
+//! Post-processing for final responses.
+//!
+//! Reasoning models often think out loud before answering, either inside
+//! `<think>` tags or behind labels such as `Thought:`. The broker can run a
+//! [`ResponsePostProcessor`] over the final content before returning it (see
+//! [`LlmBroker::with_response_post_processor`](super::LlmBroker::with_response_post_processor)),
+//! while the tracer still records the full text. [`strip_reasoning`] removes
+//! the common forms of chain-of-thought.
+
+use regex::Regex;
+use std::sync::{Arc, LazyLock};
+
+/// Rewrites the final content of a response before it is returned
+pub type ResponsePostProcessor = Arc<dyn Fn(String) -> String + Send + Sync>;
+
+/// Complete reasoning blocks, in the tag styles models commonly use
+static REASONING_BLOCK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?is)<think>.*?</think>|<thinking>.*?</thinking>|<reasoning>.*?</reasoning>|<reflection>.*?</reflection>",
+    )
+    .expect("reasoning block pattern is valid")
+});
+
+/// A reasoning block the model opened but never closed
+static UNCLOSED_REASONING_BLOCK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<(?:think|thinking|reasoning|reflection)>.*$")
+        .expect("unclosed reasoning block pattern is valid")
+});
+
+/// The label ReAct-style models put in front of their answer
+static FINAL_ANSWER_LABEL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?im)^[ \t]*(?:\*\*)?final answer(?:\*\*)?:(?:\*\*)?[ \t]*")
+        .expect("final answer pattern is valid")
+});
+
+/// Labels that open a line of reasoning rather than answer text
+const REASONING_PREFIXES: &[&str] = &["thought:", "thinking:", "reasoning:", "let me think"];
+
+/// Remove chain-of-thought from a response, leaving only the answer.
+///
+/// Strips `<think>`, `<thinking>`, `<reasoning>` and `<reflection>` blocks
+/// (an unclosed block runs to the end of the text), keeps only what follows
+/// the last `Final Answer:` label when there is one, and drops leading lines
+/// that start with a reasoning label such as `Thought:` or `Reasoning:`.
+/// The result is trimmed.
+///
+/// # Examples
+///
+/// ```
+/// use mojentic::llm::reasoning::strip_reasoning;
+///
+/// let content = "<think>The user wants a greeting.</think>\nHello!".to_string();
+/// assert_eq!(strip_reasoning(content), "Hello!");
+///
+/// let content = "Thought: 6 times 7\nFinal Answer: 42".to_string();
+/// assert_eq!(strip_reasoning(content), "42");
+/// ```
+pub fn strip_reasoning(content: String) -> String {
+    let content = REASONING_BLOCK.replace_all(&content, "");
+    let content = UNCLOSED_REASONING_BLOCK.replace(&content, "");
+
+    let answer = match FINAL_ANSWER_LABEL.find_iter(&content).last() {
+        Some(label) => &content[label.end()..],
+        None => &content,
+    };
+
+    let mut lines = answer.trim_start().lines().peekable();
+    while lines.next_if(|line| is_reasoning_line(line)).is_some() {}
+
+    lines.collect::<Vec<_>>().join("\n").trim().to_string()
+}
+
+/// Whether a line opens with one of the reasoning labels
+fn is_reasoning_line(line: &str) -> bool {
+    let line = line.trim_start().to_lowercase();
+    REASONING_PREFIXES.iter().any(|prefix| line.starts_with(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strips_think_blocks() {
+        assert_eq!(
+            strip_reasoning("<think>plan it\nstep 2</think>\n\nAnswer".to_string()),
+            "Answer"
+        );
+        assert_eq!(
+            strip_reasoning("Before <THINKING>hidden</THINKING>after".to_string()),
+            "Before after"
+        );
+    }
+
+    #[test]
+    fn test_strips_unclosed_block() {
+        assert_eq!(strip_reasoning("Answer\n<think>never finished".to_string()), "Answer");
+    }
+
+    #[test]
+    fn test_keeps_text_after_final_answer_label() {
+        let content = "Thought: I should add them\nAction: none\nFinal Answer: 4".to_string();
+
+        assert_eq!(strip_reasoning(content), "4");
+    }
+
+    #[test]
+    fn test_drops_leading_reasoning_lines() {
+        let content = "Reasoning: the sky scatters blue light\nThe sky is blue.".to_string();
+
+        assert_eq!(strip_reasoning(content), "The sky is blue.");
+    }
+
+    #[test]
+    fn test_leaves_plain_answers_alone() {
+        let content = "First line\n\nThought: quoted later is kept".to_string();
+
+        assert_eq!(strip_reasoning(content.clone()), content);
+    }
+}
@@ -0,0 +1,115 @@
+//! Replaying recorded conversations.
+//!
+//! To check how a prompt or model change affects a conversation, take a
+//! recorded history, cut it just before one of its assistant turns, and
+//! generate that turn again with [`replay_turn`]. The recorded and replayed
+//! answers can then be compared side by side.
+
+use crate::error::{MojenticError, Result};
+use crate::llm::broker::LlmBroker;
+use crate::llm::models::{LlmMessage, MessageRole};
+
+/// An assistant turn generated again from its recorded history
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayedTurn {
+    /// What the assistant said in the recording, or `None` when replaying past its end
+    pub recorded: Option<String>,
+    /// What the broker's model says now
+    pub replayed: String,
+}
+
+/// Generate assistant turn `turn` (counting from 0) of a recorded conversation again
+///
+/// Sends `broker` every message before that turn and returns its answer with
+/// the recorded one. Passing the number of assistant turns in the recording
+/// replays the turn that would come next, with the whole history. No tools are
+/// offered, so recorded tool calls aren't repeated.
+///
+/// # Errors
+///
+/// Returns [`MojenticError::InvalidArgument`] if the recording has fewer than
+/// `turn` assistant turns, or any error from the broker.
+///
+/// # Examples
+///
+/// ```ignore
+/// use mojentic::llm::replay::replay_turn;
+///
+/// let candidate = LlmBroker::new("qwen3:32b", gateway, None).with_system_prompt(new_prompt);
+/// let turn = replay_turn(&recorded, 2, &candidate).await?;
+/// println!("before: {:?}\nafter: {}", turn.recorded, turn.replayed);
+/// ```
+pub async fn replay_turn(
+    messages: &[LlmMessage],
+    turn: usize,
+    broker: &LlmBroker,
+) -> Result<ReplayedTurn> {
+    let mut assistant_turns =
+        messages.iter().enumerate().filter(|(_, m)| m.role == MessageRole::Assistant);
+    let (history, recorded) = match assistant_turns.nth(turn) {
+        Some((index, message)) => {
+            (&messages[..index], Some(message.content.clone().unwrap_or_default()))
+        }
+        None if turn == messages.iter().filter(|m| m.role == MessageRole::Assistant).count() => {
+            (messages, None)
+        }
+        None => {
+            return Err(MojenticError::InvalidArgument(format!(
+                "Recording has no assistant turn {} to replay",
+                turn
+            )))
+        }
+    };
+
+    let replayed = broker.generate(history, None, None, None).await?;
+    Ok(ReplayedTurn { recorded, replayed })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::test_support::{text_response, MockGateway};
+    use std::sync::Arc;
+
+    // Answers every request the same way and remembers what it was sent
+    fn recording_gateway() -> Arc<MockGateway> {
+        Arc::new(MockGateway::new(vec![]).with_responder(|_| Ok(text_response("Bonjour !"))))
+    }
+
+    fn recording() -> Vec<LlmMessage> {
+        vec![
+            LlmMessage::system("You are a greeter."),
+            LlmMessage::user("Hi"),
+            LlmMessage::assistant("Hello!"),
+            LlmMessage::user("Now in French"),
+            LlmMessage::assistant("Salut."),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_replays_turn_with_history_before_it() {
+        let gateway = recording_gateway();
+        let broker = LlmBroker::new("candidate-model", gateway.clone(), None);
+
+        let turn = replay_turn(&recording(), 1, &broker).await.unwrap();
+
+        assert_eq!(turn.recorded.as_deref(), Some("Salut."));
+        assert_eq!(turn.replayed, "Bonjour !");
+        let sent = gateway.last_request().messages;
+        assert_eq!(sent.len(), 4);
+        assert_eq!(sent.last().unwrap().content.as_deref(), Some("Now in French"));
+    }
+
+    #[tokio::test]
+    async fn test_replaying_past_the_recording_uses_the_whole_history() {
+        let gateway = recording_gateway();
+        let broker = LlmBroker::new("candidate-model", gateway.clone(), None);
+
+        let next = replay_turn(&recording(), 2, &broker).await.unwrap();
+        let missing = replay_turn(&recording(), 3, &broker).await;
+
+        assert_eq!(next.recorded, None);
+        assert_eq!(gateway.last_request().messages.len(), 5);
+        assert!(matches!(missing, Err(MojenticError::InvalidArgument(_))));
+    }
+}
@@ -0,0 +1,129 @@
+//! Reranking of retrieval candidates.
+//!
+//! Embedding similarity is a cheap first pass for retrieval, but it often ranks
+//! near-misses above the passages that actually answer a query. A [`Reranker`]
+//! re-scores those candidates against the query (with a cross-encoder, an LLM, or
+//! any other scorer) so the most relevant ones come first.
+
+use crate::error::Result;
+use crate::llm::broker::LlmBroker;
+use crate::llm::models::LlmMessage;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+/// Re-scores retrieval candidates against a query
+#[async_trait]
+pub trait Reranker: Send + Sync {
+    /// Score each candidate against the query.
+    ///
+    /// Returns `(candidate index, score)` pairs ordered from most to least relevant.
+    /// Higher scores are more relevant.
+    async fn rerank(&self, query: &str, candidates: &[String]) -> Result<Vec<(usize, f32)>>;
+}
+
+/// Structured relevance judgement produced by [`LlmReranker`]
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct RelevanceScore {
+    /// How well the passage answers the query, from 0.0 (irrelevant) to 1.0 (fully relevant)
+    pub score: f32,
+}
+
+/// Reranker that asks an LLM to judge each candidate's relevance
+///
+/// Each candidate is scored with a separate [`LlmBroker::generate_object`] call into a
+/// [`RelevanceScore`].
+///
+/// # Examples
+///
+/// ```ignore
+/// use mojentic::llm::reranker::{LlmReranker, Reranker};
+///
+/// let reranker = LlmReranker::new(broker);
+/// let ranked = reranker.rerank("What is Rust?", &candidates).await?;
+/// let best = &candidates[ranked[0].0];
+/// ```
+pub struct LlmReranker {
+    broker: LlmBroker,
+}
+
+impl LlmReranker {
+    /// Create a new LLM-backed reranker
+    ///
+    /// # Arguments
+    ///
+    /// * `broker` - The LLM broker used to score candidates
+    pub fn new(broker: LlmBroker) -> Self {
+        Self { broker }
+    }
+
+    async fn score(&self, query: &str, candidate: &str) -> Result<f32> {
+        let messages = vec![
+            LlmMessage::system(
+                "You judge how relevant a passage is to a query. Respond with a score \
+                 between 0.0 (irrelevant) and 1.0 (fully answers the query).",
+            ),
+            LlmMessage::user(format!("Query: {}\n\nPassage: {}", query, candidate)),
+        ];
+
+        let relevance: RelevanceScore = self.broker.generate_object(&messages, None, None).await?;
+        Ok(relevance.score)
+    }
+}
+
+#[async_trait]
+impl Reranker for LlmReranker {
+    async fn rerank(&self, query: &str, candidates: &[String]) -> Result<Vec<(usize, f32)>> {
+        let mut scored = Vec::with_capacity(candidates.len());
+        for (index, candidate) in candidates.iter().enumerate() {
+            scored.push((index, self.score(query, candidate).await?));
+        }
+
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        Ok(scored)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::test_support::MockGateway;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    // Scores passages mentioning Rust as highly relevant, everything else as not
+    fn keyword_scoring_gateway() -> Arc<MockGateway> {
+        Arc::new(MockGateway::new(vec![]).with_json_responder(|messages| {
+            let prompt = messages.last().and_then(|m| m.content.clone()).unwrap_or_default();
+            let passage = prompt.split("Passage:").nth(1).unwrap_or_default();
+            let score = if passage.contains("Rust") { 0.9 } else { 0.1 };
+            Ok(json!({ "score": score }))
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_llm_reranker_reorders_candidates() {
+        let broker = LlmBroker::new("test-model", keyword_scoring_gateway(), None);
+        let reranker = LlmReranker::new(broker);
+        let candidates = vec![
+            "Python is a dynamically typed language.".to_string(),
+            "Rust is a systems programming language.".to_string(),
+        ];
+
+        let ranked = reranker.rerank("What is Rust?", &candidates).await.unwrap();
+
+        assert_eq!(ranked.len(), 2);
+        assert_eq!(ranked[0].0, 1);
+        assert_eq!(ranked[1].0, 0);
+        assert!(ranked[0].1 > ranked[1].1);
+    }
+
+    #[tokio::test]
+    async fn test_llm_reranker_with_no_candidates() {
+        let broker = LlmBroker::new("test-model", keyword_scoring_gateway(), None);
+        let reranker = LlmReranker::new(broker);
+
+        let ranked = reranker.rerank("What is Rust?", &[]).await.unwrap();
+
+        assert!(ranked.is_empty());
+    }
+}
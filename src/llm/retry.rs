@@ -0,0 +1,166 @@
+//! Retrying failed model requests.
+//!
+//! [`LlmBroker::with_retries`](super::LlmBroker::with_retries) retries a
+//! request that fails with a [transient](is_transient) error. Per-call retries
+//! compound across an agent run: ten calls retried three times each can make
+//! forty requests. A [`RetryBudget`] installed with
+//! [`LlmBroker::with_retry_budget`](super::LlmBroker::with_retry_budget) caps the
+//! retries of a whole run instead, counting them per correlation id. Brokers
+//! made with [`LlmBroker::child`](super::LlmBroker::child) share their parent's
+//! correlation id, so a sub-agent draws on the same budget. A call made without
+//! a correlation id is a run of its own, and its count is dropped when it ends.
+
+use crate::error::MojenticError;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+/// Wait before the first retry of an error that doesn't say how long to wait
+pub const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_millis(200);
+
+/// Whether an error is worth retrying: the provider was unreachable or busy
+///
+/// Network failures, rate limiting, and HTTP transport errors are transient.
+/// Errors about the request itself, such as bad credentials or an overlong
+/// prompt, fail the same way on every attempt.
+pub fn is_transient(error: &MojenticError) -> bool {
+    matches!(
+        error,
+        MojenticError::NetworkError(_)
+            | MojenticError::RateLimited { .. }
+            | MojenticError::HttpError(_)
+    )
+}
+
+/// How long to wait before retry number `attempt` (starting at 1)
+///
+/// Honours the provider's `retry_after` when rate limited, otherwise doubles
+/// [`DEFAULT_RETRY_BACKOFF`] with each attempt.
+pub(crate) fn retry_delay(error: &MojenticError, attempt: u32) -> Duration {
+    match error {
+        MojenticError::RateLimited { retry_after } => *retry_after,
+        _ => DEFAULT_RETRY_BACKOFF * 2u32.saturating_pow(attempt.saturating_sub(1)),
+    }
+}
+
+/// Retries shared by every request of an agent run
+///
+/// Counts retries per correlation id, so concurrent runs don't drain each
+/// other's budgets. Clones share the same counts. Counts for correlation ids
+/// the caller chose are kept until [`RetryBudget::reset`], so call it once a
+/// long-lived process is done with a run.
+#[derive(Debug, Clone)]
+pub struct RetryBudget {
+    max_retries: u32,
+    spent: Arc<Mutex<HashMap<String, u32>>>,
+}
+
+impl RetryBudget {
+    /// Allow at most `max_retries` retries per correlation id
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            spent: Arc::default(),
+        }
+    }
+
+    fn spent(&self) -> MutexGuard<'_, HashMap<String, u32>> {
+        self.spent.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Spend one retry for `correlation_id`, or `false` if none are left
+    pub fn try_spend(&self, correlation_id: &str) -> bool {
+        let mut spent = self.spent();
+        let used = spent.entry(correlation_id.to_string()).or_insert(0);
+        if *used >= self.max_retries {
+            return false;
+        }
+        *used += 1;
+        true
+    }
+
+    /// Retries still available to `correlation_id`
+    pub fn remaining(&self, correlation_id: &str) -> u32 {
+        let spent = self.spent();
+        self.max_retries - spent.get(correlation_id).copied().unwrap_or(0)
+    }
+
+    /// Forget the retries spent by `correlation_id`, e.g. once its run is over
+    pub fn reset(&self, correlation_id: &str) {
+        self.spent().remove(correlation_id);
+    }
+
+    /// How many correlation ids have retries counted against them
+    #[cfg(test)]
+    pub(crate) fn tracked_runs(&self) -> usize {
+        self.spent().len()
+    }
+}
+
+/// Resets a call's retry count when dropped
+///
+/// Held by broker calls whose correlation id was generated for them, since no
+/// later call can draw on that id's budget.
+pub(crate) struct BudgetRelease {
+    budget: RetryBudget,
+    correlation_id: String,
+}
+
+impl BudgetRelease {
+    pub(crate) fn new(budget: RetryBudget, correlation_id: String) -> Self {
+        Self {
+            budget,
+            correlation_id,
+        }
+    }
+}
+
+impl Drop for BudgetRelease {
+    fn drop(&mut self) {
+        self.budget.reset(&self.correlation_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_is_counted_per_correlation_id() {
+        let budget = RetryBudget::new(2);
+        let shared = budget.clone();
+
+        assert!(budget.try_spend("run-1"));
+        assert!(shared.try_spend("run-1"));
+        assert!(!budget.try_spend("run-1"));
+        assert_eq!(budget.remaining("run-2"), 2);
+
+        budget.reset("run-1");
+        assert_eq!(shared.remaining("run-1"), 2);
+    }
+
+    #[test]
+    fn test_only_provider_availability_errors_are_transient() {
+        assert!(is_transient(&MojenticError::NetworkError("refused".to_string())));
+        assert!(is_transient(&MojenticError::RateLimited {
+            retry_after: Duration::from_secs(1)
+        }));
+        assert!(!is_transient(&MojenticError::Unauthorized("bad key".to_string())));
+        assert!(!is_transient(&MojenticError::ContextLengthExceeded {
+            estimated: 10,
+            limit: 5
+        }));
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off_unless_told_when() {
+        let network = MojenticError::NetworkError("refused".to_string());
+        let limited = MojenticError::RateLimited {
+            retry_after: Duration::from_millis(30),
+        };
+
+        assert_eq!(retry_delay(&network, 1), DEFAULT_RETRY_BACKOFF);
+        assert_eq!(retry_delay(&network, 3), DEFAULT_RETRY_BACKOFF * 4);
+        assert_eq!(retry_delay(&limited, 3), Duration::from_millis(30));
+    }
+}
@@ -0,0 +1,206 @@
+//! Post-processing for generated JSON schemas.
+//!
+//! [`LlmBroker::generate_object`](super::LlmBroker::generate_object) derives a
+//! schema from the target type with `schemars`. Some providers only accept a
+//! subset of JSON Schema, so the broker can run a [`SchemaTransformer`] over
+//! the schema before sending it (see
+//! [`LlmBroker::with_schema_transformer`](super::LlmBroker::with_schema_transformer)).
+
+use serde_json::{Map, Value};
+use std::sync::Arc;
+
+/// Rewrites a JSON schema before it is sent to a provider
+pub type SchemaTransformer = Arc<dyn Fn(Value) -> Value + Send + Sync>;
+
+/// Keywords OpenAI's strict structured-output mode rejects
+const OPENAI_UNSUPPORTED_KEYWORDS: &[&str] = &[
+    "$schema",
+    "default",
+    "format",
+    "minimum",
+    "maximum",
+    "exclusiveMinimum",
+    "exclusiveMaximum",
+    "multipleOf",
+    "minLength",
+    "maxLength",
+    "pattern",
+    "minItems",
+    "maxItems",
+    "uniqueItems",
+    "minProperties",
+    "maxProperties",
+    "patternProperties",
+];
+
+/// Make a schema acceptable to OpenAI's strict structured-output mode.
+///
+/// Every object schema, including nested ones and those under `$defs`, gets
+/// `additionalProperties: false` and lists all of its properties as required;
+/// `Option` fields stay optional because `schemars` already marks them
+/// nullable. Validation keywords strict mode rejects, such as `format` and
+/// `minimum`, are removed. Map-like objects whose `additionalProperties` is a
+/// schema are left alone, since strict mode can't express them anyway.
+///
+/// # Examples
+///
+/// ```
+/// use mojentic::llm::schema_transform::openai_strict;
+/// use serde_json::json;
+///
+/// let schema = json!({
+///     "type": "object",
+///     "properties": {"count": {"type": "integer", "format": "uint32", "minimum": 0}}
+/// });
+///
+/// assert_eq!(
+///     openai_strict(schema),
+///     json!({
+///         "type": "object",
+///         "properties": {"count": {"type": "integer"}},
+///         "required": ["count"],
+///         "additionalProperties": false
+///     })
+/// );
+/// ```
+pub fn openai_strict(mut schema: Value) -> Value {
+    make_strict(&mut schema);
+    schema
+}
+
+fn make_strict(node: &mut Value) {
+    match node {
+        Value::Object(schema) => {
+            for keyword in OPENAI_UNSUPPORTED_KEYWORDS {
+                schema.remove(*keyword);
+            }
+
+            if is_object_schema(schema) {
+                close_object(schema);
+            }
+
+            for (keyword, child) in schema.iter_mut() {
+                match keyword.as_str() {
+                    "properties" | "$defs" | "definitions" => {
+                        if let Value::Object(children) = child {
+                            children.values_mut().for_each(make_strict);
+                        }
+                    }
+                    "items" | "additionalProperties" | "not" => make_strict(child),
+                    "anyOf" | "oneOf" | "allOf" | "prefixItems" => {
+                        if let Value::Array(variants) = child {
+                            variants.iter_mut().for_each(make_strict);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(make_strict),
+        _ => {}
+    }
+}
+
+fn is_object_schema(schema: &Map<String, Value>) -> bool {
+    let typed_object = match schema.get("type") {
+        Some(Value::String(t)) => t == "object",
+        Some(Value::Array(types)) => types.iter().any(|t| t == "object"),
+        _ => false,
+    };
+    typed_object || schema.contains_key("properties")
+}
+
+fn close_object(schema: &mut Map<String, Value>) {
+    if matches!(schema.get("additionalProperties"), Some(Value::Object(_))) {
+        return;
+    }
+    schema.insert("additionalProperties".to_string(), Value::Bool(false));
+
+    let names: Vec<Value> = schema
+        .get("properties")
+        .and_then(Value::as_object)
+        .map(|properties| properties.keys().cloned().map(Value::String).collect())
+        .unwrap_or_default();
+    schema.insert("required".to_string(), Value::Array(names));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schemars::JsonSchema;
+    use serde_json::json;
+
+    #[allow(dead_code)]
+    #[derive(JsonSchema)]
+    struct Address {
+        street: String,
+        unit: Option<String>,
+    }
+
+    #[allow(dead_code)]
+    #[derive(JsonSchema)]
+    struct Person {
+        name: String,
+        age: u32,
+        home: Address,
+        previous: Vec<Address>,
+    }
+
+    #[test]
+    fn test_openai_strict_closes_nested_objects() {
+        let schema = serde_json::to_value(schemars::schema_for!(Person)).unwrap();
+
+        let strict = openai_strict(schema);
+
+        assert_eq!(strict["additionalProperties"], json!(false));
+        assert_eq!(strict["required"], json!(["age", "home", "name", "previous"]));
+        let address = &strict["$defs"]["Address"];
+        assert_eq!(address["additionalProperties"], json!(false));
+        assert_eq!(address["required"], json!(["street", "unit"]));
+        assert_eq!(address["properties"]["unit"]["type"], json!(["string", "null"]));
+    }
+
+    #[test]
+    fn test_openai_strict_removes_unsupported_keywords() {
+        let schema = serde_json::to_value(schemars::schema_for!(Person)).unwrap();
+
+        let strict = openai_strict(schema);
+
+        assert!(strict.get("$schema").is_none());
+        assert_eq!(strict["properties"]["age"], json!({"type": "integer"}));
+    }
+
+    #[test]
+    fn test_openai_strict_handles_inline_nested_objects() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "outer": {
+                    "type": "object",
+                    "properties": {
+                        "inner": {
+                            "type": "array",
+                            "items": {"type": "object", "properties": {"x": {"type": "number"}}}
+                        }
+                    }
+                }
+            }
+        });
+
+        let strict = openai_strict(schema);
+
+        let outer = &strict["properties"]["outer"];
+        assert_eq!(outer["additionalProperties"], json!(false));
+        assert_eq!(outer["properties"]["inner"]["items"]["additionalProperties"], json!(false));
+        assert_eq!(outer["properties"]["inner"]["items"]["required"], json!(["x"]));
+    }
+
+    #[test]
+    fn test_openai_strict_leaves_maps_alone() {
+        let schema = json!({"type": "object", "additionalProperties": {"type": "string"}});
+
+        let strict = openai_strict(schema.clone());
+
+        assert_eq!(strict, schema);
+    }
+}
@@ -0,0 +1,332 @@
+//! Lightweight JSON schema validation.
+//!
+//! Covers the subset of JSON Schema that tool descriptors and `schemars`-generated
+//! schemas use in practice: `type`, `properties`, `required`,
+//! `additionalProperties: false`, `items`, `enum`, `const`, `anyOf`/`oneOf`/`allOf`,
+//! and local `$ref`s into `$defs` or `definitions`. Keywords outside that subset
+//! are ignored, so validation errs on the side of accepting a value.
+//!
+//! [`validate_partial_against_schema`] checks a document that is still being
+//! streamed, reporting only violations that no continuation could fix.
+
+use serde_json::Value;
+
+/// Validate a value against a JSON schema.
+///
+/// Returns every violation found, each prefixed with the JSON path of the
+/// offending value (`$` is the root).
+///
+/// # Examples
+///
+/// ```
+/// use mojentic::llm::schema_validation::validate_against_schema;
+/// use serde_json::json;
+///
+/// let schema = json!({
+///     "type": "object",
+///     "properties": {"count": {"type": "integer"}},
+///     "required": ["count"]
+/// });
+///
+/// assert!(validate_against_schema(&json!({"count": 3}), &schema).is_ok());
+///
+/// let errors = validate_against_schema(&json!({"count": "three"}), &schema).unwrap_err();
+/// assert_eq!(errors, vec!["$.count: expected integer, found string"]);
+/// ```
+pub fn validate_against_schema(value: &Value, schema: &Value) -> Result<(), Vec<String>> {
+    collect_violations(value, schema, false)
+}
+
+/// Validate the value parsed so far from an incomplete JSON document.
+///
+/// `value` is expected to come from
+/// [`parse_partial_json`](super::partial_json::parse_partial_json), so missing
+/// required properties are allowed, a string only has to be the start of an
+/// allowed `enum` or `const` string, and other `enum` and `const` checks wait
+/// for the finished value. Anything still reported, such as a property that
+/// `additionalProperties: false` forbids or a value of the wrong type, can't be
+/// fixed by the rest of the document.
+///
+/// # Examples
+///
+/// ```
+/// use mojentic::llm::schema_validation::validate_partial_against_schema;
+/// use serde_json::json;
+///
+/// let schema = json!({
+///     "type": "object",
+///     "properties": {"status": {"enum": ["open", "closed"]}},
+///     "required": ["status"],
+///     "additionalProperties": false
+/// });
+///
+/// assert!(validate_partial_against_schema(&json!({"status": "clo"}), &schema).is_ok());
+///
+/// let errors = validate_partial_against_schema(&json!({"state": ""}), &schema).unwrap_err();
+/// assert_eq!(errors, vec!["$.state: unexpected property"]);
+/// ```
+pub fn validate_partial_against_schema(value: &Value, schema: &Value) -> Result<(), Vec<String>> {
+    collect_violations(value, schema, true)
+}
+
+fn collect_violations(value: &Value, schema: &Value, partial: bool) -> Result<(), Vec<String>> {
+    let mut errors = Vec::new();
+    validate_node(value, schema, schema, "$", partial, &mut errors);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn validate_node(
+    value: &Value,
+    schema: &Value,
+    root: &Value,
+    path: &str,
+    partial: bool,
+    errors: &mut Vec<String>,
+) {
+    let Some(schema) = schema.as_object() else {
+        // `true`, `{}`-like or malformed schemas accept anything
+        return;
+    };
+
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        match resolve_ref(root, reference) {
+            Some(target) => validate_node(value, target, root, path, partial, errors),
+            None => errors.push(format!("{}: unresolvable $ref {}", path, reference)),
+        }
+        return;
+    }
+
+    if let Some(expected) = schema.get("type") {
+        let allowed: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+            _ => vec![],
+        };
+        if !allowed.is_empty() && !allowed.iter().any(|t| matches_type(value, t)) {
+            errors.push(format!(
+                "{}: expected {}, found {}",
+                path,
+                allowed.join(" or "),
+                type_name(value)
+            ));
+            return;
+        }
+    }
+
+    if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+        if !options.iter().any(|option| could_become(value, option, partial)) {
+            errors.push(format!("{}: {} is not one of the allowed values", path, value));
+        }
+    }
+
+    if let Some(expected) = schema.get("const") {
+        if !could_become(value, expected, partial) {
+            errors.push(format!("{}: expected constant {}", path, expected));
+        }
+    }
+
+    for keyword in ["anyOf", "oneOf"] {
+        if let Some(variants) = schema.get(keyword).and_then(Value::as_array) {
+            let matches_any = variants.iter().any(|variant| {
+                let mut variant_errors = Vec::new();
+                validate_node(value, variant, root, path, partial, &mut variant_errors);
+                variant_errors.is_empty()
+            });
+            if !matches_any {
+                errors.push(format!("{}: does not match any allowed schema", path));
+            }
+        }
+    }
+
+    if let Some(variants) = schema.get("allOf").and_then(Value::as_array) {
+        for variant in variants {
+            validate_node(value, variant, root, path, partial, errors);
+        }
+    }
+
+    if let Value::Object(map) = value {
+        let properties = schema.get("properties").and_then(Value::as_object);
+
+        let required = schema.get("required").and_then(Value::as_array).filter(|_| !partial);
+        if let Some(required) = required {
+            for name in required.iter().filter_map(Value::as_str) {
+                if !map.contains_key(name) {
+                    errors.push(format!("{}: missing required property {:?}", path, name));
+                }
+            }
+        }
+
+        for (name, property_value) in map {
+            let property_path = format!("{}.{}", path, name);
+            match properties.and_then(|p| p.get(name)) {
+                Some(property_schema) => validate_node(
+                    property_value,
+                    property_schema,
+                    root,
+                    &property_path,
+                    partial,
+                    errors,
+                ),
+                None => match schema.get("additionalProperties") {
+                    Some(Value::Bool(false)) => {
+                        errors.push(format!("{}: unexpected property", property_path))
+                    }
+                    Some(extra_schema @ Value::Object(_)) => validate_node(
+                        property_value,
+                        extra_schema,
+                        root,
+                        &property_path,
+                        partial,
+                        errors,
+                    ),
+                    _ => {}
+                },
+            }
+        }
+    }
+
+    if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (index, item) in items.iter().enumerate() {
+            let item_path = format!("{}[{}]", path, index);
+            validate_node(item, item_schema, root, &item_path, partial, errors);
+        }
+    }
+}
+
+/// Whether `value` is, or while `partial` could still grow into, `expected`
+fn could_become(value: &Value, expected: &Value, partial: bool) -> bool {
+    if !partial {
+        return value == expected;
+    }
+    match (value, expected) {
+        (Value::String(prefix), Value::String(allowed)) => allowed.starts_with(prefix.as_str()),
+        (Value::String(_), _) => false,
+        _ => true,
+    }
+}
+
+fn resolve_ref<'a>(root: &'a Value, reference: &str) -> Option<&'a Value> {
+    let pointer = reference.strip_prefix('#')?;
+    root.pointer(pointer)
+}
+
+fn matches_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        "number" => value.is_number(),
+        "integer" => value.as_i64().is_some() || value.as_u64().is_some(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_valid_object_passes() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string"},
+                "tags": {"type": "array", "items": {"type": "string"}}
+            },
+            "required": ["name"]
+        });
+
+        let result = validate_against_schema(&json!({"name": "a", "tags": ["x", "y"]}), &schema);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_reports_all_violations_with_paths() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string"},
+                "tags": {"type": "array", "items": {"type": "string"}}
+            },
+            "required": ["name", "id"],
+            "additionalProperties": false
+        });
+
+        let errors =
+            validate_against_schema(&json!({"name": 1, "tags": ["x", 2], "extra": true}), &schema)
+                .unwrap_err();
+
+        assert!(errors.contains(&r#"$: missing required property "id""#.to_string()));
+        assert!(errors.contains(&"$.name: expected string, found integer".to_string()));
+        assert!(errors.contains(&"$.tags[1]: expected string, found integer".to_string()));
+        assert!(errors.contains(&"$.extra: unexpected property".to_string()));
+    }
+
+    #[test]
+    fn test_resolves_local_refs_and_nullable_types() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "status": {"$ref": "#/$defs/Status"},
+                "note": {"type": ["string", "null"]}
+            },
+            "$defs": {
+                "Status": {"type": "string", "enum": ["open", "closed"]}
+            }
+        });
+
+        assert!(validate_against_schema(&json!({"status": "open", "note": null}), &schema).is_ok());
+        assert!(validate_against_schema(&json!({"status": "pending"}), &schema).is_err());
+    }
+
+    #[test]
+    fn test_any_of_accepts_matching_variant() {
+        let schema = json!({"anyOf": [{"type": "integer"}, {"type": "string"}]});
+
+        assert!(validate_against_schema(&json!(3), &schema).is_ok());
+        assert!(validate_against_schema(&json!("three"), &schema).is_ok());
+        assert!(validate_against_schema(&json!(true), &schema).is_err());
+    }
+
+    #[test]
+    fn test_partial_values_allow_what_the_rest_of_the_document_could_fix() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "status": {"type": "string", "enum": ["open", "closed"]},
+                "count": {"type": "integer"}
+            },
+            "required": ["status", "count"],
+            "additionalProperties": false
+        });
+
+        assert!(validate_partial_against_schema(&json!({}), &schema).is_ok());
+        assert!(validate_partial_against_schema(&json!({"status": "cl"}), &schema).is_ok());
+        assert!(validate_partial_against_schema(&json!({"status": "done"}), &schema).is_err());
+        assert!(validate_partial_against_schema(&json!({"count": "3"}), &schema).is_err());
+        assert_eq!(
+            validate_partial_against_schema(&json!({"status": "open", "extra": 1}), &schema),
+            Err(vec!["$.extra: unexpected property".to_string()])
+        );
+    }
+}
@@ -0,0 +1,80 @@
+//! Combining concurrent streamed generations.
+//!
+//! A dashboard showing several agents at once needs their output in one
+//! place. [`merge_streams`] interleaves any number of streams, such as those
+//! from [`LlmBroker::generate_stream`](super::LlmBroker::generate_stream),
+//! tagging each item with the id of the stream it came from.
+
+use futures::stream::{self, Stream, StreamExt};
+use std::pin::Pin;
+
+/// Merge streams into one, tagging each item with its stream's id.
+///
+/// Items are yielded as soon as any stream produces one, so a slow stream
+/// doesn't hold up the others, and each stream's items keep their order. The
+/// merged stream ends when every stream has ended. Dropping it drops the
+/// streams, cancelling any requests still in flight.
+///
+/// # Examples
+///
+/// ```
+/// use futures::stream::{self, StreamExt};
+/// use mojentic::llm::stream_merge::merge_streams;
+///
+/// # tokio_test::block_on(async {
+/// let merged: Vec<(&str, &str)> = merge_streams(vec![
+///     ("planner", stream::iter(vec!["Step 1"])),
+///     ("critic", stream::iter(vec!["Looks good"])),
+/// ])
+/// .collect()
+/// .await;
+///
+/// assert!(merged.contains(&("planner", "Step 1")));
+/// assert!(merged.contains(&("critic", "Looks good")));
+/// # });
+/// ```
+pub fn merge_streams<'a, Id, S>(
+    streams: Vec<(Id, S)>,
+) -> Pin<Box<dyn Stream<Item = (Id, S::Item)> + 'a>>
+where
+    Id: Clone + 'a,
+    S: Stream + 'a,
+{
+    Box::pin(stream::select_all(
+        streams
+            .into_iter()
+            .map(|(id, stream)| Box::pin(stream.map(move |item| (id.clone(), item)))),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{MojenticError, Result};
+
+    #[tokio::test]
+    async fn test_merges_streams_with_attribution() {
+        let planner = stream::iter(vec![Ok("Step 1. ".to_string()), Ok("Step 2.".to_string())]);
+        let critic = stream::iter(vec![
+            Ok("Looks ".to_string()),
+            Err(MojenticError::TimeoutError("slow".to_string())),
+        ]);
+
+        let merged: Vec<(&str, Result<String>)> =
+            merge_streams(vec![("planner", planner), ("critic", critic)]).collect().await;
+
+        let chunks = |id: &str| -> Vec<String> {
+            merged
+                .iter()
+                .filter(|(source, _)| *source == id)
+                .map(|(_, item)| match item {
+                    Ok(text) => text.clone(),
+                    Err(e) => format!("error: {}", e),
+                })
+                .collect()
+        };
+        assert_eq!(merged.len(), 4);
+        assert_eq!(chunks("planner"), vec!["Step 1. ", "Step 2."]);
+        assert_eq!(chunks("critic"), vec!["Looks ", "error: Timeout error: slow"]);
+    }
+}
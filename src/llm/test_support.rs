@@ -0,0 +1,346 @@
+//! Test doubles shared by the `llm` unit tests
+
+use crate::error::{MojenticError, Result};
+use crate::llm::gateway::{CompletionConfig, LlmGateway, ResponseFormat, StreamChunk};
+use crate::llm::models::{LlmGatewayResponse, LlmMessage, LlmToolCall};
+use crate::llm::tools::{FunctionDescriptor, LlmTool, ToolDescriptor, ToolRunCtx};
+use async_trait::async_trait;
+use futures::stream::{self, Stream, StreamExt};
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+type Responder = Box<dyn Fn(&[LlmMessage]) -> Result<LlmGatewayResponse> + Send + Sync>;
+type JsonResponder = Box<dyn Fn(&[LlmMessage]) -> Result<Value> + Send + Sync>;
+
+/// Which gateway method a recorded request went through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RequestKind {
+    Complete,
+    Json,
+    Stream,
+}
+
+/// A request the mock gateway received
+#[derive(Debug, Clone)]
+pub(crate) struct Request {
+    pub kind: RequestKind,
+    pub model: String,
+    pub messages: Vec<LlmMessage>,
+    pub tool_names: Vec<String>,
+    pub config: CompletionConfig,
+    /// The JSON schema sent with `complete_json`, or the response format schema otherwise
+    pub schema: Option<Value>,
+}
+
+/// A configurable gateway that answers from a script and records every request
+///
+/// Completions pop the scripted results in order, then fall back to the
+/// responder, then to `"default response"`. Streams pop the scripted streams
+/// in order, then fall back to the endless chunk, then to a single `"test"`
+/// chunk. Every streamed chunk pulled is counted.
+pub(crate) struct MockGateway {
+    results: Mutex<VecDeque<Result<LlmGatewayResponse>>>,
+    responder: Option<Responder>,
+    streams: Mutex<VecDeque<Vec<Result<StreamChunk>>>>,
+    endless_chunk: Option<String>,
+    json_response: Value,
+    json_responder: Option<JsonResponder>,
+    requests: Mutex<Vec<Request>>,
+    produced: AtomicUsize,
+    context_lookups: AtomicUsize,
+    context_length: Option<usize>,
+    vision: bool,
+    prefill: bool,
+    embeddings: bool,
+}
+
+impl MockGateway {
+    pub(crate) fn new(responses: Vec<LlmGatewayResponse>) -> Self {
+        Self::scripted(responses.into_iter().map(Ok).collect())
+    }
+
+    /// Answers completions with the given results, which may include errors
+    pub(crate) fn scripted(results: Vec<Result<LlmGatewayResponse>>) -> Self {
+        Self {
+            results: Mutex::new(results.into()),
+            responder: None,
+            streams: Mutex::new(VecDeque::new()),
+            endless_chunk: None,
+            json_response: serde_json::json!({"test": "value"}),
+            json_responder: None,
+            requests: Mutex::new(Vec::new()),
+            produced: AtomicUsize::new(0),
+            context_lookups: AtomicUsize::new(0),
+            context_length: None,
+            vision: true,
+            prefill: false,
+            embeddings: true,
+        }
+    }
+
+    /// Answers completions the script doesn't cover from the messages sent
+    pub(crate) fn with_responder(
+        mut self,
+        responder: impl Fn(&[LlmMessage]) -> Result<LlmGatewayResponse> + Send + Sync + 'static,
+    ) -> Self {
+        self.responder = Some(Box::new(responder));
+        self
+    }
+
+    /// Adds one scripted stream, used by the next unscripted stream request
+    pub(crate) fn with_stream(mut self, chunks: Vec<StreamChunk>) -> Self {
+        self.streams.get_mut().unwrap().push_back(chunks.into_iter().map(Ok).collect());
+        self
+    }
+
+    /// Adds one scripted stream of content chunks
+    pub(crate) fn with_stream_text(self, chunks: &[&str]) -> Self {
+        self.with_stream(
+            chunks.iter().map(|chunk| StreamChunk::Content(chunk.to_string())).collect(),
+        )
+    }
+
+    /// Streams the given chunk forever once the scripted streams run out
+    pub(crate) fn with_endless_stream(mut self, chunk: &str) -> Self {
+        self.endless_chunk = Some(chunk.to_string());
+        self
+    }
+
+    pub(crate) fn with_json_response(mut self, json_response: Value) -> Self {
+        self.json_response = json_response;
+        self
+    }
+
+    pub(crate) fn with_json_responder(
+        mut self,
+        responder: impl Fn(&[LlmMessage]) -> Result<Value> + Send + Sync + 'static,
+    ) -> Self {
+        self.json_responder = Some(Box::new(responder));
+        self
+    }
+
+    pub(crate) fn with_context_length(mut self, tokens: usize) -> Self {
+        self.context_length = Some(tokens);
+        self
+    }
+
+    pub(crate) fn without_vision(mut self) -> Self {
+        self.vision = false;
+        self
+    }
+
+    pub(crate) fn with_prefill_support(mut self) -> Self {
+        self.prefill = true;
+        self
+    }
+
+    pub(crate) fn without_embeddings(mut self) -> Self {
+        self.embeddings = false;
+        self
+    }
+
+    /// Every request received so far, in order
+    pub(crate) fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    pub(crate) fn last_request(&self) -> Request {
+        self.requests.lock().unwrap().last().cloned().expect("no request was sent")
+    }
+
+    /// How many `complete` calls were made
+    pub(crate) fn calls(&self) -> usize {
+        self.requests().iter().filter(|r| r.kind == RequestKind::Complete).count()
+    }
+
+    /// The model of every request, in order
+    pub(crate) fn models(&self) -> Vec<String> {
+        self.requests().into_iter().map(|r| r.model).collect()
+    }
+
+    /// How many stream chunks have been pulled
+    pub(crate) fn chunks_produced(&self) -> usize {
+        self.produced.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn context_lookups(&self) -> usize {
+        self.context_lookups.load(Ordering::SeqCst)
+    }
+
+    fn record(
+        &self,
+        kind: RequestKind,
+        model: &str,
+        messages: &[LlmMessage],
+        tools: Option<&[Box<dyn LlmTool>]>,
+        config: &CompletionConfig,
+        schema: Option<Value>,
+    ) {
+        self.requests.lock().unwrap().push(Request {
+            kind,
+            model: model.to_string(),
+            messages: messages.to_vec(),
+            tool_names: tools
+                .unwrap_or_default()
+                .iter()
+                .map(|tool| tool.descriptor().function.name)
+                .collect(),
+            config: config.clone(),
+            schema,
+        });
+    }
+}
+
+#[async_trait]
+impl LlmGateway for MockGateway {
+    async fn complete(
+        &self,
+        model: &str,
+        messages: &[LlmMessage],
+        tools: Option<&[Box<dyn LlmTool>]>,
+        config: &CompletionConfig,
+    ) -> Result<LlmGatewayResponse> {
+        let schema = match &config.response_format {
+            Some(ResponseFormat::JsonObject { schema }) => schema.clone(),
+            _ => None,
+        };
+        self.record(RequestKind::Complete, model, messages, tools, config, schema);
+        if let Some(result) = self.results.lock().unwrap().pop_front() {
+            return result;
+        }
+        match &self.responder {
+            Some(responder) => responder(messages),
+            None => Ok(text_response("default response")),
+        }
+    }
+
+    async fn complete_json(
+        &self,
+        model: &str,
+        messages: &[LlmMessage],
+        schema: Value,
+        config: &CompletionConfig,
+    ) -> Result<Value> {
+        self.record(RequestKind::Json, model, messages, None, config, Some(schema));
+        match &self.json_responder {
+            Some(responder) => responder(messages),
+            None => Ok(self.json_response.clone()),
+        }
+    }
+
+    async fn get_available_models(&self) -> Result<Vec<String>> {
+        Ok(vec!["test-model".to_string()])
+    }
+
+    async fn calculate_embeddings(&self, _text: &str, _model: Option<&str>) -> Result<Vec<f32>> {
+        if !self.embeddings {
+            return Err(MojenticError::Unsupported("embeddings are not scripted".to_string()));
+        }
+        Ok(vec![0.1, 0.2, 0.3])
+    }
+
+    async fn supports_vision(&self, _model: &str) -> bool {
+        self.vision
+    }
+
+    fn supports_assistant_prefill(&self, _model: &str) -> bool {
+        self.prefill
+    }
+
+    async fn context_length(&self, _model: &str) -> Option<usize> {
+        self.context_lookups.fetch_add(1, Ordering::SeqCst);
+        self.context_length
+    }
+
+    fn complete_stream<'a>(
+        &'a self,
+        model: &'a str,
+        messages: &'a [LlmMessage],
+        tools: Option<&'a [Box<dyn LlmTool>]>,
+        config: &'a CompletionConfig,
+    ) -> Pin<Box<dyn Stream<Item = Result<StreamChunk>> + Send + 'a>> {
+        self.record(RequestKind::Stream, model, messages, tools, config, None);
+        let scripted = self.streams.lock().unwrap().pop_front();
+        let chunks: Pin<Box<dyn Stream<Item = Result<StreamChunk>> + Send + 'a>> =
+            match (scripted, &self.endless_chunk) {
+                (Some(chunks), _) => Box::pin(stream::iter(chunks)),
+                (None, Some(chunk)) => {
+                    Box::pin(stream::repeat_with(move || Ok(StreamChunk::Content(chunk.clone()))))
+                }
+                (None, None) => {
+                    Box::pin(stream::iter(vec![Ok(StreamChunk::Content("test".to_string()))]))
+                }
+            };
+        Box::pin(chunks.inspect(move |_| {
+            self.produced.fetch_add(1, Ordering::SeqCst);
+        }))
+    }
+}
+
+/// A plain text response with nothing else set
+pub(crate) fn text_response(content: &str) -> LlmGatewayResponse {
+    LlmGatewayResponse::builder().content(content).build()
+}
+
+/// A response that only requests the given tool calls
+pub(crate) fn tool_call_response(tool_calls: Vec<LlmToolCall>) -> LlmGatewayResponse {
+    LlmGatewayResponse::builder().tool_calls(tool_calls).build()
+}
+
+/// A tool call without arguments
+pub(crate) fn tool_call(id: &str, name: &str) -> LlmToolCall {
+    LlmToolCall {
+        id: Some(id.to_string()),
+        name: name.to_string(),
+        arguments: HashMap::new(),
+    }
+}
+
+/// A tool that always returns the same result
+pub(crate) struct MockTool {
+    pub name: String,
+    pub result: Value,
+}
+
+impl MockTool {
+    pub(crate) fn new(name: &str, result: Value) -> Self {
+        Self {
+            name: name.to_string(),
+            result,
+        }
+    }
+}
+
+#[async_trait]
+impl LlmTool for MockTool {
+    async fn run(&self, _args: &HashMap<String, Value>, _ctx: &ToolRunCtx) -> Result<Value> {
+        Ok(self.result.clone())
+    }
+
+    fn descriptor(&self) -> ToolDescriptor {
+        ToolDescriptor {
+            r#type: "function".to_string(),
+            function: FunctionDescriptor {
+                name: self.name.clone(),
+                description: "A mock tool".to_string(),
+                parameters: serde_json::json!({}),
+                returns: None,
+            },
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn LlmTool> {
+        Box::new(MockTool::new(&self.name, self.result.clone()))
+    }
+}
+
+/// Mock tools with the given names, each returning `"ok"`
+pub(crate) fn mock_tools(names: &[&str]) -> Vec<Box<dyn LlmTool>> {
+    names
+        .iter()
+        .map(|name| Box::new(MockTool::new(name, serde_json::json!("ok"))) as Box<dyn LlmTool>)
+        .collect()
+}
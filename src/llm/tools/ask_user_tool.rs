@@ -89,6 +89,7 @@ impl LlmTool for AskUserTool {
                     },
                     "required": ["user_request"]
                 }),
+                returns: None,
             },
         }
     }
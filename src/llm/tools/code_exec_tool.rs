@@ -0,0 +1,435 @@
+use crate::error::{MojenticError, Result};
+use crate::llm::tools::{FunctionDescriptor, LlmTool, ToolDescriptor, ToolRunCtx};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::{Child, Command};
+
+/// Longest a snippet may run unless configured otherwise
+pub const DEFAULT_CODE_EXEC_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Address-space limit applied to the interpreter unless configured otherwise
+pub const DEFAULT_CODE_EXEC_MEMORY_MB: u64 = 512;
+
+/// Characters of stdout or stderr returned unless configured otherwise
+pub const DEFAULT_CODE_EXEC_MAX_OUTPUT_CHARS: usize = 10_000;
+
+/// Tool that runs code snippets through an interpreter inside a sandbox directory
+///
+/// Each snippet is written to a temporary file in the sandbox directory, run
+/// with the configured interpreter from that directory, and deleted afterwards.
+/// The run is bounded by a wall-clock timeout, and on Unix by address-space and
+/// CPU-time limits set with `ulimit`. The interpreter starts with an empty
+/// environment apart from `PATH`, and captured output is truncated to a
+/// maximum length; a snippet that prints far past that length is killed.
+///
+/// These bounds contain runaway snippets; they are not isolation. The snippet
+/// runs as the current user and can reach anything that user can, so only
+/// offer this tool to agents you would trust with a local interpreter.
+///
+/// # Examples
+///
+/// ```ignore
+/// use mojentic::llm::tools::code_exec_tool::CodeExecTool;
+///
+/// let tool = CodeExecTool::python("/tmp/agent-sandbox")?
+///     .with_timeout(Duration::from_secs(5));
+///
+/// let mut args = HashMap::new();
+/// args.insert("code".to_string(), json!("print(sum(range(10)))"));
+///
+/// let result = tool.run(&args, &ToolRunCtx::default()).await?;
+/// // result contains exit_code, stdout, stderr and timed_out
+/// ```
+#[derive(Debug, Clone)]
+pub struct CodeExecTool {
+    sandbox_dir: PathBuf,
+    interpreter: String,
+    file_extension: String,
+    timeout: Duration,
+    memory_limit_mb: u64,
+    max_output_chars: usize,
+}
+
+impl CodeExecTool {
+    /// Creates a CodeExecTool that runs snippets with `interpreter` in `sandbox_dir`
+    pub fn new<P: AsRef<Path>>(sandbox_dir: P, interpreter: impl Into<String>) -> Result<Self> {
+        let sandbox_dir = sandbox_dir.as_ref();
+
+        if !sandbox_dir.is_dir() {
+            return Err(MojenticError::ToolError(format!(
+                "Sandbox directory {:?} is not a directory",
+                sandbox_dir
+            )));
+        }
+
+        Ok(Self {
+            sandbox_dir: sandbox_dir.canonicalize()?,
+            interpreter: interpreter.into(),
+            file_extension: "txt".to_string(),
+            timeout: DEFAULT_CODE_EXEC_TIMEOUT,
+            memory_limit_mb: DEFAULT_CODE_EXEC_MEMORY_MB,
+            max_output_chars: DEFAULT_CODE_EXEC_MAX_OUTPUT_CHARS,
+        })
+    }
+
+    /// Creates a CodeExecTool that runs Python snippets with `python3`
+    pub fn python<P: AsRef<Path>>(sandbox_dir: P) -> Result<Self> {
+        Ok(Self::new(sandbox_dir, "python3")?.with_file_extension("py"))
+    }
+
+    /// Set the extension given to snippet files, for interpreters that care
+    pub fn with_file_extension(mut self, extension: impl Into<String>) -> Self {
+        self.file_extension = extension.into().trim_start_matches('.').to_string();
+        self
+    }
+
+    /// Set how long a snippet may run before it is killed
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the interpreter's address-space limit in megabytes (Unix only)
+    pub fn with_memory_limit_mb(mut self, memory_limit_mb: u64) -> Self {
+        self.memory_limit_mb = memory_limit_mb;
+        self
+    }
+
+    /// Set how many characters of stdout and stderr are returned
+    pub fn with_max_output_chars(mut self, max_output_chars: usize) -> Self {
+        self.max_output_chars = max_output_chars;
+        self
+    }
+
+    /// Build the command that runs `script` under the configured limits
+    fn command(&self, script: &Path) -> Command {
+        #[cfg(unix)]
+        let mut command = {
+            // Limits are passed as arguments so nothing from the snippet reaches the shell
+            let cpu_seconds = self.timeout.as_secs().max(1);
+            let mut command = Command::new("/bin/sh");
+            command
+                .arg("-c")
+                .arg(r#"ulimit -v "$1" && ulimit -t "$2" && shift 2 && exec "$@""#)
+                .arg("sh")
+                .arg((self.memory_limit_mb * 1024).to_string())
+                .arg(cpu_seconds.to_string())
+                .arg(&self.interpreter)
+                .arg(script);
+            command
+        };
+
+        #[cfg(not(unix))]
+        let mut command = {
+            let mut command = Command::new(&self.interpreter);
+            command.arg(script);
+            command
+        };
+
+        command.env_clear().current_dir(&self.sandbox_dir);
+        if let Some(path) = std::env::var_os("PATH") {
+            command.env("PATH", path);
+        }
+        command
+    }
+
+    /// Truncate captured output to the configured length
+    fn bounded_output(&self, bytes: &[u8]) -> String {
+        let text = String::from_utf8_lossy(bytes);
+        match text.char_indices().nth(self.max_output_chars) {
+            Some((cut, _)) => format!("{}\n[output truncated]", &text[..cut]),
+            None => text.into_owned(),
+        }
+    }
+
+    /// Bytes captured from each pipe before the interpreter is killed
+    ///
+    /// A character takes at most four bytes, so anything past this would be
+    /// truncated by `bounded_output` anyway.
+    fn capture_limit(&self) -> usize {
+        self.max_output_chars.saturating_mul(4)
+    }
+}
+
+/// Read stdout and stderr until both close or either holds more than `limit` bytes
+///
+/// Returns the captured bytes and whether a pipe overflowed.
+async fn capture(child: &mut Child, limit: usize) -> std::io::Result<(Vec<u8>, Vec<u8>, bool)> {
+    let mut stdout_pipe = child.stdout.take();
+    let mut stderr_pipe = child.stderr.take();
+    let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+    let (mut stdout_chunk, mut stderr_chunk) = ([0u8; 4096], [0u8; 4096]);
+
+    loop {
+        tokio::select! {
+            read = read_chunk(&mut stdout_pipe, &mut stdout_chunk), if stdout_pipe.is_some() => {
+                match read? {
+                    0 => stdout_pipe = None,
+                    n => stdout.extend_from_slice(&stdout_chunk[..n]),
+                }
+            }
+            read = read_chunk(&mut stderr_pipe, &mut stderr_chunk), if stderr_pipe.is_some() => {
+                match read? {
+                    0 => stderr_pipe = None,
+                    n => stderr.extend_from_slice(&stderr_chunk[..n]),
+                }
+            }
+            else => return Ok((stdout, stderr, false)),
+        }
+
+        if stdout.len() > limit || stderr.len() > limit {
+            return Ok((stdout, stderr, true));
+        }
+    }
+}
+
+async fn read_chunk<R: AsyncRead + Unpin>(
+    pipe: &mut Option<R>,
+    chunk: &mut [u8],
+) -> std::io::Result<usize> {
+    match pipe {
+        Some(pipe) => pipe.read(chunk).await,
+        None => Ok(0),
+    }
+}
+
+#[async_trait]
+impl LlmTool for CodeExecTool {
+    async fn run(&self, args: &HashMap<String, Value>, ctx: &ToolRunCtx) -> Result<Value> {
+        let code = args.get("code").and_then(Value::as_str).ok_or_else(|| {
+            MojenticError::ToolError("execute_code requires a 'code' argument".to_string())
+        })?;
+
+        let script = self.sandbox_dir.join(format!(
+            "snippet-{}.{}",
+            uuid::Uuid::new_v4(),
+            self.file_extension
+        ));
+        tokio::fs::write(&script, code).await?;
+
+        let spawned = self
+            .command(&script)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn();
+
+        // Dropping the child kills the interpreter, on timeout or cancellation
+        let outcome = match spawned {
+            Ok(mut child) => {
+                let collect = async {
+                    let (stdout, stderr, overflowed) =
+                        capture(&mut child, self.capture_limit()).await?;
+                    if overflowed {
+                        child.start_kill()?;
+                    }
+                    let status = child.wait().await?;
+                    Ok::<_, std::io::Error>((status, stdout, stderr, overflowed))
+                };
+                tokio::select! {
+                    result = tokio::time::timeout(self.timeout, collect) => Ok(result),
+                    _ = ctx.cancel.cancelled() => {
+                        Err(MojenticError::ToolError("execute_code was cancelled".to_string()))
+                    }
+                }
+            }
+            Err(e) => Err(MojenticError::ToolError(format!(
+                "Failed to start '{}': {}",
+                self.interpreter, e
+            ))),
+        };
+        let _ = tokio::fs::remove_file(&script).await;
+
+        match outcome? {
+            Ok(output) => {
+                let (status, stdout, stderr, overflowed) = output?;
+                let mut result = json!({
+                    "exit_code": status.code(),
+                    "stdout": self.bounded_output(&stdout),
+                    "stderr": self.bounded_output(&stderr),
+                    "timed_out": false
+                });
+                if overflowed {
+                    result["error"] = json!("The code printed too much output and was killed");
+                }
+                Ok(result)
+            }
+            Err(_) => Ok(json!({
+                "exit_code": null,
+                "stdout": "",
+                "stderr": "",
+                "timed_out": true,
+                "error": format!(
+                    "The code did not finish within {} seconds and was killed",
+                    self.timeout.as_secs_f64()
+                )
+            })),
+        }
+    }
+
+    fn descriptor(&self) -> ToolDescriptor {
+        ToolDescriptor {
+            r#type: "function".to_string(),
+            function: FunctionDescriptor {
+                name: "execute_code".to_string(),
+                description: format!(
+                    "Run a self-contained {} script and return what it prints. Runs are limited to {} seconds; print any results you need.",
+                    self.interpreter,
+                    self.timeout.as_secs_f64()
+                ),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "code": {
+                            "type": "string",
+                            "description": "The complete source code to run"
+                        }
+                    },
+                    "required": ["code"]
+                }),
+                returns: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "exit_code": {"type": ["integer", "null"]},
+                        "stdout": {"type": "string"},
+                        "stderr": {"type": "string"},
+                        "timed_out": {"type": "boolean"},
+                        "error": {"type": "string"}
+                    },
+                    "required": ["exit_code", "stdout", "stderr", "timed_out"]
+                })),
+            },
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn LlmTool> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn code_args(code: &str) -> HashMap<String, Value> {
+        HashMap::from([("code".to_string(), json!(code))])
+    }
+
+    #[test]
+    fn test_descriptor() {
+        let dir = TempDir::new().unwrap();
+        let descriptor = CodeExecTool::python(dir.path()).unwrap().descriptor();
+
+        assert_eq!(descriptor.function.name, "execute_code");
+        assert_eq!(descriptor.function.parameters["required"], json!(["code"]));
+    }
+
+    #[tokio::test]
+    #[ignore = "requires python3"]
+    async fn test_runs_simple_script() {
+        let dir = TempDir::new().unwrap();
+        let tool = CodeExecTool::python(dir.path()).unwrap();
+
+        let result = tool
+            .run(
+                &code_args("import sys\nprint(sum(range(10)))\nprint('oops', file=sys.stderr)"),
+                &ToolRunCtx::default(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(result["stdout"], "45\n");
+        assert_eq!(result["stderr"], "oops\n");
+        assert_eq!(result["exit_code"], 0);
+        assert_eq!(result["timed_out"], false);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires python3"]
+    async fn test_kills_script_after_timeout() {
+        let dir = TempDir::new().unwrap();
+        let tool = CodeExecTool::python(dir.path())
+            .unwrap()
+            .with_timeout(Duration::from_millis(500));
+        let start = std::time::Instant::now();
+
+        let result = tool
+            .run(&code_args("import time\ntime.sleep(30)"), &ToolRunCtx::default())
+            .await
+            .unwrap();
+
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert_eq!(result["timed_out"], true);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires python3"]
+    async fn test_memory_limit_stops_large_allocation() {
+        let dir = TempDir::new().unwrap();
+        let tool = CodeExecTool::python(dir.path()).unwrap().with_memory_limit_mb(256);
+
+        let result = tool
+            .run(
+                &code_args("data = bytearray(2 * 1024 ** 3)\nprint('allocated')"),
+                &ToolRunCtx::default(),
+            )
+            .await
+            .unwrap();
+
+        assert_ne!(result["exit_code"], 0);
+        assert_eq!(result["stdout"], "");
+    }
+
+    #[tokio::test]
+    #[ignore = "requires python3"]
+    async fn test_truncates_long_output() {
+        let dir = TempDir::new().unwrap();
+        let tool = CodeExecTool::python(dir.path()).unwrap().with_max_output_chars(5);
+
+        let result =
+            tool.run(&code_args("print('x' * 100)"), &ToolRunCtx::default()).await.unwrap();
+
+        assert_eq!(result["stdout"], "xxxxx\n[output truncated]");
+    }
+
+    #[tokio::test]
+    #[ignore = "requires python3"]
+    async fn test_kills_script_that_floods_output() {
+        let dir = TempDir::new().unwrap();
+        let tool = CodeExecTool::python(dir.path())
+            .unwrap()
+            .with_max_output_chars(10)
+            .with_timeout(Duration::from_secs(30));
+        let start = std::time::Instant::now();
+
+        let result = tool
+            .run(&code_args("while True:\n    print('x' * 1000)"), &ToolRunCtx::default())
+            .await
+            .unwrap();
+
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert_eq!(result["timed_out"], false);
+        assert_eq!(result["exit_code"], Value::Null);
+        assert_eq!(result["stdout"], "xxxxxxxxxx\n[output truncated]");
+        assert!(result["error"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_requires_code() {
+        let dir = TempDir::new().unwrap();
+        let tool = CodeExecTool::python(dir.path()).unwrap();
+
+        let result = tool.run(&HashMap::new(), &ToolRunCtx::default()).await;
+
+        assert!(matches!(result, Err(MojenticError::ToolError(_))));
+    }
+}
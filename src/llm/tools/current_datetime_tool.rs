@@ -1,7 +1,8 @@
-use crate::error::Result;
+use crate::error::{MojenticError, Result};
 use crate::llm::tools::{FunctionDescriptor, LlmTool, ToolDescriptor};
 use async_trait::async_trait;
-use chrono::Local;
+use chrono::{DateTime, Local, TimeZone, Utc};
+use chrono_tz::Tz;
 use serde_json::{json, Value};
 use std::collections::HashMap;
 
@@ -9,6 +10,8 @@ use std::collections::HashMap;
 ///
 /// This tool returns the current datetime with optional formatting.
 /// It's useful when the LLM needs to know the current time or date.
+/// An optional IANA `timezone` (e.g. `Asia/Tokyo`) reports the time in that
+/// zone instead of the host's local time.
 ///
 /// # Examples
 ///
@@ -49,16 +52,22 @@ impl LlmTool for CurrentDatetimeTool {
             .and_then(|v| v.as_str())
             .unwrap_or("%Y-%m-%d %H:%M:%S");
 
-        let now = Local::now();
-        let formatted_time = now.format(format_string).to_string();
-        let timestamp = now.timestamp();
-        let timezone = format!("{}", now.offset());
-
-        Ok(json!({
-            "current_datetime": formatted_time,
-            "timestamp": timestamp,
-            "timezone": timezone
-        }))
+        match args.get("timezone").and_then(|v| v.as_str()) {
+            Some(name) => {
+                let tz: Tz = name.parse().map_err(|_| {
+                    MojenticError::ToolError(format!(
+                        "Unknown timezone '{}': expected an IANA name such as 'America/New_York' or 'UTC'",
+                        name
+                    ))
+                })?;
+                Ok(describe(Utc::now().with_timezone(&tz), format_string, tz.name()))
+            }
+            None => {
+                let now = Local::now();
+                let timezone = format!("{}", now.offset());
+                Ok(describe(now, format_string, &timezone))
+            }
+        }
     }
 
     fn descriptor(&self) -> ToolDescriptor {
@@ -73,10 +82,15 @@ impl LlmTool for CurrentDatetimeTool {
                         "format_string": {
                             "type": "string",
                             "description": "Format string for the datetime (e.g., '%Y-%m-%d %H:%M:%S', '%A, %B %d, %Y'). Default is ISO format."
+                        },
+                        "timezone": {
+                            "type": "string",
+                            "description": "IANA timezone name to report the time in (e.g., 'Asia/Tokyo', 'UTC'). Default is the local timezone."
                         }
                     },
                     "required": []
                 }),
+                returns: None,
             },
         }
     }
@@ -86,6 +100,18 @@ impl LlmTool for CurrentDatetimeTool {
     }
 }
 
+fn describe<Z: TimeZone>(now: DateTime<Z>, format_string: &str, timezone: &str) -> Value
+where
+    Z::Offset: std::fmt::Display,
+{
+    json!({
+        "current_datetime": now.format(format_string).to_string(),
+        "timestamp": now.timestamp(),
+        "timezone": timezone,
+        "utc_offset": now.format("%:z").to_string()
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -155,6 +181,49 @@ mod tests {
         assert!(!timezone.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_run_in_utc() {
+        let tool = CurrentDatetimeTool::new();
+        let mut args = HashMap::new();
+        args.insert("timezone".to_string(), json!("UTC"));
+
+        let result = tool.run(&args, &crate::llm::tools::ToolRunCtx::default()).await.unwrap();
+
+        assert_eq!(result["timezone"], "UTC");
+        assert_eq!(result["utc_offset"], "+00:00");
+    }
+
+    #[tokio::test]
+    async fn test_run_in_named_timezone() {
+        let tool = CurrentDatetimeTool::new();
+        let mut args = HashMap::new();
+        args.insert("timezone".to_string(), json!("Asia/Tokyo"));
+
+        let result = tool.run(&args, &crate::llm::tools::ToolRunCtx::default()).await.unwrap();
+
+        // Tokyo has no daylight saving, so it is always UTC+9
+        assert_eq!(result["timezone"], "Asia/Tokyo");
+        assert_eq!(result["utc_offset"], "+09:00");
+        let timestamp = result["timestamp"].as_i64().unwrap();
+        assert!((Utc::now().timestamp() - timestamp).abs() < 5);
+    }
+
+    #[tokio::test]
+    async fn test_run_with_invalid_timezone() {
+        let tool = CurrentDatetimeTool::new();
+        let mut args = HashMap::new();
+        args.insert("timezone".to_string(), json!("Mars/Olympus_Mons"));
+
+        let result = tool.run(&args, &crate::llm::tools::ToolRunCtx::default()).await;
+
+        match result {
+            Err(MojenticError::ToolError(message)) => {
+                assert!(message.contains("Unknown timezone 'Mars/Olympus_Mons'"))
+            }
+            other => panic!("expected ToolError, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_tool_matches() {
         let tool = CurrentDatetimeTool::new();
@@ -56,6 +56,7 @@ impl LlmTool for AppendTaskTool {
                     },
                     "required": ["description"]
                 }),
+                returns: None,
             },
         }
     }
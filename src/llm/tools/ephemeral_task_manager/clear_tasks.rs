@@ -45,6 +45,7 @@ impl LlmTool for ClearTasksTool {
                     "type": "object",
                     "properties": {}
                 }),
+                returns: None,
             },
         }
     }
@@ -59,6 +59,7 @@ impl LlmTool for CompleteTaskTool {
                     },
                     "required": ["id"]
                 }),
+                returns: None,
             },
         }
     }
@@ -63,6 +63,7 @@ impl LlmTool for InsertTaskAfterTool {
                     },
                     "required": ["existing_task_id", "description"]
                 }),
+                returns: None,
             },
         }
     }
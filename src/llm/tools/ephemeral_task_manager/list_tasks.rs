@@ -60,6 +60,7 @@ impl LlmTool for ListTasksTool {
                     "type": "object",
                     "properties": {}
                 }),
+                returns: None,
             },
         }
     }
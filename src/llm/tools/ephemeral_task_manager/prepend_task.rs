@@ -56,6 +56,7 @@ impl LlmTool for PrependTaskTool {
                     },
                     "required": ["description"]
                 }),
+                returns: None,
             },
         }
     }
@@ -58,6 +58,7 @@ impl LlmTool for StartTaskTool {
                     },
                     "required": ["id"]
                 }),
+                returns: None,
             },
         }
     }
@@ -1,20 +1,114 @@
 use async_trait::async_trait;
 use regex::Regex;
+use serde::Serialize;
 use serde_json::{json, Value};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
 use crate::error::{MojenticError, Result};
 use crate::llm::tools::{FunctionDescriptor, LlmTool, ToolDescriptor};
 
+/// Directory levels recursive searches descend unless configured otherwise
+pub const DEFAULT_MAX_DEPTH: usize = 32;
+
+/// Files recursive searches visit unless configured otherwise
+pub const DEFAULT_MAX_FILES: usize = 10_000;
+
+/// Bytes a single read returns unless configured otherwise
+pub const DEFAULT_MAX_READ_BYTES: usize = 256 * 1024;
+
+/// Version control and build output directories skipped by searches unless configured otherwise
+pub const DEFAULT_IGNORE_PATTERNS: &[&str] = &[
+    ".git",
+    ".hg",
+    ".svn",
+    "node_modules",
+    "target",
+    "__pycache__",
+    ".venv",
+    ".tox",
+    "dist",
+    "build",
+];
+
 /// A gateway for interacting with the filesystem within a sandboxed base path.
 ///
 /// This struct provides safe filesystem operations that are restricted to a
-/// specific base directory, preventing path traversal attacks.
+/// specific base directory, preventing path traversal attacks. Recursive
+/// searches are bounded by a maximum depth and file count, and only follow
+/// symlinks that stay inside the sandbox and don't lead back to a directory
+/// already visited.
+///
+/// Searches skip paths matching the ignore patterns ([`DEFAULT_IGNORE_PATTERNS`]
+/// unless configured). A pattern without a `/` matches a file or directory
+/// name at any depth below the search root, so `target` skips every `target`
+/// directory; a pattern containing `/` matches the path relative to the
+/// sandbox root, such as `docs/generated/**`.
 #[derive(Debug, Clone)]
 pub struct FilesystemGateway {
     base_path: PathBuf,
+    max_depth: usize,
+    max_files: usize,
+    max_read_bytes: usize,
+    ignore: Vec<glob::Pattern>,
+}
+
+/// A byte range of a text file, as returned by [`FilesystemGateway::read_range`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileChunk {
+    /// The text in the range
+    pub content: String,
+    /// Byte offset the content starts at
+    pub start: u64,
+    /// Byte offset just past the end of the content
+    pub end: u64,
+    /// Size of the whole file in bytes
+    pub size: u64,
+}
+
+impl FileChunk {
+    /// Whether the chunk covers the whole file
+    pub fn is_complete(&self) -> bool {
+        self.start == 0 && self.end >= self.size
+    }
+}
+
+/// Metadata for a file or directory, as returned by [`FilesystemGateway::stat`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileStat {
+    /// Path relative to the sandbox root
+    pub path: String,
+    /// Size in bytes
+    pub size: u64,
+    /// Whether the path is a directory
+    pub is_dir: bool,
+    /// Whether the path is a regular file
+    pub is_file: bool,
+    /// Whether the path is read-only
+    pub readonly: bool,
+    /// Last modification time in RFC 3339 (UTC), when the platform reports it
+    pub modified: Option<String>,
+}
+
+/// Files found by a recursive search
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FileListing {
+    /// Paths relative to the sandbox root
+    pub files: Vec<String>,
+    /// Whether the depth or file limit stopped the search before it covered the whole tree
+    pub truncated: bool,
+    /// Files a content search passed over because they look binary
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped_binary: Vec<String>,
+}
+
+/// Progress of a single recursive search
+#[derive(Default)]
+struct Walk {
+    visited_dirs: HashSet<PathBuf>,
+    files_seen: usize,
+    truncated: bool,
 }
 
 impl FilesystemGateway {
@@ -31,9 +125,73 @@ impl FilesystemGateway {
 
         Ok(Self {
             base_path: base_path.canonicalize()?,
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_files: DEFAULT_MAX_FILES,
+            max_read_bytes: DEFAULT_MAX_READ_BYTES,
+            ignore: compile_patterns(DEFAULT_IGNORE_PATTERNS),
         })
     }
 
+    /// Replace the glob patterns searches skip
+    ///
+    /// Pass an empty list to search everything. A pattern that isn't valid
+    /// glob syntax matches literally.
+    pub fn with_ignore<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.ignore = compile_patterns(patterns);
+        self
+    }
+
+    /// Add glob patterns to the ones searches already skip
+    pub fn with_additional_ignore<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.ignore.extend(compile_patterns(patterns));
+        self
+    }
+
+    /// Whether `path` matches an ignore pattern
+    ///
+    /// Name patterns are checked against each component of `path` below
+    /// `search_root`; path patterns against the path relative to the sandbox.
+    fn is_ignored(&self, path: &Path, search_root: &Path) -> bool {
+        let below_root = path.strip_prefix(search_root).unwrap_or(path);
+        let in_sandbox = path.strip_prefix(&self.base_path).unwrap_or(path);
+
+        self.ignore.iter().any(|pattern| {
+            if pattern.as_str().contains('/') {
+                pattern.matches_path(in_sandbox)
+            } else {
+                below_root
+                    .components()
+                    .any(|component| pattern.matches(&component.as_os_str().to_string_lossy()))
+            }
+        })
+    }
+
+    /// Set how many bytes a single read returns
+    pub fn with_max_read_bytes(mut self, max_read_bytes: usize) -> Self {
+        self.max_read_bytes = max_read_bytes;
+        self
+    }
+
+    /// Set how many directory levels below the starting directory recursive searches descend
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Set how many files a recursive search visits before it stops
+    pub fn with_max_files(mut self, max_files: usize) -> Self {
+        self.max_files = max_files;
+        self
+    }
+
     /// Resolves a path relative to the base path and ensures it stays within the sandbox.
     pub fn resolve_path<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf> {
         let path = path.as_ref();
@@ -104,38 +262,89 @@ impl FilesystemGateway {
     }
 
     /// Lists all files recursively in a directory.
-    pub fn list_all_files<P: AsRef<Path>>(&self, path: P) -> Result<Vec<String>> {
+    pub fn list_all_files<P: AsRef<Path>>(&self, path: P) -> Result<FileListing> {
         let resolved_path = self.resolve_path(path)?;
         let mut files = Vec::new();
 
-        self.collect_files_recursively(&resolved_path, &mut files)?;
+        let truncated = self.walk_files(&resolved_path, &mut |file| {
+            files.push(self.relative(file));
+        })?;
 
-        Ok(files)
+        Ok(FileListing {
+            files,
+            truncated,
+            skipped_binary: Vec::new(),
+        })
+    }
+
+    /// Visit every file under `dir` within the depth and file limits.
+    ///
+    /// Returns whether a limit cut the walk short.
+    fn walk_files(&self, dir: &Path, visit: &mut dyn FnMut(&Path)) -> Result<bool> {
+        let mut walk = Walk::default();
+        if dir.is_dir() {
+            self.walk_dir(dir, 0, &mut walk, visit)?;
+        }
+        Ok(walk.truncated)
     }
 
-    fn collect_files_recursively(&self, dir: &Path, files: &mut Vec<String>) -> Result<()> {
-        if !dir.is_dir() {
+    fn walk_dir(
+        &self,
+        dir: &Path,
+        depth: usize,
+        walk: &mut Walk,
+        visit: &mut dyn FnMut(&Path),
+    ) -> Result<()> {
+        // A directory reached a second time is a symlink cycle or alias
+        if !walk.visited_dirs.insert(dir.canonicalize()?) {
             return Ok(());
         }
 
-        let entries = fs::read_dir(dir)?;
+        for entry in fs::read_dir(dir)? {
+            if walk.files_seen >= self.max_files && walk.truncated {
+                return Ok(());
+            }
 
-        for entry in entries {
             let entry = entry?;
             let path = entry.path();
+            if self.is_ignored(&path, dir) {
+                continue;
+            }
 
-            if path.is_dir() {
-                self.collect_files_recursively(&path, files)?;
+            // Symlinks are followed only when they resolve inside the sandbox
+            let target = if entry.file_type()?.is_symlink() {
+                match path.canonicalize() {
+                    Ok(target) if target.starts_with(&self.base_path) => target,
+                    _ => continue,
+                }
             } else {
-                let relative =
-                    path.strip_prefix(&self.base_path).unwrap().to_string_lossy().to_string();
-                files.push(relative);
+                path.clone()
+            };
+
+            if target.is_dir() {
+                if depth >= self.max_depth {
+                    walk.truncated = true;
+                } else {
+                    self.walk_dir(&path, depth + 1, walk, visit)?;
+                }
+            } else if target.is_file() {
+                if walk.files_seen >= self.max_files {
+                    walk.truncated = true;
+                    return Ok(());
+                }
+                walk.files_seen += 1;
+                visit(&path);
             }
         }
 
         Ok(())
     }
 
+    /// The path of `path` relative to the sandbox root
+    fn relative(&self, path: &Path) -> String {
+        path.strip_prefix(&self.base_path).unwrap().to_string_lossy().to_string()
+    }
+
     /// Finds files matching a glob pattern.
     pub fn find_files_by_glob<P: AsRef<Path>>(
         &self,
@@ -152,6 +361,9 @@ impl FilesystemGateway {
         {
             match entry {
                 Ok(path) => {
+                    if self.is_ignored(&path, &resolved_path) {
+                        continue;
+                    }
                     if let Ok(relative) = path.strip_prefix(&self.base_path) {
                         files.push(relative.to_string_lossy().to_string());
                     }
@@ -164,54 +376,37 @@ impl FilesystemGateway {
     }
 
     /// Finds files containing text matching a regex pattern.
+    ///
+    /// Files with a null byte in their first kilobyte are treated as binary,
+    /// skipped without being read in full, and listed in
+    /// [`FileListing::skipped_binary`].
     pub fn find_files_containing<P: AsRef<Path>>(
         &self,
         path: P,
         pattern: &str,
-    ) -> Result<Vec<String>> {
+    ) -> Result<FileListing> {
         let resolved_path = self.resolve_path(path)?;
         let regex = Regex::new(pattern)
             .map_err(|e| MojenticError::ToolError(format!("Invalid regex pattern: {}", e)))?;
 
         let mut files = Vec::new();
-        self.find_matching_files(&resolved_path, &regex, &mut files)?;
-
-        Ok(files)
-    }
-
-    fn find_matching_files(
-        &self,
-        dir: &Path,
-        regex: &Regex,
-        files: &mut Vec<String>,
-    ) -> Result<()> {
-        if !dir.is_dir() {
-            return Ok(());
-        }
-
-        let entries = fs::read_dir(dir)?;
-
-        for entry in entries {
-            let entry = entry?;
-            let path = entry.path();
-
-            if path.is_dir() {
-                self.find_matching_files(&path, regex, files)?;
-            } else if path.is_file() {
-                if let Ok(content) = fs::read_to_string(&path) {
-                    if regex.is_match(&content) {
-                        let relative = path
-                            .strip_prefix(&self.base_path)
-                            .unwrap()
-                            .to_string_lossy()
-                            .to_string();
-                        files.push(relative);
-                    }
+        let mut skipped_binary = Vec::new();
+        let truncated = self.walk_files(&resolved_path, &mut |file| {
+            // Sniff before reading so large binaries are never loaded whole
+            if looks_binary(file).unwrap_or(true) {
+                skipped_binary.push(self.relative(file));
+            } else if let Ok(content) = fs::read_to_string(file) {
+                if regex.is_match(&content) {
+                    files.push(self.relative(file));
                 }
             }
-        }
+        })?;
 
-        Ok(())
+        Ok(FileListing {
+            files,
+            truncated,
+            skipped_binary,
+        })
     }
 
     /// Finds all lines in a file matching a regex pattern.
@@ -241,11 +436,77 @@ impl FilesystemGateway {
         Ok(matching_lines)
     }
 
-    /// Reads the content of a file.
-    pub fn read<P: AsRef<Path>>(&self, path: P, file_name: &str) -> Result<String> {
+    /// Reports the size, type, and modification time of a file or directory.
+    pub fn stat<P: AsRef<Path>>(&self, path: P) -> Result<FileStat> {
         let resolved_path = self.resolve_path(path)?;
-        let file_path = resolved_path.join(file_name);
-        Ok(fs::read_to_string(file_path)?)
+        let metadata = fs::metadata(&resolved_path)?;
+
+        Ok(FileStat {
+            path: self.relative(&resolved_path),
+            size: metadata.len(),
+            is_dir: metadata.is_dir(),
+            is_file: metadata.is_file(),
+            readonly: metadata.permissions().readonly(),
+            modified: metadata.modified().ok().map(|time| {
+                chrono::DateTime::<chrono::Utc>::from(time)
+                    .to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+            }),
+        })
+    }
+
+    /// Reads a file from the start, up to the maximum read size.
+    ///
+    /// Check [`FileChunk::is_complete`] to tell whether the file was longer,
+    /// and use [`FilesystemGateway::read_range`] to page through the rest.
+    pub fn read<P: AsRef<Path>>(&self, path: P, file_name: &str) -> Result<FileChunk> {
+        self.read_range(path, file_name, 0, self.max_read_bytes)
+    }
+
+    /// Reads up to `len` bytes of a file starting at byte `start`.
+    ///
+    /// `len` is capped at the maximum read size. The range is adjusted so it
+    /// never splits a UTF-8 character: a start inside a character moves to the
+    /// next one, and a character cut off at the end is left for the next read.
+    pub fn read_range<P: AsRef<Path>>(
+        &self,
+        path: P,
+        file_name: &str,
+        start: u64,
+        len: usize,
+    ) -> Result<FileChunk> {
+        use std::io::{Read, Seek, SeekFrom};
+
+        let resolved_path = self.resolve_path(path)?;
+        let mut file = fs::File::open(resolved_path.join(file_name))?;
+        let size = file.metadata()?.len();
+        let start = start.min(size);
+
+        let mut bytes = Vec::new();
+        file.seek(SeekFrom::Start(start))?;
+        file.take(len.min(self.max_read_bytes) as u64).read_to_end(&mut bytes)?;
+
+        // Continuation bytes at the front belong to a character that began earlier
+        let skipped = bytes.iter().take(3).take_while(|&&b| b & 0xC0 == 0x80).count();
+        let text = &bytes[skipped..];
+        let valid = match std::str::from_utf8(text) {
+            Ok(_) => text.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => {
+                return Err(MojenticError::ToolError(format!(
+                    "File {:?} is not valid UTF-8 text",
+                    file_name
+                )))
+            }
+        };
+        // Checked above, so nothing is replaced
+        let content = String::from_utf8_lossy(&text[..valid]).into_owned();
+
+        Ok(FileChunk {
+            content,
+            start: start + skipped as u64,
+            end: start + (skipped + valid) as u64,
+            size,
+        })
     }
 
     /// Writes content to a file.
@@ -292,10 +553,15 @@ impl LlmTool for ListFilesTool {
                     "additionalProperties": false,
                     "required": ["path"]
                 }),
+                returns: None,
             },
         }
     }
 
+    fn validate_input(&self, args: &HashMap<String, Value>) -> Result<()> {
+        check_sandboxed_path(&self.fs, args)
+    }
+
     async fn run(
         &self,
         args: &HashMap<String, Value>,
@@ -342,22 +608,35 @@ impl LlmTool for ReadFileTool {
             r#type: "function".to_string(),
             function: FunctionDescriptor {
                 name: "read_file".to_string(),
-                description: "Read the entire content of a file as a string. Use this when you need to access or analyze the complete contents of a file.".to_string(),
+                description: "Read the content of a file as a string. Use this when you need to access or analyze the contents of a file. Large files are returned in parts: a truncated result ends with a note giving the file's size and the offset to continue reading from.".to_string(),
                 parameters: json!({
                     "type": "object",
                     "properties": {
                         "path": {
                             "type": "string",
                             "description": "The full relative path including the filename of the file to read. For example, 'README.md' for a file in the root directory, 'src/main.py' for a file in the src directory, or 'docs/images/diagram.png' for a file in a nested directory."
+                        },
+                        "offset": {
+                            "type": "integer",
+                            "description": "Byte offset to start reading from. Omit to read from the beginning of the file; use the offset given in a truncated result to read the next part."
+                        },
+                        "length": {
+                            "type": "integer",
+                            "description": "Maximum number of bytes to read. Omit to read as much as the tool allows."
                         }
                     },
                     "additionalProperties": false,
                     "required": ["path"]
                 }),
+                returns: None,
             },
         }
     }
 
+    fn validate_input(&self, args: &HashMap<String, Value>) -> Result<()> {
+        check_sandboxed_path(&self.fs, args)
+    }
+
     async fn run(
         &self,
         args: &HashMap<String, Value>,
@@ -368,9 +647,21 @@ impl LlmTool for ReadFileTool {
             .and_then(|v| v.as_str())
             .ok_or_else(|| MojenticError::ToolError("Missing 'path' parameter".to_string()))?;
 
+        let offset = args.get("offset").and_then(|v| v.as_u64()).unwrap_or(0);
+        let length = args.get("length").and_then(|v| v.as_u64()).map_or(usize::MAX, |n| n as usize);
+
         let (directory, file_name) = split_path(path);
-        let content = self.fs.read(directory, file_name)?;
-        Ok(json!(content))
+        let chunk = self.fs.read_range(directory, file_name, offset, length)?;
+        if chunk.is_complete() {
+            return Ok(json!(chunk.content));
+        }
+
+        let mut note =
+            format!("[truncated: showing bytes {}-{} of {}", chunk.start, chunk.end, chunk.size);
+        if chunk.end < chunk.size {
+            note.push_str(&format!("; read again with offset {} for more", chunk.end));
+        }
+        Ok(json!(format!("{}\n{}]", chunk.content, note)))
     }
     fn clone_box(&self) -> Box<dyn LlmTool> {
         Box::new(self.clone())
@@ -412,10 +703,15 @@ impl LlmTool for WriteFileTool {
                     "additionalProperties": false,
                     "required": ["path", "content"]
                 }),
+                returns: None,
             },
         }
     }
 
+    fn validate_input(&self, args: &HashMap<String, Value>) -> Result<()> {
+        check_sandboxed_path(&self.fs, args)
+    }
+
     async fn run(
         &self,
         args: &HashMap<String, Value>,
@@ -459,22 +755,32 @@ impl LlmTool for ListAllFilesTool {
             r#type: "function".to_string(),
             function: FunctionDescriptor {
                 name: "list_all_files".to_string(),
-                description: "List all files recursively in the specified directory, including files in subdirectories. Use this when you need a complete inventory of all files in a directory and its subdirectories.".to_string(),
+                description: "List all files recursively in the specified directory, including files in subdirectories. Use this when you need a complete inventory of all files in a directory and its subdirectories. Returns the files and a 'truncated' flag that is true when the listing hit the depth or file limit.".to_string(),
                 parameters: json!({
                     "type": "object",
                     "properties": {
                         "path": {
                             "type": "string",
                             "description": "The path relative to the sandbox root to list files from recursively. For example, '.' for the root directory and all subdirectories, 'src' for the src directory and all its subdirectories, or 'docs/images' for a nested directory and its subdirectories."
+                        },
+                        "ignore": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "description": IGNORE_ARG_DESCRIPTION
                         }
                     },
                     "additionalProperties": false,
                     "required": ["path"]
                 }),
+                returns: None,
             },
         }
     }
 
+    fn validate_input(&self, args: &HashMap<String, Value>) -> Result<()> {
+        check_sandboxed_path(&self.fs, args)
+    }
+
     async fn run(
         &self,
         args: &HashMap<String, Value>,
@@ -485,8 +791,9 @@ impl LlmTool for ListAllFilesTool {
             .and_then(|v| v.as_str())
             .ok_or_else(|| MojenticError::ToolError("Missing 'path' parameter".to_string()))?;
 
-        let files = self.fs.list_all_files(path)?;
-        Ok(json!(files))
+        let listing =
+            self.fs.clone().with_additional_ignore(ignore_arg(args)).list_all_files(path)?;
+        Ok(json!(listing))
     }
     fn clone_box(&self) -> Box<dyn LlmTool> {
         Box::new(self.clone())
@@ -523,15 +830,32 @@ impl LlmTool for FindFilesByGlobTool {
                         "pattern": {
                             "type": "string",
                             "description": "The glob pattern to match files against. Examples: '*.py' for all Python files in the specified directory, '**/*.txt' for all text files in the specified directory and any subdirectory, or '**/*test*.py' for all Python files with 'test' in their name in the specified directory and any subdirectory."
+                        },
+                        "ignore": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "description": IGNORE_ARG_DESCRIPTION
                         }
                     },
                     "additionalProperties": false,
                     "required": ["path", "pattern"]
                 }),
+                returns: None,
             },
         }
     }
 
+    fn validate_input(&self, args: &HashMap<String, Value>) -> Result<()> {
+        check_sandboxed_path(&self.fs, args)?;
+        match args.get("pattern").and_then(|v| v.as_str()) {
+            Some(pattern) if escapes_directory(pattern) => Err(MojenticError::ToolError(format!(
+                "Pattern {:?} attempts to escape the sandbox",
+                pattern
+            ))),
+            _ => Ok(()),
+        }
+    }
+
     async fn run(
         &self,
         args: &HashMap<String, Value>,
@@ -547,7 +871,11 @@ impl LlmTool for FindFilesByGlobTool {
             .and_then(|v| v.as_str())
             .ok_or_else(|| MojenticError::ToolError("Missing 'pattern' parameter".to_string()))?;
 
-        let files = self.fs.find_files_by_glob(path, pattern)?;
+        let files = self
+            .fs
+            .clone()
+            .with_additional_ignore(ignore_arg(args))
+            .find_files_by_glob(path, pattern)?;
         Ok(json!(files))
     }
     fn clone_box(&self) -> Box<dyn LlmTool> {
@@ -574,7 +902,7 @@ impl LlmTool for FindFilesContainingTool {
             r#type: "function".to_string(),
             function: FunctionDescriptor {
                 name: "find_files_containing".to_string(),
-                description: "Find files containing text matching a regex pattern in the specified directory. Use this when you need to search for specific content across multiple files, such as finding all files that contain a particular function name or text string.".to_string(),
+                description: "Find files containing text matching a regex pattern in the specified directory. Use this when you need to search for specific content across multiple files, such as finding all files that contain a particular function name or text string. Returns the matching files, a 'truncated' flag that is true when the search hit the depth or file limit, and any binary files that were skipped.".to_string(),
                 parameters: json!({
                     "type": "object",
                     "properties": {
@@ -585,15 +913,25 @@ impl LlmTool for FindFilesContainingTool {
                         "pattern": {
                             "type": "string",
                             "description": "The regex pattern to search for in files. Examples: 'function\\s+main' to find files containing a main function, 'import\\s+os' to find files importing the os module, or 'TODO|FIXME' to find files containing TODO or FIXME comments. The pattern uses Rust's regex crate syntax."
+                        },
+                        "ignore": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "description": IGNORE_ARG_DESCRIPTION
                         }
                     },
                     "additionalProperties": false,
                     "required": ["path", "pattern"]
                 }),
+                returns: None,
             },
         }
     }
 
+    fn validate_input(&self, args: &HashMap<String, Value>) -> Result<()> {
+        check_sandboxed_path(&self.fs, args)
+    }
+
     async fn run(
         &self,
         args: &HashMap<String, Value>,
@@ -609,8 +947,12 @@ impl LlmTool for FindFilesContainingTool {
             .and_then(|v| v.as_str())
             .ok_or_else(|| MojenticError::ToolError("Missing 'pattern' parameter".to_string()))?;
 
-        let files = self.fs.find_files_containing(path, pattern)?;
-        Ok(json!(files))
+        let listing = self
+            .fs
+            .clone()
+            .with_additional_ignore(ignore_arg(args))
+            .find_files_containing(path, pattern)?;
+        Ok(json!(listing))
     }
     fn clone_box(&self) -> Box<dyn LlmTool> {
         Box::new(self.clone())
@@ -652,10 +994,15 @@ impl LlmTool for FindLinesMatchingTool {
                     "additionalProperties": false,
                     "required": ["path", "pattern"]
                 }),
+                returns: None,
             },
         }
     }
 
+    fn validate_input(&self, args: &HashMap<String, Value>) -> Result<()> {
+        check_sandboxed_path(&self.fs, args)
+    }
+
     async fn run(
         &self,
         args: &HashMap<String, Value>,
@@ -711,10 +1058,15 @@ impl LlmTool for CreateDirectoryTool {
                     "additionalProperties": false,
                     "required": ["path"]
                 }),
+                returns: None,
             },
         }
     }
 
+    fn validate_input(&self, args: &HashMap<String, Value>) -> Result<()> {
+        check_sandboxed_path(&self.fs, args)
+    }
+
     async fn run(
         &self,
         args: &HashMap<String, Value>,
@@ -734,6 +1086,137 @@ impl LlmTool for CreateDirectoryTool {
     }
 }
 
+/// Tool for reporting a file's size, type, and modification time without reading it.
+#[derive(Clone)]
+pub struct FileStatTool {
+    fs: FilesystemGateway,
+}
+
+impl FileStatTool {
+    pub fn new(fs: FilesystemGateway) -> Self {
+        Self { fs }
+    }
+}
+
+#[async_trait]
+impl LlmTool for FileStatTool {
+    fn descriptor(&self) -> ToolDescriptor {
+        ToolDescriptor {
+            r#type: "function".to_string(),
+            function: FunctionDescriptor {
+                name: "file_stat".to_string(),
+                description: "Get a file or directory's size in bytes, whether it is a file or a directory, whether it is read-only, and when it was last modified. Use this before reading a file you know nothing about, to avoid reading very large files in full.".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "The path relative to the sandbox root of the file or directory. For example, 'README.md' for a file in the root directory, 'logs/app.log' for a file in the logs directory, or 'src' for a directory."
+                        }
+                    },
+                    "additionalProperties": false,
+                    "required": ["path"]
+                }),
+                returns: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {"type": "string"},
+                        "size": {"type": "integer"},
+                        "is_dir": {"type": "boolean"},
+                        "is_file": {"type": "boolean"},
+                        "readonly": {"type": "boolean"},
+                        "modified": {"type": ["string", "null"]}
+                    },
+                    "required": ["path", "size", "is_dir", "is_file", "readonly", "modified"]
+                })),
+            },
+        }
+    }
+
+    fn validate_input(&self, args: &HashMap<String, Value>) -> Result<()> {
+        check_sandboxed_path(&self.fs, args)
+    }
+
+    async fn run(
+        &self,
+        args: &HashMap<String, Value>,
+        _ctx: &crate::llm::tools::ToolRunCtx,
+    ) -> Result<Value> {
+        let path = args
+            .get("path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| MojenticError::ToolError("Missing 'path' parameter".to_string()))?;
+
+        Ok(json!(self.fs.stat(path)?))
+    }
+    fn clone_box(&self) -> Box<dyn LlmTool> {
+        Box::new(self.clone())
+    }
+}
+
+/// How the search tools describe their `ignore` argument to the model
+const IGNORE_ARG_DESCRIPTION: &str = "Extra glob patterns to skip, on top of the default ignores \
+     for version control and build output directories (.git, node_modules, target, and similar). \
+     A pattern without '/' matches a file or directory name anywhere, like 'vendor' or '*.min.js'; \
+     a pattern with '/' matches the path from the sandbox root, like 'docs/generated/**'.";
+
+/// Bytes inspected when deciding whether a file is binary
+const BINARY_SNIFF_BYTES: u64 = 1024;
+
+/// Whether the start of a file contains a null byte, which text files don't
+fn looks_binary(path: &Path) -> std::io::Result<bool> {
+    use std::io::Read;
+
+    let mut head = Vec::with_capacity(BINARY_SNIFF_BYTES as usize);
+    fs::File::open(path)?.take(BINARY_SNIFF_BYTES).read_to_end(&mut head)?;
+    Ok(head.contains(&0))
+}
+
+/// Compile glob patterns, matching any invalid pattern literally
+///
+/// A pattern that can't be compiled even when escaped is skipped.
+fn compile_patterns<I, S>(patterns: I) -> Vec<glob::Pattern>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    patterns
+        .into_iter()
+        .filter_map(|pattern| {
+            let pattern = pattern.as_ref();
+            glob::Pattern::new(pattern)
+                .or_else(|_| glob::Pattern::new(&glob::Pattern::escape(pattern)))
+                .ok()
+        })
+        .collect()
+}
+
+/// The extra ignore patterns a search tool was called with
+fn ignore_arg(args: &HashMap<String, Value>) -> Vec<String> {
+    args.get("ignore")
+        .and_then(|v| v.as_array())
+        .map(|patterns| patterns.iter().filter_map(|p| p.as_str().map(String::from)).collect())
+        .unwrap_or_default()
+}
+
+/// Reject a `path` argument that resolves outside the sandbox
+///
+/// The gateway checks again when the tool runs; checking up front turns an
+/// escape attempt into feedback for the model before anything is touched.
+fn check_sandboxed_path(fs: &FilesystemGateway, args: &HashMap<String, Value>) -> Result<()> {
+    match args.get("path").and_then(|v| v.as_str()) {
+        Some(path) => fs.resolve_path(path).map(|_| ()),
+        None => Ok(()),
+    }
+}
+
+/// Whether a relative glob pattern is absolute or climbs out of its directory
+fn escapes_directory(pattern: &str) -> bool {
+    Path::new(pattern)
+        .components()
+        .any(|c| matches!(c, Component::ParentDir | Component::RootDir | Component::Prefix(_)))
+}
+
 fn split_path(path: &str) -> (&str, &str) {
     let path_obj = Path::new(path);
     let directory = path_obj.parent().and_then(|p| p.to_str()).unwrap_or(".");
@@ -763,6 +1246,50 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_write_file_tool_rejects_path_traversal_with_feedback() {
+        use crate::llm::tools::{SerialToolRunner, ToolCallExecution, ToolRunCtx, ToolRunner};
+
+        let temp_dir = TempDir::new().unwrap();
+        let sandbox = temp_dir.path().join("sandbox");
+        fs::create_dir(&sandbox).unwrap();
+        let tools: Vec<Box<dyn LlmTool>> = vec![Box::new(WriteFileTool::new(
+            FilesystemGateway::new(&sandbox).unwrap(),
+        ))];
+        let call = ToolCallExecution {
+            id: "call_1".to_string(),
+            name: "write_file".to_string(),
+            args: HashMap::from([
+                ("path".to_string(), json!("../escaped.txt")),
+                ("content".to_string(), json!("pwned")),
+            ]),
+        };
+
+        let outcomes = SerialToolRunner.run_batch(&[call], &tools, &ToolRunCtx::default()).await;
+
+        assert!(!outcomes[0].ok);
+        let error = outcomes[0].error.as_deref().unwrap();
+        assert!(error.contains("rejected its input"), "{}", error);
+        assert!(error.contains("escape the sandbox"), "{}", error);
+        assert!(!temp_dir.path().join("escaped.txt").exists());
+    }
+
+    #[test]
+    fn test_glob_tool_rejects_patterns_outside_the_sandbox() {
+        let temp_dir = TempDir::new().unwrap();
+        let tool = FindFilesByGlobTool::new(FilesystemGateway::new(temp_dir.path()).unwrap());
+        let args = |pattern: &str| {
+            HashMap::from([
+                ("path".to_string(), json!(".")),
+                ("pattern".to_string(), json!(pattern)),
+            ])
+        };
+
+        assert!(tool.validate_input(&args("**/*.rs")).is_ok());
+        assert!(tool.validate_input(&args("../**/*")).is_err());
+        assert!(tool.validate_input(&args("/etc/*")).is_err());
+    }
+
     #[test]
     fn test_ls() {
         let temp_dir = TempDir::new().unwrap();
@@ -781,8 +1308,263 @@ mod tests {
         let gateway = FilesystemGateway::new(temp_dir.path()).unwrap();
 
         gateway.write(".", "test.txt", "Hello, world!").unwrap();
-        let content = gateway.read(".", "test.txt").unwrap();
+        let chunk = gateway.read(".", "test.txt").unwrap();
+
+        assert_eq!(chunk.content, "Hello, world!");
+        assert!(chunk.is_complete());
+    }
+
+    #[test]
+    fn test_list_all_files_stops_at_max_depth() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut dir = temp_dir.path().to_path_buf();
+        for level in 0..10 {
+            fs::write(dir.join(format!("level{level}.txt")), "content").unwrap();
+            dir = dir.join(format!("d{level}"));
+            fs::create_dir(&dir).unwrap();
+        }
 
-        assert_eq!(content, "Hello, world!");
+        let gateway = FilesystemGateway::new(temp_dir.path()).unwrap().with_max_depth(3);
+        let listing = gateway.list_all_files(".").unwrap();
+
+        let mut files = listing.files;
+        files.sort();
+        assert_eq!(
+            files,
+            vec![
+                "d0/d1/d2/level3.txt",
+                "d0/d1/level2.txt",
+                "d0/level1.txt",
+                "level0.txt",
+            ]
+        );
+        assert!(listing.truncated);
+    }
+
+    #[test]
+    fn test_list_all_files_stops_at_max_files() {
+        let temp_dir = TempDir::new().unwrap();
+        for i in 0..5 {
+            fs::write(temp_dir.path().join(format!("{i}.txt")), "content").unwrap();
+        }
+
+        let gateway = FilesystemGateway::new(temp_dir.path()).unwrap().with_max_files(3);
+        let listing = gateway.list_all_files(".").unwrap();
+        assert_eq!(listing.files.len(), 3);
+        assert!(listing.truncated);
+
+        let gateway = gateway.with_max_files(5);
+        let listing = gateway.list_all_files(".").unwrap();
+        assert_eq!(listing.files.len(), 5);
+        assert!(!listing.truncated);
+    }
+
+    #[test]
+    fn test_find_files_containing_reports_matches() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("src")).unwrap();
+        fs::write(temp_dir.path().join("src/main.rs"), "fn main() {}").unwrap();
+        fs::write(temp_dir.path().join("notes.txt"), "nothing here").unwrap();
+
+        let gateway = FilesystemGateway::new(temp_dir.path()).unwrap();
+        let listing = gateway.find_files_containing(".", r"fn\s+main").unwrap();
+
+        assert_eq!(
+            listing,
+            FileListing {
+                files: vec!["src/main.rs".to_string()],
+                truncated: false,
+                skipped_binary: vec![],
+            }
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_recursive_search_skips_symlink_cycles_and_escapes() {
+        let temp_dir = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        fs::write(outside.path().join("secret.txt"), "secret").unwrap();
+        fs::create_dir(temp_dir.path().join("a")).unwrap();
+        fs::write(temp_dir.path().join("a/file.txt"), "content").unwrap();
+        std::os::unix::fs::symlink(temp_dir.path(), temp_dir.path().join("a/loop")).unwrap();
+        std::os::unix::fs::symlink(outside.path(), temp_dir.path().join("escape")).unwrap();
+
+        let gateway = FilesystemGateway::new(temp_dir.path()).unwrap();
+        let listing = gateway.list_all_files(".").unwrap();
+
+        assert_eq!(listing.files, vec!["a/file.txt"]);
+        assert!(!listing.truncated);
+    }
+
+    #[test]
+    fn test_find_files_containing_skips_binary_files() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("notes.txt"), "needle").unwrap();
+        let mut binary = vec![0u8, 159, 146, 150];
+        binary.extend_from_slice(b"needle");
+        binary.resize(64 * 1024, 0);
+        fs::write(temp_dir.path().join("image.bin"), binary).unwrap();
+
+        let gateway = FilesystemGateway::new(temp_dir.path()).unwrap();
+        let listing = gateway.find_files_containing(".", "needle").unwrap();
+
+        assert_eq!(listing.files, vec!["notes.txt"]);
+        assert_eq!(listing.skipped_binary, vec!["image.bin"]);
+        assert_eq!(
+            json!(listing),
+            json!({"files": ["notes.txt"], "truncated": false, "skipped_binary": ["image.bin"]})
+        );
+    }
+
+    #[test]
+    fn test_read_stops_at_max_read_bytes() {
+        let temp_dir = TempDir::new().unwrap();
+        let gateway = FilesystemGateway::new(temp_dir.path()).unwrap().with_max_read_bytes(10);
+        gateway.write(".", "log.txt", &"x".repeat(100)).unwrap();
+
+        let chunk = gateway.read(".", "log.txt").unwrap();
+
+        assert_eq!(chunk.content, "x".repeat(10));
+        assert_eq!((chunk.start, chunk.end, chunk.size), (0, 10, 100));
+        assert!(!chunk.is_complete());
+    }
+
+    #[test]
+    fn test_read_range_pages_without_splitting_characters() {
+        let temp_dir = TempDir::new().unwrap();
+        let gateway = FilesystemGateway::new(temp_dir.path()).unwrap();
+        gateway.write(".", "text.txt", "abcdé fin").unwrap();
+
+        let middle = gateway.read_range(".", "text.txt", 2, 3).unwrap();
+        assert_eq!(middle.content, "cd");
+        assert_eq!((middle.start, middle.end), (2, 4));
+
+        let rest = gateway.read_range(".", "text.txt", 5, 100).unwrap();
+        assert_eq!(rest.content, " fin");
+        assert_eq!((rest.start, rest.end, rest.size), (6, 10, 10));
+
+        let past_end = gateway.read_range(".", "text.txt", 50, 10).unwrap();
+        assert_eq!(past_end.content, "");
+    }
+
+    #[tokio::test]
+    async fn test_read_file_tool_marks_truncated_results() {
+        let temp_dir = TempDir::new().unwrap();
+        let gateway = FilesystemGateway::new(temp_dir.path()).unwrap().with_max_read_bytes(5);
+        gateway.write(".", "log.txt", "0123456789").unwrap();
+        let tool = ReadFileTool::new(gateway);
+        let ctx = crate::llm::tools::ToolRunCtx::default();
+
+        let first = tool.run(&HashMap::from([("path".to_string(), json!("log.txt"))]), &ctx).await;
+        let next = tool
+            .run(
+                &HashMap::from([
+                    ("path".to_string(), json!("log.txt")),
+                    ("offset".to_string(), json!(5)),
+                ]),
+                &ctx,
+            )
+            .await;
+
+        assert_eq!(
+            first.unwrap(),
+            json!("01234\n[truncated: showing bytes 0-5 of 10; read again with offset 5 for more]")
+        );
+        assert_eq!(next.unwrap(), json!("56789\n[truncated: showing bytes 5-10 of 10]"));
+    }
+
+    #[test]
+    fn test_stat_reports_size_and_type() {
+        let temp_dir = TempDir::new().unwrap();
+        let gateway = FilesystemGateway::new(temp_dir.path()).unwrap();
+        fs::create_dir(temp_dir.path().join("logs")).unwrap();
+        gateway.write("logs", "app.log", "twelve bytes").unwrap();
+
+        let file = gateway.stat("logs/app.log").unwrap();
+        let dir = gateway.stat("logs").unwrap();
+
+        assert_eq!(file.path, "logs/app.log");
+        assert_eq!(file.size, 12);
+        assert!(file.is_file && !file.is_dir);
+        assert!(file.modified.is_some());
+        assert!(dir.is_dir && !dir.is_file);
+        assert!(gateway.stat("../outside").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_file_stat_tool_returns_metadata() {
+        let temp_dir = TempDir::new().unwrap();
+        let gateway = FilesystemGateway::new(temp_dir.path()).unwrap();
+        gateway.write(".", "notes.txt", "hello").unwrap();
+        let tool = FileStatTool::new(gateway);
+
+        let result = tool
+            .run(
+                &HashMap::from([("path".to_string(), json!("notes.txt"))]),
+                &crate::llm::tools::ToolRunCtx::default(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(result["size"], 5);
+        assert_eq!(result["is_file"], true);
+    }
+
+    #[test]
+    fn test_searches_skip_ignored_directories() {
+        let temp_dir = TempDir::new().unwrap();
+        for dir in ["src", "node_modules/pkg", "target/debug", "vendor"] {
+            fs::create_dir_all(temp_dir.path().join(dir)).unwrap();
+        }
+        for file in [
+            "src/lib.rs",
+            "node_modules/pkg/index.js",
+            "target/debug/app.rs",
+            "vendor/dep.rs",
+        ] {
+            fs::write(temp_dir.path().join(file), "fn main() {}").unwrap();
+        }
+
+        let gateway = FilesystemGateway::new(temp_dir.path()).unwrap();
+        let mut files = gateway.list_all_files(".").unwrap().files;
+        files.sort();
+        assert_eq!(files, vec!["src/lib.rs", "vendor/dep.rs"]);
+
+        let gateway = gateway.with_additional_ignore(["vendor"]);
+        assert_eq!(gateway.list_all_files(".").unwrap().files, vec!["src/lib.rs"]);
+        assert_eq!(gateway.find_files_by_glob(".", "**/*.rs").unwrap(), vec!["src/lib.rs"]);
+        assert_eq!(
+            gateway.find_files_containing(".", "fn main").unwrap().files,
+            vec!["src/lib.rs"]
+        );
+
+        // Listing an ignored directory directly still works
+        assert_eq!(gateway.list_all_files("target").unwrap().files, vec!["target/debug/app.rs"]);
+
+        let everything = gateway.with_ignore(Vec::<String>::new());
+        assert_eq!(everything.list_all_files(".").unwrap().files.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_list_all_files_tool_accepts_ignore_patterns() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("generated")).unwrap();
+        fs::write(temp_dir.path().join("generated/out.txt"), "x").unwrap();
+        fs::write(temp_dir.path().join("keep.txt"), "x").unwrap();
+        let tool = ListAllFilesTool::new(FilesystemGateway::new(temp_dir.path()).unwrap());
+
+        let result = tool
+            .run(
+                &HashMap::from([
+                    ("path".to_string(), json!(".")),
+                    ("ignore".to_string(), json!(["generated/**"])),
+                ]),
+                &crate::llm::tools::ToolRunCtx::default(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(result["files"], json!(["keep.txt"]));
     }
 }
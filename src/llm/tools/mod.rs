@@ -1,17 +1,24 @@
 pub mod ask_user_tool;
+pub mod code_exec_tool;
 pub mod current_datetime_tool;
 pub mod ephemeral_task_manager;
 pub mod file_manager;
+pub mod parse_relative_date_tool;
 pub mod runner;
+pub mod shell_tool;
 pub mod simple_date_tool;
 pub mod tell_user_tool;
 mod tool;
 pub mod tool_wrapper;
+pub mod wait_tool;
 pub mod web_search_tool;
 
 pub use runner::{
     fresh_cancel_token, ParallelToolRunner, SerialToolRunner, ToolCallExecution, ToolCallOutcome,
     ToolRunner,
 };
-pub use tool::{FunctionDescriptor, LlmTool, ToolDescriptor, ToolRunCtx};
+pub use tool::{
+    tools_to_provider_json, FunctionDescriptor, LlmTool, ToolDescriptor, ToolPriority,
+    ToolProgress, ToolProgressSender, ToolProvider, ToolResultCache, ToolRunCtx,
+};
 pub use tool_wrapper::ToolWrapper;
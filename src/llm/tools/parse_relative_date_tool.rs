@@ -0,0 +1,224 @@
+use crate::error::{MojenticError, Result};
+use crate::llm::tools::{FunctionDescriptor, LlmTool, ToolDescriptor, ToolRunCtx};
+use async_trait::async_trait;
+use chrono::{Local, NaiveDate, TimeZone, Utc};
+use interim::{parse_date_string, Dialect};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Tool that resolves natural-language relative dates against a reference date
+///
+/// Handles expressions such as "next Friday", "3 weeks from now", "2 days ago",
+/// "last Monday" or "tomorrow" using the `interim` date parser. "Next Friday"
+/// means the Friday of next week; plain "Friday" means the coming Friday.
+///
+/// Unparseable expressions produce a result with an `error` field rather than
+/// failing the tool call, so the model can rephrase and try again.
+///
+/// # Examples
+///
+/// ```ignore
+/// use mojentic::llm::tools::parse_relative_date_tool::ParseRelativeDateTool;
+///
+/// let tool = ParseRelativeDateTool::new();
+/// let args = HashMap::from([
+///     ("expression".to_string(), json!("3 weeks from now")),
+///     ("reference_date".to_string(), json!("2024-05-15")),
+/// ]);
+///
+/// let result = tool.run(&args, &ToolRunCtx::default()).await?;
+/// assert_eq!(result["resolved_date"], "2024-06-05");
+/// ```
+#[derive(Clone)]
+pub struct ParseRelativeDateTool;
+
+impl ParseRelativeDateTool {
+    /// Creates a new ParseRelativeDateTool instance
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for ParseRelativeDateTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl LlmTool for ParseRelativeDateTool {
+    async fn run(&self, args: &HashMap<String, Value>, _ctx: &ToolRunCtx) -> Result<Value> {
+        let expression = args.get("expression").and_then(|v| v.as_str()).ok_or_else(|| {
+            MojenticError::ToolError("Missing required argument: expression".to_string())
+        })?;
+
+        let reference = match args.get("reference_date").and_then(|v| v.as_str()) {
+            Some(text) => match NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+                Ok(date) => date,
+                Err(_) => {
+                    return Ok(json!({
+                        "expression": expression,
+                        "error": format!(
+                            "Could not read reference_date '{}': expected YYYY-MM-DD",
+                            text
+                        )
+                    }))
+                }
+            },
+            None => Local::now().date_naive(),
+        };
+        let reference_date = reference.format("%Y-%m-%d").to_string();
+
+        match resolve(expression, reference) {
+            Some(date) => {
+                let resolved_date = date.format("%Y-%m-%d").to_string();
+                Ok(json!({
+                    "expression": expression,
+                    "reference_date": reference_date,
+                    "resolved_date": resolved_date,
+                    "day_of_week": date.format("%A").to_string(),
+                    "summary": format!("'{}' from {} is {}", expression, reference_date, resolved_date)
+                }))
+            }
+            None => Ok(json!({
+                "expression": expression,
+                "reference_date": reference_date,
+                "error": format!(
+                    "Could not understand the date expression '{}'. Try forms like 'next friday', '3 weeks from now', '2 days ago' or an ISO date.",
+                    expression
+                )
+            })),
+        }
+    }
+
+    fn descriptor(&self) -> ToolDescriptor {
+        ToolDescriptor {
+            r#type: "function".to_string(),
+            function: FunctionDescriptor {
+                name: "parse_relative_date".to_string(),
+                description: "Resolve a relative date expression such as 'next Friday', '3 weeks from now' or '2 days ago' to an ISO date (YYYY-MM-DD), counting from a reference date."
+                    .to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "expression": {
+                            "type": "string",
+                            "description": "The relative date expression to resolve (e.g., 'next friday', '3 weeks from now', 'last monday')"
+                        },
+                        "reference_date": {
+                            "type": "string",
+                            "description": "The date to count from, in YYYY-MM-DD format. Default is today."
+                        }
+                    },
+                    "required": ["expression"]
+                }),
+                returns: None,
+            },
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn LlmTool> {
+        Box::new(self.clone())
+    }
+}
+
+/// Resolve an expression against a reference date, or `None` if it can't be parsed
+fn resolve(expression: &str, reference: NaiveDate) -> Option<NaiveDate> {
+    let normalized = normalize(expression);
+    // Anchor at noon UTC so day arithmetic can't cross a date boundary
+    let now = Utc.from_utc_datetime(&reference.and_hms_opt(12, 0, 0)?);
+    parse_date_string(&normalized, now, Dialect::Uk).ok().map(|dt| dt.date_naive())
+}
+
+/// Rewrite phrasings the parser doesn't know ("in 3 weeks", "3 weeks from now")
+/// into the bare interval form it does ("3 weeks")
+fn normalize(expression: &str) -> String {
+    let lower = expression.trim().trim_end_matches(['.', '?', '!']).to_lowercase();
+    let without_prefix = lower.strip_prefix("in ").unwrap_or(&lower);
+    ["from now", "from today", "later", "hence"]
+        .iter()
+        .find_map(|suffix| without_prefix.strip_suffix(suffix))
+        .unwrap_or(without_prefix)
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Wednesday
+    const REFERENCE: &str = "2024-05-15";
+
+    async fn resolve_expression(expression: &str) -> Value {
+        let args = HashMap::from([
+            ("expression".to_string(), json!(expression)),
+            ("reference_date".to_string(), json!(REFERENCE)),
+        ]);
+        ParseRelativeDateTool::new().run(&args, &ToolRunCtx::default()).await.unwrap()
+    }
+
+    #[test]
+    fn test_descriptor() {
+        let descriptor = ParseRelativeDateTool::new().descriptor();
+
+        assert_eq!(descriptor.function.name, "parse_relative_date");
+        assert_eq!(descriptor.function.parameters["required"], json!(["expression"]));
+    }
+
+    #[tokio::test]
+    async fn test_resolves_relative_expressions() {
+        let cases = [
+            ("tomorrow", "2024-05-16"),
+            ("yesterday", "2024-05-14"),
+            ("friday", "2024-05-17"),
+            ("next Friday", "2024-05-24"),
+            ("last monday", "2024-05-13"),
+            ("3 weeks from now", "2024-06-05"),
+            ("in 2 days", "2024-05-17"),
+            ("2 days ago", "2024-05-13"),
+            ("2 months", "2024-07-15"),
+        ];
+
+        for (expression, expected) in cases {
+            let result = resolve_expression(expression).await;
+            assert_eq!(result["resolved_date"], expected, "expression: {}", expression);
+            assert_eq!(result["reference_date"], REFERENCE);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reports_day_of_week() {
+        let result = resolve_expression("next friday").await;
+
+        assert_eq!(result["day_of_week"], "Friday");
+    }
+
+    #[tokio::test]
+    async fn test_unparseable_expression_returns_error_payload() {
+        let result = resolve_expression("when pigs fly").await;
+
+        assert!(result.get("resolved_date").is_none());
+        assert!(result["error"].as_str().unwrap().contains("when pigs fly"));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_reference_date_returns_error_payload() {
+        let args = HashMap::from([
+            ("expression".to_string(), json!("tomorrow")),
+            ("reference_date".to_string(), json!("15/05/2024")),
+        ]);
+
+        let result = ParseRelativeDateTool::new().run(&args, &ToolRunCtx::default()).await.unwrap();
+
+        assert!(result["error"].as_str().unwrap().contains("YYYY-MM-DD"));
+    }
+
+    #[tokio::test]
+    async fn test_missing_expression_is_tool_error() {
+        let result =
+            ParseRelativeDateTool::new().run(&HashMap::new(), &ToolRunCtx::default()).await;
+
+        assert!(matches!(result, Err(MojenticError::ToolError(_))));
+    }
+}
@@ -4,6 +4,7 @@
 //! stay independent of concurrency policy. Mirrors the Python and TypeScript
 //! `ToolRunner` design.
 
+use crate::llm::schema_validation::validate_against_schema;
 use crate::llm::tools::tool::{LlmTool, ToolRunCtx};
 use async_trait::async_trait;
 use futures::stream::{FuturesUnordered, StreamExt};
@@ -155,6 +156,10 @@ async fn execute_one(
     tools: &[Box<dyn LlmTool>],
     ctx: &ToolRunCtx,
 ) -> ToolCallOutcome {
+    let _permit = match &ctx.concurrency {
+        Some(limit) => Some(limit.acquire().await.expect("semaphore closed")),
+        None => None,
+    };
     let start = Instant::now();
     let tool = tools.iter().find(|t| t.matches(&call.name));
     let outcome = match tool {
@@ -163,10 +168,10 @@ async fn execute_one(
             name: call.name.clone(),
             ok: false,
             result: None,
-            error: Some(format!("Tool {:?} not found", call.name)),
+            error: Some(tool_not_found_message(&call.name, tools)),
             duration_ms: start.elapsed().as_millis() as u64,
         },
-        Some(tool) => match tool.run(&call.args, ctx).await {
+        Some(tool) => match run_or_recall(tool.as_ref(), call, ctx).await {
             Ok(result) => ToolCallOutcome {
                 id: call.id.clone(),
                 name: call.name.clone(),
@@ -188,6 +193,66 @@ async fn execute_one(
     outcome
 }
 
+/// Run the tool, or answer from the context's result cache when the tool is cacheable
+///
+/// Input the tool rejects in [`LlmTool::validate_input`] is never run or cached.
+async fn run_or_recall(
+    tool: &dyn LlmTool,
+    call: &ToolCallExecution,
+    ctx: &ToolRunCtx,
+) -> crate::error::Result<Value> {
+    tool.validate_input(&call.args).map_err(|e| {
+        crate::error::MojenticError::ToolError(format!(
+            "Tool {:?} rejected its input: {}",
+            call.name, e
+        ))
+    })?;
+
+    let cache = ctx.result_cache.as_ref().filter(|_| tool.cacheable());
+    if let Some(result) = cache.and_then(|cache| cache.get(&call.name, &call.args)) {
+        return Ok(result);
+    }
+
+    let result = tool
+        .run(&call.args, ctx)
+        .await
+        .and_then(|result| check_declared_return(tool, result))?;
+    if let Some(cache) = cache {
+        cache.insert(&call.name, &call.args, result.clone());
+    }
+    Ok(result)
+}
+
+/// In debug builds, reject a result that doesn't match the tool's declared
+/// `returns` schema so tool bugs surface instead of reaching the model silently.
+fn check_declared_return(tool: &dyn LlmTool, result: Value) -> crate::error::Result<Value> {
+    if cfg!(debug_assertions) {
+        let descriptor = tool.descriptor();
+        if let Some(schema) = &descriptor.function.returns {
+            if let Err(violations) = validate_against_schema(&result, schema) {
+                return Err(crate::error::MojenticError::ToolError(format!(
+                    "Tool {:?} returned a value that doesn't match its declared schema: {}",
+                    descriptor.function.name,
+                    violations.join("; ")
+                )));
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Feedback for a call to an unregistered tool, listing the tools that do exist
+/// so the model can correct itself on the next turn.
+fn tool_not_found_message(name: &str, tools: &[Box<dyn LlmTool>]) -> String {
+    let available: Vec<String> = tools.iter().map(|t| t.descriptor().function.name).collect();
+    let available = if available.is_empty() {
+        "none".to_string()
+    } else {
+        available.join(", ")
+    };
+    format!("Tool {:?} does not exist; available tools: {}", name, available)
+}
+
 /// Convenience: build a fresh cancellation token for callers that don't have
 /// one already (used by the sync broker code path).
 pub fn fresh_cancel_token() -> CancellationToken {
@@ -197,7 +262,7 @@ pub fn fresh_cancel_token() -> CancellationToken {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::llm::tools::tool::{FunctionDescriptor, ToolDescriptor};
+    use crate::llm::tools::tool::{FunctionDescriptor, ToolDescriptor, ToolResultCache};
     use serde_json::json;
 
     struct EchoTool;
@@ -219,6 +284,7 @@ mod tests {
                     name: "echo".to_string(),
                     description: "Echo".to_string(),
                     parameters: json!({}),
+                    returns: None,
                 },
             }
         }
@@ -238,6 +304,95 @@ mod tests {
         }
     }
 
+    /// Counts its runs and returns the run number
+    #[derive(Clone)]
+    struct CountingTool {
+        runs: Arc<std::sync::atomic::AtomicUsize>,
+        cacheable: bool,
+    }
+
+    impl CountingTool {
+        fn new(cacheable: bool) -> Self {
+            Self {
+                runs: Arc::new(std::sync::atomic::AtomicUsize::new(0)),
+                cacheable,
+            }
+        }
+
+        fn runs(&self) -> usize {
+            self.runs.load(std::sync::atomic::Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl LlmTool for CountingTool {
+        async fn run(
+            &self,
+            _args: &HashMap<String, Value>,
+            _ctx: &ToolRunCtx,
+        ) -> crate::error::Result<Value> {
+            let run = self.runs.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+            Ok(json!({ "run": run }))
+        }
+
+        fn descriptor(&self) -> ToolDescriptor {
+            ToolDescriptor {
+                r#type: "function".to_string(),
+                function: FunctionDescriptor {
+                    name: "count".to_string(),
+                    description: "Count".to_string(),
+                    parameters: json!({}),
+                    returns: None,
+                },
+            }
+        }
+
+        fn cacheable(&self) -> bool {
+            self.cacheable
+        }
+
+        fn clone_box(&self) -> Box<dyn LlmTool> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn cacheable_tool_result_is_reused_across_batches() {
+        let tool = CountingTool::new(true);
+        let tools: Vec<Box<dyn LlmTool>> = vec![Box::new(tool.clone())];
+        let ctx = ToolRunCtx {
+            result_cache: Some(ToolResultCache::new()),
+            ..Default::default()
+        };
+
+        let first = SerialToolRunner.run_batch(&[exec("1", "count", "a")], &tools, &ctx).await;
+        let second = SerialToolRunner.run_batch(&[exec("2", "count", "a")], &tools, &ctx).await;
+        let other = SerialToolRunner.run_batch(&[exec("3", "count", "b")], &tools, &ctx).await;
+
+        assert_eq!(tool.runs(), 2);
+        assert_eq!(first[0].result, Some(json!({"run": 1})));
+        assert_eq!(second[0].id, "2");
+        assert_eq!(second[0].result, Some(json!({"run": 1})));
+        assert_eq!(other[0].result, Some(json!({"run": 2})));
+    }
+
+    #[tokio::test]
+    async fn tools_that_are_not_cacheable_always_run() {
+        let tool = CountingTool::new(false);
+        let tools: Vec<Box<dyn LlmTool>> = vec![Box::new(tool.clone())];
+        let calls = vec![exec("1", "count", "a"), exec("2", "count", "a")];
+        let ctx = ToolRunCtx {
+            result_cache: Some(ToolResultCache::new()),
+            ..Default::default()
+        };
+
+        let outcomes = SerialToolRunner.run_batch(&calls, &tools, &ctx).await;
+
+        assert_eq!(tool.runs(), 2);
+        assert_eq!(outcomes[1].result, Some(json!({"run": 2})));
+        assert!(ctx.result_cache.unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn serial_preserves_input_order() {
         let runner = SerialToolRunner;
@@ -283,6 +438,61 @@ mod tests {
         assert!(outcomes[0].error.as_ref().map(|s| s.contains("missing")).unwrap_or(false));
     }
 
+    struct MisdeclaredTool;
+
+    #[async_trait]
+    impl LlmTool for MisdeclaredTool {
+        async fn run(
+            &self,
+            _args: &HashMap<String, Value>,
+            _ctx: &ToolRunCtx,
+        ) -> crate::error::Result<Value> {
+            Ok(json!({ "count": "three" }))
+        }
+
+        fn descriptor(&self) -> ToolDescriptor {
+            ToolDescriptor {
+                r#type: "function".to_string(),
+                function: FunctionDescriptor {
+                    name: "counter".to_string(),
+                    description: "Counts things".to_string(),
+                    parameters: json!({}),
+                    returns: Some(json!({
+                        "type": "object",
+                        "properties": { "count": { "type": "integer" } },
+                        "required": ["count"]
+                    })),
+                },
+            }
+        }
+
+        fn clone_box(&self) -> Box<dyn LlmTool> {
+            Box::new(MisdeclaredTool)
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    #[tokio::test]
+    async fn results_violating_declared_schema_fail_in_debug_builds() {
+        let runner = SerialToolRunner;
+        let tools: Vec<Box<dyn LlmTool>> = vec![Box::new(MisdeclaredTool)];
+        let calls = vec![exec("1", "counter", "x")];
+
+        let outcomes = runner.run_batch(&calls, &tools, &ToolRunCtx::default()).await;
+
+        assert!(!outcomes[0].ok);
+        let error = outcomes[0].error.as_deref().unwrap();
+        assert!(error.contains("doesn't match its declared schema"));
+        assert!(error.contains("$.count: expected integer, found string"));
+    }
+
+    #[tokio::test]
+    async fn declared_returns_is_not_sent_to_providers() {
+        let descriptor = serde_json::to_value(MisdeclaredTool.descriptor()).unwrap();
+
+        assert!(descriptor["function"].get("returns").is_none());
+    }
+
     #[tokio::test]
     async fn pre_cancelled_batch_skips_dispatch() {
         let runner = ParallelToolRunner::new(2);
@@ -0,0 +1,360 @@
+use crate::error::{MojenticError, Result};
+use crate::llm::tools::{FunctionDescriptor, LlmTool, ToolDescriptor, ToolRunCtx};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// Longest a command may run unless configured otherwise
+pub const DEFAULT_SHELL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Characters a shell would interpret, refused in arguments by default
+pub const SHELL_METACHARACTERS: &[char] = &[
+    ';', '&', '|', '<', '>', '$', '`', '(', ')', '{', '}', '[', ']', '*', '?', '!', '~', '#', '\'',
+    '"', '\\', '\n', '\r',
+];
+
+/// Tool that runs allowlisted commands inside a working directory
+///
+/// Execution is default-deny: a tool with no allowed commands refuses every
+/// request, and only programs named exactly in the allowlist may run. Commands
+/// are spawned directly rather than through a shell, so pipes, redirects and
+/// substitutions never take effect. Arguments containing shell metacharacters
+/// are still refused unless explicitly permitted with
+/// [`ShellTool::allow_metacharacters`], which passes them through literally
+/// (e.g. a `grep` pattern such as `foo|bar`). Arguments may still name paths
+/// outside the working directory, so allow only programs that are safe with
+/// any arguments.
+///
+/// Commands that outlive the timeout, or whose batch is cancelled, are killed.
+/// The result reports stdout, stderr, the exit code, and whether the command
+/// timed out.
+///
+/// # Examples
+///
+/// ```ignore
+/// use mojentic::llm::tools::shell_tool::ShellTool;
+///
+/// let tool = ShellTool::new("/path/to/project")?
+///     .with_allowed_commands(["ls", "cargo"])
+///     .with_timeout(Duration::from_secs(120));
+///
+/// let mut args = HashMap::new();
+/// args.insert("command".to_string(), json!("cargo"));
+/// args.insert("args".to_string(), json!(["test"]));
+///
+/// let result = tool.run(&args, &ToolRunCtx::default()).await?;
+/// // result contains exit_code, stdout, stderr and timed_out
+/// ```
+#[derive(Debug, Clone)]
+pub struct ShellTool {
+    working_dir: PathBuf,
+    allowed_commands: BTreeSet<String>,
+    timeout: Duration,
+    allow_metacharacters: bool,
+}
+
+impl ShellTool {
+    /// Creates a ShellTool that runs commands in `working_dir`, with nothing allowed yet
+    pub fn new<P: AsRef<Path>>(working_dir: P) -> Result<Self> {
+        let working_dir = working_dir.as_ref();
+
+        if !working_dir.is_dir() {
+            return Err(MojenticError::ToolError(format!(
+                "Working directory {:?} is not a directory",
+                working_dir
+            )));
+        }
+
+        Ok(Self {
+            working_dir: working_dir.canonicalize()?,
+            allowed_commands: BTreeSet::new(),
+            timeout: DEFAULT_SHELL_TIMEOUT,
+            allow_metacharacters: false,
+        })
+    }
+
+    /// Add programs that may be run, matched exactly against the requested command
+    pub fn with_allowed_commands<I, S>(mut self, commands: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed_commands.extend(commands.into_iter().map(Into::into));
+        self
+    }
+
+    /// Set how long a command may run before it is killed
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Accept shell metacharacters in arguments, passed to the program literally
+    pub fn allow_metacharacters(mut self) -> Self {
+        self.allow_metacharacters = true;
+        self
+    }
+
+    /// Check a request against the allowlist and metacharacter policy
+    fn validate(&self, command: &str, args: &[String]) -> Result<()> {
+        if !self.allowed_commands.contains(command) {
+            let allowed: Vec<&str> = self.allowed_commands.iter().map(String::as_str).collect();
+            return Err(MojenticError::ToolError(format!(
+                "Command '{}' is not allowed (allowed commands: {})",
+                command,
+                if allowed.is_empty() {
+                    "none".to_string()
+                } else {
+                    allowed.join(", ")
+                }
+            )));
+        }
+
+        if !self.allow_metacharacters {
+            if let Some(arg) = args.iter().find(|arg| arg.contains(SHELL_METACHARACTERS)) {
+                return Err(MojenticError::ToolError(format!(
+                    "Argument '{}' contains shell metacharacters, which are not permitted",
+                    arg
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl LlmTool for ShellTool {
+    async fn run(&self, args: &HashMap<String, Value>, ctx: &ToolRunCtx) -> Result<Value> {
+        let command = args.get("command").and_then(Value::as_str).ok_or_else(|| {
+            MojenticError::ToolError("run_command requires a 'command' argument".to_string())
+        })?;
+        let command_args: Vec<String> = match args.get("args") {
+            None | Some(Value::Null) => Vec::new(),
+            Some(Value::Array(items)) => items
+                .iter()
+                .map(|item| {
+                    item.as_str().map(str::to_string).ok_or_else(|| {
+                        MojenticError::ToolError(
+                            "run_command 'args' must be an array of strings".to_string(),
+                        )
+                    })
+                })
+                .collect::<Result<_>>()?,
+            Some(_) => {
+                return Err(MojenticError::ToolError(
+                    "run_command 'args' must be an array of strings".to_string(),
+                ))
+            }
+        };
+
+        self.validate(command, &command_args)?;
+
+        let child = Command::new(command)
+            .args(&command_args)
+            .current_dir(&self.working_dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| {
+                MojenticError::ToolError(format!("Failed to start '{}': {}", command, e))
+            })?;
+
+        // Dropping the output future kills the child, on timeout or cancellation
+        let output = tokio::select! {
+            result = tokio::time::timeout(self.timeout, child.wait_with_output()) => result,
+            _ = ctx.cancel.cancelled() => {
+                return Err(MojenticError::ToolError(format!("'{}' was cancelled", command)));
+            }
+        };
+
+        match output {
+            Ok(output) => {
+                let output = output?;
+                Ok(json!({
+                    "command": command,
+                    "args": command_args,
+                    "exit_code": output.status.code(),
+                    "stdout": String::from_utf8_lossy(&output.stdout),
+                    "stderr": String::from_utf8_lossy(&output.stderr),
+                    "timed_out": false
+                }))
+            }
+            Err(_) => Ok(json!({
+                "command": command,
+                "args": command_args,
+                "exit_code": null,
+                "stdout": "",
+                "stderr": "",
+                "timed_out": true,
+                "error": format!(
+                    "'{}' did not finish within {} seconds and was killed",
+                    command,
+                    self.timeout.as_secs_f64()
+                )
+            })),
+        }
+    }
+
+    fn descriptor(&self) -> ToolDescriptor {
+        let allowed: Vec<&str> = self.allowed_commands.iter().map(String::as_str).collect();
+        ToolDescriptor {
+            r#type: "function".to_string(),
+            function: FunctionDescriptor {
+                name: "run_command".to_string(),
+                description: format!(
+                    "Run a program in the project directory and return its output. Only these programs may be run: {}. Arguments are passed directly to the program, not through a shell.",
+                    if allowed.is_empty() { "none".to_string() } else { allowed.join(", ") }
+                ),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "command": {
+                            "type": "string",
+                            "enum": allowed,
+                            "description": "The program to run"
+                        },
+                        "args": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "description": "Arguments to pass to the program"
+                        }
+                    },
+                    "required": ["command"]
+                }),
+                returns: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "command": {"type": "string"},
+                        "args": {"type": "array", "items": {"type": "string"}},
+                        "exit_code": {"type": ["integer", "null"]},
+                        "stdout": {"type": "string"},
+                        "stderr": {"type": "string"},
+                        "timed_out": {"type": "boolean"},
+                        "error": {"type": "string"}
+                    },
+                    "required": ["command", "args", "exit_code", "stdout", "stderr", "timed_out"]
+                })),
+            },
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn LlmTool> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn command_args(command: &str, args: &[&str]) -> HashMap<String, Value> {
+        HashMap::from([
+            ("command".to_string(), json!(command)),
+            ("args".to_string(), json!(args)),
+        ])
+    }
+
+    #[test]
+    fn test_descriptor_lists_allowed_commands() {
+        let dir = TempDir::new().unwrap();
+        let tool = ShellTool::new(dir.path()).unwrap().with_allowed_commands(["ls", "echo"]);
+
+        let descriptor = tool.descriptor();
+
+        assert_eq!(descriptor.function.name, "run_command");
+        assert_eq!(
+            descriptor.function.parameters["properties"]["command"]["enum"],
+            json!(["echo", "ls"])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_runs_allowed_command_in_working_directory() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("marker.txt"), "").unwrap();
+        let tool = ShellTool::new(dir.path()).unwrap().with_allowed_commands(["echo", "ls"]);
+
+        let echo = tool
+            .run(&command_args("echo", &["hello", "world"]), &ToolRunCtx::default())
+            .await
+            .unwrap();
+        let ls = tool.run(&command_args("ls", &[]), &ToolRunCtx::default()).await.unwrap();
+
+        assert_eq!(echo["stdout"], "hello world\n");
+        assert_eq!(echo["exit_code"], 0);
+        assert_eq!(echo["timed_out"], false);
+        assert_eq!(ls["stdout"], "marker.txt\n");
+    }
+
+    #[tokio::test]
+    async fn test_reports_failing_exit_code_and_stderr() {
+        let dir = TempDir::new().unwrap();
+        let tool = ShellTool::new(dir.path()).unwrap().with_allowed_commands(["ls"]);
+
+        let result = tool
+            .run(&command_args("ls", &["does-not-exist"]), &ToolRunCtx::default())
+            .await
+            .unwrap();
+
+        assert_ne!(result["exit_code"], 0);
+        assert!(!result["stderr"].as_str().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_blocks_commands_not_on_allowlist() {
+        let dir = TempDir::new().unwrap();
+        let default_deny = ShellTool::new(dir.path()).unwrap();
+        let tool = ShellTool::new(dir.path()).unwrap().with_allowed_commands(["echo"]);
+
+        for (tool, command) in [(&default_deny, "echo"), (&tool, "rm"), (&tool, "/bin/echo")] {
+            let result = tool.run(&command_args(command, &[]), &ToolRunCtx::default()).await;
+            assert!(matches!(result, Err(MojenticError::ToolError(_))), "{} ran", command);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_metacharacters_refused_unless_permitted() {
+        let dir = TempDir::new().unwrap();
+        let strict = ShellTool::new(dir.path()).unwrap().with_allowed_commands(["echo"]);
+        let permissive = strict.clone().allow_metacharacters();
+        let args = command_args("echo", &["hi; rm -rf ."]);
+
+        let refused = strict.run(&args, &ToolRunCtx::default()).await;
+        let literal = permissive.run(&args, &ToolRunCtx::default()).await.unwrap();
+
+        assert!(matches!(refused, Err(MojenticError::ToolError(_))));
+        assert_eq!(literal["stdout"], "hi; rm -rf .\n");
+    }
+
+    #[tokio::test]
+    async fn test_kills_command_after_timeout() {
+        let dir = TempDir::new().unwrap();
+        let tool = ShellTool::new(dir.path())
+            .unwrap()
+            .with_allowed_commands(["sleep"])
+            .with_timeout(Duration::from_millis(100));
+        let start = std::time::Instant::now();
+
+        let result =
+            tool.run(&command_args("sleep", &["10"]), &ToolRunCtx::default()).await.unwrap();
+
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(result["timed_out"], true);
+        assert_eq!(result["exit_code"], Value::Null);
+    }
+
+    #[test]
+    fn test_working_directory_must_exist() {
+        let result = ShellTool::new("/definitely/not/a/real/directory");
+
+        assert!(matches!(result, Err(MojenticError::ToolError(_))));
+    }
+}
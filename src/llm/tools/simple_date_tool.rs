@@ -149,6 +149,7 @@ impl LlmTool for SimpleDateTool {
                     },
                     "required": ["relative_date"]
                 }),
+                returns: None,
             },
         }
     }
@@ -80,6 +80,7 @@ impl LlmTool for TellUserTool {
                     },
                     "required": ["message"]
                 }),
+                returns: None,
             },
         }
     }
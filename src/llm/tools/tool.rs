@@ -2,6 +2,10 @@ use crate::error::Result;
 use async_trait::async_trait;
 use serde_json::Value;
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::Semaphore;
 use tokio_util::sync::CancellationToken;
 
 /// Descriptor for tool function parameters
@@ -16,6 +20,33 @@ pub struct FunctionDescriptor {
     pub name: String,
     pub description: String,
     pub parameters: Value,
+    /// Optional JSON schema describing the value `run` returns.
+    ///
+    /// Kept out of the descriptor sent to providers. In debug builds the tool
+    /// runner validates each result against it and reports mismatches as tool
+    /// errors, catching tool bugs early.
+    #[serde(default, skip_serializing)]
+    pub returns: Option<Value>,
+}
+
+impl FunctionDescriptor {
+    /// Create a descriptor without a `returns` schema
+    ///
+    /// Unlike a struct literal, this keeps compiling when optional fields are added.
+    pub fn new(name: impl Into<String>, description: impl Into<String>, parameters: Value) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            parameters,
+            returns: None,
+        }
+    }
+
+    /// Set the JSON schema the tool's results must match
+    pub fn with_returns(mut self, returns: Value) -> Self {
+        self.returns = Some(returns);
+        self
+    }
 }
 
 /// Context passed to a tool runner (and, optionally, to the tools it runs)
@@ -33,6 +64,164 @@ pub struct ToolRunCtx {
     pub correlation_id: Option<String>,
     /// Optional source identifier propagated to per-tool tracing.
     pub source: Option<String>,
+    /// Optional limit on tool calls in flight, shared across batches. The
+    /// built-in runners hold a permit for the duration of each tool call.
+    pub concurrency: Option<Arc<Semaphore>>,
+    /// Optional memo of earlier results from [cacheable](LlmTool::cacheable)
+    /// tools. The built-in runners answer a repeated call from it instead of
+    /// running the tool again.
+    pub result_cache: Option<ToolResultCache>,
+    /// Optional destination for progress updates from long-running tools
+    /// (see [`ToolRunCtx::report_progress`]).
+    pub progress: Option<ToolProgressSender>,
+}
+
+impl ToolRunCtx {
+    /// Report progress from a running tool
+    ///
+    /// Lets a slow tool (an HTTP fetch, a shell command) show that it is still
+    /// working. [`crate::llm::LlmBroker::generate_stream_with_progress`]
+    /// forwards each update into its stream. Does nothing when no one is
+    /// listening.
+    pub fn report_progress(&self, tool_name: &str, message: impl Into<String>) {
+        if let Some(progress) = &self.progress {
+            progress.send(tool_name, message);
+        }
+    }
+}
+
+/// A progress update from a running tool (see [`ToolRunCtx::report_progress`])
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolProgress {
+    pub tool_name: String,
+    pub message: String,
+    /// Time since the tool batch started
+    pub elapsed: Duration,
+}
+
+/// Sends a batch's [`ToolProgress`] updates to whoever is listening
+#[derive(Debug, Clone)]
+pub struct ToolProgressSender {
+    sender: UnboundedSender<ToolProgress>,
+    started: Instant,
+}
+
+impl ToolProgressSender {
+    /// Send updates to `sender`, timing them from now
+    pub fn new(sender: UnboundedSender<ToolProgress>) -> Self {
+        Self {
+            sender,
+            started: Instant::now(),
+        }
+    }
+
+    /// Send an update, dropping it if the receiver has gone away
+    pub fn send(&self, tool_name: &str, message: impl Into<String>) {
+        let _ = self.sender.send(ToolProgress {
+            tool_name: tool_name.to_string(),
+            message: message.into(),
+            elapsed: self.started.elapsed(),
+        });
+    }
+}
+
+/// Results of cacheable tool calls, keyed on the tool name and arguments
+///
+/// Clones share the same entries, so one cache can follow a conversation
+/// across turns. Only successful results are stored.
+#[derive(Debug, Clone, Default)]
+pub struct ToolResultCache {
+    entries: Arc<Mutex<HashMap<(String, String), Value>>>,
+}
+
+impl ToolResultCache {
+    /// Create an empty cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The stored result of calling `name` with `args`, if there is one
+    pub fn get(&self, name: &str, args: &HashMap<String, Value>) -> Option<Value> {
+        self.entries.lock().unwrap().get(&Self::key(name, args)).cloned()
+    }
+
+    /// Store the result of calling `name` with `args`
+    pub fn insert(&self, name: &str, args: &HashMap<String, Value>, result: Value) {
+        self.entries.lock().unwrap().insert(Self::key(name, args), result);
+    }
+
+    /// Number of stored results
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// Whether no results are stored
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forget every stored result
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    /// Arguments are serialized through a JSON object, whose keys are sorted,
+    /// so the key doesn't depend on `HashMap` iteration order
+    fn key(name: &str, args: &HashMap<String, Value>) -> (String, String) {
+        let args: serde_json::Map<String, Value> =
+            args.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+        (name.to_string(), Value::Object(args).to_string())
+    }
+}
+
+/// Provider whose tool-definition JSON to produce (see [`LlmTool::to_provider_json`])
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolProvider {
+    /// An entry of the Chat Completions `tools` array
+    OpenAI,
+    /// An entry of Ollama's `/api/chat` `tools` array
+    Ollama,
+}
+
+/// How strongly the model should be steered towards a tool (see [`LlmTool::priority`])
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ToolPriority {
+    /// A fallback, to use only when no other tool fits
+    Low,
+    /// No preference
+    #[default]
+    Normal,
+    /// Preferred over other tools that could also apply
+    High,
+}
+
+/// The `tools` array a gateway sends for `tools`, in the given provider's format
+///
+/// Tools are listed from highest to lowest [`LlmTool::priority`], keeping the
+/// given order among tools of equal priority. Useful for handing tools to an
+/// external orchestrator that talks to the provider directly.
+pub fn tools_to_provider_json(tools: &[Box<dyn LlmTool>], provider: ToolProvider) -> Value {
+    let mut ordered: Vec<&Box<dyn LlmTool>> = tools.iter().collect();
+    ordered.sort_by_key(|tool| std::cmp::Reverse(tool.priority()));
+    Value::Array(ordered.into_iter().map(|tool| tool.to_provider_json(provider)).collect())
+}
+
+/// A tool's description with its usage hint and priority rendered in
+fn hinted_description(description: &str, hint: Option<&str>, priority: ToolPriority) -> String {
+    let mut description = description.to_string();
+    if let Some(hint) = hint {
+        description.push_str(&format!("\n\nUse this tool when: {}", hint));
+    }
+    match priority {
+        ToolPriority::High => {
+            description.push_str("\n\nPrefer this tool over others that could also apply.")
+        }
+        ToolPriority::Low => {
+            description.push_str("\n\nUse this tool only when no other tool fits.")
+        }
+        ToolPriority::Normal => {}
+    }
+    description
 }
 
 /// Trait for LLM tools.
@@ -54,6 +243,78 @@ pub trait LlmTool: Send + Sync {
         self.descriptor().function.name == name
     }
 
+    /// Whether repeated calls with the same arguments may reuse an earlier result
+    ///
+    /// Return `true` only for idempotent tools without side effects, such as
+    /// lookups of data that doesn't change during a conversation. When the
+    /// [`ToolRunCtx`] carries a [`ToolResultCache`], a repeated call to a
+    /// cacheable tool is answered from it without running the tool. Defaults
+    /// to `false`.
+    fn cacheable(&self) -> bool {
+        false
+    }
+
+    /// Check a call's arguments before the tool runs
+    ///
+    /// Tool runners call this before [`LlmTool::run`] and skip the run when it
+    /// fails, reporting the error back to the model so it can correct the
+    /// call. Tools that act on the outside world, such as writing files or
+    /// running commands, can use it to reject hostile input (path traversal,
+    /// injected shell syntax) from a misbehaving model. Accepts everything by
+    /// default.
+    fn validate_input(&self, _args: &HashMap<String, Value>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Whether this tool's result is the final answer
+    ///
+    /// When a terminal tool runs successfully the broker returns its result
+    /// (a string as-is, any other value as JSON) instead of sending it back to
+    /// the model, ending generation. Useful for tools whose output should
+    /// reach the user verbatim, such as a rendered chart URL. Defaults to
+    /// `false`.
+    fn terminal(&self) -> bool {
+        false
+    }
+
+    /// When the model should choose this tool, for agents offering many tools
+    ///
+    /// Rendered into the description sent to the provider, e.g. `"the user
+    /// asks about a date relative to today"`. Defaults to `None`.
+    fn usage_hint(&self) -> Option<String> {
+        None
+    }
+
+    /// How strongly the model should be steered towards this tool
+    ///
+    /// Non-default priorities are stated in the description sent to the
+    /// provider, and tools are offered from highest to lowest priority.
+    /// Defaults to [`ToolPriority::Normal`].
+    fn priority(&self) -> ToolPriority {
+        ToolPriority::Normal
+    }
+
+    /// The tool's definition as the given provider expects it in a request
+    ///
+    /// OpenAI and Ollama currently share the function-tool shape
+    /// (`{"type": "function", "function": {...}}`), with the
+    /// [`usage_hint`](Self::usage_hint) and [`priority`](Self::priority)
+    /// appended to the description. The declared `returns` schema is never
+    /// included.
+    fn to_provider_json(&self, provider: ToolProvider) -> Value {
+        let mut descriptor = self.descriptor();
+        descriptor.function.description = hinted_description(
+            &descriptor.function.description,
+            self.usage_hint().as_deref(),
+            self.priority(),
+        );
+        match provider {
+            ToolProvider::OpenAI | ToolProvider::Ollama => {
+                serde_json::to_value(descriptor).expect("tool descriptors serialize to JSON")
+            }
+        }
+    }
+
     /// Clone the tool into a Box
     ///
     /// This method is required to support cloning trait objects.
@@ -79,6 +340,7 @@ mod tests {
                         "arg1": {"type": "string"}
                     }
                 }),
+                returns: None,
             },
         };
 
@@ -116,6 +378,7 @@ mod tests {
             name: "test".to_string(),
             description: "desc".to_string(),
             parameters: json!({"type": "object"}),
+            returns: None,
         };
 
         let desc2 = desc1.clone();
@@ -123,6 +386,29 @@ mod tests {
         assert_eq!(desc1.description, desc2.description);
     }
 
+    #[test]
+    fn test_function_descriptor_new() {
+        let desc = FunctionDescriptor::new("test", "desc", json!({"type": "object"}));
+        assert_eq!(desc.name, "test");
+        assert!(desc.returns.is_none());
+
+        let desc = desc.with_returns(json!({"type": "string"}));
+        assert_eq!(desc.returns, Some(json!({"type": "string"})));
+    }
+
+    #[test]
+    fn test_tool_result_cache_key_ignores_argument_order() {
+        let cache = ToolResultCache::new();
+        let args: HashMap<String, Value> = (0..16).map(|i| (format!("arg{i}"), json!(i))).collect();
+        let reordered: HashMap<String, Value> =
+            (0..16).rev().map(|i| (format!("arg{i}"), json!(i))).collect();
+
+        cache.insert("lookup", &args, json!("found"));
+
+        assert_eq!(cache.get("lookup", &reordered), Some(json!("found")));
+        assert_eq!(cache.get("other", &reordered), None);
+    }
+
     struct MockTool;
 
     #[async_trait]
@@ -138,6 +424,7 @@ mod tests {
                     name: "mock_tool".to_string(),
                     description: "A mock tool".to_string(),
                     parameters: json!({}),
+                    returns: None,
                 },
             }
         }
@@ -162,4 +449,90 @@ mod tests {
         let result = tool.run(&args, &ctx).await.unwrap();
         assert_eq!(result, json!("result"));
     }
+
+    #[test]
+    fn test_tools_to_provider_json() {
+        let tools: Vec<Box<dyn LlmTool>> = vec![Box::new(MockTool)];
+        let expected = json!([{
+            "type": "function",
+            "function": {"name": "mock_tool", "description": "A mock tool", "parameters": {}}
+        }]);
+
+        assert_eq!(tools_to_provider_json(&tools, ToolProvider::OpenAI), expected);
+        assert_eq!(tools_to_provider_json(&tools, ToolProvider::Ollama), expected);
+    }
+
+    struct HintedTool {
+        name: &'static str,
+        priority: ToolPriority,
+    }
+
+    #[async_trait]
+    impl LlmTool for HintedTool {
+        async fn run(&self, _args: &HashMap<String, Value>, _ctx: &ToolRunCtx) -> Result<Value> {
+            Ok(json!("result"))
+        }
+
+        fn descriptor(&self) -> ToolDescriptor {
+            ToolDescriptor {
+                r#type: "function".to_string(),
+                function: FunctionDescriptor {
+                    name: self.name.to_string(),
+                    description: "Look up an order".to_string(),
+                    parameters: json!({}),
+                    returns: None,
+                },
+            }
+        }
+
+        fn usage_hint(&self) -> Option<String> {
+            Some("the user mentions an order number".to_string())
+        }
+
+        fn priority(&self) -> ToolPriority {
+            self.priority
+        }
+
+        fn clone_box(&self) -> Box<dyn LlmTool> {
+            Box::new(HintedTool {
+                name: self.name,
+                priority: self.priority,
+            })
+        }
+    }
+
+    #[test]
+    fn test_usage_hints_and_priority_are_rendered() {
+        let tools: Vec<Box<dyn LlmTool>> = vec![
+            Box::new(MockTool),
+            Box::new(HintedTool {
+                name: "fallback_lookup",
+                priority: ToolPriority::Low,
+            }),
+            Box::new(HintedTool {
+                name: "order_lookup",
+                priority: ToolPriority::High,
+            }),
+        ];
+
+        let rendered = tools_to_provider_json(&tools, ToolProvider::OpenAI);
+
+        let names: Vec<&str> = rendered
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["function"]["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["order_lookup", "mock_tool", "fallback_lookup"]);
+        assert_eq!(
+            rendered[0]["function"]["description"],
+            "Look up an order\n\nUse this tool when: the user mentions an order number\n\n\
+             Prefer this tool over others that could also apply."
+        );
+        assert_eq!(rendered[1]["function"]["description"], "A mock tool");
+        assert!(rendered[2]["function"]["description"]
+            .as_str()
+            .unwrap()
+            .ends_with("Use this tool only when no other tool fits."));
+    }
 }
@@ -53,6 +53,7 @@ impl ToolWrapper {
             content: Some(self.behaviour.clone()),
             tool_calls: None,
             image_paths: None,
+            cache: false,
         }]
     }
 }
@@ -78,6 +79,7 @@ impl LlmTool for ToolWrapper {
             content: Some(input.to_string()),
             tool_calls: None,
             image_paths: None,
+            cache: false,
         });
 
         let response = self.broker.generate(&messages, Some(&self.tools), None, None).await?;
@@ -102,6 +104,7 @@ impl LlmTool for ToolWrapper {
                     "required": ["input"],
                     "additionalProperties": false
                 }),
+                returns: None,
             },
         }
     }
@@ -163,6 +166,8 @@ mod tests {
                 object: None,
                 tool_calls: vec![],
                 thinking: None,
+                usage: None,
+                payload_size: None,
             })
         }
 
@@ -302,6 +307,7 @@ mod tests {
                         name: "mock_tool".to_string(),
                         description: "A mock tool".to_string(),
                         parameters: json!({}),
+                        returns: None,
                     },
                 }
             }
@@ -0,0 +1,189 @@
+use crate::error::{MojenticError, Result};
+use crate::llm::tools::{FunctionDescriptor, LlmTool, ToolDescriptor, ToolRunCtx};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Longest wait a single call may request unless configured otherwise
+pub const DEFAULT_MAX_WAIT_SECONDS: f64 = 60.0;
+
+/// Tool that lets the model pause before its next step
+///
+/// Useful for agents polling an external job: the model can wait between
+/// status checks instead of calling a status tool in a tight loop. Requests
+/// longer than the configured maximum are clamped to it, and the wait ends
+/// early if the batch is cancelled.
+///
+/// # Examples
+///
+/// ```ignore
+/// use mojentic::llm::tools::wait_tool::WaitTool;
+///
+/// let tool = WaitTool::new().with_max_seconds(30.0);
+/// let mut args = HashMap::new();
+/// args.insert("seconds".to_string(), json!(5));
+///
+/// let result = tool.run(&args, &ToolRunCtx::default()).await?;
+/// // result contains waited_seconds and a confirmation message
+/// ```
+#[derive(Clone)]
+pub struct WaitTool {
+    max_seconds: f64,
+}
+
+impl WaitTool {
+    /// Creates a new WaitTool capped at [`DEFAULT_MAX_WAIT_SECONDS`]
+    pub fn new() -> Self {
+        Self {
+            max_seconds: DEFAULT_MAX_WAIT_SECONDS,
+        }
+    }
+
+    /// Set the longest wait a single call may request
+    pub fn with_max_seconds(mut self, max_seconds: f64) -> Self {
+        self.max_seconds = max_seconds.max(0.0);
+        self
+    }
+}
+
+impl Default for WaitTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl LlmTool for WaitTool {
+    async fn run(&self, args: &HashMap<String, Value>, ctx: &ToolRunCtx) -> Result<Value> {
+        let requested = args.get("seconds").and_then(Value::as_f64).ok_or_else(|| {
+            MojenticError::ToolError("wait requires a numeric 'seconds' argument".to_string())
+        })?;
+        if !requested.is_finite() || requested < 0.0 {
+            return Err(MojenticError::ToolError(format!(
+                "wait 'seconds' must be a non-negative number, got {}",
+                requested
+            )));
+        }
+
+        let seconds = requested.min(self.max_seconds);
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs_f64(seconds)) => {}
+            _ = ctx.cancel.cancelled() => {
+                return Err(MojenticError::ToolError("wait was cancelled".to_string()));
+            }
+        }
+
+        let message = if seconds < requested {
+            format!("Waited {} seconds (capped from the requested {})", seconds, requested)
+        } else {
+            format!("Waited {} seconds", seconds)
+        };
+        Ok(json!({
+            "waited_seconds": seconds,
+            "message": message
+        }))
+    }
+
+    fn descriptor(&self) -> ToolDescriptor {
+        ToolDescriptor {
+            r#type: "function".to_string(),
+            function: FunctionDescriptor {
+                name: "wait".to_string(),
+                description: format!(
+                    "Pause for a number of seconds before continuing, e.g. between checks on a long-running job. Waits longer than {} seconds are shortened to {} seconds.",
+                    self.max_seconds, self.max_seconds
+                ),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "seconds": {
+                            "type": "number",
+                            "description": "How many seconds to wait"
+                        }
+                    },
+                    "required": ["seconds"]
+                }),
+                returns: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "waited_seconds": {"type": "number"},
+                        "message": {"type": "string"}
+                    },
+                    "required": ["waited_seconds", "message"]
+                })),
+            },
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn LlmTool> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::Instant;
+
+    fn wait_args(seconds: Value) -> HashMap<String, Value> {
+        HashMap::from([("seconds".to_string(), seconds)])
+    }
+
+    #[test]
+    fn test_descriptor() {
+        let descriptor = WaitTool::new().descriptor();
+
+        assert_eq!(descriptor.function.name, "wait");
+        assert_eq!(descriptor.function.parameters["required"], json!(["seconds"]));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_waits_requested_duration() {
+        let tool = WaitTool::new();
+        let start = Instant::now();
+
+        let result = tool.run(&wait_args(json!(5)), &ToolRunCtx::default()).await.unwrap();
+
+        assert_eq!(start.elapsed(), Duration::from_secs(5));
+        assert_eq!(result["waited_seconds"], 5.0);
+        assert_eq!(result["message"], "Waited 5 seconds");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_clamps_to_max_seconds() {
+        let tool = WaitTool::new().with_max_seconds(10.0);
+        let start = Instant::now();
+
+        let result = tool.run(&wait_args(json!(3600)), &ToolRunCtx::default()).await.unwrap();
+
+        assert_eq!(start.elapsed(), Duration::from_secs(10));
+        assert_eq!(result["waited_seconds"], 10.0);
+        assert!(result["message"].as_str().unwrap().contains("capped"));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_invalid_seconds() {
+        let tool = WaitTool::new();
+
+        for args in [
+            HashMap::new(),
+            wait_args(json!("soon")),
+            wait_args(json!(-1)),
+        ] {
+            let result = tool.run(&args, &ToolRunCtx::default()).await;
+            assert!(matches!(result, Err(MojenticError::ToolError(_))));
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_cancellation_ends_wait_early() {
+        let tool = WaitTool::new();
+        let ctx = ToolRunCtx::default();
+        ctx.cancel.cancel();
+
+        let result = tool.run(&wait_args(json!(30)), &ctx).await;
+
+        assert!(matches!(result, Err(MojenticError::ToolError(_))));
+    }
+}
@@ -193,6 +193,7 @@ impl LlmTool for WebSearchTool {
                     },
                     "required": ["query"]
                 }),
+                returns: None,
             },
         }
     }
@@ -21,7 +21,7 @@ use crate::realtime::config::{
 };
 use crate::realtime::events::{InterruptReason, RealtimeEvent, SessionCloseReason, TokenUsage};
 use crate::realtime::gateway::RealtimeGatewaySession;
-use crate::tracer::TracerSystem;
+use crate::tracer::{ToolCallStatus, TracerSystem};
 use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -545,6 +545,9 @@ impl SessionDriver {
             cancel: turn.cancel_token.clone(),
             correlation_id: Some(self.correlation_id.clone()),
             source: Some("RealtimeVoiceBroker".to_string()),
+            concurrency: None,
+            result_cache: None,
+            progress: None,
         };
 
         let outcomes = self.tool_runner.run_batch(&executions, &tools_vec, &ctx).await;
@@ -567,10 +570,16 @@ impl SessionDriver {
 
         if let Some(tracer) = &self.tracer {
             for (exec, outcome) in executions.iter().zip(outcomes.iter()) {
-                tracer.record_tool_call(
+                let status = if outcome.ok {
+                    ToolCallStatus::Succeeded
+                } else {
+                    ToolCallStatus::RecoverableError
+                };
+                tracer.record_tool_outcome(
                     &outcome.name,
                     exec.args.clone(),
                     outcome.result.clone().unwrap_or(Value::Null),
+                    status,
                     Some("RealtimeVoiceBroker".to_string()),
                     Some(outcome.duration_ms as f64),
                     "RealtimeVoiceBroker",
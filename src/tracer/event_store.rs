@@ -3,8 +3,13 @@
 //! This module provides thread-safe event storage with support for callbacks,
 //! filtering by type, time range, and custom predicates.
 
-use super::tracer_events::TracerEvent;
-use std::sync::{Arc, Mutex};
+use super::tracer_events::{SummaryFormat, TracerEvent};
+use serde_json::Value;
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::broadcast;
+
+/// How many recorded events a slow subscriber may fall behind before missing some
+const SUBSCRIBER_CAPACITY: usize = 256;
 
 /// Type alias for event callback functions
 pub type EventCallback = Arc<dyn Fn(&dyn TracerEvent) + Send + Sync>;
@@ -17,9 +22,12 @@ pub type EventCallback = Arc<dyn Fn(&dyn TracerEvent) + Send + Sync>;
 /// - Filtering by time range
 /// - Custom filter predicates
 /// - Query for last N events
+/// - Subscribing to events as they are stored
 pub struct EventStore {
-    events: Arc<Mutex<Vec<Box<dyn TracerEvent>>>>,
+    events: Arc<Mutex<Vec<Arc<dyn TracerEvent>>>>,
     on_store_callback: Option<EventCallback>,
+    subscribers: broadcast::Sender<Arc<dyn TracerEvent>>,
+    summary_format: RwLock<SummaryFormat>,
 }
 
 impl EventStore {
@@ -32,12 +40,25 @@ impl EventStore {
         Self {
             events: Arc::new(Mutex::new(Vec::new())),
             on_store_callback,
+            subscribers: broadcast::channel(SUBSCRIBER_CAPACITY).0,
+            summary_format: RwLock::new(SummaryFormat::default()),
         }
     }
 
+    /// Set how timestamps and durations are rendered in event summaries
+    pub fn set_summary_format(&self, format: SummaryFormat) {
+        *self.summary_format.write().unwrap() = format;
+    }
+
+    /// Get the format used for event summaries
+    pub fn summary_format(&self) -> SummaryFormat {
+        *self.summary_format.read().unwrap()
+    }
+
     /// Store an event in the event store
     ///
-    /// If a callback is configured, it will be called with the stored event.
+    /// If a callback is configured, it will be called with the stored event. The
+    /// event is then delivered to every current subscriber.
     ///
     /// # Arguments
     ///
@@ -49,8 +70,20 @@ impl EventStore {
         }
 
         // Store the event
-        let mut events = self.events.lock().unwrap();
-        events.push(event);
+        let event: Arc<dyn TracerEvent> = Arc::from(event);
+        self.events.lock().unwrap().push(Arc::clone(&event));
+
+        // Sending only fails when nobody is subscribed
+        let _ = self.subscribers.send(event);
+    }
+
+    /// Subscribe to events as they are stored
+    ///
+    /// The receiver only sees events stored after subscribing. A receiver that
+    /// falls more than 256 events behind skips the oldest ones and reports
+    /// [`broadcast::error::RecvError::Lagged`].
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<dyn TracerEvent>> {
+        self.subscribers.subscribe()
     }
 
     /// Count events matching filters
@@ -104,7 +137,8 @@ impl EventStore {
 
     /// Get summaries of events matching filters
     ///
-    /// Returns printable summaries instead of cloning events
+    /// Returns printable summaries, rendered with the store's [`SummaryFormat`],
+    /// instead of cloning events
     ///
     /// # Arguments
     ///
@@ -121,6 +155,38 @@ impl EventStore {
         end_time: Option<f64>,
         filter_func: Option<&dyn super::EventFilterFn>,
     ) -> Vec<String> {
+        let format = self.summary_format();
+        self.map_matching(start_time, end_time, filter_func, |event| {
+            event.formatted_summary(&format)
+        })
+    }
+
+    /// Get structured JSON for events matching filters
+    ///
+    /// The JSON companion to [`get_event_summaries`](Self::get_event_summaries),
+    /// returning each event's [`TracerEvent::to_json`] representation.
+    ///
+    /// # Arguments
+    ///
+    /// * `start_time` - Include events with timestamp >= start_time
+    /// * `end_time` - Include events with timestamp <= end_time
+    /// * `filter_func` - Custom filter function to apply to events
+    pub fn get_event_json(
+        &self,
+        start_time: Option<f64>,
+        end_time: Option<f64>,
+        filter_func: Option<&dyn super::EventFilterFn>,
+    ) -> Vec<Value> {
+        self.map_matching(start_time, end_time, filter_func, |event| event.to_json())
+    }
+
+    fn map_matching<T>(
+        &self,
+        start_time: Option<f64>,
+        end_time: Option<f64>,
+        filter_func: Option<&dyn super::EventFilterFn>,
+        render: impl Fn(&dyn TracerEvent) -> T,
+    ) -> Vec<T> {
         let events = self.events.lock().unwrap();
         let mut result = Vec::new();
 
@@ -147,12 +213,24 @@ impl EventStore {
                 }
             }
 
-            result.push(event_ref.printable_summary());
+            result.push(render(event_ref));
         }
 
         result
     }
 
+    /// Get the events carrying the tag `key` with the given value, in stored order
+    pub fn get_events_by_tag(&self, key: &str, value: &str) -> Vec<Arc<dyn TracerEvent>> {
+        let events = self.events.lock().unwrap();
+        events
+            .iter()
+            .filter(|event| {
+                event.tags().and_then(|tags| tags.get(key)).is_some_and(|tag| tag == value)
+            })
+            .cloned()
+            .collect()
+    }
+
     /// Get the last N event summaries, optionally filtered
     ///
     /// # Arguments
@@ -182,7 +260,28 @@ impl EventStore {
             0
         };
 
-        filtered[start_idx..].iter().map(|e| e.as_ref().printable_summary()).collect()
+        let format = self.summary_format();
+        filtered[start_idx..]
+            .iter()
+            .map(|e| e.as_ref().formatted_summary(&format))
+            .collect()
+    }
+
+    /// Copy every event from `other` into this store, keeping timestamps
+    ///
+    /// The combined events are ordered by timestamp, so a sub-agent's events
+    /// interleave with this store's own. Events recorded at the same instant
+    /// keep their relative order. Merged events are not passed to this store's
+    /// callback or subscribers, and merging a store into itself does nothing.
+    pub fn merge_from(&self, other: &EventStore) {
+        if Arc::ptr_eq(&self.events, &other.events) {
+            return;
+        }
+        let incoming: Vec<_> = other.events.lock().unwrap().clone();
+
+        let mut events = self.events.lock().unwrap();
+        events.extend(incoming);
+        events.sort_by(|a, b| a.timestamp().total_cmp(&b.timestamp()));
     }
 
     /// Clear all events from the store
@@ -306,6 +405,30 @@ mod tests {
         assert_eq!(store.len(), 5);
     }
 
+    #[test]
+    fn test_get_event_json_filters_events() {
+        let store = EventStore::default();
+
+        for i in 0..3 {
+            store.store(Box::new(LlmCallTracerEvent {
+                timestamp: 100.0 + i as f64,
+                correlation_id: format!("test-{}", i),
+                source: "test".to_string(),
+                model: "llama3.2".to_string(),
+                messages: vec![],
+                temperature: 1.0,
+                tools: None,
+            }));
+        }
+
+        let json = store.get_event_json(Some(101.0), None, None);
+
+        assert_eq!(json.len(), 2);
+        assert_eq!(json[0]["type"], "LlmCallTracerEvent");
+        assert_eq!(json[0]["correlation_id"], "test-1");
+        assert_eq!(json[1]["correlation_id"], "test-2");
+    }
+
     #[test]
     fn test_len_and_is_empty() {
         let store = EventStore::default();
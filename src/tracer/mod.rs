@@ -11,7 +11,13 @@
 //! - **TracerEvent**: Base trait for all event types with timestamps and correlation IDs
 //! - **EventStore**: Thread-safe storage for events with callbacks and filtering
 //! - **TracerSystem**: Coordination layer providing convenience methods for recording events
+//! - **ScopedTracer**: Read-only view of a shared tracer filtered to one agent's events
 //! - **NullTracer**: Null object pattern for when tracing is disabled
+//! - **TracerRedactor**: Hook that masks sensitive content before events are stored
+//! - **TracerStats**: Per-tool call and failure counts kept by a TracerSystem
+//! - **TraceSampling**: Policy that records only some requests under heavy load
+//! - **TracerVerbosity**: Whether producers record full message content or metadata only
+//! - **SummaryFormat**: How timestamps and durations are rendered in printable summaries
 //!
 //! # Event Types
 //!
@@ -54,14 +60,23 @@
 
 pub mod event_store;
 pub mod null_tracer;
+pub mod redaction;
+pub mod sampling;
+pub mod scoped_tracer;
+pub mod stats;
 pub mod tracer_events;
 pub mod tracer_system;
 
 // Re-export main types
 pub use event_store::{EventCallback, EventStore};
 pub use null_tracer::NullTracer;
+pub use redaction::{redact_pii, TracerRedactor};
+pub use sampling::TraceSampling;
+pub use scoped_tracer::ScopedTracer;
+pub use stats::{ToolStats, TracerStats};
 pub use tracer_events::{
-    AgentInteractionTracerEvent, EventFilterFn, LlmCallTracerEvent, LlmResponseTracerEvent,
-    ToolCallTracerEvent, TracerEvent,
+    AgentInteractionTracerEvent, DurationUnit, EventFilterFn, LlmCallTracerEvent,
+    LlmResponseTracerEvent, SpanTracerEvent, SummaryFormat, TaggedTracerEvent, TimestampFormat,
+    ToolCallStatus, ToolCallTracerEvent, TracerEvent,
 };
-pub use tracer_system::TracerSystem;
+pub use tracer_system::{TracerSystem, TracerVerbosity};
@@ -65,6 +65,21 @@ impl NullTracer {
         // Do nothing
     }
 
+    /// Do nothing implementation of record_llm_response_with_payload
+    #[allow(clippy::too_many_arguments)]
+    pub fn record_llm_response_with_payload(
+        &self,
+        _model: impl Into<String>,
+        _content: impl Into<String>,
+        _tool_calls: Option<Vec<HashMap<String, serde_json::Value>>>,
+        _call_duration_ms: Option<f64>,
+        _payload_size: Option<crate::llm::models::PayloadSize>,
+        _source: impl Into<String>,
+        _correlation_id: impl Into<String>,
+    ) {
+        // Do nothing
+    }
+
     /// Do nothing implementation of record_tool_call
     #[allow(clippy::too_many_arguments)]
     pub fn record_tool_call(
@@ -103,6 +118,16 @@ impl NullTracer {
         Vec::new()
     }
 
+    /// Return an empty vector for any get_event_json request
+    pub fn get_event_json(
+        &self,
+        _start_time: Option<f64>,
+        _end_time: Option<f64>,
+        _filter_func: Option<&dyn super::EventFilterFn>,
+    ) -> Vec<serde_json::Value> {
+        Vec::new()
+    }
+
     /// Return an empty vector for any get_last_n_summaries request
     pub fn get_last_n_summaries(
         &self,
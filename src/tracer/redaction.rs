@@ -0,0 +1,143 @@
+//! Redaction of sensitive content in tracer events
+//!
+//! Tracer events copy message content, responses, and tool arguments, which can
+//! include personal data. A [`TracerRedactor`] installed with
+//! [`TracerSystem::with_redactor`](super::TracerSystem::with_redactor) rewrites
+//! that content before an event is stored or delivered to callbacks and
+//! subscribers. [`redact_pii`] masks emails, phone numbers, and card numbers.
+
+use regex::{Captures, Regex};
+use serde_json::Value;
+use std::sync::{Arc, LazyLock};
+
+/// Rewrites the content of a tracer event in place
+///
+/// Called once for each content value an event carries (message bodies,
+/// response text, tool arguments and results).
+pub type TracerRedactor = Arc<dyn Fn(&mut Value) + Send + Sync>;
+
+/// Replaces email addresses
+pub const REDACTED_EMAIL: &str = "[REDACTED_EMAIL]";
+
+/// Replaces phone numbers
+pub const REDACTED_PHONE: &str = "[REDACTED_PHONE]";
+
+/// Replaces card numbers
+pub const REDACTED_CARD: &str = "[REDACTED_CARD]";
+
+static EMAIL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}")
+        .expect("email pattern is valid")
+});
+
+/// Runs of 13 to 19 digits, optionally grouped with spaces or dashes
+static CARD: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(?:\d[ -]?){12,18}\d\b").expect("card pattern is valid"));
+
+/// North American and international numbers such as `(555) 123-4567` or `+44 20 7946 0958`
+static PHONE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:\+\d{1,3}[ .-]?)?(?:\(\d{2,4}\)[ .-]?|\b\d{2,4}[ .-])\d{3,4}[ .-]?\d{4}\b")
+        .expect("phone pattern is valid")
+});
+
+/// Mask emails, phone numbers, and card numbers in every string within `value`
+///
+/// Digit runs are only treated as card numbers when they pass the Luhn check,
+/// so order numbers and timestamps are left alone. Object keys are not changed.
+///
+/// # Examples
+///
+/// ```
+/// use mojentic::tracer::redaction::redact_pii;
+/// use serde_json::json;
+///
+/// let mut value = json!({"content": "Reach me at ada@example.com"});
+/// redact_pii(&mut value);
+/// assert_eq!(value, json!({"content": "Reach me at [REDACTED_EMAIL]"}));
+/// ```
+pub fn redact_pii(value: &mut Value) {
+    match value {
+        Value::String(text) => {
+            if let Some(redacted) = redact_pii_text(text) {
+                *text = redacted;
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_pii),
+        Value::Object(fields) => fields.values_mut().for_each(redact_pii),
+        _ => {}
+    }
+}
+
+/// The redacted text, or `None` if nothing needed masking
+fn redact_pii_text(text: &str) -> Option<String> {
+    let emails = EMAIL.replace_all(text, REDACTED_EMAIL);
+    let cards = CARD.replace_all(&emails, |caps: &Captures| {
+        if passes_luhn(&caps[0]) {
+            REDACTED_CARD.to_string()
+        } else {
+            caps[0].to_string()
+        }
+    });
+    let phones = PHONE.replace_all(&cards, REDACTED_PHONE);
+
+    (phones != text).then(|| phones.into_owned())
+}
+
+/// Whether the digits in `candidate` form a valid Luhn checksum
+fn passes_luhn(candidate: &str) -> bool {
+    let digits: Vec<u32> = candidate.chars().filter_map(|c| c.to_digit(10)).collect();
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &digit)| match (i % 2, digit * 2) {
+            (1, doubled) if doubled > 9 => doubled - 9,
+            (1, doubled) => doubled,
+            _ => digit,
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn redacted(text: &str) -> Value {
+        let mut value = json!(text);
+        redact_pii(&mut value);
+        value
+    }
+
+    #[test]
+    fn test_masks_emails() {
+        assert_eq!(
+            redacted("mail ada.l+x@math.example.org now"),
+            json!("mail [REDACTED_EMAIL] now")
+        );
+    }
+
+    #[test]
+    fn test_masks_phone_numbers() {
+        assert_eq!(redacted("call (555) 123-4567"), json!("call [REDACTED_PHONE]"));
+        assert_eq!(redacted("call 555.123.4567"), json!("call [REDACTED_PHONE]"));
+        assert_eq!(redacted("call +44 20 7946 0958"), json!("call [REDACTED_PHONE]"));
+    }
+
+    #[test]
+    fn test_masks_luhn_valid_card_numbers_only() {
+        assert_eq!(redacted("card 4111 1111 1111 1111"), json!("card [REDACTED_CARD]"));
+        assert_eq!(redacted("card 4111-1111-1111-1111"), json!("card [REDACTED_CARD]"));
+        assert_eq!(redacted("order 1234567890123"), json!("order 1234567890123"));
+    }
+
+    #[test]
+    fn test_walks_nested_values() {
+        let mut value = json!({"args": {"to": ["ada@example.com"]}, "count": 3});
+
+        redact_pii(&mut value);
+
+        assert_eq!(value, json!({"args": {"to": ["[REDACTED_EMAIL]"]}, "count": 3}));
+    }
+}
@@ -0,0 +1,139 @@
+//! Sampling of traced requests
+//!
+//! Recording every event can cost too much at high request rates. A
+//! [`TraceSampling`] policy installed with
+//! [`TracerSystem::with_sampling`](super::TracerSystem::with_sampling) decides
+//! per correlation id whether a request is traced, so a request is either
+//! recorded completely or not at all.
+
+use super::tracer_events::TracerEvent;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+
+/// Sampled-out requests whose events are held in case they fail
+pub const MAX_PENDING_TRACES: usize = 256;
+
+/// Which requests a tracer records
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TraceSampling {
+    /// Record every request
+    #[default]
+    All,
+    /// Record one request in `n`, chosen by correlation id
+    OneIn(u32),
+    /// Record one request in `n`, plus every request that reports an error
+    ///
+    /// Events of sampled-out requests are held back until the request reports
+    /// an error (see [`TracerEvent::is_error`]), at which point its whole trace
+    /// is recorded. Only the most recent [`MAX_PENDING_TRACES`] requests are
+    /// held; older ones are dropped.
+    ErrorsAndOneIn(u32),
+}
+
+impl TraceSampling {
+    /// Whether the request with this correlation id is sampled in
+    ///
+    /// The decision depends only on the correlation id, so every event of a
+    /// request gets the same answer.
+    pub fn samples(&self, correlation_id: &str) -> bool {
+        match self {
+            TraceSampling::All => true,
+            TraceSampling::OneIn(n) | TraceSampling::ErrorsAndOneIn(n) => {
+                let mut hasher = DefaultHasher::new();
+                correlation_id.hash(&mut hasher);
+                hasher.finish().is_multiple_of(u64::from(*n))
+            }
+        }
+    }
+}
+
+/// What is known about a sampled-out request
+enum Pending {
+    /// Events held until the request fails or is evicted
+    Held(Vec<Box<dyn TracerEvent>>),
+    /// The request failed, so the rest of its events are recorded
+    Promoted,
+}
+
+/// Applies a [`TraceSampling`] policy to events on their way to the store
+#[derive(Default)]
+pub(crate) struct Sampler {
+    policy: TraceSampling,
+    pending: Mutex<PendingTraces>,
+}
+
+#[derive(Default)]
+struct PendingTraces {
+    traces: HashMap<String, Pending>,
+    order: VecDeque<String>,
+}
+
+impl Sampler {
+    pub(crate) fn new(policy: TraceSampling) -> Self {
+        Self {
+            policy,
+            pending: Mutex::default(),
+        }
+    }
+
+    /// The events to store now, in order, given a newly recorded one
+    pub(crate) fn admit(&self, event: Box<dyn TracerEvent>) -> Vec<Box<dyn TracerEvent>> {
+        if self.policy.samples(event.correlation_id()) {
+            return vec![event];
+        }
+        if !matches!(self.policy, TraceSampling::ErrorsAndOneIn(_)) {
+            return Vec::new();
+        }
+
+        let mut pending = self.pending.lock().unwrap();
+        let correlation_id = event.correlation_id().to_string();
+        if !pending.traces.contains_key(&correlation_id) {
+            if pending.order.len() >= MAX_PENDING_TRACES {
+                if let Some(oldest) = pending.order.pop_front() {
+                    pending.traces.remove(&oldest);
+                }
+            }
+            pending.order.push_back(correlation_id.clone());
+            pending.traces.insert(correlation_id.clone(), Pending::Held(Vec::new()));
+        }
+
+        let trace = pending.traces.get_mut(&correlation_id).expect("pending trace was just added");
+        match trace {
+            Pending::Promoted => vec![event],
+            Pending::Held(held) if event.is_error() => {
+                let mut admitted = std::mem::take(held);
+                admitted.push(event);
+                *trace = Pending::Promoted;
+                admitted
+            }
+            Pending::Held(held) => {
+                held.push(event);
+                Vec::new()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_samples_everything() {
+        assert!(TraceSampling::All.samples("any"));
+    }
+
+    #[test]
+    fn test_one_in_n_is_stable_per_correlation_id() {
+        let policy = TraceSampling::OneIn(2);
+        let ids: Vec<String> = (0..64).map(|i| format!("request-{i}")).collect();
+
+        let sampled = ids.iter().filter(|id| policy.samples(id)).count();
+
+        assert!(ids.iter().all(|id| policy.samples(id) == policy.samples(id)));
+        assert!(sampled > 0 && sampled < ids.len());
+        assert!(ids.iter().all(|id| TraceSampling::OneIn(1).samples(id)));
+    }
+}
@@ -0,0 +1,194 @@
+//! Per-agent views over a shared tracer
+//!
+//! When many agents record into one [`TracerSystem`](super::TracerSystem), their
+//! events interleave. A [`ScopedTracer`] answers the same queries as the tracer
+//! system, but only over events whose `source` belongs to a single agent.
+
+use super::event_store::EventStore;
+use super::tracer_events::{EventFilterFn, TracerEvent};
+use std::sync::Arc;
+
+/// Read-only view of a tracer's events, filtered to one source
+///
+/// An event belongs to the scope when its source equals the scope name, or is
+/// namespaced under it with `::` (so the `"LlmBroker"` scope also includes
+/// `"LlmBroker::generate_object"`). Create one with
+/// [`TracerSystem::scoped`](super::TracerSystem::scoped).
+///
+/// # Examples
+///
+/// ```
+/// use mojentic::tracer::TracerSystem;
+///
+/// let tracer = TracerSystem::default();
+/// tracer.record_llm_call("llama3.2", vec![], 0.7, None, "planner", "corr-1");
+/// tracer.record_llm_call("llama3.2", vec![], 0.7, None, "critic", "corr-2");
+///
+/// let planner = tracer.scoped("planner");
+/// assert_eq!(planner.len(), 1);
+/// ```
+#[derive(Clone)]
+pub struct ScopedTracer {
+    event_store: Arc<EventStore>,
+    source: String,
+}
+
+impl ScopedTracer {
+    pub(crate) fn new(event_store: Arc<EventStore>, source: impl Into<String>) -> Self {
+        Self {
+            event_store,
+            source: source.into(),
+        }
+    }
+
+    /// Get the source this view is scoped to
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Check whether an event belongs to this scope
+    pub fn includes(&self, event: &dyn TracerEvent) -> bool {
+        let source = event.source();
+        source == self.source
+            || source
+                .strip_prefix(self.source.as_str())
+                .is_some_and(|rest| rest.starts_with("::"))
+    }
+
+    /// Get summaries of this scope's events, optionally filtered further
+    ///
+    /// # Arguments
+    ///
+    /// * `start_time` - Include events with timestamp >= start_time
+    /// * `end_time` - Include events with timestamp <= end_time
+    /// * `filter_func` - Custom filter function to apply to events
+    pub fn get_event_summaries(
+        &self,
+        start_time: Option<f64>,
+        end_time: Option<f64>,
+        filter_func: Option<&dyn EventFilterFn>,
+    ) -> Vec<String> {
+        let filter = self.combined_filter(filter_func);
+        self.event_store.get_event_summaries(start_time, end_time, Some(&filter))
+    }
+
+    /// Get structured JSON for this scope's events, optionally filtered further
+    ///
+    /// # Arguments
+    ///
+    /// * `start_time` - Include events with timestamp >= start_time
+    /// * `end_time` - Include events with timestamp <= end_time
+    /// * `filter_func` - Custom filter function to apply to events
+    pub fn get_event_json(
+        &self,
+        start_time: Option<f64>,
+        end_time: Option<f64>,
+        filter_func: Option<&dyn EventFilterFn>,
+    ) -> Vec<serde_json::Value> {
+        let filter = self.combined_filter(filter_func);
+        self.event_store.get_event_json(start_time, end_time, Some(&filter))
+    }
+
+    /// Get the last N summaries of this scope's events, optionally filtered further
+    ///
+    /// # Arguments
+    ///
+    /// * `n` - Number of events to return
+    /// * `filter_func` - Optional custom filter function
+    pub fn get_last_n_summaries(
+        &self,
+        n: usize,
+        filter_func: Option<&dyn EventFilterFn>,
+    ) -> Vec<String> {
+        let filter = self.combined_filter(filter_func);
+        self.event_store.get_last_n_summaries(n, Some(&filter))
+    }
+
+    /// Count this scope's events, optionally filtered further
+    ///
+    /// # Arguments
+    ///
+    /// * `start_time` - Include events with timestamp >= start_time
+    /// * `end_time` - Include events with timestamp <= end_time
+    /// * `filter_func` - Custom filter function to apply to events
+    pub fn count_events(
+        &self,
+        start_time: Option<f64>,
+        end_time: Option<f64>,
+        filter_func: Option<&dyn EventFilterFn>,
+    ) -> usize {
+        let filter = self.combined_filter(filter_func);
+        self.event_store.count_events(start_time, end_time, Some(&filter))
+    }
+
+    /// Get the number of events in this scope
+    pub fn len(&self) -> usize {
+        self.count_events(None, None, None)
+    }
+
+    /// Check whether this scope has no events
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn combined_filter<'f>(
+        &'f self,
+        filter_func: Option<&'f dyn EventFilterFn>,
+    ) -> impl Fn(&dyn TracerEvent) -> bool + Send + Sync + 'f {
+        move |event: &dyn TracerEvent| {
+            self.includes(event) && filter_func.is_none_or(|filter| filter.matches(event))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tracer::TracerSystem;
+
+    fn shared_tracer() -> TracerSystem {
+        let tracer = TracerSystem::default();
+        tracer.record_llm_call("llama3.2", vec![], 0.7, None, "planner", "corr-1");
+        tracer.record_llm_response("llama3.2", "Plan", None, None, "planner", "corr-1");
+        tracer.record_llm_call("llama3.2", vec![], 0.7, None, "critic", "corr-2");
+        tracer.record_llm_call("llama3.2", vec![], 0.7, None, "planner_v2", "corr-3");
+        tracer.record_llm_response("llama3.2", "{}", None, None, "planner::object", "corr-1");
+        tracer
+    }
+
+    #[test]
+    fn test_scoped_view_only_returns_matching_events() {
+        let tracer = shared_tracer();
+
+        let planner = tracer.scoped("planner");
+        let summaries = planner.get_event_summaries(None, None, None);
+
+        assert_eq!(planner.len(), 3);
+        assert_eq!(summaries.len(), 3);
+        assert!(summaries.iter().all(|s| !s.contains("corr-2") && !s.contains("corr-3")));
+        assert_eq!(tracer.scoped("critic").len(), 1);
+        assert!(tracer.scoped("nobody").is_empty());
+    }
+
+    #[test]
+    fn test_scoped_view_applies_additional_filter() {
+        let tracer = shared_tracer();
+        let planner = tracer.scoped("planner");
+
+        let corr_1 = |event: &dyn TracerEvent| event.correlation_id() == "corr-1";
+
+        assert_eq!(planner.count_events(None, None, Some(&corr_1)), 3);
+        assert_eq!(planner.get_last_n_summaries(1, Some(&corr_1)).len(), 1);
+    }
+
+    #[test]
+    fn test_scoped_view_sees_events_recorded_later() {
+        let tracer = TracerSystem::default();
+        let critic = tracer.scoped("critic");
+        assert!(critic.is_empty());
+
+        tracer.record_llm_call("llama3.2", vec![], 0.7, None, "critic", "corr-1");
+
+        assert_eq!(critic.len(), 1);
+    }
+}
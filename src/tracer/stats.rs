@@ -0,0 +1,63 @@
+//! Running counts of tool outcomes.
+//!
+//! A [`TracerSystem`](super::TracerSystem) counts every tool call it is asked
+//! to record, including calls that sampling leaves out of the event store, so
+//! failure rates can be monitored without keeping every event.
+
+use super::tracer_events::ToolCallStatus;
+use std::collections::HashMap;
+
+/// Outcomes of the calls made to one tool
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ToolStats {
+    /// Calls made, whatever their outcome
+    pub calls: usize,
+    /// Calls whose error was returned to the model, which could try again
+    pub recoverable_errors: usize,
+    /// Calls whose error ended the request
+    pub hard_errors: usize,
+}
+
+impl ToolStats {
+    /// Calls that failed, recoverably or not
+    pub fn failures(&self) -> usize {
+        self.recoverable_errors + self.hard_errors
+    }
+
+    /// Fraction of calls that failed, or 0.0 before any call
+    pub fn failure_rate(&self) -> f64 {
+        if self.calls == 0 {
+            0.0
+        } else {
+            self.failures() as f64 / self.calls as f64
+        }
+    }
+
+    pub(crate) fn record(&mut self, status: ToolCallStatus) {
+        self.calls += 1;
+        match status {
+            ToolCallStatus::Succeeded => {}
+            ToolCallStatus::RecoverableError => self.recoverable_errors += 1,
+            ToolCallStatus::HardError => self.hard_errors += 1,
+        }
+    }
+}
+
+/// Tool outcomes counted by a tracer, from [`TracerSystem::stats`](super::TracerSystem::stats)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TracerStats {
+    /// Counts for each tool, by name
+    pub tools: HashMap<String, ToolStats>,
+}
+
+impl TracerStats {
+    /// Counts for `tool_name`, all zero if it was never called
+    pub fn tool(&self, tool_name: &str) -> ToolStats {
+        self.tools.get(tool_name).copied().unwrap_or_default()
+    }
+
+    /// Calls made to any tool
+    pub fn total_tool_calls(&self) -> usize {
+        self.tools.values().map(|stats| stats.calls).sum()
+    }
+}
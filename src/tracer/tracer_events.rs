@@ -4,7 +4,7 @@
 //! LLM calls, tool executions, and agent interactions. All events implement the
 //! `TracerEvent` trait which provides timestamps, correlation IDs, and printable summaries.
 
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, SecondsFormat};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -27,6 +27,60 @@ where
     }
 }
 
+/// How timestamps are rendered in event summaries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TimestampFormat {
+    /// Local wall-clock time with milliseconds, e.g. `14:03:27.512`
+    #[default]
+    LocalTime,
+    /// RFC 3339 in UTC with milliseconds, e.g. `2024-05-01T18:03:27.512Z`
+    Rfc3339,
+    /// Milliseconds since the Unix epoch, e.g. `1714586607512`
+    EpochMillis,
+}
+
+/// Unit used for call durations in event summaries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DurationUnit {
+    /// Milliseconds with two decimals, e.g. `150.50ms`
+    #[default]
+    Milliseconds,
+    /// Seconds with three decimals, e.g. `0.151s`
+    Seconds,
+}
+
+/// Controls how timestamps and durations appear in printable summaries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SummaryFormat {
+    /// How event timestamps are rendered
+    pub timestamp: TimestampFormat,
+    /// Unit for call durations
+    pub duration: DurationUnit,
+}
+
+impl SummaryFormat {
+    /// Render a Unix timestamp (in seconds) according to this format
+    pub fn format_timestamp(&self, timestamp: f64) -> String {
+        let millis = (timestamp * 1000.0).round() as i64;
+        let utc = DateTime::from_timestamp_millis(millis).unwrap_or(DateTime::UNIX_EPOCH);
+        match self.timestamp {
+            TimestampFormat::LocalTime => {
+                utc.with_timezone(&Local).format("%H:%M:%S%.3f").to_string()
+            }
+            TimestampFormat::Rfc3339 => utc.to_rfc3339_opts(SecondsFormat::Millis, true),
+            TimestampFormat::EpochMillis => millis.to_string(),
+        }
+    }
+
+    /// Render a duration given in milliseconds according to this format
+    pub fn format_duration(&self, duration_ms: f64) -> String {
+        match self.duration {
+            DurationUnit::Milliseconds => format!("{:.2}ms", duration_ms),
+            DurationUnit::Seconds => format!("{:.3}s", duration_ms / 1000.0),
+        }
+    }
+}
+
 /// Base trait for all tracer events
 ///
 /// Tracer events are used to track system interactions for observability purposes.
@@ -43,6 +97,67 @@ pub trait TracerEvent: Send + Sync {
 
     /// Get a formatted string summary of the event
     fn printable_summary(&self) -> String;
+
+    /// Get a string summary of the event using the given timestamp and duration format
+    ///
+    /// Built-in events render every timestamp and duration in `format`. The
+    /// default starts from [`printable_summary`](Self::printable_summary) and,
+    /// when it opens with a bracketed time as the built-in summaries do,
+    /// replaces that time with [`timestamp`](Self::timestamp) in `format`.
+    fn formatted_summary(&self, format: &SummaryFormat) -> String {
+        let summary = self.printable_summary();
+        match summary.strip_prefix('[').and_then(|rest| rest.split_once("] ")) {
+            Some((_, rest)) => format!("[{}] {}", format.format_timestamp(self.timestamp()), rest),
+            None => summary,
+        }
+    }
+
+    /// Get a structured JSON representation of the event
+    ///
+    /// Built-in events carry every field plus a `type` naming the event's Rust
+    /// type, so tooling can consume traces without parsing
+    /// [`printable_summary`](Self::printable_summary). The default carries the
+    /// `type`, `timestamp`, `correlation_id` and `source`.
+    fn to_json(&self) -> serde_json::Value {
+        let type_name = std::any::type_name::<Self>();
+        serde_json::json!({
+            "type": type_name.rsplit("::").next().unwrap_or(type_name),
+            "timestamp": self.timestamp(),
+            "correlation_id": self.correlation_id(),
+            "source": self.source(),
+        })
+    }
+
+    /// Apply a redactor to each content value the event carries
+    ///
+    /// Events without user content (such as agent interactions) keep the
+    /// default, which changes nothing.
+    fn redact(&mut self, _redactor: &dyn Fn(&mut serde_json::Value)) {}
+
+    /// Whether the event records a failure
+    ///
+    /// Used by [`TraceSampling::ErrorsAndOneIn`](super::sampling::TraceSampling::ErrorsAndOneIn)
+    /// to keep the traces of failed requests. Defaults to `false`.
+    fn is_error(&self) -> bool {
+        false
+    }
+
+    /// Key-value tags attached to the event, such as a tenant or experiment
+    ///
+    /// Events recorded through a [`TracerSystem::tagged`](super::TracerSystem::tagged)
+    /// tracer carry that tracer's tags. Defaults to none.
+    fn tags(&self) -> Option<&HashMap<String, String>> {
+        None
+    }
+
+    /// The span the event's recorder was started from, such as the broker
+    /// that created a [child broker](crate::llm::LlmBroker::child)
+    ///
+    /// Events recorded through a [`TracerSystem::in_span`](super::TracerSystem::in_span)
+    /// tracer carry that span. Defaults to none.
+    fn parent_span_id(&self) -> Option<&str> {
+        None
+    }
 }
 
 /// Records when an LLM is called with specific messages
@@ -77,11 +192,23 @@ impl TracerEvent for LlmCallTracerEvent {
         &self.source
     }
 
+    fn to_json(&self) -> serde_json::Value {
+        tagged_json("LlmCallTracerEvent", self)
+    }
+
+    fn redact(&mut self, redactor: &dyn Fn(&mut serde_json::Value)) {
+        self.messages
+            .iter_mut()
+            .flat_map(|message| message.values_mut())
+            .for_each(redactor);
+    }
+
     fn printable_summary(&self) -> String {
-        let dt = DateTime::from_timestamp(self.timestamp as i64, 0)
-            .unwrap_or_else(|| DateTime::from_timestamp(0, 0).unwrap())
-            .with_timezone(&Local);
-        let time_str = dt.format("%H:%M:%S%.3f").to_string();
+        self.formatted_summary(&SummaryFormat::default())
+    }
+
+    fn formatted_summary(&self, format: &SummaryFormat) -> String {
+        let time_str = format.format_timestamp(self.timestamp);
 
         let mut summary = format!(
             "[{}] LlmCallTracerEvent (correlation_id: {})\n   Model: {}",
@@ -129,6 +256,18 @@ pub struct LlmResponseTracerEvent {
     pub tool_calls: Option<Vec<HashMap<String, serde_json::Value>>>,
     /// Duration of the LLM call in milliseconds
     pub call_duration_ms: Option<f64>,
+    /// Size of the serialized request body in bytes, when the gateway measured it
+    #[serde(default)]
+    pub request_bytes: Option<u64>,
+    /// Size of the response body in bytes, when the gateway measured it
+    #[serde(default)]
+    pub response_bytes: Option<u64>,
+    /// Prompt tokens the provider reported for the call
+    #[serde(default)]
+    pub prompt_tokens: Option<u64>,
+    /// Completion tokens the provider reported for the call
+    #[serde(default)]
+    pub completion_tokens: Option<u64>,
 }
 
 impl TracerEvent for LlmResponseTracerEvent {
@@ -144,11 +283,23 @@ impl TracerEvent for LlmResponseTracerEvent {
         &self.source
     }
 
+    fn to_json(&self) -> serde_json::Value {
+        tagged_json("LlmResponseTracerEvent", self)
+    }
+
+    fn redact(&mut self, redactor: &dyn Fn(&mut serde_json::Value)) {
+        redact_string(&mut self.content, redactor);
+        if let Some(tool_calls) = &mut self.tool_calls {
+            tool_calls.iter_mut().flat_map(|call| call.values_mut()).for_each(redactor);
+        }
+    }
+
     fn printable_summary(&self) -> String {
-        let dt = DateTime::from_timestamp(self.timestamp as i64, 0)
-            .unwrap_or_else(|| DateTime::from_timestamp(0, 0).unwrap())
-            .with_timezone(&Local);
-        let time_str = dt.format("%H:%M:%S%.3f").to_string();
+        self.formatted_summary(&SummaryFormat::default())
+    }
+
+    fn formatted_summary(&self, format: &SummaryFormat) -> String {
+        let time_str = format.format_timestamp(self.timestamp);
 
         let mut summary = format!(
             "[{}] LlmResponseTracerEvent (correlation_id: {})\n   Model: {}",
@@ -171,7 +322,18 @@ impl TracerEvent for LlmResponseTracerEvent {
         }
 
         if let Some(duration) = self.call_duration_ms {
-            summary.push_str(&format!("\n   Duration: {:.2}ms", duration));
+            summary.push_str(&format!("\n   Duration: {}", format.format_duration(duration)));
+        }
+
+        if let (Some(sent), Some(received)) = (self.request_bytes, self.response_bytes) {
+            summary.push_str(&format!(
+                "\n   Payload: {} bytes sent, {} bytes received",
+                sent, received
+            ));
+        }
+
+        if let (Some(prompt), Some(completion)) = (self.prompt_tokens, self.completion_tokens) {
+            summary.push_str(&format!("\n   Tokens: {} prompt, {} completion", prompt, completion));
         }
 
         summary
@@ -197,6 +359,22 @@ pub struct ToolCallTracerEvent {
     pub caller: Option<String>,
     /// Duration of the tool call in milliseconds
     pub call_duration_ms: Option<f64>,
+    /// Whether the call succeeded, and if not whether the request could go on
+    #[serde(default)]
+    pub status: ToolCallStatus,
+}
+
+/// How a tool call ended
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ToolCallStatus {
+    /// The tool returned a result
+    #[default]
+    Succeeded,
+    /// The tool failed and its error was returned to the model
+    RecoverableError,
+    /// The call failed in a way that ended the request, such as an unknown tool
+    /// under [`ToolNotFoundPolicy::Error`](crate::llm::ToolNotFoundPolicy::Error)
+    HardError,
 }
 
 impl TracerEvent for ToolCallTracerEvent {
@@ -212,11 +390,28 @@ impl TracerEvent for ToolCallTracerEvent {
         &self.source
     }
 
+    fn to_json(&self) -> serde_json::Value {
+        tagged_json("ToolCallTracerEvent", self)
+    }
+
+    fn redact(&mut self, redactor: &dyn Fn(&mut serde_json::Value)) {
+        self.arguments.values_mut().for_each(redactor);
+        redactor(&mut self.result);
+    }
+
+    /// Failed tool calls have a failed status, a null result, or an `error` field
+    fn is_error(&self) -> bool {
+        self.status != ToolCallStatus::Succeeded
+            || self.result.is_null()
+            || self.result.get("error").is_some()
+    }
+
     fn printable_summary(&self) -> String {
-        let dt = DateTime::from_timestamp(self.timestamp as i64, 0)
-            .unwrap_or_else(|| DateTime::from_timestamp(0, 0).unwrap())
-            .with_timezone(&Local);
-        let time_str = dt.format("%H:%M:%S%.3f").to_string();
+        self.formatted_summary(&SummaryFormat::default())
+    }
+
+    fn formatted_summary(&self, format: &SummaryFormat) -> String {
+        let time_str = format.format_timestamp(self.timestamp);
 
         let mut summary = format!(
             "[{}] ToolCallTracerEvent (correlation_id: {})\n   Tool: {}",
@@ -240,7 +435,7 @@ impl TracerEvent for ToolCallTracerEvent {
         }
 
         if let Some(duration) = self.call_duration_ms {
-            summary.push_str(&format!("\n   Duration: {:.2}ms", duration));
+            summary.push_str(&format!("\n   Duration: {}", format.format_duration(duration)));
         }
 
         summary
@@ -278,20 +473,29 @@ impl TracerEvent for ToolBatchTracerEvent {
         &self.source
     }
 
+    fn to_json(&self) -> serde_json::Value {
+        tagged_json("ToolBatchTracerEvent", self)
+    }
+
+    fn is_error(&self) -> bool {
+        self.failure_count > 0
+    }
+
     fn printable_summary(&self) -> String {
-        let dt = DateTime::from_timestamp(self.timestamp as i64, 0)
-            .unwrap_or_else(|| DateTime::from_timestamp(0, 0).unwrap())
-            .with_timezone(&Local);
-        let time_str = dt.format("%H:%M:%S%.3f").to_string();
+        self.formatted_summary(&SummaryFormat::default())
+    }
+
+    fn formatted_summary(&self, format: &SummaryFormat) -> String {
+        let time_str = format.format_timestamp(self.timestamp);
         format!(
-            "[{}] ToolBatchTracerEvent (correlation_id: {})\n   Batch: {}\n   Tools: {}\n   Outcome: {} ok / {} failed\n   Duration: {:.2}ms",
+            "[{}] ToolBatchTracerEvent (correlation_id: {})\n   Batch: {}\n   Tools: {}\n   Outcome: {} ok / {} failed\n   Duration: {}",
             time_str,
             self.correlation_id,
             self.batch_id,
             self.tool_names.join(", "),
             self.success_count,
             self.failure_count,
-            self.call_duration_ms
+            format.format_duration(self.call_duration_ms)
         )
     }
 }
@@ -328,11 +532,16 @@ impl TracerEvent for AgentInteractionTracerEvent {
         &self.source
     }
 
+    fn to_json(&self) -> serde_json::Value {
+        tagged_json("AgentInteractionTracerEvent", self)
+    }
+
     fn printable_summary(&self) -> String {
-        let dt = DateTime::from_timestamp(self.timestamp as i64, 0)
-            .unwrap_or_else(|| DateTime::from_timestamp(0, 0).unwrap())
-            .with_timezone(&Local);
-        let time_str = dt.format("%H:%M:%S%.3f").to_string();
+        self.formatted_summary(&SummaryFormat::default())
+    }
+
+    fn formatted_summary(&self, format: &SummaryFormat) -> String {
+        let time_str = format.format_timestamp(self.timestamp);
 
         let mut summary = format!(
             "[{}] AgentInteractionTracerEvent (correlation_id: {})\n   From: {} → To: {}\n   Event Type: {}",
@@ -347,6 +556,145 @@ impl TracerEvent for AgentInteractionTracerEvent {
     }
 }
 
+/// An event recorded with key-value tags
+///
+/// Wraps any other event and answers for it, adding the tags to its JSON
+/// under `tags` and to its summary.
+pub struct TaggedTracerEvent {
+    /// The event being tagged
+    pub event: Box<dyn TracerEvent>,
+    /// Tags attached to the event
+    pub tags: HashMap<String, String>,
+}
+
+impl TracerEvent for TaggedTracerEvent {
+    fn timestamp(&self) -> f64 {
+        self.event.timestamp()
+    }
+
+    fn correlation_id(&self) -> &str {
+        self.event.correlation_id()
+    }
+
+    fn source(&self) -> &str {
+        self.event.source()
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let mut value = self.event.to_json();
+        if let Some(fields) = value.as_object_mut() {
+            fields.insert("tags".to_string(), serde_json::json!(self.tags));
+        }
+        value
+    }
+
+    fn redact(&mut self, redactor: &dyn Fn(&mut serde_json::Value)) {
+        self.event.redact(redactor);
+    }
+
+    fn is_error(&self) -> bool {
+        self.event.is_error()
+    }
+
+    fn tags(&self) -> Option<&HashMap<String, String>> {
+        Some(&self.tags)
+    }
+
+    fn parent_span_id(&self) -> Option<&str> {
+        self.event.parent_span_id()
+    }
+
+    fn printable_summary(&self) -> String {
+        self.formatted_summary(&SummaryFormat::default())
+    }
+
+    fn formatted_summary(&self, format: &SummaryFormat) -> String {
+        let mut tags: Vec<_> = self.tags.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+        tags.sort();
+        format!("{}\n   Tags: {}", self.event.formatted_summary(format), tags.join(", "))
+    }
+}
+
+/// An event recorded within a parent span
+///
+/// Wraps any other event and answers for it, adding the parent span ID to its
+/// JSON under `parent_span_id` and to its summary.
+pub struct SpanTracerEvent {
+    /// The event recorded within the span
+    pub event: Box<dyn TracerEvent>,
+    /// ID of the span the event's recorder was started from
+    pub parent_span_id: String,
+}
+
+impl TracerEvent for SpanTracerEvent {
+    fn timestamp(&self) -> f64 {
+        self.event.timestamp()
+    }
+
+    fn correlation_id(&self) -> &str {
+        self.event.correlation_id()
+    }
+
+    fn source(&self) -> &str {
+        self.event.source()
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let mut value = self.event.to_json();
+        if let Some(fields) = value.as_object_mut() {
+            fields.insert("parent_span_id".to_string(), serde_json::json!(self.parent_span_id));
+        }
+        value
+    }
+
+    fn redact(&mut self, redactor: &dyn Fn(&mut serde_json::Value)) {
+        self.event.redact(redactor);
+    }
+
+    fn is_error(&self) -> bool {
+        self.event.is_error()
+    }
+
+    fn tags(&self) -> Option<&HashMap<String, String>> {
+        self.event.tags()
+    }
+
+    fn parent_span_id(&self) -> Option<&str> {
+        Some(&self.parent_span_id)
+    }
+
+    fn printable_summary(&self) -> String {
+        self.formatted_summary(&SummaryFormat::default())
+    }
+
+    fn formatted_summary(&self, format: &SummaryFormat) -> String {
+        format!(
+            "{}\n   Parent span: {}",
+            self.event.formatted_summary(format),
+            self.parent_span_id
+        )
+    }
+}
+
+/// Run a redactor over a plain string field
+fn redact_string(text: &mut String, redactor: &dyn Fn(&mut serde_json::Value)) {
+    let mut value = serde_json::Value::String(std::mem::take(text));
+    redactor(&mut value);
+    *text = match value {
+        serde_json::Value::String(redacted) => redacted,
+        other => other.to_string(),
+    };
+}
+
+/// Serialize an event and tag it with its type name
+fn tagged_json(event_type: &str, event: &impl Serialize) -> serde_json::Value {
+    let mut value = serde_json::to_value(event).unwrap_or_else(|_| serde_json::json!({}));
+    if let Some(fields) = value.as_object_mut() {
+        fields.insert("type".to_string(), serde_json::Value::String(event_type.to_string()));
+    }
+    value
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -356,6 +704,65 @@ mod tests {
         SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs_f64()
     }
 
+    #[test]
+    fn test_summary_format_renders_timestamps_and_durations() {
+        let rfc = SummaryFormat {
+            timestamp: TimestampFormat::Rfc3339,
+            duration: DurationUnit::Seconds,
+        };
+        let epoch = SummaryFormat {
+            timestamp: TimestampFormat::EpochMillis,
+            duration: DurationUnit::Milliseconds,
+        };
+
+        assert_eq!(rfc.format_timestamp(1_700_000_000.25), "2023-11-14T22:13:20.250Z");
+        assert_eq!(epoch.format_timestamp(1_700_000_000.25), "1700000000250");
+        assert_eq!(rfc.format_duration(1500.0), "1.500s");
+        assert_eq!(epoch.format_duration(1500.0), "1500.00ms");
+    }
+
+    #[test]
+    fn test_custom_event_defaults_follow_format_and_common_fields() {
+        struct CustomEvent;
+
+        impl TracerEvent for CustomEvent {
+            fn timestamp(&self) -> f64 {
+                1_700_000_000.25
+            }
+
+            fn correlation_id(&self) -> &str {
+                "custom-1"
+            }
+
+            fn source(&self) -> &str {
+                "test"
+            }
+
+            fn printable_summary(&self) -> String {
+                "[22:13:20.250] CustomEvent (correlation_id: custom-1)".to_string()
+            }
+        }
+
+        let format = SummaryFormat {
+            timestamp: TimestampFormat::EpochMillis,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            CustomEvent.formatted_summary(&format),
+            "[1700000000250] CustomEvent (correlation_id: custom-1)"
+        );
+        assert_eq!(
+            CustomEvent.to_json(),
+            serde_json::json!({
+                "type": "CustomEvent",
+                "timestamp": 1_700_000_000.25,
+                "correlation_id": "custom-1",
+                "source": "test",
+            })
+        );
+    }
+
     #[test]
     fn test_llm_call_event() {
         let event = LlmCallTracerEvent {
@@ -378,6 +785,35 @@ mod tests {
         assert!(summary.contains("llama3.2"));
     }
 
+    #[test]
+    fn test_llm_call_event_to_json() {
+        let mut tool = HashMap::new();
+        tool.insert("name".to_string(), serde_json::json!("get_weather"));
+        let mut message = HashMap::new();
+        message.insert("role".to_string(), serde_json::json!("user"));
+
+        let event = LlmCallTracerEvent {
+            timestamp: 1_700_000_000.5,
+            correlation_id: "test-123".to_string(),
+            source: "broker".to_string(),
+            model: "llama3.2".to_string(),
+            messages: vec![message],
+            temperature: 0.7,
+            tools: Some(vec![tool]),
+        };
+
+        let json = event.to_json();
+
+        assert_eq!(json["type"], "LlmCallTracerEvent");
+        assert_eq!(json["timestamp"], 1_700_000_000.5);
+        assert_eq!(json["correlation_id"], "test-123");
+        assert_eq!(json["source"], "broker");
+        assert_eq!(json["model"], "llama3.2");
+        assert_eq!(json["messages"], serde_json::json!([{"role": "user"}]));
+        assert_eq!(json["temperature"], 0.7);
+        assert_eq!(json["tools"], serde_json::json!([{"name": "get_weather"}]));
+    }
+
     #[test]
     fn test_llm_response_event() {
         let event = LlmResponseTracerEvent {
@@ -388,6 +824,10 @@ mod tests {
             content: "Hello, world!".to_string(),
             tool_calls: None,
             call_duration_ms: Some(150.5),
+            request_bytes: None,
+            response_bytes: None,
+            prompt_tokens: None,
+            completion_tokens: None,
         };
 
         assert_eq!(event.content, "Hello, world!");
@@ -399,6 +839,34 @@ mod tests {
         assert!(summary.contains("150.5"));
     }
 
+    #[test]
+    fn test_llm_response_event_to_json() {
+        let mut call = HashMap::new();
+        call.insert("name".to_string(), serde_json::json!("get_weather"));
+
+        let event = LlmResponseTracerEvent {
+            timestamp: current_timestamp(),
+            correlation_id: "test-456".to_string(),
+            source: "broker".to_string(),
+            model: "llama3.2".to_string(),
+            content: "Hello, world!".to_string(),
+            tool_calls: Some(vec![call]),
+            call_duration_ms: Some(150.5),
+            request_bytes: None,
+            response_bytes: None,
+            prompt_tokens: None,
+            completion_tokens: None,
+        };
+
+        let json = event.to_json();
+
+        assert_eq!(json["type"], "LlmResponseTracerEvent");
+        assert_eq!(json["model"], "llama3.2");
+        assert_eq!(json["content"], "Hello, world!");
+        assert_eq!(json["tool_calls"], serde_json::json!([{"name": "get_weather"}]));
+        assert_eq!(json["call_duration_ms"], 150.5);
+    }
+
     #[test]
     fn test_tool_call_event() {
         let mut args = HashMap::new();
@@ -413,6 +881,7 @@ mod tests {
             result: serde_json::json!({"output": "result"}),
             caller: Some("agent1".to_string()),
             call_duration_ms: Some(25.0),
+            status: ToolCallStatus::Succeeded,
         };
 
         assert_eq!(event.tool_name, "example_tool");
@@ -423,6 +892,58 @@ mod tests {
         assert!(summary.contains("example_tool"));
     }
 
+    #[test]
+    fn test_tool_call_event_to_json() {
+        let mut args = HashMap::new();
+        args.insert("input".to_string(), serde_json::json!("test"));
+
+        let event = ToolCallTracerEvent {
+            timestamp: current_timestamp(),
+            correlation_id: "test-789".to_string(),
+            source: "broker".to_string(),
+            tool_name: "example_tool".to_string(),
+            arguments: args,
+            result: serde_json::json!({"output": "result"}),
+            caller: None,
+            call_duration_ms: Some(25.0),
+            status: ToolCallStatus::Succeeded,
+        };
+
+        let json = event.to_json();
+
+        assert_eq!(json["type"], "ToolCallTracerEvent");
+        assert_eq!(json["tool_name"], "example_tool");
+        assert_eq!(json["arguments"], serde_json::json!({"input": "test"}));
+        assert_eq!(json["result"], serde_json::json!({"output": "result"}));
+        assert!(json["caller"].is_null());
+        assert_eq!(json["call_duration_ms"], 25.0);
+    }
+
+    #[test]
+    fn test_tool_batch_event_to_json() {
+        let event = ToolBatchTracerEvent {
+            timestamp: current_timestamp(),
+            correlation_id: "test-batch".to_string(),
+            source: "broker".to_string(),
+            batch_id: "batch-1".to_string(),
+            tool_names: vec!["walk".to_string(), "swim".to_string()],
+            success_count: 1,
+            failure_count: 1,
+            call_duration_ms: 12.5,
+            caller: Some("agent1".to_string()),
+        };
+
+        let json = event.to_json();
+
+        assert_eq!(json["type"], "ToolBatchTracerEvent");
+        assert_eq!(json["batch_id"], "batch-1");
+        assert_eq!(json["tool_names"], serde_json::json!(["walk", "swim"]));
+        assert_eq!(json["success_count"], 1);
+        assert_eq!(json["failure_count"], 1);
+        assert_eq!(json["call_duration_ms"], 12.5);
+        assert_eq!(json["caller"], "agent1");
+    }
+
     #[test]
     fn test_agent_interaction_event() {
         let event = AgentInteractionTracerEvent {
@@ -443,4 +964,26 @@ mod tests {
         assert!(summary.contains("agent1"));
         assert!(summary.contains("agent2"));
     }
+
+    #[test]
+    fn test_agent_interaction_event_to_json() {
+        let event = AgentInteractionTracerEvent {
+            timestamp: current_timestamp(),
+            correlation_id: "test-abc".to_string(),
+            source: "dispatcher".to_string(),
+            from_agent: "agent1".to_string(),
+            to_agent: "agent2".to_string(),
+            event_type: "message".to_string(),
+            event_id: Some("evt-123".to_string()),
+        };
+
+        let json = event.to_json();
+
+        assert_eq!(json["type"], "AgentInteractionTracerEvent");
+        assert_eq!(json["event_type"], "message");
+        assert_eq!(json["from_agent"], "agent1");
+        assert_eq!(json["to_agent"], "agent2");
+        assert_eq!(json["event_id"], "evt-123");
+        assert_eq!(json["source"], "dispatcher");
+    }
 }
@@ -5,11 +5,33 @@
 //! for recording different types of events.
 
 use super::event_store::EventStore;
+use super::redaction::TracerRedactor;
+use super::sampling::{Sampler, TraceSampling};
+use super::scoped_tracer::ScopedTracer;
+use super::stats::TracerStats;
 use super::tracer_events::*;
+use crate::llm::models::{PayloadSize, TokenUsage};
+use futures::stream::Stream;
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, PoisonError};
 use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast::error::RecvError;
+
+/// How much message detail producers should copy into tracer events
+///
+/// Recording full message bodies for every LLM call is expensive for long
+/// conversations. `Summary` keeps only metadata (roles, lengths, counts) so
+/// high-volume tracing doesn't duplicate whole histories in memory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TracerVerbosity {
+    /// Record metadata only, omitting message and response content and
+    /// replacing tool arguments and results with their sizes
+    Summary,
+    /// Record complete message and response content
+    #[default]
+    Full,
+}
 
 /// Central system for capturing and querying tracer events
 ///
@@ -19,6 +41,12 @@ use std::time::{SystemTime, UNIX_EPOCH};
 pub struct TracerSystem {
     event_store: Arc<EventStore>,
     enabled: Arc<AtomicBool>,
+    verbosity: TracerVerbosity,
+    redactor: Option<TracerRedactor>,
+    sampler: Arc<Sampler>,
+    tags: HashMap<String, String>,
+    parent_span_id: Option<String>,
+    stats: Arc<Mutex<TracerStats>>,
 }
 
 impl TracerSystem {
@@ -32,7 +60,129 @@ impl TracerSystem {
         Self {
             event_store: event_store.unwrap_or_else(|| Arc::new(EventStore::default())),
             enabled: Arc::new(AtomicBool::new(enabled)),
+            verbosity: TracerVerbosity::default(),
+            redactor: None,
+            sampler: Arc::new(Sampler::default()),
+            tags: HashMap::new(),
+            parent_span_id: None,
+            stats: Arc::default(),
+        }
+    }
+
+    /// Set how much message detail is recorded (default: [`TracerVerbosity::Full`])
+    pub fn with_verbosity(mut self, verbosity: TracerVerbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    /// Redact event content before it is stored
+    ///
+    /// The redactor runs over every message body, response, and tool argument
+    /// and result an event carries, before the event reaches the store, its
+    /// callbacks, or stream subscribers, so unredacted content is never kept.
+    /// Use [`redact_pii`](super::redaction::redact_pii) to mask emails, phone
+    /// numbers, and card numbers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mojentic::tracer::redaction::redact_pii;
+    /// use mojentic::tracer::TracerSystem;
+    ///
+    /// let tracer = TracerSystem::default().with_redactor(redact_pii);
+    /// ```
+    pub fn with_redactor(
+        mut self,
+        redactor: impl Fn(&mut serde_json::Value) + Send + Sync + 'static,
+    ) -> Self {
+        self.redactor = Some(Arc::new(redactor));
+        self
+    }
+
+    /// Record only a sample of requests (default: [`TraceSampling::All`])
+    ///
+    /// The decision is made per correlation id, so a sampled-in request keeps
+    /// its complete trace and a sampled-out one records nothing, unless the
+    /// policy keeps failed requests.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a policy samples one in zero requests.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mojentic::tracer::{TraceSampling, TracerSystem};
+    ///
+    /// let tracer = TracerSystem::default().with_sampling(TraceSampling::ErrorsAndOneIn(100));
+    /// ```
+    pub fn with_sampling(mut self, sampling: TraceSampling) -> Self {
+        if let TraceSampling::OneIn(n) | TraceSampling::ErrorsAndOneIn(n) = sampling {
+            assert!(n >= 1, "sampling must keep one request in n, with n >= 1");
         }
+        self.sampler = Arc::new(Sampler::new(sampling));
+        self
+    }
+
+    /// A tracer that attaches `tags` to every event it records
+    ///
+    /// The returned tracer shares this one's event store, switch, redactor,
+    /// sampling and stats, so its events land in the same timeline; query them with
+    /// [`get_events_by_tag`](Self::get_events_by_tag). Tags already on this
+    /// tracer are kept unless `tags` gives the same key a new value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mojentic::tracer::TracerSystem;
+    /// use std::collections::HashMap;
+    ///
+    /// let tracer = TracerSystem::default();
+    /// let acme = tracer.tagged(HashMap::from([("tenant".to_string(), "acme".to_string())]));
+    /// acme.record_llm_call("llama3.2", vec![], 0.7, None, "agent", "request-1");
+    ///
+    /// assert_eq!(tracer.get_events_by_tag("tenant", "acme").len(), 1);
+    /// ```
+    pub fn tagged(&self, tags: HashMap<String, String>) -> TracerSystem {
+        let mut merged = self.tags.clone();
+        merged.extend(tags);
+        TracerSystem {
+            event_store: Arc::clone(&self.event_store),
+            enabled: Arc::clone(&self.enabled),
+            verbosity: self.verbosity,
+            redactor: self.redactor.clone(),
+            sampler: Arc::clone(&self.sampler),
+            tags: merged,
+            parent_span_id: self.parent_span_id.clone(),
+            stats: Arc::clone(&self.stats),
+        }
+    }
+
+    /// A tracer that marks every event it records as within `parent_span_id`
+    ///
+    /// Like [`tagged`](Self::tagged), the returned tracer shares this one's
+    /// event store, switch, redactor, sampling, tags and stats. Its events carry
+    /// the span under `parent_span_id`, which links a sub-agent's events to the
+    /// broker that started it (see [`LlmBroker::child`](crate::llm::LlmBroker::child)).
+    pub fn in_span(&self, parent_span_id: impl Into<String>) -> TracerSystem {
+        TracerSystem {
+            parent_span_id: Some(parent_span_id.into()),
+            ..self.tagged(HashMap::new())
+        }
+    }
+
+    /// Set how timestamps and durations are rendered in event summaries
+    ///
+    /// The format lives on the event store, so it also applies to scoped views
+    /// and to other tracers sharing the same store.
+    pub fn with_summary_format(self, format: SummaryFormat) -> Self {
+        self.event_store.set_summary_format(format);
+        self
+    }
+
+    /// Get the configured verbosity
+    pub fn verbosity(&self) -> TracerVerbosity {
+        self.verbosity
     }
 
     /// Check if the tracer is enabled
@@ -59,7 +209,7 @@ impl TracerSystem {
         if !self.is_enabled() {
             return;
         }
-        self.event_store.store(event);
+        self.store(event);
     }
 
     /// Record an LLM call event
@@ -95,7 +245,7 @@ impl TracerSystem {
             tools,
         });
 
-        self.event_store.store(event);
+        self.store(event);
     }
 
     /// Record an LLM response event
@@ -116,6 +266,68 @@ impl TracerSystem {
         call_duration_ms: Option<f64>,
         source: impl Into<String>,
         correlation_id: impl Into<String>,
+    ) {
+        self.record_llm_response_with_payload(
+            model,
+            content,
+            tool_calls,
+            call_duration_ms,
+            None,
+            source,
+            correlation_id,
+        );
+    }
+
+    /// Record an LLM response event along with the request and response body sizes
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - The name of the LLM model that responded
+    /// * `content` - The content of the LLM response
+    /// * `tool_calls` - Any tool calls made by the LLM in its response
+    /// * `call_duration_ms` - The duration of the LLM call in milliseconds
+    /// * `payload_size` - Bytes sent and received, as measured by the gateway
+    /// * `source` - The source of the event
+    /// * `correlation_id` - UUID string for tracing related events
+    #[allow(clippy::too_many_arguments)]
+    pub fn record_llm_response_with_payload(
+        &self,
+        model: impl Into<String>,
+        content: impl Into<String>,
+        tool_calls: Option<Vec<HashMap<String, serde_json::Value>>>,
+        call_duration_ms: Option<f64>,
+        payload_size: Option<PayloadSize>,
+        source: impl Into<String>,
+        correlation_id: impl Into<String>,
+    ) {
+        self.record_llm_response_with_usage(
+            model,
+            content,
+            tool_calls,
+            call_duration_ms,
+            payload_size,
+            None,
+            source,
+            correlation_id,
+        );
+    }
+
+    /// Record an LLM response event along with body sizes and token usage
+    ///
+    /// Takes the same arguments as
+    /// [`record_llm_response_with_payload`](Self::record_llm_response_with_payload),
+    /// plus the `usage` the provider reported, if any.
+    #[allow(clippy::too_many_arguments)]
+    pub fn record_llm_response_with_usage(
+        &self,
+        model: impl Into<String>,
+        content: impl Into<String>,
+        tool_calls: Option<Vec<HashMap<String, serde_json::Value>>>,
+        call_duration_ms: Option<f64>,
+        payload_size: Option<PayloadSize>,
+        usage: Option<TokenUsage>,
+        source: impl Into<String>,
+        correlation_id: impl Into<String>,
     ) {
         if !self.is_enabled() {
             return;
@@ -129,13 +341,21 @@ impl TracerSystem {
             content: content.into(),
             tool_calls,
             call_duration_ms,
+            request_bytes: payload_size.map(|size| size.request_bytes),
+            response_bytes: payload_size.map(|size| size.response_bytes),
+            prompt_tokens: usage.map(|usage| usage.prompt_tokens),
+            completion_tokens: usage.map(|usage| usage.completion_tokens),
         });
 
-        self.event_store.store(event);
+        self.store(event);
     }
 
     /// Record a tool call event
     ///
+    /// A null result or one with an `error` field is counted as a
+    /// [`ToolCallStatus::RecoverableError`]; use
+    /// [`record_tool_outcome`](Self::record_tool_outcome) to give the status.
+    ///
     /// # Arguments
     ///
     /// * `tool_name` - The name of the tool being called
@@ -155,23 +375,75 @@ impl TracerSystem {
         call_duration_ms: Option<f64>,
         source: impl Into<String>,
         correlation_id: impl Into<String>,
+    ) {
+        let status = if result.is_null() || result.get("error").is_some() {
+            ToolCallStatus::RecoverableError
+        } else {
+            ToolCallStatus::Succeeded
+        };
+        self.record_tool_outcome(
+            tool_name,
+            arguments,
+            result,
+            status,
+            caller,
+            call_duration_ms,
+            source,
+            correlation_id,
+        );
+    }
+
+    /// Record a tool call event with how the call ended
+    ///
+    /// Besides storing the event, counts the call in [`stats`](Self::stats).
+    /// Takes the same arguments as [`record_tool_call`](Self::record_tool_call),
+    /// plus the call's `status`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn record_tool_outcome(
+        &self,
+        tool_name: impl Into<String>,
+        arguments: HashMap<String, serde_json::Value>,
+        result: serde_json::Value,
+        status: ToolCallStatus,
+        caller: Option<String>,
+        call_duration_ms: Option<f64>,
+        source: impl Into<String>,
+        correlation_id: impl Into<String>,
     ) {
         if !self.is_enabled() {
             return;
         }
 
+        let tool_name = tool_name.into();
+        self.stats
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .tools
+            .entry(tool_name.clone())
+            .or_default()
+            .record(status);
+
+        let (arguments, result) = match self.verbosity {
+            TracerVerbosity::Full => (arguments, result),
+            TracerVerbosity::Summary => (
+                arguments.into_iter().map(|(key, value)| (key, value_size(&value))).collect(),
+                value_size(&result),
+            ),
+        };
+
         let event = Box::new(ToolCallTracerEvent {
             timestamp: current_timestamp(),
             correlation_id: correlation_id.into(),
             source: source.into(),
-            tool_name: tool_name.into(),
+            tool_name,
             arguments,
             result,
             caller,
             call_duration_ms,
+            status,
         });
 
-        self.event_store.store(event);
+        self.store(event);
     }
 
     /// Record a parallel tool batch event.
@@ -203,7 +475,7 @@ impl TracerSystem {
             caller,
         });
 
-        self.event_store.store(event);
+        self.store(event);
     }
 
     /// Record an agent interaction event
@@ -239,7 +511,32 @@ impl TracerSystem {
             event_id,
         });
 
-        self.event_store.store(event);
+        self.store(event);
+    }
+
+    /// Store an event if its request is sampled, redacting it first when a redactor is installed
+    fn store(&self, event: Box<dyn TracerEvent>) {
+        let event: Box<dyn TracerEvent> = match &self.parent_span_id {
+            Some(parent_span_id) => Box::new(SpanTracerEvent {
+                event,
+                parent_span_id: parent_span_id.clone(),
+            }),
+            None => event,
+        };
+        let event: Box<dyn TracerEvent> = if self.tags.is_empty() {
+            event
+        } else {
+            Box::new(TaggedTracerEvent {
+                event,
+                tags: self.tags.clone(),
+            })
+        };
+        for mut event in self.sampler.admit(event) {
+            if let Some(redactor) = &self.redactor {
+                event.redact(redactor.as_ref());
+            }
+            self.event_store.store(event);
+        }
     }
 
     /// Get event summaries from the store, optionally filtered
@@ -262,6 +559,27 @@ impl TracerSystem {
         self.event_store.get_event_summaries(start_time, end_time, filter_func)
     }
 
+    /// Get structured JSON for events matching filters
+    ///
+    /// # Arguments
+    ///
+    /// * `start_time` - Include events with timestamp >= start_time
+    /// * `end_time` - Include events with timestamp <= end_time
+    /// * `filter_func` - Custom filter function to apply to events
+    ///
+    /// # Returns
+    ///
+    /// Vector of [`TracerEvent::to_json`](super::TracerEvent::to_json) values
+    /// matching the filter criteria
+    pub fn get_event_json(
+        &self,
+        start_time: Option<f64>,
+        end_time: Option<f64>,
+        filter_func: Option<&dyn super::EventFilterFn>,
+    ) -> Vec<serde_json::Value> {
+        self.event_store.get_event_json(start_time, end_time, filter_func)
+    }
+
     /// Get the last N event summaries, optionally filtered
     ///
     /// # Arguments
@@ -300,9 +618,99 @@ impl TracerSystem {
         self.event_store.count_events(start_time, end_time, filter_func)
     }
 
-    /// Clear all events from the event store
+    /// Subscribe to recorded events as an async stream
+    ///
+    /// The stream yields every event recorded after subscribing, in order, and
+    /// ends when the tracer's event store is dropped. A consumer that falls far
+    /// behind skips the oldest missed events rather than blocking producers.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use futures::stream::StreamExt;
+    ///
+    /// let mut events = Box::pin(tracer.subscribe_stream());
+    /// while let Some(event) = events.next().await {
+    ///     println!("{}", event.printable_summary());
+    /// }
+    /// ```
+    pub fn subscribe_stream(&self) -> impl Stream<Item = Arc<dyn TracerEvent>> + Send + 'static {
+        let mut receiver = self.event_store.subscribe();
+        async_stream::stream! {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => yield event,
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!(skipped, "Tracer subscriber lagged, skipping events");
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        }
+    }
+
+    /// Create a view of this tracer's events filtered to a single agent
+    ///
+    /// The view shares this tracer's event store, so it reflects events
+    /// recorded after it was created.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The agent name, as recorded in each event's `source`
+    pub fn scoped(&self, source: impl Into<String>) -> ScopedTracer {
+        ScopedTracer::new(Arc::clone(&self.event_store), source)
+    }
+
+    /// Combine another tracer's events into this one's timeline
+    ///
+    /// Useful when sub-agents run with their own tracers and their events
+    /// belong to a single request. Events keep their original timestamps and
+    /// correlation ids and are ordered by timestamp alongside this tracer's
+    /// events; `other` is left unchanged. See [`EventStore::merge_from`].
+    pub fn merge_from(&self, other: &TracerSystem) {
+        self.event_store.merge_from(&other.event_store);
+    }
+
+    /// Get the events carrying the tag `key` with the given value
+    ///
+    /// See [`TracerSystem::tagged`] for recording tagged events.
+    pub fn get_events_by_tag(&self, key: &str, value: &str) -> Vec<Arc<dyn TracerEvent>> {
+        self.event_store.get_events_by_tag(key, value)
+    }
+
+    /// Per-tool call and failure counts recorded so far
+    ///
+    /// Every tool call recorded while the tracer is enabled is counted, even
+    /// when sampling leaves its event out of the store.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mojentic::tracer::{ToolCallStatus, TracerSystem};
+    /// use std::collections::HashMap;
+    ///
+    /// let tracer = TracerSystem::default();
+    /// tracer.record_tool_outcome(
+    ///     "lookup",
+    ///     HashMap::new(),
+    ///     serde_json::Value::Null,
+    ///     ToolCallStatus::RecoverableError,
+    ///     None,
+    ///     None,
+    ///     "agent",
+    ///     "request-1",
+    /// );
+    ///
+    /// assert_eq!(tracer.stats().tool("lookup").failure_rate(), 1.0);
+    /// ```
+    pub fn stats(&self) -> TracerStats {
+        self.stats.lock().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// Clear all events from the event store, and the stats
     pub fn clear(&self) {
         self.event_store.clear();
+        *self.stats.lock().unwrap_or_else(PoisonError::into_inner) = TracerStats::default();
     }
 
     /// Get the total number of events in the store
@@ -327,9 +735,18 @@ fn current_timestamp() -> f64 {
     SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs_f64()
 }
 
+/// Stand-in for a tool value in summary mode: its serialized length, or null
+fn value_size(value: &serde_json::Value) -> serde_json::Value {
+    if value.is_null() {
+        return serde_json::Value::Null;
+    }
+    serde_json::json!({ "length": value.to_string().len() })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use serde_json::{json, Value};
 
     #[test]
     fn test_new_tracer_system() {
@@ -338,6 +755,199 @@ mod tests {
         assert_eq!(tracer.len(), 0);
     }
 
+    #[test]
+    fn test_verbosity_defaults_to_full() {
+        let tracer = TracerSystem::default();
+        assert_eq!(tracer.verbosity(), TracerVerbosity::Full);
+
+        let tracer = TracerSystem::default().with_verbosity(TracerVerbosity::Summary);
+        assert_eq!(tracer.verbosity(), TracerVerbosity::Summary);
+    }
+
+    #[test]
+    fn test_summary_verbosity_records_tool_value_sizes() {
+        let tracer = TracerSystem::default().with_verbosity(TracerVerbosity::Summary);
+        let mut args = HashMap::new();
+        args.insert("query".to_string(), json!("secret"));
+
+        tracer.record_tool_outcome(
+            "search",
+            args,
+            json!({"hits": ["private"]}),
+            ToolCallStatus::Succeeded,
+            None,
+            None,
+            "test",
+            "corr-1",
+        );
+
+        let event = &tracer.get_event_json(None, None, None)[0];
+        assert_eq!(event["arguments"], json!({"query": {"length": 8}}));
+        assert_eq!(event["result"], json!({"length": 20}));
+        assert_eq!(tracer.stats().tools["search"].calls, 1);
+    }
+
+    #[test]
+    fn test_record_llm_response_with_usage_records_token_counts() {
+        let tracer = TracerSystem::default();
+
+        tracer.record_llm_response_with_usage(
+            "llama3.2",
+            "",
+            None,
+            None,
+            None,
+            Some(TokenUsage::new(120, 30)),
+            "test",
+            "corr-1",
+        );
+
+        let event = &tracer.get_event_json(None, None, None)[0];
+        assert_eq!(event["prompt_tokens"], 120);
+        assert_eq!(event["completion_tokens"], 30);
+        assert!(tracer.get_event_summaries(None, None, None)[0]
+            .contains("Tokens: 120 prompt, 30 completion"));
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_stream_yields_recorded_events() {
+        use futures::stream::StreamExt;
+
+        let tracer = TracerSystem::default();
+        let mut events = Box::pin(tracer.subscribe_stream());
+
+        tracer.record_llm_call("llama3.2", vec![], 0.7, None, "test", "corr-1");
+        tracer.record_llm_response("llama3.2", "Hi", None, None, "test", "corr-2");
+
+        let first = events.next().await.unwrap();
+        let second = events.next().await.unwrap();
+        assert_eq!(first.correlation_id(), "corr-1");
+        assert_eq!(second.correlation_id(), "corr-2");
+        assert!(second.printable_summary().contains("LlmResponseTracerEvent"));
+    }
+
+    fn call_at(timestamp: f64, correlation_id: &str) -> Box<dyn TracerEvent> {
+        Box::new(LlmCallTracerEvent {
+            timestamp,
+            correlation_id: correlation_id.to_string(),
+            source: "test".to_string(),
+            model: "llama3.2".to_string(),
+            messages: vec![],
+            temperature: 1.0,
+            tools: None,
+        })
+    }
+
+    #[test]
+    fn test_merge_from_interleaves_events_by_timestamp() {
+        let parent = TracerSystem::default();
+        let child = TracerSystem::default();
+        parent.record_event(call_at(1.0, "parent-1"));
+        parent.record_event(call_at(3.0, "parent-2"));
+        child.record_event(call_at(2.0, "child-1"));
+        child.record_event(call_at(4.0, "child-2"));
+
+        parent.merge_from(&child);
+
+        let events = parent.get_event_json(None, None, None);
+        let order: Vec<_> = events.iter().map(|e| e["correlation_id"].clone()).collect();
+        assert_eq!(parent.len(), 4);
+        assert_eq!(order, vec!["parent-1", "child-1", "parent-2", "child-2"]);
+        assert_eq!(child.len(), 2);
+    }
+
+    fn record_request(tracer: &TracerSystem, correlation_id: &str) {
+        tracer.record_llm_call("llama3.2", vec![], 0.7, None, "test", correlation_id);
+        tracer.record_tool_call(
+            "lookup",
+            HashMap::new(),
+            json!("ok"),
+            None,
+            None,
+            "test",
+            correlation_id,
+        );
+        tracer.record_llm_response("llama3.2", "Done", None, None, "test", correlation_id);
+    }
+
+    fn events_for(tracer: &TracerSystem, correlation_id: &str) -> usize {
+        let correlation_id = correlation_id.to_string();
+        tracer.count_events(
+            None,
+            None,
+            Some(&move |e: &dyn TracerEvent| e.correlation_id() == correlation_id),
+        )
+    }
+
+    #[test]
+    fn test_one_in_two_sampling_keeps_whole_requests() {
+        let policy = TraceSampling::OneIn(2);
+        let tracer = TracerSystem::default().with_sampling(policy);
+        let ids: Vec<String> = (0..20).map(|i| format!("request-{i}")).collect();
+
+        for id in &ids {
+            record_request(&tracer, id);
+        }
+
+        let (sampled_in, sampled_out): (Vec<_>, Vec<_>) =
+            ids.iter().partition(|id| policy.samples(id));
+        assert!(!sampled_in.is_empty() && !sampled_out.is_empty());
+        assert!(sampled_in.iter().all(|id| events_for(&tracer, id) == 3));
+        assert!(sampled_out.iter().all(|id| events_for(&tracer, id) == 0));
+        assert_eq!(tracer.len(), sampled_in.len() * 3);
+    }
+
+    #[test]
+    fn test_errors_sampling_keeps_failed_requests_in_full() {
+        let policy = TraceSampling::ErrorsAndOneIn(1_000_000);
+        let tracer = TracerSystem::default().with_sampling(policy);
+        let id = (0..).map(|i| format!("request-{i}")).find(|id| !policy.samples(id)).unwrap();
+
+        record_request(&tracer, &id);
+        assert_eq!(tracer.len(), 0);
+
+        tracer.record_tool_call("lookup", HashMap::new(), Value::Null, None, None, "test", &id);
+        tracer.record_llm_response("llama3.2", "Sorry", None, None, "test", &id);
+
+        assert_eq!(events_for(&tracer, &id), 5);
+    }
+
+    #[test]
+    fn test_merge_from_self_is_a_no_op() {
+        let tracer = TracerSystem::default();
+        tracer.record_event(call_at(1.0, "only"));
+
+        tracer.merge_from(&tracer);
+
+        assert_eq!(tracer.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_stream_ends_when_tracer_dropped() {
+        use futures::stream::StreamExt;
+
+        let tracer = TracerSystem::default();
+        let mut events = Box::pin(tracer.subscribe_stream());
+        drop(tracer);
+
+        assert!(events.next().await.is_none());
+    }
+
+    #[test]
+    fn test_redactor_masks_email_before_storing() {
+        let tracer = TracerSystem::default().with_redactor(crate::tracer::redaction::redact_pii);
+        let message = HashMap::from([
+            ("role".to_string(), serde_json::json!("user")),
+            ("content".to_string(), serde_json::json!("Email me at ada@example.com")),
+        ]);
+
+        tracer.record_llm_call("llama3.2", vec![message], 0.7, None, "test", "corr-1");
+
+        let events = tracer.get_event_json(None, None, None);
+        assert_eq!(events[0]["messages"][0]["content"], "Email me at [REDACTED_EMAIL]");
+        assert!(!events[0].to_string().contains("ada@example.com"));
+    }
+
     #[test]
     fn test_enable_disable() {
         let tracer = TracerSystem::default();
@@ -394,6 +1004,43 @@ mod tests {
         assert_eq!(tracer.len(), 1);
     }
 
+    #[test]
+    fn test_summary_format_applies_to_summaries() {
+        let response = || {
+            Box::new(LlmResponseTracerEvent {
+                timestamp: 1_700_000_000.25,
+                correlation_id: "corr-fmt".to_string(),
+                source: "test".to_string(),
+                model: "llama3.2".to_string(),
+                content: "Hello".to_string(),
+                tool_calls: None,
+                call_duration_ms: Some(1500.0),
+                request_bytes: None,
+                response_bytes: None,
+                prompt_tokens: None,
+                completion_tokens: None,
+            })
+        };
+        let rfc = TracerSystem::default().with_summary_format(SummaryFormat {
+            timestamp: TimestampFormat::Rfc3339,
+            duration: DurationUnit::Seconds,
+        });
+        let epoch = TracerSystem::default().with_summary_format(SummaryFormat {
+            timestamp: TimestampFormat::EpochMillis,
+            duration: DurationUnit::Milliseconds,
+        });
+        rfc.record_event(response());
+        epoch.record_event(response());
+
+        let rfc_summary = &rfc.get_event_summaries(None, None, None)[0];
+        let epoch_summary = &epoch.get_last_n_summaries(1, None)[0];
+
+        assert!(rfc_summary.starts_with("[2023-11-14T22:13:20.250Z] LlmResponseTracerEvent"));
+        assert!(rfc_summary.contains("Duration: 1.500s"));
+        assert!(epoch_summary.starts_with("[1700000000250] LlmResponseTracerEvent"));
+        assert!(epoch_summary.contains("Duration: 1500.00ms"));
+    }
+
     #[test]
     fn test_record_agent_interaction() {
         let tracer = TracerSystem::default();
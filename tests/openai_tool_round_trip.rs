@@ -59,6 +59,7 @@ impl LlmTool for GetWeatherTool {
                     },
                     "required": ["location"]
                 }),
+                returns: None,
             },
         }
     }